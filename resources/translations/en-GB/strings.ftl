@@ -8,6 +8,7 @@ add-collection = Add Collection...
 open-collection = Open Collection...
 open-recent = Open Recent Collection
 clear-recents = Clear Recent Collections
+close-collection = Close Collection
 open-settings = Settings...
 quit = Quit
 
@@ -26,7 +27,10 @@ next-sample = Next Sample
 view = View
 show-collections = Show Collections Panel
 show-tags = Show Tags Panel
+show-kits = Show Kits Panel
 show-waveview = Show Waveview Panel
+show-inspector = Show Inspector Panel
+show-pads = Show Pads Panel
 columns = Columns
 restore-default-columns = Restore Default Columns
 show-all-columns = Show All Columns
@@ -38,7 +42,9 @@ bit-depth = Bit Depth
 num-channels = Num Channels
 bpm = BPM
 key = Key
+note = Note
 size = Size
+format = Format
 toggle-fullscreen = Toggle Fullscreen
 reset-view = Reset View
 
@@ -55,7 +61,127 @@ general = General
 user-interface = User Interface
 audio = Audio
 audio-driver = Audio Driver
-input-device = Input Device
 output-device = Output Device
+buffer-size = Buffer Size
+indexed-formats = Indexed Formats
 
 
+
+indexing-progress = Indexing { $name }: { $scanned } / { $total }
+cancel-indexing = Cancel Indexing
+indexing-errors = { $count ->
+    [one] 1 file could not be indexed
+   *[other] { $count } files could not be indexed
+}
+clear-report = Clear
+close = Close
+
+inspector = Inspector
+no-metadata = No embedded metadata
+one-shot = One-shot
+embedded-loop = Loop
+
+suggested-tags = Suggested Tags
+min-confidence = At least { $confidence }%
+accept = Accept
+reject = Reject
+
+find-similar = Find Similar
+similar-to = Similar to { $name }
+pin-to-layer = Pin to Layer
+pin-to-pad = Pin to Pad
+send-to-kit = Send to Kit
+
+retune-to = Retune To
+as-recorded = As Recorded
+trim-silence = Skip Leading Silence
+
+speed = Speed
+transpose = Transpose
+tempo-sync = Sync
+sync-resample = Sync Tempo by Resampling
+metronome = Click
+quantize-start = Start on Bar
+reverse = Reverse
+low-pass = LP
+high-pass = HP
+fade-in = Fade In
+fade-out = Fade Out
+channels = Channels
+channels-both = Both
+channels-left = Left
+channels-right = Right
+channels-mid = Mid
+channels-side = Side
+ambisonic = Four Channels as Ambisonic
+show-spectrum = Show Spectrum
+crossfade = Crossfade
+crossfade-off = Off
+layer-gain = Gain
+layer-pan = Pan
+layer-mute = M
+slice = Slice into One-shots
+slice-count = { $count ->
+    [one] 1 slice
+   *[other] { $count } slices
+}
+slice-detect = Detect
+slice-clear = Clear
+slice-hint = Click a slice to play it, drag a marker to move it, double-click to add one and right-click to remove it
+
+pads = Pads
+pad-one-shot = One-shot
+pad-gate = Gate
+pad-choke = Choke { $group ->
+    [0] Off
+   *[other] { $group }
+}
+
+kits = Kits
+new-kit = New Kit
+kits-empty = Create a kit to assemble one-shots into slots
+kit-drop-hint = Drag a sample here
+add-slot = Add Slot
+remove-slot = Remove
+play-as-pads = Play as Pads
+export-kit = Export Kit...
+remove-kit = Remove Kit
+kit-exported = Exported to { $folder }
+
+export = Export
+export-selection = Export...
+export-files = Export { $count ->
+    [one] 1 file
+   *[other] { $count } files
+}
+export-region = Only the region selected in the waveform is exported
+export-dither = Dither
+export-normalize = Normalize Peak
+export-effects = Apply Preview Effects
+export-template = File Name
+export-template-hint = {"{"}name{"}"}, {"{"}index{"}"} and {"{"}rate{"}"} are replaced by the name of each file, its number and the sample rate
+export-slices = Export Slices...
+slices-exported = Exported { $count ->
+    [one] 1 slice
+   *[other] { $count } slices
+}
+export-instrument = Export as Instrument...
+export-instrument-zones = Map { $count ->
+    [one] 1 sample
+   *[other] { $count } samples
+}
+instrument-sample = Sample
+instrument-root = Root
+instrument-keys = Keys
+instrument-velocities = Velocities
+instrument-loop = Loop
+instrument-unmapped = Left out without a root note: { $names }
+instrument-dspreset = Also write a Decent Sampler preset
+instrument-written = Wrote { $files }
+export-finished = Exported { $exported ->
+    [one] 1 file
+   *[other] { $exported } files
+}{ $failed ->
+    [0] {""}
+   *[other] , { $failed } failed
+}
@@ -0,0 +1,148 @@
+//! Commands run from the command line without opening the window, such as exporting files.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::engine::{
+    export_batch, BitDepth, ExportSettings, ExportSource, FilterType, PreviewEffects, MAX_CUTOFF,
+    MAX_FADE, MIN_CUTOFF,
+};
+
+const USAGE: &str = "\
+usage: vizia-sample-browser export [options] --out <folder> <file>...
+
+options:
+    --rate <hz>          convert to the sample rate, keeping that of each file by default
+    --bits <16|24|32>    write 16 or 24-bit integer or 32-bit float samples, 24-bit by default
+    --no-dither          do not dither integer samples
+    --normalize <db>     normalize the peak level to the level in dBFS
+    --start <frame>      export from the frame on
+    --end <frame>        export up to the frame
+    --reverse            reverse the files
+    --low-pass <hz>      filter with a low-pass filter at the cutoff
+    --high-pass <hz>     filter with a high-pass filter at the cutoff
+    --fade-in <s>        fade in over the length in seconds
+    --fade-out <s>       fade out over the length in seconds
+    --template <name>    name the files after the template, in which {name}, {index} and {rate} are replaced by
+                         the name of each file, its number and the sample rate, {name} by default";
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+    #[error("{0} of {1} files could not be exported")]
+    Failed(usize, usize),
+}
+
+// The files to export from the command line, and where and how they are exported
+struct ExportCommand {
+    sources: Vec<ExportSource>,
+    directory: PathBuf,
+    template: String,
+    settings: ExportSettings,
+}
+
+/// Run the command named by the first of the arguments following the program name, or return `None` if there
+/// is no command so that the window should be opened.
+pub fn run(args: &[String]) -> Option<Result<(), CommandError>> {
+    match args.first()?.as_str() {
+        "export" => Some(parse_export(&args[1..]).and_then(run_export)),
+        _ => None,
+    }
+}
+
+fn parse_export(args: &[String]) -> Result<ExportCommand, CommandError> {
+    let mut paths = Vec::new();
+    let mut directory = None;
+    let mut template = String::from("{name}");
+    let mut settings = ExportSettings::default();
+    let mut effects = PreviewEffects::default();
+    let (mut start, mut end) = (None, None);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value =
+            || args.next().ok_or_else(|| CommandError::Usage(format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--out" => directory = Some(PathBuf::from(value()?)),
+            "--rate" => settings.sample_rate = Some(parse(arg, value()?)?),
+            "--bits" => {
+                settings.bit_depth = match value()?.as_str() {
+                    "16" => BitDepth::Int16,
+                    "24" => BitDepth::Int24,
+                    "32" => BitDepth::Float32,
+                    bits => {
+                        return Err(CommandError::Usage(format!("unsupported bit depth {}", bits)))
+                    }
+                }
+            }
+            "--no-dither" => settings.dither = false,
+            "--normalize" => settings.normalize = Some(parse::<f32>(arg, value()?)?.min(0.0)),
+            "--start" => start = Some(parse(arg, value()?)?),
+            "--end" => end = Some(parse(arg, value()?)?),
+            "--reverse" => effects.reverse = true,
+            "--low-pass" | "--high-pass" => {
+                effects.filter = Some(match arg.as_str() {
+                    "--low-pass" => FilterType::LowPass,
+                    _ => FilterType::HighPass,
+                });
+                effects.cutoff = parse::<f32>(arg, value()?)?.clamp(MIN_CUTOFF, MAX_CUTOFF);
+            }
+            "--fade-in" => effects.fade_in = parse::<f32>(arg, value()?)?.clamp(0.0, MAX_FADE),
+            "--fade-out" => effects.fade_out = parse::<f32>(arg, value()?)?.clamp(0.0, MAX_FADE),
+            "--template" => template = value()?.clone(),
+            option if option.starts_with("--") => {
+                return Err(CommandError::Usage(format!("unknown option {}", option)));
+            }
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    let directory = directory.ok_or_else(|| CommandError::Usage(String::from("missing --out")))?;
+    if paths.is_empty() {
+        return Err(CommandError::Usage(String::from("no files to export")));
+    }
+
+    let region = match (start, end) {
+        (None, None) => None,
+        (start, end) => Some((start.unwrap_or(0), end.unwrap_or(usize::MAX))),
+    };
+    settings.effects = (effects != PreviewEffects::default()).then_some(effects);
+    Ok(ExportCommand {
+        sources: paths.into_iter().map(|path| ExportSource { path, region }).collect(),
+        directory,
+        template,
+        settings,
+    })
+}
+
+// Parse the value of an option.
+fn parse<T: FromStr>(option: &str, value: &str) -> Result<T, CommandError> {
+    value
+        .parse()
+        .map_err(|_| CommandError::Usage(format!("invalid value {} for {}", value, option)))
+}
+
+// Export the files, printing the path of each exported file and the errors of the others.
+fn run_export(command: ExportCommand) -> Result<(), CommandError> {
+    let results =
+        export_batch(&command.sources, &command.directory, &command.template, &command.settings);
+
+    let mut failed = 0;
+    for (source, result) in command.sources.iter().zip(results) {
+        match result {
+            Ok(path) => println!("{}", path.display()),
+            Err(err) => {
+                eprintln!("{}: {}", source.path.display(), err);
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(CommandError::Failed(failed, command.sources.len())),
+    }
+}
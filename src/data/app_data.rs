@@ -1,11 +1,12 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex, MutexGuard},
+    sync::{atomic::Ordering, Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 
 use basedrop::{Collector, Owned, Shared};
-use creek::{Decoder, ReadDiskStream, ReadStreamOptions, SymphoniaDecoder};
+use creek::{ReadDiskStream, ReadStreamOptions, SymphoniaDecoder};
 use rfd::FileDialog;
 use ron::de::from_reader;
 use serde::{Deserialize, Serialize};
@@ -14,16 +15,44 @@ use vizia::prelude::*;
 use crate::{
     data::{
         browser_data::{BrowserData, Directory},
-        TagsData,
+        ExportData, ExportEvent, IndexingData, IndexingEvent, InstrumentData, InstrumentEvent,
+        KitsData, KitsEvent, PadsData, PadsEvent, SlicesData, SlicesEvent, TagsData, TagsEvent,
     },
     database::prelude::{
-        AudioFile, CollectionID, Database, DatabaseAudioFileHandler, DatabaseCollectionHandler,
+        apply_library_changes, detect_file_transients, index_library, retune_cents, watch_library,
+        AudioFile, AudioFileID, CollectionID, Database, DatabaseAudioFileHandler,
+        DatabaseCollectionHandler, DatabaseError, DatabaseFeatureHandler, DatabaseKitHandler,
+        DatabaseMetadataHandler, DatabaseStore, IndexError, IndexReport, KitID, LibraryChange,
+        LibraryWatcher, MetadataEntry, Pitch, SamplerInfo, DEFAULT_KIT_SLOTS,
+    },
+    engine::{
+        export_batch, export_instrument, export_kit, map_instrument, AudioBackend, ChannelSolo,
+        DecodedAudio, ExportSource, FilterType, InstrumentSample, KitExport, KitExportSlot,
+        LayerMix, OutputSettings, PreviewEffects, SamplePlayerController, Waveform, MAX_CUTOFF,
+        MAX_FADE, MAX_LAYERS, MAX_LAYER_GAIN, MIN_CUTOFF, MIN_LAYER_GAIN, NUM_PADS,
     },
-    engine::{SamplePlayerController, Waveform},
     AudioData, Collection, DatabaseTagHandler, PlayerState, Tag,
 };
 
-use super::{Config, SamplesData, SettingsData};
+use super::{Config, MeterData, SamplesData, SettingsData, SettingsEvent};
+
+// The number of similar audio files shown by "Find similar"
+const SIMILAR_COUNT: usize = 100;
+
+// The minimum time between two updates of the browser while a library is indexed, each reading the whole index
+const LIBRARY_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+// The range of the varispeed factor and of the transposition in semitones
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 2.0;
+pub const MAX_TRANSPOSE: i32 = 12;
+
+// The range of the time-stretch factor, beyond which stretched previews sound too grainy
+const MIN_STRETCH: f64 = 0.25;
+const MAX_STRETCH: f64 = 4.0;
+
+// The names of exported slices, numbered after the name of the sliced file
+const SLICE_TEMPLATE: &str = "{name}-{index}";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ChannelMode {
@@ -41,6 +70,13 @@ pub enum UnitsMode {
     Decibel,
 }
 
+/// A sample layered over the preview, shown in the layer strip of the wave panel.
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct PinnedLayer {
+    pub name: String,
+    pub mix: LayerMix,
+}
+
 /// The state of the playhead.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayState {
@@ -65,6 +101,8 @@ pub enum ZoomMode {
 pub struct AppData {
     // Timer
     timer: Timer,
+    // The timer which checks that the output device is still available
+    output_timer: Timer,
     // Dialogs
     // Whether the about dialog should be shown.
     pub show_about_dialog: bool,
@@ -82,6 +120,18 @@ pub struct AppData {
     pub tags_data: TagsData,
     // The data model for the settings dialog.
     pub settings_data: SettingsData,
+    // The data model for the indexing status.
+    pub indexing_data: IndexingData,
+    // The data model for the pads panel.
+    pub pads_data: PadsData,
+    // The data model for the slices of the selected file.
+    pub slices_data: SlicesData,
+    // The data model for the export dialog.
+    pub export_data: ExportData,
+    // The data model for the export as instrument dialog.
+    pub instrument_data: InstrumentData,
+    // The data model for the kits panel.
+    pub kits_data: KitsData,
 
     // The configuration data of the application
     pub config: Config,
@@ -89,42 +139,101 @@ pub struct AppData {
     // Database
     #[lens(ignore)]
     pub database: Option<Arc<Mutex<Database>>>,
+    // The filesystem watchers of the open libraries by root path, each stopped when its library is closed
+    #[lens(ignore)]
+    pub watchers: HashMap<PathBuf, LibraryWatcher>,
 
     // Audio Engine
     #[lens(ignore)]
     pub collector: Collector,
     pub controller: SamplePlayerController,
+    #[lens(ignore)]
+    pub output: Box<dyn AudioBackend>,
 
     // Audio GUI State
     pub waveform: Option<Arc<Waveform>>,
     pub zoom_level: usize,
     pub start: usize,
+    // The frames of the selected file selected in the waveform, exported instead of the whole file
+    pub wave_selection: Option<(usize, usize)>,
+    // The end of the slice being auditioned, at which playback stops unless looping
+    #[lens(ignore)]
+    auditioned_slice: Option<usize>,
 
     pub should_autoplay: bool,
 
+    // The varispeed factor and the transposition in semitones of the preview
+    pub speed: f32,
+    pub transpose: i32,
+
+    // The non-destructive effects applied to the preview
+    pub effects: PreviewEffects,
+    // The part of the stereo signal previewed on both channels
+    pub channel_solo: ChannelSolo,
+
+    // The samples layered over the preview, in the order of the layers of the player
+    pub layers: Vec<PinnedLayer>,
+
+    // The levels of the output, and the level in dB of each band of its spectrum when shown
+    pub meter: MeterData,
+    pub spectrum: Vec<f32>,
+
+    // The streams opened ahead of time for the files next to the selected one, `None` while opening
+    #[lens(ignore)]
+    prefetched: HashMap<PathBuf, Option<Testy>>,
+
+    // The path of the selected file, which the decoded reverse audio must match
+    #[lens(ignore)]
+    pub selected_file_path: Option<PathBuf>,
     pub selected_file_name: String,
     pub selected_file_sample_rate: u32,
-    pub selected_file_bit_depth: u32,
-    pub selected_file_num_channels: u32,
+    pub selected_file_bit_depth: Option<u16>,
+    pub selected_file_num_channels: u16,
+    // The root note, loop points and tempo of the selected file
+    pub selected_file_sampler: SamplerInfo,
+    // The detected pitch of the selected file and its tuning correction in cents
+    pub selected_file_pitch: Option<Pitch>,
+    pub selected_file_tuning_offset: Option<f32>,
+    // The tempo of the selected file in BPM, from its metadata or acid chunk
+    pub selected_file_bpm: Option<f32>,
+    // The frames between which the selected file is audible, the end being `None` when unknown
+    pub selected_file_audible: Option<(usize, Option<usize>)>,
+    // The embedded metadata of the selected file, shown in the inspector
+    pub selected_metadata: Vec<MetadataEntry>,
 }
 
 impl AppData {
-    pub fn new(collector: Collector, controller: SamplePlayerController, timer: Timer) -> Self {
+    pub fn new(
+        collector: Collector,
+        controller: SamplePlayerController,
+        output: Box<dyn AudioBackend>,
+        timer: Timer,
+        output_timer: Timer,
+    ) -> Self {
         Self {
             timer,
+            output_timer,
             // GUI State
             browser_data: BrowserData::new(),
             samples_data: SamplesData::new(),
             tags_data: TagsData::default(),
+            indexing_data: IndexingData::default(),
+            pads_data: PadsData::default(),
+            slices_data: SlicesData::default(),
+            export_data: ExportData::default(),
+            instrument_data: InstrumentData::default(),
+            kits_data: KitsData::default(),
 
             config: Config::new(),
 
             // Database
             database: None,
+            watchers: HashMap::new(),
 
             // Audio Engine
             collector,
             controller,
+            output,
 
             waveform: None,
             zoom_level: 9,
@@ -132,12 +241,29 @@ impl AppData {
             show_about_dialog: false,
             show_settings_dialog: false,
             show_add_collection_dialog: false,
-            settings_data: SettingsData::dummy(),
+            settings_data: SettingsData::new(),
             should_autoplay: true,
+            speed: 1.0,
+            transpose: 0,
+            effects: PreviewEffects::default(),
+            channel_solo: ChannelSolo::Both,
+            prefetched: HashMap::new(),
+            layers: Vec::new(),
+            meter: MeterData::default(),
+            spectrum: Vec::new(),
+            wave_selection: None,
+            auditioned_slice: None,
+            selected_file_path: None,
             selected_file_name: String::new(),
             selected_file_sample_rate: 0,
-            selected_file_bit_depth: 0,
+            selected_file_bit_depth: None,
             selected_file_num_channels: 0,
+            selected_file_sampler: SamplerInfo::default(),
+            selected_file_pitch: None,
+            selected_file_tuning_offset: None,
+            selected_file_bpm: None,
+            selected_file_audible: None,
+            selected_metadata: Vec::new(),
         }
     }
 }
@@ -146,6 +272,8 @@ pub struct Testy(pub Owned<ReadDiskStream<SymphoniaDecoder>>);
 unsafe impl Send for Testy {}
 unsafe impl Sync for Testy {}
 
+pub struct Reversed(pub PathBuf, pub Owned<DecodedAudio>);
+
 pub enum AppEvent {
     // Show the about dialog.
     ShowAboutDialog,
@@ -167,19 +295,37 @@ pub enum AppEvent {
     ViewCollection(CollectionID),
     // Update the samples table with the given audio files.
     UpdateTable(Vec<AudioFile>),
+    // Reload the samples table from the database.
+    RefreshTable,
 
     // Open a collection from the given path.
     OpenCollection(PathBuf),
-    // The collection has been opened.
-    CollectionOpened(Database, Directory, Vec<Tag>),
+    // The collection with the given path has been opened and is being indexed.
+    CollectionOpened(PathBuf, Arc<Mutex<Database>>, Vec<Tag>),
+    // Close the open library and stop watching it.
+    CloseLibrary,
+    // Files have changed on disk in the library of the given database.
+    LibraryChanged(Arc<Mutex<Database>>, Vec<LibraryChange>),
+    // The index of a library has been added or updated.
+    LibraryUpdated(Directory),
 
     // Select a sample from the given collection.
     SelectSample(CollectionID, String),
+    // Set the playback rate of the selected sample from the speed, transposition, tuning and tempo settings.
+    ApplyPlaybackRate,
+    // Send the tempo and time signature of the metronome, and whether previews start on the bar, to the player.
+    ApplyMetronome,
+    // Start previews of the selected sample at its audible start, or at the start of the file.
+    ApplyTrim,
 
     // Audio Control Events
     LoadSample(PathBuf),
-    SampleLoaded(Testy),
+    SampleLoaded(PathBuf, Testy),
+    // A stream has been opened ahead of time for a file next to the selected one.
+    Prefetched(PathBuf, Testy),
     AppendWaveform(Vec<f32>, usize),
+    // Select the frames of the selected file between which it is exported, or clear the selection.
+    SetWaveSelection(Option<(usize, usize)>),
     Play,
     Pause,
     Stop,
@@ -188,6 +334,53 @@ pub enum AppEvent {
     Tick,
     ToggleLooping,
     ToggleAutoplay,
+    // Set the varispeed, which changes both the tempo and the pitch.
+    SetSpeed(f32),
+    // Transpose the preview by the given number of semitones without changing its tempo.
+    SetTranspose(i32),
+    // Toggle playing the preview in reverse.
+    ToggleReverse,
+    // The selected file has been decoded to be played in reverse.
+    ReversedLoaded(Reversed),
+    // Filter the preview with a low- or high-pass filter, or turn the filter off.
+    SetFilter(Option<FilterType>),
+    // Set the cutoff of the filter in Hz.
+    SetFilterCutoff(f32),
+    // Set the length of the fades at the start and end of the preview in seconds.
+    SetFadeIn(f32),
+    SetFadeOut(f32),
+    // Preview only part of the stereo signal on both channels.
+    SetChannelSolo(ChannelSolo),
+    // Send the channel solo and the layout of four channel files to the player.
+    ApplyChannels,
+    // Send the output of the player to the spectrum analyzer when it is shown.
+    ApplySpectrum,
+
+    // Layer the audio file with the given name in the given collection over the preview.
+    PinLayer(CollectionID, String),
+    // An audio file with the given name has been decoded to be layered over the preview.
+    LayerLoaded(String, Owned<DecodedAudio>),
+    // Remove the layer with the given index.
+    RemoveLayer(usize),
+    // Set the gain in dB, the pan or toggle muting the layer with the given index.
+    SetLayerGain(usize, f32),
+    SetLayerPan(usize, f32),
+    ToggleLayerMute(usize),
+
+    // Pin the audio file with the given name in the given collection to the selected pad.
+    PinPad(CollectionID, String),
+    // An audio file with the given name has been decoded to be pinned to the pad with the given index.
+    PadLoaded(usize, String, Owned<DecodedAudio>),
+
+    // Open the output stream on the output chosen in the settings.
+    OpenOutput,
+    // Reopen the output stream if its device has been lost or the chosen device has become available.
+    CheckOutput,
+    // Choose the host, device, sample rate or buffer size of the output by their index in the settings.
+    SelectAudioDriver(usize),
+    SelectOutputDevice(usize),
+    SelectSampleRate(usize),
+    SelectBufferSize(usize),
 }
 
 fn view_collection(id: usize, db: &MutexGuard<Database>, rows: &mut Vec<AudioFile>) {
@@ -208,6 +401,535 @@ fn view_collection(id: usize, db: &MutexGuard<Database>, rows: &mut Vec<AudioFil
     }
 }
 
+// The chosen audio file followed by its nearest neighbours, closest first.
+fn view_similar(id: AudioFileID, db: &MutexGuard<Database>, rows: &mut Vec<AudioFile>) {
+    rows.extend(db.get_audio_file(id).ok());
+
+    if let Ok(similar) = db.find_similar_audio_files(id, SIMILAR_COUNT) {
+        rows.extend(similar.into_iter().filter_map(|similar| db.get_audio_file(similar.id).ok()));
+    }
+}
+
+impl AppData {
+    // Reload the rows of the collection shown in the samples table, or of the audio files similar to the
+    // chosen one, keeping those matching the search text.
+    fn refresh_table(&self, cx: &mut EventContext) {
+        let similar_to = self.samples_data.similar_to.as_ref().map(|(id, _)| *id);
+        if let (Some(database), Some(id)) = (&self.database, self.samples_data.collection) {
+            let database = database.clone();
+            let search_text = self.samples_data.search_text.clone();
+            cx.spawn(move |cx| {
+                let mut audio_files = Vec::with_capacity(500);
+                if let Ok(db) = database.lock() {
+                    match similar_to {
+                        Some(similar_to) => view_similar(similar_to, &db, &mut audio_files),
+                        None => view_collection(id, &db, &mut audio_files),
+                    }
+
+                    if !search_text.is_empty() {
+                        let matches: HashSet<AudioFileID> = db
+                            .search_audio_files(&search_text)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .collect();
+                        audio_files.retain(|audio_file| matches.contains(&audio_file.id));
+                    }
+                }
+                cx.emit(AppEvent::UpdateTable(audio_files));
+            });
+        }
+    }
+
+    // Set the rates at which the selected sample is played. The varispeed and the retuning to the chosen root,
+    // using the smpl root note when no pitch was detected, change the tempo and the pitch like a tape. The
+    // transposition only changes the pitch, and a file with a known tempo follows the project tempo when synced
+    // or started on the bar, time-stretched or resampled.
+    fn apply_playback_rate(&mut self) {
+        let note = self.selected_file_pitch.map(|pitch| pitch.note);
+        let cents = match (self.config.tuning_root, note.or(self.selected_file_sampler.root_note)) {
+            (Some(root), Some(note)) => {
+                retune_cents(note, self.selected_file_tuning_offset.unwrap_or(0.0), root)
+            }
+            _ => 0.0,
+        };
+
+        let tape = self.speed as f64 * (cents as f64 / 1200.0).exp2();
+        let pitch = tape * (self.transpose as f64 / 12.0).exp2();
+        // Resampled loops are only stretched to keep their transposition
+        let synced = self.config.tempo_sync || self.config.quantize_start;
+        let (rate, tempo) = match (synced, self.selected_file_bpm) {
+            (true, Some(bpm)) if bpm > 0.0 => {
+                let tempo = (self.config.project_tempo / bpm) as f64;
+                if self.config.sync_resample {
+                    (tempo * pitch / tape, tempo)
+                } else {
+                    (pitch, tempo)
+                }
+            }
+            _ => (pitch, tape),
+        };
+
+        self.controller.set_playback_rate(rate);
+        self.controller.set_time_stretch((tempo / rate).clamp(MIN_STRETCH, MAX_STRETCH));
+    }
+
+    // Send the project tempo and time signature to the metronome, and whether previews start on the bar.
+    fn apply_metronome(&mut self) {
+        let config = &self.config;
+        self.controller.set_metronome(
+            config.project_tempo as f64,
+            config.beats_per_bar as u32,
+            config.beat_unit as u32,
+            config.metronome,
+        );
+        self.controller.set_quantize(config.quantize_start);
+    }
+
+    // The frames between which the selected sample is previewed, skipping its leading silence and noise tail
+    // when trimming is enabled. An end of 0 plays to the end of the file.
+    fn preview_region(&self) -> (usize, usize) {
+        match (self.config.trim_preview, self.selected_file_audible) {
+            (true, Some((start, end))) => (start, end.unwrap_or(0)),
+            _ => (0, 0),
+        }
+    }
+
+    // Start playback of the selected sample at the start of its preview region, looping over its embedded loop
+    // points, or over the region if it has none. This also replaces the loop of an auditioned slice.
+    fn apply_trim(&mut self) {
+        let (start, end) = self.preview_region();
+        self.controller.set_region(start, end);
+        match self.selected_file_sampler.loop_points() {
+            Some((loop_start, loop_end)) => {
+                self.controller.set_loop(loop_start as usize, loop_end as usize)
+            }
+            None => self.controller.set_loop(start, end),
+        }
+    }
+
+    // Send the filter and fades of the preview to the player.
+    fn apply_effects(&mut self) {
+        let effects = self.effects;
+        self.controller.set_filter(effects.filter.map(|filter| (filter, effects.cutoff)));
+        self.controller.set_fades(effects.fade_in, effects.fade_out);
+    }
+
+    // Send the channel solo and whether four channel files are ambisonic to the player.
+    fn apply_channels(&mut self) {
+        self.controller.set_channel_solo(self.channel_solo);
+        self.controller.set_ambisonic(self.config.ambisonic);
+    }
+
+    // The path of the audio file with the given name in the given collection.
+    fn audio_file_path(&self, collection_id: CollectionID, name: &str) -> Option<PathBuf> {
+        let db = self.database.as_ref()?.lock().ok()?;
+        let collection = db.get_collection(collection_id).ok()?;
+        Some(collection.path().join(name))
+    }
+
+    // Export the rows selected in the table into a chosen folder in the background. When only the selected
+    // file is exported and a region of its waveform is selected, only that region is exported.
+    fn export_selection(&mut self, cx: &mut EventContext) {
+        let mut sources: Vec<ExportSource> = self
+            .samples_data
+            .selected_rows()
+            .iter()
+            .filter_map(|audio_file| self.audio_file_path(audio_file.collection, &audio_file.name))
+            .map(ExportSource::file)
+            .collect();
+        if let ([source], Some(region)) = (sources.as_mut_slice(), self.wave_selection) {
+            if self.selected_file_path.as_ref() == Some(&source.path) {
+                source.region = Some(region);
+            }
+        }
+
+        if sources.is_empty() {
+            return;
+        }
+        let Some(directory) = FileDialog::new().pick_folder() else {
+            return;
+        };
+
+        let mut settings = self.export_data.settings;
+        settings.effects = self.export_data.apply_effects.then_some(self.effects);
+        let template = self.export_data.template.clone();
+        self.export_data.exporting = true;
+        cx.spawn(move |cx| {
+            let results = export_batch(&sources, &directory, &template, &settings);
+            let errors: Vec<String> = sources
+                .iter()
+                .zip(&results)
+                .filter_map(|(source, result)| {
+                    let error = result.as_ref().err()?;
+                    Some(format!("{}: {}", source.path.display(), error))
+                })
+                .collect();
+            cx.emit(ExportEvent::Finished(results.len() - errors.len(), errors));
+        });
+    }
+
+    // The path of the folder of the collection shown in the table.
+    fn collection_path(&self) -> Option<PathBuf> {
+        let db = self.database.as_ref()?.lock().ok()?;
+        let collection = db.get_collection(self.samples_data.collection?).ok()?;
+        Some(collection.path().clone())
+    }
+
+    // Map the rows selected in the table across the keyboard for the export as instrument dialog. Embedded root
+    // notes are preferred, then detected pitches, tuned by their deviation, and files with neither are left out.
+    // The files are decoded in the background to order the velocity layers by their peak levels.
+    fn map_selection(&mut self, cx: &mut EventContext) {
+        let mut samples = Vec::new();
+        let mut unmapped = Vec::new();
+        for audio_file in self.samples_data.selected_rows() {
+            let Some(path) = self.audio_file_path(audio_file.collection, &audio_file.name) else {
+                continue;
+            };
+
+            let (root_note, tune) = match (audio_file.sampler.root_note, audio_file.pitch) {
+                (Some(root_note), _) => (root_note, 0),
+                (None, Some(pitch)) => (pitch.note, -pitch.cents.round() as i32),
+                (None, None) => {
+                    unmapped.push(audio_file.name.clone());
+                    continue;
+                }
+            };
+            samples.push(InstrumentSample {
+                path,
+                root_note: root_note.min(127),
+                tune,
+                loop_points: audio_file.sampler.loop_points(),
+                level: 0.0,
+            });
+        }
+
+        self.instrument_data.set_zones(Vec::new(), Vec::new());
+        cx.spawn(move |cx| {
+            for sample in samples.iter_mut() {
+                if let Some(audio) = DecodedAudio::read(&sample.path, false) {
+                    sample.level = audio
+                        .left
+                        .iter()
+                        .chain(&audio.right)
+                        .fold(0.0, |peak, s| peak.max(s.abs()));
+                }
+            }
+            cx.emit(InstrumentEvent::Mapped(map_instrument(samples), unmapped));
+        });
+    }
+
+    // Write the mapped samples as an instrument to a file chosen in the folder of the collection shown, named
+    // after it.
+    fn export_instrument(&mut self) {
+        if self.instrument_data.zones.is_empty() {
+            return;
+        }
+
+        let mut dialog = FileDialog::new().add_filter("SFZ", &["sfz"]);
+        if let Some(collection_path) = self.collection_path() {
+            if let Some(name) = collection_path.file_name() {
+                dialog = dialog.set_file_name(format!("{}.sfz", name.to_string_lossy()));
+            }
+            dialog = dialog.set_directory(collection_path);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+
+        let data = &self.instrument_data;
+        let result = export_instrument(&data.zones, &path, data.with_dspreset);
+        self.instrument_data.finish(result);
+    }
+
+    // Reload the kits of the open library.
+    fn load_kits(&mut self) {
+        let Some(db) = self.database.as_ref().and_then(|database| database.lock().ok()) else {
+            return;
+        };
+
+        match db.get_kits() {
+            Ok(kits) => self.kits_data.set_kits(kits),
+            Err(err) => eprintln!("Failed to load the kits: {:?}", err),
+        }
+    }
+
+    // Change the kits of the open library with `change`, then reload them.
+    fn change_kits(&mut self, change: impl FnOnce(&mut Database) -> Result<(), DatabaseError>) {
+        if let Some(mut db) = self.database.as_ref().and_then(|database| database.lock().ok()) {
+            if let Err(err) = change(&mut *db) {
+                eprintln!("Failed to change the kits: {:?}", err);
+            }
+        }
+        self.load_kits();
+    }
+
+    // Change the kit shown in the kits panel with `change`, given its id, then reload the kits.
+    fn change_kit(
+        &mut self,
+        change: impl FnOnce(&mut Database, KitID) -> Result<(), DatabaseError>,
+    ) {
+        if let Some(kit) = self.kits_data.selected_kit().map(|kit| kit.id) {
+            self.change_kits(|db| change(db, kit));
+        }
+    }
+
+    // Pin the samples of the shown kit to the pads in the order of its slots, clearing the other pads, and show
+    // the pads.
+    fn play_kit_as_pads(&mut self, cx: &mut EventContext) {
+        let Some(kit) = self.kits_data.selected_kit().cloned() else {
+            return;
+        };
+
+        for pad in 0..NUM_PADS {
+            match kit.slots.get(pad).and_then(|slot| slot.sample.clone()) {
+                Some(sample) => {
+                    self.decode_file(cx, sample.collection, sample.name, move |name, audio| {
+                        AppEvent::PadLoaded(pad, name, audio)
+                    });
+                }
+                None => {
+                    self.controller.set_pad(pad, None);
+                    self.pads_data.pads[pad].name.clear();
+                }
+            }
+        }
+        self.config.pads_visible = true;
+    }
+
+    // Export the shown kit into a folder named after it in a chosen folder, converted like the exports of the
+    // export dialog. The folder is indexed at once when it is inside the library.
+    fn export_kit(&mut self, cx: &mut EventContext) {
+        let Some(kit) = self.kits_data.selected_kit() else {
+            return;
+        };
+        let name = kit.name.clone();
+        let slots: Vec<KitExportSlot> = kit
+            .slots
+            .iter()
+            .map(|slot| KitExportSlot {
+                name: slot.name.clone(),
+                path: slot
+                    .sample
+                    .as_ref()
+                    .and_then(|sample| self.audio_file_path(sample.collection, &sample.name)),
+            })
+            .collect();
+        if slots.iter().all(|slot| slot.path.is_none()) {
+            return;
+        }
+
+        let library = self.database.clone().and_then(|database| {
+            let root = database.lock().ok()?.get_root_path().clone();
+            Some((root, database))
+        });
+        let mut dialog = FileDialog::new();
+        if let Some(collection_path) = self.collection_path() {
+            dialog = dialog.set_directory(collection_path);
+        }
+        let Some(directory) = dialog.pick_folder() else {
+            return;
+        };
+
+        let mut settings = self.export_data.settings;
+        settings.effects = self.export_data.apply_effects.then_some(self.effects);
+        self.kits_data.exporting = true;
+        cx.spawn(move |cx| match export_kit(&name, &slots, &directory, &settings) {
+            Ok(KitExport { folder, errors }) => {
+                let folder_name = folder.file_name().unwrap_or_default().to_string_lossy();
+                let errors =
+                    errors.iter().map(|(slot, err)| format!("{}: {}", slot, err)).collect();
+                cx.emit(KitsEvent::Exported(folder_name.into_owned(), errors));
+
+                if let Some((_, database)) = library.filter(|(root, _)| folder.starts_with(root)) {
+                    cx.emit(AppEvent::LibraryChanged(
+                        database,
+                        vec![LibraryChange::Created(folder)],
+                    ));
+                }
+            }
+            Err(err) => cx.emit(KitsEvent::ExportFailed(err.to_string())),
+        });
+    }
+
+    // Detect the transients of the selected file in the background to place the slice markers at them.
+    fn detect_slices(&mut self, cx: &mut EventContext) {
+        let Some(path) = self.selected_file_path.clone() else {
+            return;
+        };
+
+        self.slices_data.detecting = true;
+        cx.spawn(move |cx| {
+            let (transients, num_frames) = detect_file_transients(&path).unwrap_or_default();
+            cx.emit(SlicesEvent::Detected(path, transients, num_frames));
+        });
+    }
+
+    // Return to previewing the selected file after auditioning a slice.
+    fn end_audition(&mut self) {
+        if self.auditioned_slice.take().is_some() {
+            self.apply_trim();
+            self.controller.seek(self.preview_region().0);
+        }
+    }
+
+    // Export the slices of the selected file as numbered one-shots into a chosen folder in the background. The
+    // folder of the collection shown in the table is offered first, and slices exported into the open library
+    // are indexed at once.
+    fn export_slices(&mut self, cx: &mut EventContext) {
+        let Some(path) = self.selected_file_path.clone() else {
+            return;
+        };
+        let sources: Vec<ExportSource> = (0..self.slices_data.count())
+            .filter_map(|index| self.slices_data.slice(index))
+            .filter(|(start, end)| end > start)
+            .map(|(start, end)| ExportSource::region(&path, start, end))
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+
+        let library = self.database.clone().and_then(|database| {
+            let root = database.lock().ok()?.get_root_path().clone();
+            Some((root, database))
+        });
+        let mut dialog = FileDialog::new();
+        if let Some(collection_path) = self.collection_path() {
+            dialog = dialog.set_directory(collection_path);
+        }
+        let Some(directory) = dialog.pick_folder() else {
+            return;
+        };
+
+        let mut settings = self.export_data.settings;
+        settings.effects = self.export_data.apply_effects.then_some(self.effects);
+        self.slices_data.exporting = true;
+        cx.spawn(move |cx| {
+            let mut exported = Vec::new();
+            for result in export_batch(&sources, &directory, SLICE_TEMPLATE, &settings) {
+                match result {
+                    Ok(slice) => exported.push(slice),
+                    Err(err) => {
+                        eprintln!("Failed to export a slice of {}: {}", path.display(), err)
+                    }
+                }
+            }
+            cx.emit(SlicesEvent::Exported(exported.len()));
+
+            if let Some((_, database)) = library.filter(|(root, _)| directory.starts_with(root)) {
+                let changes = exported.into_iter().map(LibraryChange::Created).collect();
+                cx.emit(AppEvent::LibraryChanged(database, changes));
+            }
+        });
+    }
+
+    // Decode the audio file with the given name in the given collection into memory in the background, then
+    // emit the event made by `loaded` from its name and audio.
+    fn decode_file(
+        &self,
+        cx: &mut EventContext,
+        collection_id: CollectionID,
+        name: String,
+        loaded: impl FnOnce(String, Owned<DecodedAudio>) -> AppEvent + Send + 'static,
+    ) {
+        let Some(path) = self.audio_file_path(collection_id, &name) else {
+            return;
+        };
+
+        let collector_handle = self.collector.handle();
+        let ambisonic = self.config.ambisonic;
+        cx.spawn(move |cx| match DecodedAudio::read(&path, ambisonic) {
+            Some(audio) => {
+                let audio = Owned::new(&collector_handle, audio);
+                cx.emit(loaded(name, audio));
+            }
+            None => eprintln!("Failed to decode {}", path.display()),
+        });
+    }
+
+    // Send the mix of the layer at `index` to the player after changing it with `change`.
+    fn update_layer(&mut self, index: usize, change: impl FnOnce(&mut LayerMix)) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            change(&mut layer.mix);
+            self.controller.set_layer_mix(index, layer.mix);
+        }
+    }
+
+    // Drop the meters and the spectrum to silence when playback stops.
+    fn reset_meters(&mut self) {
+        self.meter.reset();
+        self.spectrum.clear();
+    }
+
+    // Reopen the output stream with the chosen output and update the output settings to match.
+    fn open_output(&mut self) {
+        if let Err(err) = self.output.open(&self.config.output) {
+            eprintln!("Failed to open the audio output: {}", err);
+        }
+        self.settings_data.update_output(&self.config.output, self.output.info());
+    }
+
+    // Decode the selected file into memory in the background to play it in reverse.
+    fn load_reversed(&self, cx: &mut EventContext) {
+        if let Some(path) = self.selected_file_path.clone() {
+            let collector_handle = self.collector.handle();
+            let ambisonic = self.config.ambisonic;
+            cx.spawn(move |cx| match DecodedAudio::read(&path, ambisonic) {
+                Some(audio) => {
+                    let audio = Owned::new(&collector_handle, audio);
+                    cx.emit(AppEvent::ReversedLoaded(Reversed(path, audio)));
+                }
+                None => eprintln!("Failed to decode {} for reverse playback", path.display()),
+            });
+        }
+    }
+
+    // Open streams for the rows before and after the selected row in the background, so that stepping to them
+    // starts at once, and drop those for other files apart from the selected `path`.
+    fn prefetch_neighbours(&mut self, cx: &mut EventContext, db: &Database, path: &Path) {
+        let selected = self.samples_data.selected;
+        let neighbours: Vec<PathBuf> =
+            [selected.and_then(|index| index.checked_sub(1)), selected.map(|index| index + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|index| self.samples_data.table_rows.get(index))
+                .filter_map(|row| {
+                    let collection = db.get_collection(row.collection).ok()?;
+                    Some(collection.path().join(&row.name))
+                })
+                .collect();
+
+        self.prefetched
+            .retain(|prefetched, _| prefetched == path || neighbours.contains(prefetched));
+
+        for neighbour in neighbours {
+            if neighbour == path || self.prefetched.contains_key(&neighbour) {
+                continue;
+            }
+
+            self.prefetched.insert(neighbour.clone(), None);
+            let collector_handle = self.collector.handle();
+            cx.spawn(move |cx| {
+                if let Some(audio_file) = open_ready_stream(&neighbour, &collector_handle) {
+                    cx.emit(AppEvent::Prefetched(neighbour, audio_file));
+                }
+            });
+        }
+    }
+
+    // Reload the tags suggested by the analysis of the indexed files.
+    fn refresh_suggestions(&self, cx: &mut EventContext) {
+        if let Some(database) = &self.database {
+            let database = database.clone();
+            cx.spawn(move |cx| {
+                if let Ok(db) = database.lock() {
+                    let suggestions = db.get_tag_suggestions().unwrap_or_default();
+                    cx.emit(TagsEvent::UpdateSuggestions(suggestions));
+                }
+            });
+        }
+    }
+}
+
 impl Model for AppData {
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         // Handle events for all the data models
@@ -215,8 +937,143 @@ impl Model for AppData {
         self.samples_data.event(cx, event);
         self.tags_data.event(cx, event);
         self.settings_data.event(cx, event);
+        self.indexing_data.event(cx, event);
+        self.pads_data.event(cx, event);
+        self.slices_data.event(cx, event);
+        self.export_data.event(cx, event);
+        self.instrument_data.event(cx, event);
+        self.kits_data.event(cx, event);
         self.config.event(cx, event);
 
+        event.map(|settings_event, _| {
+            if let SettingsEvent::ShowAudio = settings_event {
+                self.settings_data.update_output(&self.config.output, self.output.info());
+            }
+        });
+
+        event.map(|pads_event, _| match *pads_event {
+            PadsEvent::Trigger(index) => self.controller.trigger_pad(index, 1.0),
+            PadsEvent::Release(index) => self.controller.release_pad(index),
+            PadsEvent::SetMode(index, _) | PadsEvent::CycleChokeGroup(index) => {
+                if let Some(pad) = self.pads_data.pads.get(index) {
+                    self.controller.set_pad_settings(index, pad.settings);
+                }
+            }
+            PadsEvent::Clear(index) => self.controller.set_pad(index, None),
+            PadsEvent::Select(_) => {}
+        });
+
+        event.map(|export_event, _| {
+            if let ExportEvent::Export = export_event {
+                self.export_selection(cx);
+            }
+        });
+
+        event.map(|instrument_event, _| match instrument_event {
+            InstrumentEvent::ShowDialog => self.map_selection(cx),
+            InstrumentEvent::Export => self.export_instrument(),
+            _ => {}
+        });
+
+        event.map(|kits_event, _| match kits_event {
+            KitsEvent::Create => {
+                let name = format!("Kit {}", self.kits_data.kits.len() + 1);
+                self.change_kits(|db| db.insert_kit(&name, &DEFAULT_KIT_SLOTS).map(|_| ()));
+                self.kits_data.selected = self.kits_data.kits.len().checked_sub(1);
+                self.kits_data.selected_slot = 0;
+            }
+            KitsEvent::Rename(name) => self.change_kit(|db, kit| db.rename_kit(kit, name)),
+            KitsEvent::Remove => self.change_kit(|db, kit| db.remove_kit(kit)),
+            KitsEvent::AddSlot => {
+                let slots = self.kits_data.selected_kit().map_or(0, |kit| kit.slots.len());
+                let name = format!("Slot {}", slots + 1);
+                self.change_kit(|db, kit| db.add_kit_slot(kit, &name));
+            }
+            KitsEvent::RenameSlot(slot, name) => {
+                self.change_kit(|db, kit| db.rename_kit_slot(kit, *slot, name));
+            }
+            KitsEvent::ClearSlot(slot) => {
+                self.change_kit(|db, kit| db.set_kit_slot(kit, *slot, None));
+            }
+            KitsEvent::RemoveSlot(slot) => {
+                self.change_kit(|db, kit| db.remove_kit_slot(kit, *slot));
+            }
+            KitsEvent::SendToKit(audio_file) => {
+                let slot = self.kits_data.selected_slot;
+                self.change_kit(|db, kit| db.set_kit_slot(kit, slot, Some(*audio_file)));
+                self.kits_data.advance_slot(slot);
+            }
+            KitsEvent::Drop(slot) => {
+                if let Some(audio_file) = self.kits_data.dragged.take() {
+                    self.change_kit(|db, kit| db.set_kit_slot(kit, *slot, Some(audio_file)));
+                }
+            }
+            KitsEvent::PlayAsPads => self.play_kit_as_pads(cx),
+            KitsEvent::Export => self.export_kit(cx),
+            _ => {}
+        });
+
+        event.map(|slices_event, _| match slices_event {
+            SlicesEvent::ToggleSlicing => {
+                if self.slices_data.markers.is_some() {
+                    self.detect_slices(cx);
+                } else {
+                    self.end_audition();
+                }
+            }
+            SlicesEvent::Detect => self.detect_slices(cx),
+            SlicesEvent::Detected(path, transients, num_frames) => {
+                // Ignore transients detected in a file which is no longer selected
+                if self.selected_file_path.as_ref() == Some(path) {
+                    self.slices_data.detecting = false;
+                    self.slices_data.num_frames = *num_frames;
+                    if let Some(markers) = &mut self.slices_data.markers {
+                        *markers = transients.clone();
+                    }
+                }
+            }
+            SlicesEvent::Audition(index) => {
+                if let Some((start, end)) = self.slices_data.slice(*index) {
+                    self.controller.set_region(start, end);
+                    self.controller.set_loop(start, end);
+                    self.controller.seek(start);
+                    self.controller.play();
+                    self.auditioned_slice = Some(end);
+                    cx.start_timer(self.timer);
+                }
+            }
+            SlicesEvent::Export => self.export_slices(cx),
+            _ => {}
+        });
+
+        event.map(|tags_event, _| match tags_event {
+            TagsEvent::AcceptSuggestions(tag) => {
+                if let Some(database) = &self.database {
+                    if let Ok(mut db) = database.lock() {
+                        if let Err(err) =
+                            db.accept_suggested_tags(*tag, self.tags_data.min_confidence)
+                        {
+                            eprintln!("Failed to accept suggested tags: {:?}", err);
+                        }
+                    }
+                }
+                self.refresh_suggestions(cx);
+            }
+
+            TagsEvent::RejectSuggestions(tag) => {
+                if let Some(database) = &self.database {
+                    if let Ok(mut db) = database.lock() {
+                        if let Err(err) = db.reject_suggested_tags(*tag) {
+                            eprintln!("Failed to reject suggested tags: {:?}", err);
+                        }
+                    }
+                }
+                self.refresh_suggestions(cx);
+            }
+
+            _ => {}
+        });
+
         event.take(|app_event, _| match app_event {
             AppEvent::ShowAboutDialog => self.show_about_dialog = true,
             AppEvent::HideAboutDialog => self.show_about_dialog = false,
@@ -226,98 +1083,61 @@ impl Model for AppData {
             AppEvent::HideAddCollectionDialog => self.show_add_collection_dialog = false,
 
             AppEvent::ViewCollection(id) => {
-                if let Some(database) = &self.database {
-                    let database = database.clone();
-                    cx.spawn(move |cx| {
-                        let mut audio_files = Vec::with_capacity(500);
-                        if let Ok(db) = database.lock() {
-                            view_collection(id, &db, &mut audio_files);
-                        }
-                        cx.emit(AppEvent::UpdateTable(audio_files));
-                    });
-                }
+                self.samples_data.collection = Some(id);
                 self.samples_data.selected = None;
+                self.samples_data.selection.clear();
+                self.samples_data.similar_to = None;
+                self.refresh_table(cx);
             }
 
+            AppEvent::RefreshTable => self.refresh_table(cx),
+
             AppEvent::UpdateTable(audio_files) => {
+                // Keep the selected file selected if it is still in the table
+                let selected = self
+                    .samples_data
+                    .selected
+                    .and_then(|index| self.samples_data.table_rows.get(index))
+                    .map(|audio_file| audio_file.id);
+
+                self.samples_data.selected =
+                    selected.and_then(|id| audio_files.iter().position(|af| af.id == id));
+                self.samples_data.selection = self.samples_data.selected.into_iter().collect();
                 self.samples_data.table_rows = audio_files;
             }
 
             AppEvent::LoadSample(path) => {
-                let collector_handle = self.collector.handle();
-                let path2 = path.clone();
-                cx.spawn(move |cx| {
-                    let opts = ReadStreamOptions {
-                        // The number of prefetch blocks in a cache block. This will cause a cache to be
-                        // used whenever the stream is seeked to a frame in the range:
-                        //
-                        // `[cache_start, cache_start + (num_cache_blocks * block_size))`
-                        //
-                        // If this is 0, then the cache is only used when seeked to exactly `cache_start`.
-                        num_cache_blocks: 20,
-
-                        // The maximum number of caches that can be active in this stream. Keep in mind each
-                        // cache uses some memory (but memory is only allocated when the cache is created).
-                        //
-                        // The default is `1`.
-                        num_caches: 2,
-                        ..Default::default()
-                    };
-
-                    // This is how to calculate the total size of a cache block.
-                    let cache_size = opts.num_cache_blocks * SymphoniaDecoder::DEFAULT_BLOCK_SIZE;
-
-                    // Open the read stream.
-                    let mut read_stream =
-                        ReadDiskStream::<SymphoniaDecoder>::new(path, 0, opts).unwrap();
-
-                    // Cache the start of the file into cache with index `0`.
-                    let _ = read_stream.cache(0, 0);
+                self.selected_file_path = Some(path.clone());
 
-                    // Tell the stream to seek to the beginning of file. This will also alert the stream to the existence
-                    // of the cache with index `0`.
-                    read_stream.seek(0, Default::default()).unwrap();
-
-                    // Wait until the buffer is filled before sending it to the process thread.
-                    read_stream.block_until_ready().unwrap();
-                    let audio_file = Owned::new(&collector_handle, read_stream);
-                    cx.emit(AppEvent::SampleLoaded(Testy(audio_file)));
-                });
+                // Use the stream prefetched for the file, or open it in the background
+                match self.prefetched.remove(&path) {
+                    Some(Some(audio_file)) => {
+                        cx.emit(AppEvent::SampleLoaded(path.clone(), audio_file))
+                    }
+                    _ => {
+                        let collector_handle = self.collector.handle();
+                        let path = path.clone();
+                        cx.spawn(move |cx| match open_ready_stream(&path, &collector_handle) {
+                            Some(audio_file) => cx.emit(AppEvent::SampleLoaded(path, audio_file)),
+                            None => eprintln!("Failed to open {}", path.display()),
+                        });
+                    }
+                }
 
                 self.waveform = Some(Arc::new(Waveform::new()));
+                self.wave_selection = None;
+                self.auditioned_slice = None;
+                if let Some(markers) = &mut self.slices_data.markers {
+                    markers.clear();
+                    self.slices_data.exported = None;
+                    self.detect_slices(cx);
+                }
 
                 cx.spawn(move |cx| {
-                    let opts = ReadStreamOptions {
-                        // The number of prefetch blocks in a cache block. This will cause a cache to be
-                        // used whenever the stream is seeked to a frame in the range:
-                        //
-                        // `[cache_start, cache_start + (num_cache_blocks * block_size))`
-                        //
-                        // If this is 0, then the cache is only used when seeked to exactly `cache_start`.
-                        num_cache_blocks: 20,
-
-                        // The maximum number of caches that can be active in this stream. Keep in mind each
-                        // cache uses some memory (but memory is only allocated when the cache is created).
-                        //
-                        // The default is `1`.
-                        num_caches: 2,
-                        ..Default::default()
+                    let Some(mut read_stream) = open_read_stream(&path) else {
+                        return;
                     };
 
-                    // This is how to calculate the total size of a cache block.
-                    let cache_size = opts.num_cache_blocks * SymphoniaDecoder::DEFAULT_BLOCK_SIZE;
-
-                    // Open the read stream.
-                    let mut read_stream =
-                        ReadDiskStream::<SymphoniaDecoder>::new(path2, 0, opts).unwrap();
-
-                    // Cache the start of the file into cache with index `0`.
-                    let _ = read_stream.cache(0, 0);
-
-                    // Tell the stream to seek to the beginning of file. This will also alert the stream to the existence
-                    // of the cache with index `0`.
-                    read_stream.seek(0, Default::default()).unwrap();
-
                     let mut pos = 0usize;
 
                     while pos < read_stream.info().num_frames {
@@ -336,9 +1156,22 @@ impl Model for AppData {
                 });
             }
 
-            AppEvent::SampleLoaded(audio_file) => {
+            AppEvent::SampleLoaded(path, audio_file) => {
+                // Ignore a file which finished loading after another was selected
+                if self.selected_file_path.as_ref() != Some(&path) {
+                    return;
+                }
+
+                self.controller.set_crossfade(self.config.crossfade);
                 self.controller.load_file(audio_file.0);
-                self.controller.seek(0);
+                self.controller.seek(self.preview_region().0);
+                self.apply_trim();
+                self.apply_playback_rate();
+                self.apply_effects();
+                self.apply_channels();
+                if self.effects.reverse {
+                    self.load_reversed(cx);
+                }
 
                 if self.should_autoplay {
                     self.controller.play();
@@ -361,7 +1194,9 @@ impl Model for AppData {
             AppEvent::Play => {
                 if self.controller.play_state == PlayerState::Playing {
                     self.controller.stop();
+                    self.end_audition();
                     cx.stop_timer(self.timer);
+                    self.reset_meters();
                 } else {
                     self.controller.play();
                     cx.start_timer(self.timer);
@@ -371,11 +1206,14 @@ impl Model for AppData {
             AppEvent::Pause => {
                 self.controller.pause();
                 cx.stop_timer(self.timer);
+                self.reset_meters();
             }
 
             AppEvent::Stop => {
                 self.controller.stop();
+                self.end_audition();
                 cx.stop_timer(self.timer);
+                self.reset_meters();
             }
 
             AppEvent::ShowOpenCollectionDialog => {
@@ -384,47 +1222,334 @@ impl Model for AppData {
                 }
             }
             AppEvent::OpenCollection(path) => {
-                cx.spawn(|cx| {
-                    if let Ok(db) = Database::from_directory(path) {
-                        let collections = db.get_all_collections().unwrap();
-                        let audio_files = db.get_all_audio_files().unwrap();
-                        let mut tags = db.get_all_tags().unwrap();
-                        tags.sort_by_cached_key(|tag| tag.name.clone());
-                        let root =
-                            collections.iter().find(|v| v.parent_collection().is_none()).unwrap();
-
-                        let root =
-                            collections_to_directories(&collections, &audio_files, root.clone());
-
-                        cx.emit(AppEvent::CollectionOpened(db, root, tags));
+                let job = self.indexing_data.start(&path);
+                let filter = self.config.filter_for(&path);
+                cx.spawn(move |cx| {
+                    let database =
+                        Database::from_directory(path.clone()).and_then(|mut database| {
+                            database.set_filter(&filter)?;
+                            Ok(database)
+                        });
+
+                    let database = match database {
+                        Ok(database) => database,
+                        Err(err) => {
+                            let report = IndexReport {
+                                errors: vec![IndexError::new(&path, format!("{:?}", err))],
+                                ..Default::default()
+                            };
+                            cx.emit(IndexingEvent::Finished(path, report));
+                            return;
+                        }
+                    };
+
+                    let mut tags = database.get_all_tags().unwrap_or_default();
+                    tags.sort_by_cached_key(|tag| tag.name.clone());
+
+                    let database = Arc::new(Mutex::new(database));
+                    cx.emit(AppEvent::CollectionOpened(path.clone(), database.clone(), tags));
+
+                    // The browser is filled in as directories are added, at most every
+                    // `LIBRARY_UPDATE_INTERVAL`
+                    let mut shown_directories = 0;
+                    let mut last_update: Option<Instant> = None;
+                    let result = index_library(&database, &job, |job| {
+                        cx.emit(IndexingEvent::Progress(path.clone(), job.scanned(), job.total()));
+
+                        if job.directories() != shown_directories
+                            && last_update
+                                .is_none_or(|last| last.elapsed() >= LIBRARY_UPDATE_INTERVAL)
+                        {
+                            shown_directories = job.directories();
+                            last_update = Some(Instant::now());
+                            if let Some(root) =
+                                database.lock().ok().and_then(|db| library_directory(&db))
+                            {
+                                cx.emit(AppEvent::LibraryUpdated(root));
+                            }
+                        }
+                    });
+
+                    // The whole library is shown once the job has finished
+                    if let Some(root) = database.lock().ok().and_then(|db| library_directory(&db)) {
+                        cx.emit(AppEvent::LibraryUpdated(root));
                     }
+
+                    let report = result.unwrap_or_else(|err| IndexReport {
+                        errors: vec![IndexError::new(&path, format!("{:?}", err))],
+                        ..Default::default()
+                    });
+                    cx.emit(IndexingEvent::Finished(path, report));
                 });
             }
 
-            AppEvent::CollectionOpened(database, root, tags) => {
-                self.database = Some(Arc::new(Mutex::new(database)));
-                self.config.libraries.insert(root.path.clone());
-                self.config.recents.push(root.path.clone());
-                self.browser_data.libraries.push(root);
+            AppEvent::CollectionOpened(path, database, tags) => {
+                let watcher = LibraryWatcher::new();
+                let running = watcher.running();
+                let watch_path = path.clone();
+                let watched = database.clone();
+                cx.spawn(move |cx| {
+                    let result = watch_library(
+                        &watch_path,
+                        &running,
+                        |changes| cx.emit(AppEvent::LibraryChanged(watched.clone(), changes)),
+                        |error| cx.emit(IndexingEvent::Error(error)),
+                    );
+
+                    if let Err(err) = result {
+                        cx.emit(IndexingEvent::Error(IndexError::new(
+                            &watch_path,
+                            format!("{:?}", err),
+                        )));
+                    }
+                });
+                // The watchers of the other open libraries keep running. Reopening a library replaces its
+                // watcher and indexes it again, which picks up the changes made while it was not watched.
+                self.watchers.insert(path.clone(), watcher);
+
+                self.database = Some(database);
+                self.config.libraries.insert(path.clone());
+                self.config.recents.push(path);
                 self.tags_data.tags = tags;
+                self.load_kits();
+                self.refresh_suggestions(cx);
+            }
+
+            AppEvent::CloseLibrary => {
+                let closed = self.database.take();
+                if let Some(db) = closed.as_ref().and_then(|database| database.lock().ok()) {
+                    let path = db.get_root_path();
+                    // Dropping the watcher stops it
+                    self.watchers.remove(path);
+                    self.config.libraries.remove(path);
+                    self.browser_data.libraries.retain(|library| library.path != *path);
+                }
+
+                self.samples_data.collection = None;
+                self.samples_data.selected = None;
+                self.samples_data.selection.clear();
+                self.samples_data.similar_to = None;
+                self.samples_data.table_rows.clear();
+                self.kits_data.set_kits(Vec::new());
+            }
+
+            AppEvent::LibraryChanged(database, changes) => {
+                let database = database.clone();
+                cx.spawn(move |cx| {
+                    let Some(path) = database.lock().ok().map(|db| db.get_root_path().clone())
+                    else {
+                        return;
+                    };
+
+                    // The changed files are probed without holding the lock
+                    if let Err(err) = apply_library_changes(&database, &changes) {
+                        cx.emit(IndexingEvent::Error(IndexError::new(&path, format!("{:?}", err))));
+                    }
+
+                    if let Some(root) = database.lock().ok().and_then(|db| library_directory(&db)) {
+                        cx.emit(AppEvent::LibraryUpdated(root));
+                    }
+                });
+            }
+
+            AppEvent::LibraryUpdated(root) => {
+                self.browser_data.update_library(root);
+                self.refresh_table(cx);
+                self.refresh_suggestions(cx);
             }
 
             AppEvent::SelectSample(collection_id, name) => {
-                if let Some(database) = &self.database {
-                    //let database = database.clone();
+                if let Some(database) = self.database.clone() {
                     if let Ok(db) = database.lock() {
                         if let Ok(collection) = db.get_collection(collection_id) {
                             let path: PathBuf = collection.path().join(&name);
 
+                            if let Ok(audio_file) = db.get_audio_file_by_name(collection_id, &name)
+                            {
+                                self.selected_file_sample_rate = audio_file.sample_rate;
+                                self.selected_file_bit_depth = audio_file.bit_depth;
+                                self.selected_file_num_channels = audio_file.num_channels;
+                                self.selected_file_sampler = audio_file.sampler.clone();
+                                self.selected_file_pitch = audio_file.pitch;
+                                self.selected_file_tuning_offset = audio_file.tuning_offset;
+                                self.selected_file_bpm =
+                                    audio_file.bpm.or(audio_file.sampler.tempo);
+                                self.selected_file_audible =
+                                    audio_file.audible_start.map(|start| {
+                                        (
+                                            start as usize,
+                                            audio_file.audible_end.map(|end| end as usize),
+                                        )
+                                    });
+                                self.selected_metadata = db
+                                    .get_metadata_for_audio_file(audio_file.id)
+                                    .unwrap_or_default();
+                            }
+
                             self.selected_file_name = name;
+                            self.prefetch_neighbours(cx, &db, &path);
                             cx.emit(AppEvent::LoadSample(path));
                         }
                     }
                 }
             }
-            AppEvent::Tick => {}
+            AppEvent::Prefetched(path, audio_file) => {
+                // Keep the stream only if the file is still next to the selected one
+                if let Some(prefetched @ None) = self.prefetched.get_mut(&path) {
+                    *prefetched = Some(audio_file);
+                }
+            }
+            AppEvent::ApplyPlaybackRate => self.apply_playback_rate(),
+            AppEvent::ApplyMetronome => self.apply_metronome(),
+            AppEvent::ApplyTrim => self.apply_trim(),
+            AppEvent::Tick => {
+                let (peak, rms) = self.controller.levels();
+                self.meter.update(peak, rms);
+
+                // An auditioned slice stops at its end unless looping, or played in reverse towards its start
+                if let Some(end) = self.auditioned_slice {
+                    let looping = self.controller.should_loop.load(Ordering::SeqCst);
+                    let forwards = !looping && !self.effects.reverse;
+                    if forwards && self.controller.playhead_position() >= end {
+                        cx.emit(AppEvent::Stop);
+                    }
+                }
+
+                if self.config.show_spectrum {
+                    if let Some(info) = self.output.info() {
+                        self.spectrum = self.controller.spectrum(info.sample_rate as f32);
+                    }
+                }
+            }
             AppEvent::ToggleLooping => self.controller.toggle_looping(),
             AppEvent::ToggleAutoplay => self.should_autoplay = !self.should_autoplay,
+            AppEvent::SetSpeed(speed) => {
+                self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+                self.apply_playback_rate();
+            }
+            AppEvent::SetTranspose(semitones) => {
+                self.transpose = semitones.clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+                self.apply_playback_rate();
+            }
+            AppEvent::ToggleReverse => {
+                self.effects.reverse = !self.effects.reverse;
+                if self.effects.reverse {
+                    self.load_reversed(cx);
+                } else {
+                    self.controller.set_reversed(None);
+                }
+            }
+            AppEvent::ReversedLoaded(Reversed(path, audio)) => {
+                // Ignore audio decoded for a file which is no longer selected
+                if self.effects.reverse && self.selected_file_path.as_ref() == Some(&path) {
+                    self.controller.set_reversed(Some(audio));
+                }
+            }
+            AppEvent::SetFilter(filter) => {
+                self.effects.filter = filter;
+                self.apply_effects();
+            }
+            AppEvent::SetFilterCutoff(cutoff) => {
+                self.effects.cutoff = cutoff.clamp(MIN_CUTOFF, MAX_CUTOFF);
+                self.apply_effects();
+            }
+            AppEvent::SetFadeIn(fade_in) => {
+                self.effects.fade_in = fade_in.clamp(0.0, MAX_FADE);
+                self.apply_effects();
+            }
+            AppEvent::SetFadeOut(fade_out) => {
+                self.effects.fade_out = fade_out.clamp(0.0, MAX_FADE);
+                self.apply_effects();
+            }
+            AppEvent::SetChannelSolo(solo) => {
+                self.channel_solo = solo;
+                self.apply_channels();
+            }
+            AppEvent::ApplySpectrum => {
+                self.controller.set_spectrum_enabled(self.config.show_spectrum);
+                self.spectrum.clear();
+            }
+            AppEvent::PinLayer(collection_id, name) => {
+                if self.layers.len() < MAX_LAYERS {
+                    self.decode_file(cx, collection_id, name, AppEvent::LayerLoaded);
+                }
+            }
+            AppEvent::LayerLoaded(name, audio) => {
+                if self.layers.len() < MAX_LAYERS {
+                    let mix = LayerMix::default();
+                    self.controller.add_layer(audio, mix);
+                    self.layers.push(PinnedLayer { name, mix });
+                }
+            }
+            AppEvent::RemoveLayer(index) => {
+                if index < self.layers.len() {
+                    self.layers.remove(index);
+                    self.controller.remove_layer(index);
+                }
+            }
+            AppEvent::SetLayerGain(index, gain) => {
+                self.update_layer(index, |mix| {
+                    mix.gain = gain.clamp(MIN_LAYER_GAIN, MAX_LAYER_GAIN)
+                });
+            }
+            AppEvent::SetLayerPan(index, pan) => {
+                self.update_layer(index, |mix| mix.pan = pan.clamp(-1.0, 1.0));
+            }
+            AppEvent::ToggleLayerMute(index) => self.update_layer(index, |mix| mix.muted ^= true),
+            AppEvent::PinPad(collection_id, name) => {
+                let pad = self.pads_data.selected;
+                self.decode_file(cx, collection_id, name, move |name, audio| {
+                    AppEvent::PadLoaded(pad, name, audio)
+                });
+            }
+            AppEvent::PadLoaded(pad, name, audio) => {
+                self.controller.set_pad(pad, Some(audio));
+                self.pads_data.pin(pad, name);
+            }
+            AppEvent::SetWaveSelection(selection) => self.wave_selection = selection,
+            AppEvent::OpenOutput => {
+                self.open_output();
+                cx.start_timer(self.output_timer);
+            }
+            AppEvent::CheckOutput => {
+                if self.output.needs_reopen(&self.config.output) {
+                    self.open_output();
+                }
+            }
+            AppEvent::SelectAudioDriver(index) => {
+                // Devices, sample rates and buffer sizes differ between hosts
+                let host = self.settings_data.audio_driver.get(index).cloned();
+                self.config.output = OutputSettings { host, ..Default::default() };
+                self.open_output();
+            }
+            AppEvent::SelectOutputDevice(index) => {
+                self.config.output.device = match index {
+                    0 => None,
+                    _ => self.settings_data.output_device.get(index).cloned(),
+                };
+                self.open_output();
+            }
+            AppEvent::SelectSampleRate(index) => {
+                self.config.output.sample_rate = index
+                    .checked_sub(1)
+                    .and_then(|i| self.settings_data.sample_rates.get(i))
+                    .copied();
+                self.open_output();
+            }
+            AppEvent::SelectBufferSize(index) => {
+                self.config.output.buffer_size = index
+                    .checked_sub(1)
+                    .and_then(|i| self.settings_data.buffer_sizes.get(i))
+                    .copied();
+                self.open_output();
+            }
+            AppEvent::ApplyChannels => {
+                self.apply_channels();
+                // The reversed audio is downmixed when decoded
+                if self.effects.reverse {
+                    self.load_reversed(cx);
+                }
+            }
         });
 
         event.map(|window_event, _| match window_event {
@@ -445,11 +1570,76 @@ impl Model for AppData {
                 self.config.save();
             }
 
+            // The pads are played from the keyboard while they are shown, ignoring repeats of held keys
+            WindowEvent::KeyDown(code, _) if self.config.pads_visible => {
+                if let Some(index) = PadsData::pad_for_key(*code) {
+                    if !self.pads_data.pads[index].held {
+                        cx.emit(PadsEvent::Trigger(index));
+                    }
+                }
+            }
+            WindowEvent::KeyUp(code, _) if self.config.pads_visible => {
+                if let Some(index) = PadsData::pad_for_key(*code) {
+                    if self.pads_data.pads[index].held {
+                        cx.emit(PadsEvent::Release(index));
+                    }
+                }
+            }
+
             _ => {}
         })
     }
 }
 
+/// Open a disk stream of the file at `path` with its start cached.
+fn open_read_stream(path: &Path) -> Option<ReadDiskStream<SymphoniaDecoder>> {
+    let opts = ReadStreamOptions {
+        // The number of prefetch blocks in a cache block. This will cause a cache to be
+        // used whenever the stream is seeked to a frame in the range:
+        //
+        // `[cache_start, cache_start + (num_cache_blocks * block_size))`
+        //
+        // If this is 0, then the cache is only used when seeked to exactly `cache_start`.
+        num_cache_blocks: 20,
+
+        // The maximum number of caches that can be active in this stream. Keep in mind each
+        // cache uses some memory (but memory is only allocated when the cache is created).
+        //
+        // The default is `1`.
+        num_caches: 2,
+        ..Default::default()
+    };
+
+    // Open the read stream.
+    let mut read_stream = ReadDiskStream::<SymphoniaDecoder>::new(path, 0, opts).ok()?;
+
+    // Cache the start of the file into cache with index `0`.
+    let _ = read_stream.cache(0, 0);
+
+    // Tell the stream to seek to the beginning of file. This will also alert the stream to the existence
+    // of the cache with index `0`.
+    read_stream.seek(0, Default::default()).ok()?;
+
+    Some(read_stream)
+}
+
+/// Open a disk stream of the file at `path` and wait until its buffer is filled, so that it is ready to be sent
+/// to the player.
+fn open_ready_stream(path: &Path, collector_handle: &basedrop::Handle) -> Option<Testy> {
+    let mut read_stream = open_read_stream(path)?;
+    read_stream.block_until_ready().ok()?;
+    Some(Testy(Owned::new(collector_handle, read_stream)))
+}
+
+/// Build the directory tree of the library indexed in a database.
+fn library_directory(db: &Database) -> Option<Directory> {
+    let collections = db.get_all_collections().ok()?;
+    let audio_files = db.get_all_audio_files().ok()?;
+    let root = collections.iter().find(|v| v.parent_collection().is_none())?;
+
+    Some(collections_to_directories(&collections, &audio_files, root.clone()))
+}
+
 /// Recursively convert a list of collections into a tree of directories.
 fn collections_to_directories(
     collections: &Vec<Collection>,
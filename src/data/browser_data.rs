@@ -31,6 +31,27 @@ impl BrowserData {
     pub fn new() -> Self {
         Self { ..Default::default() }
     }
+
+    /// Add a library, or replace it with a freshly indexed copy keeping expanded directories open.
+    pub fn update_library(&mut self, mut library: Directory) {
+        if let Some(old) = self.libraries.iter_mut().find(|old| old.path == library.path) {
+            let mut open = HashSet::new();
+            collect_open(old, &mut open);
+            restore_open(&mut library, &open);
+            *old = library;
+        } else {
+            self.libraries.push(library);
+        }
+
+        if !self.search_text.is_empty() && !self.libraries.is_empty() {
+            search(
+                &mut self.libraries[0],
+                &self.search_text,
+                self.filter_search,
+                !self.search_case_sensitive,
+            );
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -211,6 +232,24 @@ fn toggle_open(root: &mut Directory, path: &PathBuf) {
     }
 }
 
+fn collect_open(root: &Directory, open: &mut HashSet<PathBuf>) {
+    if root.is_open {
+        open.insert(root.path.clone());
+    }
+
+    for child in root.children.iter() {
+        collect_open(child, open);
+    }
+}
+
+fn restore_open(root: &mut Directory, open: &HashSet<PathBuf>) {
+    root.is_open = open.contains(&root.path);
+
+    for child in root.children.iter_mut() {
+        restore_open(child, open);
+    }
+}
+
 fn set_expand_directory(root: &mut Directory, path: &PathBuf, expand: bool) {
     if root.path == *path {
         root.is_open = expand;
@@ -1,21 +1,35 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use ron::de::from_reader;
 use serde::{Deserialize, Serialize};
 use vizia::prelude::*;
 
 use super::AppEvent;
+use crate::database::prelude::{AudioFormat, LibraryFilter};
+use crate::engine::{OutputSettings, MAX_CROSSFADE};
 
 #[derive(Default, Debug, Clone, PartialEq, Data, Serialize, Deserialize)]
 pub enum SidebarView {
     #[default]
     Browser,
     Tags,
+    Kits,
 }
 
 // The width at which the sidebar is hidden
 pub const SIDEBAR_HIDDEN_WIDTH: f32 = 50.0;
 
+// The range of the project tempo in BPM
+pub const MIN_PROJECT_TEMPO: f32 = 20.0;
+pub const MAX_PROJECT_TEMPO: f32 = 300.0;
+
+// The most beats in a bar, and the note values of a beat in the time signature
+pub const MAX_BEATS_PER_BAR: u8 = 16;
+pub const BEAT_UNITS: [u8; 4] = [2, 4, 8, 16];
+
 // The configuration data of the application, saved to disk and loaded on startup
 #[derive(Lens, Debug, Default, Serialize, Deserialize)]
 pub struct Config {
@@ -37,10 +51,81 @@ pub struct Config {
     pub show_sidebar: bool,
     // Whether the waveview panel is visible
     pub waveview_visible: bool,
+    // Whether the inspector panel is visible
+    #[serde(default)]
+    pub inspector_visible: bool,
+    // Whether the pads are visible, and played from the computer keyboard
+    #[serde(default)]
+    pub pads_visible: bool,
 
     pub libraries: HashSet<PathBuf>,
 
     pub recents: Vec<PathBuf>,
+
+    // The files indexed in libraries without their own filter
+    #[serde(default)]
+    pub library_filter: LibraryFilter,
+    // The files indexed in specific libraries
+    #[serde(default)]
+    pub library_filters: HashMap<PathBuf, LibraryFilter>,
+
+    // The pitch class to which tonal samples are retuned when auditioned, `None` to play them as recorded
+    #[serde(default)]
+    pub tuning_root: Option<u8>,
+    // Whether previews start at the audible start of a file, skipping its leading silence
+    #[serde(default)]
+    pub trim_preview: bool,
+
+    // The tempo of the project in BPM, to which loops with a known tempo are stretched when synced
+    #[serde(default = "default_project_tempo")]
+    pub project_tempo: f32,
+    // Whether loops are previewed at the project tempo
+    #[serde(default)]
+    pub tempo_sync: bool,
+    // Whether loops follow the project tempo by resampling, which also shifts their pitch, rather than by
+    // time-stretching
+    #[serde(default)]
+    pub sync_resample: bool,
+
+    // The time signature of the metronome, which keeps the project tempo
+    #[serde(default = "default_time_signature")]
+    pub beats_per_bar: u8,
+    #[serde(default = "default_time_signature")]
+    pub beat_unit: u8,
+    // Whether the metronome clicks on each beat
+    #[serde(default)]
+    pub metronome: bool,
+    // Whether previews start on the next bar of the metronome, following the project tempo
+    #[serde(default)]
+    pub quantize_start: bool,
+
+    // Whether four channel files are first order ambisonics in the AmbiX format rather than quad
+    #[serde(default)]
+    pub ambisonic: bool,
+
+    // Whether the spectrum of the output is shown next to the level meters
+    #[serde(default)]
+    pub show_spectrum: bool,
+
+    // The length in seconds of the crossfade when stepping to another file during playback, 0 to cut
+    #[serde(default = "default_crossfade")]
+    pub crossfade: f32,
+
+    // The host, device, sample rate and buffer size of the audio output
+    #[serde(default)]
+    pub output: OutputSettings,
+}
+
+fn default_project_tempo() -> f32 {
+    120.0
+}
+
+fn default_time_signature() -> u8 {
+    4
+}
+
+fn default_crossfade() -> f32 {
+    0.02
 }
 
 impl Config {
@@ -53,6 +138,11 @@ impl Config {
 
             sidebar_view: SidebarView::Browser,
             waveview_visible: true,
+            inspector_visible: true,
+            project_tempo: default_project_tempo(),
+            beats_per_bar: default_time_signature(),
+            beat_unit: default_time_signature(),
+            crossfade: default_crossfade(),
 
             ..Default::default()
         }
@@ -66,6 +156,15 @@ impl Config {
                 cx.emit(AppEvent::OpenCollection(path.clone()));
             }
         }
+
+        cx.emit(AppEvent::OpenOutput);
+        cx.emit(AppEvent::ApplySpectrum);
+        cx.emit(AppEvent::ApplyMetronome);
+    }
+
+    /// The filter deciding which files of the library at `path` are indexed.
+    pub fn filter_for(&self, path: &Path) -> LibraryFilter {
+        self.library_filters.get(path).unwrap_or(&self.library_filter).clone()
     }
 
     pub fn save(&self) {
@@ -87,6 +186,34 @@ pub enum ConfigEvent {
     ShowSidebarView(SidebarView),
     // Toggle the visibility of the waveview panel
     ToggleWaveviewVisibility,
+    // Toggle the visibility of the inspector panel
+    ToggleInspectorVisibility,
+    // Toggle the visibility of the pads
+    TogglePadsVisibility,
+    // Toggle whether files of the given format are indexed
+    ToggleFormat(AudioFormat),
+    // Set the pitch class to which tonal samples are retuned, or play them as recorded
+    SetTuningRoot(Option<u8>),
+    // Toggle whether previews skip leading silence and noise tails
+    ToggleTrimPreview,
+    // Set the tempo of the project in BPM
+    SetProjectTempo(f32),
+    // Toggle whether loops are time-stretched to the project tempo
+    ToggleTempoSync,
+    // Toggle whether loops follow the project tempo by resampling rather than time-stretching
+    ToggleSyncResample,
+    // Set the number of beats in a bar and the note value of a beat
+    SetTimeSignature(u8, u8),
+    // Toggle the click of the metronome
+    ToggleMetronome,
+    // Toggle whether previews start on the next bar
+    ToggleQuantizeStart,
+    // Toggle whether four channel files are previewed as ambisonics
+    ToggleAmbisonic,
+    // Toggle the visibility of the spectrum analyzer
+    ToggleSpectrum,
+    // Set the length of the crossfade between files in seconds
+    SetCrossfade(f32),
 }
 
 impl Model for Config {
@@ -114,6 +241,63 @@ impl Model for Config {
                 self.sidebar_view = view;
             }
             ConfigEvent::ToggleWaveviewVisibility => self.waveview_visible ^= true,
+            ConfigEvent::ToggleInspectorVisibility => self.inspector_visible ^= true,
+            ConfigEvent::TogglePadsVisibility => self.pads_visible ^= true,
+            ConfigEvent::ToggleFormat(format) => {
+                if !self.library_filter.formats.remove(&format) {
+                    self.library_filter.formats.insert(format);
+                }
+            }
+            ConfigEvent::SetTuningRoot(root) => {
+                self.tuning_root = root;
+                cx.emit(AppEvent::ApplyPlaybackRate);
+            }
+            ConfigEvent::ToggleTrimPreview => {
+                self.trim_preview ^= true;
+                cx.emit(AppEvent::ApplyTrim);
+            }
+            ConfigEvent::SetProjectTempo(tempo) => {
+                self.project_tempo = tempo.clamp(MIN_PROJECT_TEMPO, MAX_PROJECT_TEMPO);
+                cx.emit(AppEvent::ApplyPlaybackRate);
+                cx.emit(AppEvent::ApplyMetronome);
+            }
+            ConfigEvent::ToggleTempoSync => {
+                self.tempo_sync ^= true;
+                cx.emit(AppEvent::ApplyPlaybackRate);
+            }
+            ConfigEvent::ToggleSyncResample => {
+                self.sync_resample ^= true;
+                cx.emit(AppEvent::ApplyPlaybackRate);
+            }
+            ConfigEvent::SetTimeSignature(beats_per_bar, beat_unit) => {
+                if (1..=MAX_BEATS_PER_BAR).contains(&beats_per_bar)
+                    && BEAT_UNITS.contains(&beat_unit)
+                {
+                    self.beats_per_bar = beats_per_bar;
+                    self.beat_unit = beat_unit;
+                    cx.emit(AppEvent::ApplyMetronome);
+                }
+            }
+            ConfigEvent::ToggleMetronome => {
+                self.metronome ^= true;
+                cx.emit(AppEvent::ApplyMetronome);
+            }
+            ConfigEvent::ToggleQuantizeStart => {
+                self.quantize_start ^= true;
+                cx.emit(AppEvent::ApplyMetronome);
+                cx.emit(AppEvent::ApplyPlaybackRate);
+            }
+            ConfigEvent::ToggleAmbisonic => {
+                self.ambisonic ^= true;
+                cx.emit(AppEvent::ApplyChannels);
+            }
+            ConfigEvent::SetCrossfade(crossfade) => {
+                self.crossfade = crossfade.clamp(0.0, MAX_CROSSFADE);
+            }
+            ConfigEvent::ToggleSpectrum => {
+                self.show_spectrum ^= true;
+                cx.emit(AppEvent::ApplySpectrum);
+            }
         })
     }
 }
@@ -0,0 +1,147 @@
+//! GUI state for the export dialog
+
+use vizia::prelude::*;
+
+use crate::engine::{BitDepth, ExportSettings, EXPORT_SAMPLE_RATES};
+
+// The entry of the sample rate pick list which keeps the sample rate of each file
+const ORIGINAL_ENTRY: &str = "Original";
+
+// The bit depths in the order of the bit depth pick list
+const BIT_DEPTHS: [BitDepth; 3] = [BitDepth::Int16, BitDepth::Int24, BitDepth::Float32];
+
+// The peak level in dBFS to which files are normalized until another is chosen
+const DEFAULT_NORMALIZE_LEVEL: f32 = -1.0;
+
+// The data model for the export dialog
+#[derive(Debug, Lens, Clone)]
+pub struct ExportData {
+    // Whether the export dialog is shown
+    pub show_dialog: bool,
+    // The conversion of the exported files, whose effects are set from the preview when exporting
+    pub settings: ExportSettings,
+    // Whether the preview effects are applied to the exported files
+    pub apply_effects: bool,
+    // The peak level of normalized files in dBFS, kept while normalization is off
+    pub normalize_level: f32,
+    // The template of the names of the exported files
+    pub template: String,
+    // The entries of the sample rate and bit depth pick lists, the sample rates starting with the original entry
+    pub sample_rate_entries: Vec<String>,
+    pub bit_depth_entries: Vec<String>,
+    // Whether an export is running
+    pub exporting: bool,
+    // The number of files written by the last export and the errors of those which failed, `None` before
+    pub exported: Option<usize>,
+    pub errors: Vec<String>,
+}
+
+impl Default for ExportData {
+    fn default() -> Self {
+        Self {
+            show_dialog: false,
+            settings: ExportSettings::default(),
+            apply_effects: false,
+            normalize_level: DEFAULT_NORMALIZE_LEVEL,
+            template: String::from("{name}"),
+            sample_rate_entries: std::iter::once(ORIGINAL_ENTRY.to_string())
+                .chain(EXPORT_SAMPLE_RATES.iter().map(|rate| rate.to_string()))
+                .collect(),
+            bit_depth_entries: vec![
+                String::from("16-bit"),
+                String::from("24-bit"),
+                String::from("32-bit float"),
+            ],
+            exporting: false,
+            exported: None,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl ExportData {
+    /// The index of the chosen sample rate in the sample rate pick list.
+    pub fn selected_sample_rate(&self) -> usize {
+        self.settings
+            .sample_rate
+            .and_then(|rate| EXPORT_SAMPLE_RATES.iter().position(|r| *r == rate))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// The index of the chosen bit depth in the bit depth pick list.
+    pub fn selected_bit_depth(&self) -> usize {
+        BIT_DEPTHS.iter().position(|depth| *depth == self.settings.bit_depth).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportEvent {
+    // Show the export dialog
+    ShowDialog,
+    // Hide the export dialog
+    HideDialog,
+    // Choose the sample rate or bit depth by their index in the pick lists
+    SelectSampleRate(usize),
+    SelectBitDepth(usize),
+    // Toggle dithering integer samples
+    ToggleDither,
+    // Toggle normalizing the files, and set the peak level in dBFS to which they are normalized
+    ToggleNormalize,
+    SetNormalizeLevel(f32),
+    // Toggle applying the preview effects to the files
+    ToggleEffects,
+    // Set the template of the names of the exported files
+    SetTemplate(String),
+    // Export the selected rows into a chosen folder, or the region selected in the waveform of the selected file
+    Export,
+    // An export has finished, with the number of files written and the errors of those which failed
+    Finished(usize, Vec<String>),
+}
+
+impl Model for ExportData {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|export_event, _| match export_event {
+            ExportEvent::ShowDialog => self.show_dialog = true,
+            ExportEvent::HideDialog => self.show_dialog = false,
+
+            ExportEvent::SelectSampleRate(index) => {
+                self.settings.sample_rate =
+                    index.checked_sub(1).and_then(|index| EXPORT_SAMPLE_RATES.get(index)).copied();
+            }
+
+            ExportEvent::SelectBitDepth(index) => {
+                if let Some(bit_depth) = BIT_DEPTHS.get(*index) {
+                    self.settings.bit_depth = *bit_depth;
+                }
+            }
+
+            ExportEvent::ToggleDither => self.settings.dither ^= true,
+
+            ExportEvent::ToggleNormalize => {
+                self.settings.normalize = match self.settings.normalize {
+                    Some(_) => None,
+                    None => Some(self.normalize_level),
+                };
+            }
+
+            ExportEvent::SetNormalizeLevel(level) => {
+                self.normalize_level = level.min(0.0);
+                if self.settings.normalize.is_some() {
+                    self.settings.normalize = Some(self.normalize_level);
+                }
+            }
+
+            ExportEvent::ToggleEffects => self.apply_effects ^= true,
+
+            ExportEvent::SetTemplate(template) => self.template = template.clone(),
+
+            ExportEvent::Finished(exported, errors) => {
+                self.exporting = false;
+                self.exported = Some(*exported);
+                self.errors = errors.clone();
+            }
+
+            ExportEvent::Export => {}
+        });
+    }
+}
@@ -0,0 +1,107 @@
+//! GUI state for library indexing jobs
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use vizia::prelude::*;
+
+use crate::database::prelude::{IndexError, IndexJob, IndexReport};
+
+// The progress of a running indexing job
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct IndexingProgress {
+    // The path of the library being indexed
+    pub path: PathBuf,
+    // The name of the library being indexed
+    pub name: String,
+    // The number of files probed so far
+    pub scanned: usize,
+    // The number of files found so far
+    pub total: usize,
+}
+
+// The data model for the indexing status in the bottom bar
+#[derive(Debug, Lens, Default)]
+pub struct IndexingData {
+    // The running indexing jobs
+    pub jobs: Vec<IndexingProgress>,
+    // The errors reported by finished jobs
+    pub errors: Vec<IndexError>,
+    // Whether the indexing report dialog is shown
+    pub show_report: bool,
+    // Handles used to cancel the running jobs
+    #[lens(ignore)]
+    handles: HashMap<PathBuf, Arc<IndexJob>>,
+}
+
+impl IndexingData {
+    /// Register a new indexing job for the library at `path`, cancelling any job already running for it.
+    pub fn start(&mut self, path: &PathBuf) -> Arc<IndexJob> {
+        let job = Arc::new(IndexJob::new());
+
+        if let Some(previous) = self.handles.insert(path.clone(), job.clone()) {
+            previous.cancel();
+        }
+
+        self.jobs.retain(|progress| progress.path != *path);
+        self.jobs.push(IndexingProgress {
+            path: path.clone(),
+            name: path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string(),
+            scanned: 0,
+            total: 0,
+        });
+
+        job
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexingEvent {
+    // Update the number of scanned and total files of the job for a library
+    Progress(PathBuf, usize, usize),
+    // The job for a library has finished
+    Finished(PathBuf, IndexReport),
+    // A change to an open library could not be watched or applied to its index
+    Error(IndexError),
+    // Cancel the job for a library
+    Cancel(PathBuf),
+    // Show the indexing report dialog
+    ShowReport,
+    // Hide the indexing report dialog
+    HideReport,
+    // Clear the errors in the indexing report
+    ClearReport,
+}
+
+impl Model for IndexingData {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.take(|indexing_event, _| match indexing_event {
+            IndexingEvent::Progress(path, scanned, total) => {
+                if let Some(progress) = self.jobs.iter_mut().find(|p| p.path == path) {
+                    progress.scanned = scanned;
+                    progress.total = total;
+                }
+            }
+
+            IndexingEvent::Finished(path, report) => {
+                self.jobs.retain(|progress| progress.path != path);
+                self.handles.remove(&path);
+                self.errors.extend(report.errors);
+            }
+
+            IndexingEvent::Error(error) => self.errors.push(error),
+
+            IndexingEvent::Cancel(path) => {
+                if let Some(job) = self.handles.get(&path) {
+                    job.cancel();
+                }
+            }
+
+            IndexingEvent::ShowReport => self.show_report = true,
+            IndexingEvent::HideReport => self.show_report = false,
+            IndexingEvent::ClearReport => {
+                self.errors.clear();
+                self.show_report = false;
+            }
+        });
+    }
+}
@@ -0,0 +1,112 @@
+//! GUI state for the export as instrument dialog
+
+use std::path::PathBuf;
+
+use vizia::prelude::*;
+
+use crate::database::prelude::note_name;
+use crate::engine::InstrumentZone;
+
+// A zone of the instrument as shown in the mapping preview
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct ZoneRow {
+    pub name: String,
+    pub root: String,
+    pub keys: String,
+    pub velocities: String,
+    pub looped: bool,
+}
+
+impl ZoneRow {
+    fn new(zone: &InstrumentZone) -> Self {
+        let sample = &zone.sample;
+        let name = sample.path.file_name().map(|name| name.to_string_lossy().into_owned());
+        let root = match sample.tune {
+            0 => note_name(sample.root_note),
+            tune => format!("{} {:+} ct", note_name(sample.root_note), tune),
+        };
+
+        Self {
+            name: name.unwrap_or_default(),
+            root,
+            keys: format!("{} – {}", note_name(zone.keys.0), note_name(zone.keys.1)),
+            velocities: format!("{} – {}", zone.velocities.0, zone.velocities.1),
+            looped: sample.loop_points.is_some(),
+        }
+    }
+}
+
+// The data model for the export as instrument dialog
+#[derive(Debug, Lens, Clone, Default)]
+pub struct InstrumentData {
+    // Whether the export as instrument dialog is shown
+    pub show_dialog: bool,
+    // The selected samples mapped across the keyboard, and their rows in the mapping preview
+    #[lens(ignore)]
+    pub zones: Vec<InstrumentZone>,
+    pub rows: Vec<ZoneRow>,
+    // The names of the selected samples left out for having no root note
+    pub unmapped: Vec<String>,
+    // Whether a Decent Sampler preset is written next to the SFZ definition
+    pub with_dspreset: bool,
+    // The names of the files written by the last export, or the error which stopped it
+    pub written: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl InstrumentData {
+    /// Show the mapping of the selected samples, forgetting the outcome of the last export.
+    pub fn set_zones(&mut self, zones: Vec<InstrumentZone>, unmapped: Vec<String>) {
+        self.rows = zones.iter().map(ZoneRow::new).collect();
+        self.zones = zones;
+        self.unmapped = unmapped;
+        self.written.clear();
+        self.error = None;
+    }
+
+    /// Show the outcome of an export.
+    pub fn finish(&mut self, result: std::io::Result<Vec<PathBuf>>) {
+        match result {
+            Ok(written) => {
+                self.written = written
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect();
+                self.error = None;
+            }
+            Err(err) => {
+                self.written.clear();
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstrumentEvent {
+    // Map the selected rows across the keyboard and show the mapping in the export as instrument dialog
+    ShowDialog,
+    // The selected rows have been mapped, leaving out those with the given names
+    Mapped(Vec<InstrumentZone>, Vec<String>),
+    // Hide the export as instrument dialog
+    HideDialog,
+    // Toggle writing a Decent Sampler preset next to the SFZ definition
+    ToggleDspreset,
+    // Write the instrument to a chosen file
+    Export,
+}
+
+impl Model for InstrumentData {
+    fn event(&mut self, _: &mut EventContext, event: &mut Event) {
+        event.map(|instrument_event, _| match instrument_event {
+            InstrumentEvent::ShowDialog => self.show_dialog = true,
+            InstrumentEvent::Mapped(zones, unmapped) => {
+                self.set_zones(zones.clone(), unmapped.clone())
+            }
+            InstrumentEvent::HideDialog => self.show_dialog = false,
+            InstrumentEvent::ToggleDspreset => self.with_dspreset ^= true,
+            InstrumentEvent::Export => {}
+        });
+    }
+}
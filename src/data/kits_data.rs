@@ -0,0 +1,123 @@
+//! GUI state for the kits panel
+
+use vizia::prelude::*;
+
+use crate::database::prelude::{AudioFileID, Kit};
+
+// The data model for the kits panel
+#[derive(Debug, Lens, Clone, Default)]
+pub struct KitsData {
+    // The kits of the open library
+    pub kits: Vec<Kit>,
+    // The index of the kit shown in the panel
+    pub selected: Option<usize>,
+    // The slot of the shown kit filled by the next sample sent to the kit
+    pub selected_slot: usize,
+    // The audio file dragged from the samples table, dropped onto a slot to fill it
+    #[lens(ignore)]
+    pub dragged: Option<AudioFileID>,
+    // Whether the shown kit is being exported
+    pub exporting: bool,
+    // The folder written by the last export, and the errors of the slots it left out or the error which stopped
+    // it
+    pub exported: Option<String>,
+    pub error: Option<String>,
+}
+
+impl KitsData {
+    /// The kit shown in the panel.
+    pub fn selected_kit(&self) -> Option<&Kit> {
+        self.kits.get(self.selected?)
+    }
+
+    /// Replace the kits with those loaded from the library, keeping the shown kit if it still exists.
+    pub fn set_kits(&mut self, kits: Vec<Kit>) {
+        let shown = self.selected_kit().map(|kit| kit.id);
+        self.selected = shown
+            .and_then(|id| kits.iter().position(|kit| kit.id == id))
+            .or((!kits.is_empty()).then_some(0));
+        self.kits = kits;
+
+        let slots = self.selected_kit().map_or(0, |kit| kit.slots.len());
+        self.selected_slot = self.selected_slot.min(slots.saturating_sub(1));
+    }
+
+    /// Select the slot after `slot` in the shown kit, so that samples sent one after another fill the slots in
+    /// order.
+    pub fn advance_slot(&mut self, slot: usize) {
+        if let Some(slots) = self.selected_kit().map(|kit| kit.slots.len()).filter(|n| *n > 0) {
+            self.selected_slot = (slot + 1) % slots;
+        }
+    }
+}
+
+// The event types for the kits panel
+#[derive(Debug, Clone, PartialEq)]
+pub enum KitsEvent {
+    // Show the kit with the given index
+    Select(usize),
+    // Select the slot filled by the next sample sent to the kit
+    SelectSlot(usize),
+    // Create a kit with the usual drum slots and show it
+    Create,
+    // Rename or remove the shown kit
+    Rename(String),
+    Remove,
+    // Add an empty slot to the shown kit
+    AddSlot,
+    // Rename, empty or remove the slot of the shown kit with the given index
+    RenameSlot(usize, String),
+    ClearSlot(usize),
+    RemoveSlot(usize),
+    // Fill the selected slot of the shown kit with an audio file
+    SendToKit(AudioFileID),
+    // An audio file is being dragged from the samples table
+    Drag(AudioFileID),
+    // The dragged audio file has been dropped onto the slot with the given index
+    Drop(usize),
+    // Pin the samples of the shown kit to the pads in the order of its slots
+    PlayAsPads,
+    // Export the shown kit into a chosen folder
+    Export,
+    // An export has finished, writing the folder with the given name without the slots which failed with the
+    // given errors, or has failed with an error
+    Exported(String, Vec<String>),
+    ExportFailed(String),
+}
+
+impl Model for KitsData {
+    fn event(&mut self, _: &mut EventContext, event: &mut Event) {
+        event.map(|kits_event, _| match kits_event {
+            KitsEvent::Select(index) => {
+                if *index < self.kits.len() {
+                    self.selected = Some(*index);
+                    self.selected_slot = 0;
+                    self.exported = None;
+                    self.error = None;
+                }
+            }
+
+            KitsEvent::SelectSlot(slot) => self.selected_slot = *slot,
+
+            KitsEvent::Drag(audio_file) => self.dragged = Some(*audio_file),
+
+            KitsEvent::Export => {
+                self.exported = None;
+                self.error = None;
+            }
+
+            KitsEvent::Exported(folder, errors) => {
+                self.exporting = false;
+                self.exported = Some(folder.clone());
+                self.error = (!errors.is_empty()).then(|| errors.join("\n"));
+            }
+
+            KitsEvent::ExportFailed(error) => {
+                self.exporting = false;
+                self.error = Some(error.clone());
+            }
+
+            _ => {}
+        });
+    }
+}
@@ -0,0 +1,63 @@
+use vizia::prelude::*;
+
+use crate::engine::{level_db, MIN_LEVEL_DB};
+
+// The fall of the shown levels in dB per tick of the meters, so that they rise at once and fall smoothly
+const LEVEL_FALL: f32 = 0.3;
+
+// The number of ticks for which the highest peak is held before it falls
+const HOLD_TICKS: u32 = 150;
+
+/// The levels in dB shown by the meter of an output channel.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct ChannelLevel {
+    pub peak: f32,
+    pub rms: f32,
+    // The highest recent peak
+    pub hold: f32,
+    #[lens(ignore)]
+    hold_ticks: u32,
+}
+
+impl Default for ChannelLevel {
+    fn default() -> Self {
+        Self { peak: MIN_LEVEL_DB, rms: MIN_LEVEL_DB, hold: MIN_LEVEL_DB, hold_ticks: 0 }
+    }
+}
+
+impl ChannelLevel {
+    // Update the shown levels from the linear levels measured since the last tick.
+    fn update(&mut self, peak: f32, rms: f32) {
+        self.peak = level_db(peak).max(self.peak - LEVEL_FALL);
+        self.rms = level_db(rms).max(self.rms - LEVEL_FALL);
+
+        if self.peak >= self.hold {
+            self.hold = self.peak;
+            self.hold_ticks = HOLD_TICKS;
+        } else if self.hold_ticks > 0 {
+            self.hold_ticks -= 1;
+        } else {
+            self.hold = (self.hold - LEVEL_FALL).max(self.peak);
+        }
+    }
+}
+
+/// The data model for the output level meters in the footer of the wave panel.
+#[derive(Debug, Clone, Default, Lens)]
+pub struct MeterData {
+    pub left: ChannelLevel,
+    pub right: ChannelLevel,
+}
+
+impl MeterData {
+    /// Update the meters from the linear peak and RMS levels of the left and right channels.
+    pub fn update(&mut self, peak: [f32; 2], rms: [f32; 2]) {
+        self.left.update(peak[0], rms[0]);
+        self.right.update(peak[1], rms[1]);
+    }
+
+    /// Drop the meters to silence, forgetting the held peaks.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
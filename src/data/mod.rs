@@ -15,3 +15,24 @@ pub use settings_data::*;
 
 pub mod samples_data;
 pub use samples_data::*;
+
+pub mod indexing_data;
+pub use indexing_data::*;
+
+pub mod meter_data;
+pub use meter_data::*;
+
+pub mod pads_data;
+pub use pads_data::*;
+
+pub mod export_data;
+pub use export_data::*;
+
+pub mod instrument_data;
+pub use instrument_data::*;
+
+pub mod slices_data;
+pub use slices_data::*;
+
+pub mod kits_data;
+pub use kits_data::*;
@@ -0,0 +1,123 @@
+//! GUI state for the pads
+
+use vizia::prelude::*;
+
+use crate::engine::{PadMode, PadSettings, CHOKE_GROUPS, NUM_PADS};
+
+// The keys playing the pads, from the bottom left pad to the top right, laid out on the keyboard like the grid
+// of pads
+const PAD_KEYS: [Code; NUM_PADS] = [
+    Code::KeyZ,
+    Code::KeyX,
+    Code::KeyC,
+    Code::KeyV,
+    Code::KeyA,
+    Code::KeyS,
+    Code::KeyD,
+    Code::KeyF,
+    Code::KeyQ,
+    Code::KeyW,
+    Code::KeyE,
+    Code::KeyR,
+    Code::Digit1,
+    Code::Digit2,
+    Code::Digit3,
+    Code::Digit4,
+];
+
+// A pad in the pads panel
+#[derive(Debug, Clone, Default, PartialEq, Data, Lens)]
+pub struct PadData {
+    // The name of the sample pinned to the pad, empty if there is none
+    pub name: String,
+    pub settings: PadSettings,
+    // Whether the pad is held down with the keyboard or the mouse
+    pub held: bool,
+}
+
+// The data model for the pads panel
+#[derive(Debug, Lens, Clone)]
+pub struct PadsData {
+    pub pads: Vec<PadData>,
+    // The pad whose settings are shown, to which the next sample is pinned
+    pub selected: usize,
+}
+
+impl Default for PadsData {
+    fn default() -> Self {
+        Self { pads: vec![PadData::default(); NUM_PADS], selected: 0 }
+    }
+}
+
+// The event types for the pads panel
+#[derive(Debug, Clone, PartialEq)]
+pub enum PadsEvent {
+    // Select the pad whose settings are shown
+    Select(usize),
+    // Start and stop playing a pad
+    Trigger(usize),
+    Release(usize),
+    // Set whether a pad plays its whole sample or only while held
+    SetMode(usize, PadMode),
+    // Move a pad to the next choke group, or to none after the last
+    CycleChokeGroup(usize),
+    // Remove the sample from a pad
+    Clear(usize),
+}
+
+impl PadsData {
+    /// The pad played by the key with the given code.
+    pub fn pad_for_key(code: Code) -> Option<usize> {
+        PAD_KEYS.iter().position(|key| *key == code)
+    }
+
+    /// The label of the key playing the pad at `index`.
+    pub fn key_name(index: usize) -> &'static str {
+        ["Z", "X", "C", "V", "A", "S", "D", "F", "Q", "W", "E", "R", "1", "2", "3", "4"][index]
+    }
+
+    /// Show the sample `name` on the pad at `index`, and select the next pad, so that samples pinned one after
+    /// another fill the pads in order.
+    pub fn pin(&mut self, index: usize, name: String) {
+        if let Some(pad) = self.pads.get_mut(index) {
+            pad.name = name;
+            self.selected = (index + 1) % NUM_PADS;
+        }
+    }
+}
+
+impl Model for PadsData {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|pads_event, _| match *pads_event {
+            PadsEvent::Select(index) => {
+                if index < NUM_PADS {
+                    self.selected = index;
+                }
+            }
+
+            PadsEvent::Trigger(index) | PadsEvent::Release(index) => {
+                if let Some(pad) = self.pads.get_mut(index) {
+                    pad.held = matches!(pads_event, PadsEvent::Trigger(_));
+                }
+            }
+
+            PadsEvent::SetMode(index, mode) => {
+                if let Some(pad) = self.pads.get_mut(index) {
+                    pad.settings.mode = mode;
+                }
+            }
+
+            PadsEvent::CycleChokeGroup(index) => {
+                if let Some(pad) = self.pads.get_mut(index) {
+                    pad.settings.choke_group = (pad.settings.choke_group + 1) % (CHOKE_GROUPS + 1);
+                }
+            }
+
+            PadsEvent::Clear(index) => {
+                if let Some(pad) = self.pads.get_mut(index) {
+                    pad.name.clear();
+                }
+            }
+        })
+    }
+}
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use super::app_data::{AppData, AppEvent};
+use super::KitsEvent;
 use crate::database::prelude::*;
 
 use vizia::prelude::*;
@@ -14,8 +15,14 @@ pub struct SamplesData {
     pub table_rows: Vec<AudioFile>,
     // The currently selected row
     pub selected: Option<usize>,
+    // The rows selected for batch actions such as exports, including the selected row
+    pub selection: Vec<usize>,
+    // The collection currently shown in the table
+    pub collection: Option<CollectionID>,
     // The search text in the search box
     pub search_text: String,
+    // The audio file whose nearest neighbours are shown instead of the collection, and its name
+    pub similar_to: Option<(AudioFileID, String)>,
 }
 
 impl SamplesData {
@@ -29,7 +36,9 @@ impl SamplesData {
             "Num Channels",
             "BPM",
             "Key",
+            "Note",
             "Size",
+            "Format",
             "",
         ]
         .iter_mut()
@@ -38,6 +47,13 @@ impl SamplesData {
 
         Self { table_headers: headers, ..Default::default() }
     }
+
+    /// The audio files of the rows selected for batch actions, in the order of the table.
+    pub fn selected_rows(&self) -> Vec<&AudioFile> {
+        let mut selection = self.selection.clone();
+        selection.sort_unstable();
+        selection.iter().filter_map(|index| self.table_rows.get(*index)).collect()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -46,6 +62,10 @@ pub enum SampleEvent {
     Select(usize),
     // Deselect the currently selected row
     Deselect,
+    // Add a row to the selection for batch actions, or remove it if it is already selected
+    AddSelection(usize),
+    // Select the rows from the selected row to the given row for batch actions
+    SelectRange(usize),
     // Move selection to the next row
     SelectNext,
     // Move selection to the previous row
@@ -56,6 +76,18 @@ pub enum SampleEvent {
     ShowColumn(usize),
     // Toggle the visibility of a column
     ToggleColumn(usize),
+    // Filter the rows by name and embedded metadata
+    Search(String),
+    // Show the audio files most similar to the selected row
+    FindSimilar,
+    // Show the collection again instead of the similar audio files
+    ClearSimilar,
+    // Layer the selected row over the preview
+    PinToLayer,
+    // Pin the selected row to the selected pad
+    PinToPad,
+    // Fill the selected slot of the shown kit with the selected row
+    SendToKit,
 }
 
 impl Model for SamplesData {
@@ -64,6 +96,7 @@ impl Model for SamplesData {
             SampleEvent::Select(row_index) => {
                 if *row_index < self.table_rows.len() {
                     self.selected = Some(*row_index);
+                    self.selection = vec![*row_index];
                     if let Some(audio_file) = self.table_rows.get(*row_index) {
                         cx.emit(AppEvent::SelectSample(
                             audio_file.collection,
@@ -75,6 +108,25 @@ impl Model for SamplesData {
 
             SampleEvent::Deselect => {
                 self.selected = None;
+                self.selection.clear();
+            }
+
+            SampleEvent::AddSelection(row_index) => {
+                if *row_index < self.table_rows.len() {
+                    match self.selection.iter().position(|index| index == row_index) {
+                        Some(position) => {
+                            self.selection.remove(position);
+                        }
+                        None => self.selection.push(*row_index),
+                    }
+                }
+            }
+
+            SampleEvent::SelectRange(row_index) => {
+                if *row_index < self.table_rows.len() {
+                    let from = self.selected.unwrap_or(*row_index);
+                    self.selection = (from.min(*row_index)..=from.max(*row_index)).collect();
+                }
             }
 
             SampleEvent::SelectNext => {
@@ -93,6 +145,45 @@ impl Model for SamplesData {
                 self.table_headers[*index].1 ^= true;
             }
 
+            SampleEvent::Search(text) => {
+                self.search_text = text.clone();
+                cx.emit(AppEvent::RefreshTable);
+            }
+
+            SampleEvent::FindSimilar => {
+                if let Some(audio_file) = self.selected.and_then(|index| self.table_rows.get(index))
+                {
+                    self.similar_to = Some((audio_file.id, audio_file.name.clone()));
+                    cx.emit(AppEvent::RefreshTable);
+                }
+            }
+
+            SampleEvent::ClearSimilar => {
+                self.similar_to = None;
+                cx.emit(AppEvent::RefreshTable);
+            }
+
+            SampleEvent::PinToLayer => {
+                if let Some(audio_file) = self.selected.and_then(|index| self.table_rows.get(index))
+                {
+                    cx.emit(AppEvent::PinLayer(audio_file.collection, audio_file.name.clone()));
+                }
+            }
+
+            SampleEvent::PinToPad => {
+                if let Some(audio_file) = self.selected.and_then(|index| self.table_rows.get(index))
+                {
+                    cx.emit(AppEvent::PinPad(audio_file.collection, audio_file.name.clone()));
+                }
+            }
+
+            SampleEvent::SendToKit => {
+                if let Some(audio_file) = self.selected.and_then(|index| self.table_rows.get(index))
+                {
+                    cx.emit(KitsEvent::SendToKit(audio_file.id));
+                }
+            }
+
             _ => {}
         });
     }
@@ -3,33 +3,101 @@ use vizia::prelude::*;
 use strum::EnumString;
 use strum::VariantNames;
 
+use crate::engine::{host_names, output_device_names, output_options, OutputInfo, OutputSettings};
+
+// The entry of the output pick lists which uses the default of the host or device
+const DEFAULT_ENTRY: &str = "Default";
+
 #[derive(Lens)]
 pub struct SettingsData {
     pub selected_page: SettingsPage,
 
+    // The entries of the output pick lists, each but the hosts starting with the default entry
     pub audio_driver: Vec<String>,
-    pub input_device: Vec<String>,
     pub output_device: Vec<String>,
+    pub sample_rate: Vec<String>,
+    pub buffer_size: Vec<String>,
 
     pub selected_audio_driver: usize,
-    pub selected_input_device: usize,
     pub selected_output_device: usize,
+    pub selected_sample_rate: usize,
+    pub selected_buffer_size: usize,
+
+    // The sample rates and buffer sizes of the entries after the default entry
+    #[lens(ignore)]
+    pub sample_rates: Vec<u32>,
+    #[lens(ignore)]
+    pub buffer_sizes: Vec<u32>,
+
+    // The device and configuration of the open output stream
+    pub output_status: String,
 }
 
 impl SettingsData {
-    pub fn dummy() -> Self {
+    pub fn new() -> Self {
         Self {
             selected_page: SettingsPage::General,
 
-            audio_driver: vec![String::from("WASAPI"), String::from("ASIO")],
-            input_device: vec![String::from("Microphone 1"), String::from("USB Microphone")],
-            output_device: vec![String::from("Speakers"), String::from("USB Headphones")],
+            audio_driver: Vec::new(),
+            output_device: Vec::new(),
+            sample_rate: Vec::new(),
+            buffer_size: Vec::new(),
 
             selected_audio_driver: 0,
-            selected_input_device: 0,
             selected_output_device: 0,
+            selected_sample_rate: 0,
+            selected_buffer_size: 0,
+
+            sample_rates: Vec::new(),
+            buffer_sizes: Vec::new(),
+
+            output_status: String::new(),
         }
     }
+
+    /// List the hosts, devices, sample rates and buffer sizes which can be chosen for the output, selecting
+    /// those of `settings`, and show the stream which is open.
+    pub fn update_output(&mut self, settings: &OutputSettings, info: Option<&OutputInfo>) {
+        self.audio_driver = host_names();
+        let devices = output_device_names(settings.host.as_deref());
+        (self.sample_rates, self.buffer_sizes) = output_options(settings);
+
+        // The first host is the default host
+        self.selected_audio_driver = settings
+            .host
+            .as_ref()
+            .and_then(|host| self.audio_driver.iter().position(|name| name == host))
+            .unwrap_or(0);
+        self.selected_output_device = settings
+            .device
+            .as_ref()
+            .and_then(|device| devices.iter().position(|name| name == device))
+            .map_or(0, |index| index + 1);
+        self.selected_sample_rate = settings
+            .sample_rate
+            .and_then(|rate| self.sample_rates.iter().position(|r| *r == rate))
+            .map_or(0, |index| index + 1);
+        self.selected_buffer_size = settings
+            .buffer_size
+            .and_then(|size| self.buffer_sizes.iter().position(|s| *s == size))
+            .map_or(0, |index| index + 1);
+
+        let with_default = |entries: Vec<String>| {
+            std::iter::once(String::from(DEFAULT_ENTRY)).chain(entries).collect::<Vec<_>>()
+        };
+        self.output_device = with_default(devices);
+        self.sample_rate =
+            with_default(self.sample_rates.iter().map(|rate| format!("{} Hz", rate)).collect());
+        self.buffer_size =
+            with_default(self.buffer_sizes.iter().map(|size| format!("{} frames", size)).collect());
+
+        self.output_status = match info {
+            Some(info) => {
+                format!("{}, {} Hz, {} channels", info.device, info.sample_rate, info.num_channels)
+            }
+            None => String::from("No output device"),
+        };
+    }
 }
 
 pub enum SettingsEvent {
@@ -0,0 +1,121 @@
+//! GUI state for slicing the selected file into one-shots
+
+use std::path::PathBuf;
+
+use vizia::prelude::*;
+
+// The data model for slicing the selected file in the wave panel
+#[derive(Debug, Lens, Clone, Default)]
+pub struct SlicesData {
+    // The frames at which the slices of the selected file start, in order, `None` when not slicing
+    pub markers: Option<Vec<usize>>,
+    // The length of the selected file in frames, at which the last slice ends
+    pub num_frames: usize,
+    // Whether the transients of the selected file are being detected
+    pub detecting: bool,
+    // Whether the slices are being exported
+    pub exporting: bool,
+    // The number of slices written by the last export, `None` before
+    pub exported: Option<usize>,
+}
+
+impl SlicesData {
+    /// The number of slices of the selected file.
+    pub fn count(&self) -> usize {
+        self.markers.as_ref().map_or(0, Vec::len)
+    }
+
+    /// The frames between which the slice at `index` is played and exported, up to the next marker or the end
+    /// of the file.
+    pub fn slice(&self, index: usize) -> Option<(usize, usize)> {
+        let markers = self.markers.as_ref()?;
+        let start = *markers.get(index)?;
+        let end = markers.get(index + 1).copied().unwrap_or(self.num_frames.max(start));
+        Some((start, end))
+    }
+
+    /// The index of the slice playing at `frame`, `None` before the first marker.
+    pub fn slice_at(&self, frame: usize) -> Option<usize> {
+        let markers = self.markers.as_ref()?;
+        markers.iter().rposition(|marker| *marker <= frame)
+    }
+}
+
+// The event types for slicing the selected file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlicesEvent {
+    // Show or hide the slice markers, detecting the transients of the selected file when shown
+    ToggleSlicing,
+    // Replace the markers with the transients detected in the selected file
+    Detect,
+    // The transients of the file with the given path and its length in frames have been detected
+    Detected(PathBuf, Vec<usize>, usize),
+    // Add a marker at a frame
+    Add(usize),
+    // Move the marker with the given index to a frame, between the markers next to it
+    Move(usize, usize),
+    // Remove the marker with the given index
+    Remove(usize),
+    // Remove all markers
+    Clear,
+    // Play the slice with the given index
+    Audition(usize),
+    // Export the slices as numbered one-shots into a chosen folder
+    Export,
+    // An export has finished with the given number of slices written
+    Exported(usize),
+}
+
+impl Model for SlicesData {
+    fn event(&mut self, _: &mut EventContext, event: &mut Event) {
+        event.map(|slices_event, _| match slices_event {
+            SlicesEvent::ToggleSlicing => {
+                self.markers = match self.markers {
+                    Some(_) => None,
+                    None => Some(Vec::new()),
+                };
+                self.exported = None;
+            }
+
+            SlicesEvent::Add(frame) => {
+                if let Some(markers) = &mut self.markers {
+                    if let Err(index) = markers.binary_search(frame) {
+                        markers.insert(index, *frame);
+                    }
+                }
+            }
+
+            SlicesEvent::Move(index, frame) => {
+                if let Some(markers) = self.markers.as_mut().filter(|m| *index < m.len()) {
+                    let lower = index.checked_sub(1).map_or(0, |previous| markers[previous] + 1);
+                    let upper = markers.get(index + 1).map_or(usize::MAX, |next| next - 1);
+                    markers[*index] = (*frame).clamp(lower, upper.max(lower));
+                }
+            }
+
+            SlicesEvent::Remove(index) => {
+                if let Some(markers) = &mut self.markers {
+                    if *index < markers.len() {
+                        markers.remove(*index);
+                    }
+                }
+            }
+
+            SlicesEvent::Clear => {
+                if let Some(markers) = &mut self.markers {
+                    markers.clear();
+                }
+            }
+
+            SlicesEvent::Exported(exported) => {
+                self.exporting = false;
+                self.exported = Some(*exported);
+            }
+
+            SlicesEvent::Detect
+            | SlicesEvent::Detected(..)
+            | SlicesEvent::Audition(_)
+            | SlicesEvent::Export => {}
+        });
+    }
+}
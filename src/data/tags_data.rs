@@ -3,10 +3,10 @@
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use vizia::prelude::*;
 
-use crate::Tag;
+use crate::{Tag, TagID, TagSuggestion};
 
 // The data model for the tags panel
-#[derive(Debug, Lens, Clone, Default)]
+#[derive(Debug, Lens, Clone)]
 pub struct TagsData {
     // The search text in the search box
     pub search_text: String,
@@ -16,6 +16,23 @@ pub struct TagsData {
     pub search_case_sensitive: bool,
     // The tags to display
     pub tags: Vec<Tag>,
+    // The tags suggested by the analysis of the indexed files
+    pub suggestions: Vec<TagSuggestion>,
+    // The lowest confidence of the suggestions assigned when accepting a tag
+    pub min_confidence: f32,
+}
+
+impl Default for TagsData {
+    fn default() -> Self {
+        Self {
+            search_text: String::new(),
+            filter_search: false,
+            search_case_sensitive: false,
+            tags: Vec::new(),
+            suggestions: Vec::new(),
+            min_confidence: 0.5,
+        }
+    }
 }
 
 // The event types for the tags panel
@@ -29,6 +46,14 @@ pub enum TagsEvent {
     ToggleSearchFilter,
     // Toggle the case sensitivity of the search
     ToggleSearchCaseSensitivity,
+    // Assign a suggested tag to the files it is suggested for with enough confidence
+    AcceptSuggestions(TagID),
+    // Discard the suggestions of a tag
+    RejectSuggestions(TagID),
+    // Set the lowest confidence of the accepted suggestions
+    SetMinConfidence(f32),
+    // Update the list of suggested tags
+    UpdateSuggestions(Vec<TagSuggestion>),
 }
 
 impl Model for TagsData {
@@ -48,6 +73,12 @@ impl Model for TagsData {
                 }
             }
 
+            TagsEvent::SetMinConfidence(min_confidence) => {
+                self.min_confidence = min_confidence.clamp(0.0, 1.0);
+            }
+
+            TagsEvent::UpdateSuggestions(suggestions) => self.suggestions = suggestions.clone(),
+
             _ => {}
         })
     }
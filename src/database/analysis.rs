@@ -0,0 +1,580 @@
+//! Classification of audio files as one-shots or loops, and of one-shots by drum type.
+//!
+//! The decoded signal is also described by a feature vector to find similar sounds, and the pitch of
+//! one-shots is detected.
+//!
+//! The results are suggestions stored as tags with a confidence, which the user accepts or rejects.
+
+use super::*;
+use std::{f32::consts::PI, fs::File, path::Path};
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, formats::FormatOptions, io::MediaSourceStream,
+    meta::MetadataOptions, probe::Hint,
+};
+
+// Only the start of long files is decoded
+const MAX_ANALYSIS_SECONDS: f32 = 30.0;
+
+// The length of the frames of the amplitude envelope
+const ENVELOPE_FRAME_SECONDS: f32 = 0.005;
+
+// Below this peak level a file is treated as silent
+const SILENCE_DB: f32 = -80.0;
+
+// The length of the attack from which the spectral features of a one-shot are measured
+const ATTACK_SECONDS: f32 = 0.25;
+
+// The level relative to the peak below which the start and end of a file are inaudible
+const AUDIBLE_DB: f32 = -60.0;
+
+// A noise tail is cut this far above its level, if it is at least 30 dB below the peak
+const NOISE_FLOOR_MARGIN_DB: f32 = 6.0;
+
+// Kept before the audible start so that the attack is not cut
+const START_MARGIN_SECONDS: f32 = 0.001;
+
+// Files are sliced up to this length
+const MAX_SLICING_SECONDS: f32 = 600.0;
+
+// The rise of the envelope in dB at a transient, and the shortest slice between transients, so that the
+// ringing of a hit is not sliced
+const TRANSIENT_RISE_DB: f32 = 9.0;
+const MIN_SLICE_SECONDS: f32 = 0.05;
+
+/// Whether a file is a single hit or a loop meant to be repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleKind {
+    OneShot,
+    Loop,
+}
+
+impl SampleKind {
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            SampleKind::OneShot => "OneShot",
+            SampleKind::Loop => "Loop",
+        }
+    }
+}
+
+/// The kind of drum a one-shot most likely contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrumType {
+    Kick,
+    Snare,
+    HiHat,
+    Clap,
+    Tom,
+    Perc,
+}
+
+impl DrumType {
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            DrumType::Kick => "Kick",
+            DrumType::Snare => "Snare",
+            DrumType::HiHat => "HiHat",
+            DrumType::Clap => "Clap",
+            DrumType::Tom => "Tom",
+            DrumType::Perc => "Percussion",
+        }
+    }
+
+    // Guess the drum type from the words of a file name, e.g. `BD_Deep_01.wav`.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let stem = name.rsplit_once('.').map_or(name.as_str(), |(stem, _)| stem);
+
+        stem.split(|c: char| !c.is_ascii_alphabetic()).find_map(|word| match word {
+            "kick" | "kicks" | "kik" | "bd" | "bassdrum" => Some(DrumType::Kick),
+            "snare" | "snares" | "snr" | "sd" | "rim" | "rimshot" => Some(DrumType::Snare),
+            "hat" | "hats" | "hihat" | "hihats" | "hh" | "oh" | "ch" => Some(DrumType::HiHat),
+            "clap" | "claps" | "clp" | "cp" => Some(DrumType::Clap),
+            "tom" | "toms" => Some(DrumType::Tom),
+            "perc" | "percussion" | "shaker" | "conga" | "bongo" | "cowbell" | "tamb" => {
+                Some(DrumType::Perc)
+            }
+            _ => None,
+        })
+    }
+}
+
+/// The classification of an audio file, with confidences between 0 and 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub kind: SampleKind,
+    pub kind_confidence: f32,
+    // Only one-shots are classified by drum type
+    pub drum: Option<(DrumType, f32)>,
+}
+
+impl Classification {
+    /// The names of the tags suggested for the file and their confidence.
+    pub fn tags(&self) -> Vec<(&'static str, f32)> {
+        let mut tags = vec![(self.kind.tag_name(), self.kind_confidence)];
+        if let Some((drum, confidence)) = self.drum {
+            tags.push((drum.tag_name(), confidence));
+        }
+        tags
+    }
+}
+
+/// The results of the analysis of an audio file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub classification: Classification,
+    pub features: FeatureVector,
+    // Only one-shots are tested for a pitch
+    pub pitch: Option<Pitch>,
+    // The audible region in frames, `None` for silent files
+    pub audible: Option<AudibleRange>,
+}
+
+impl Analysis {
+    /// Store the detected pitch and audible region in `audio_file`, tuned to the nearest note by default.
+    pub fn apply(&self, audio_file: &mut AudioFile) {
+        audio_file.pitch = self.pitch;
+        audio_file.tuning_offset = self.pitch.map(|pitch| -pitch.cents);
+        audio_file.audible_start = self.audible.map(|audible| audible.start);
+        audio_file.audible_end = self.audible.and_then(|audible| audible.end);
+    }
+}
+
+/// The frames between which a file is audible, without its leading silence and noise tail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudibleRange {
+    pub start: u64,
+    // `None` when the end lies past the analysed start of a long file
+    pub end: Option<u64>,
+}
+
+/// Decode and analyse the file at `path`, or `None` if it cannot be decoded.
+pub fn analyze_audio_file(path: &Path, sampler: &SamplerInfo) -> Option<Analysis> {
+    let (samples, sample_rate) = decode_mono(path, MAX_ANALYSIS_SECONDS)?;
+    let truncated = samples.len() >= (MAX_ANALYSIS_SECONDS * sample_rate as f32) as usize;
+    let name = path.file_name()?.to_str()?;
+    let classification = classify(&samples, sample_rate, name, sampler);
+    let pitch = match classification.kind {
+        SampleKind::OneShot => detect_pitch(&samples, sample_rate),
+        SampleKind::Loop => None,
+    };
+
+    let audible = audible_range(&samples, sample_rate).map(|(start, end)| AudibleRange {
+        start: start as u64,
+        end: (!truncated).then_some(end as u64),
+    });
+
+    Some(Analysis {
+        classification,
+        features: FeatureVector::new(&samples, sample_rate),
+        pitch,
+        audible,
+    })
+}
+
+// Decode up to `max_seconds` of the file at `path`, mixed down to mono.
+fn decode_mono(path: &Path, max_seconds: f32) -> Option<(Vec<f32>, u32)> {
+    let source = MediaSourceStream::new(Box::new(File::open(path).ok()?), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?
+        .format;
+
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .ok()?;
+
+    let max_samples = (max_seconds * sample_rate as f32) as usize;
+    let mut samples = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    while samples.len() < max_samples {
+        let Ok(packet) = format.next_packet() else { break };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let Ok(decoded) = decoder.decode(&packet) else { continue };
+        let spec = *decoded.spec();
+        let num_channels = spec.channels.count().max(1);
+
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * num_channels => buffer,
+            _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+
+        samples.extend(
+            buffer
+                .samples()
+                .chunks(num_channels)
+                .map(|frame| frame.iter().sum::<f32>() / num_channels as f32),
+        );
+    }
+
+    samples.truncate(max_samples);
+    Some((samples, sample_rate))
+}
+
+/// The range of samples of a mono signal above a threshold, or `None` if it is silent.
+///
+/// The threshold is 60 dB below the peak, raised above the level of a steady noise tail so that hiss and hum
+/// at the end of a file are trimmed.
+pub fn audible_range(samples: &[f32], sample_rate: u32) -> Option<(usize, usize)> {
+    let frame_length = ((ENVELOPE_FRAME_SECONDS * sample_rate.max(1) as f32) as usize).max(1);
+    let envelope = envelope_db(samples, frame_length);
+
+    let peak_db = envelope.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if peak_db < SILENCE_DB {
+        return None;
+    }
+
+    // The last tenth of the file is a noise tail if it is quiet and does not decay, unlike a natural fade
+    let tail = &envelope[envelope.len() - (envelope.len() / 10).max(2).min(envelope.len())..];
+    let (early, late) = tail.split_at(tail.len() / 2);
+    let mean = |frames: &[f32]| frames.iter().sum::<f32>() / frames.len().max(1) as f32;
+    let floor_db = mean(tail);
+
+    let mut threshold_db = peak_db + AUDIBLE_DB;
+    if floor_db < peak_db - 30.0 && (mean(early) - mean(late)).abs() < 3.0 {
+        threshold_db = threshold_db.max(floor_db + NOISE_FLOOR_MARGIN_DB);
+    }
+
+    let first = envelope.iter().position(|db| *db > threshold_db)?;
+    let last = envelope.iter().rposition(|db| *db > threshold_db)?;
+
+    // The first sample above the threshold, from the frame before the first loud frame
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    let from = first.saturating_sub(1) * frame_length;
+    let start = samples[from..]
+        .iter()
+        .position(|sample| sample.abs() > threshold)
+        .map_or(first * frame_length, |i| from + i);
+    let start = start.saturating_sub((START_MARGIN_SECONDS * sample_rate as f32) as usize);
+
+    let end = ((last + 1) * frame_length).min(samples.len());
+    Some((start, end))
+}
+
+/// The frames at which the transients of a mono signal start, in order, to slice a loop into one-shots.
+///
+/// A transient is a sudden rise of the level within 30 dB of the peak. It starts at the first sample of its
+/// attack louder than the signal before it, so that each slice keeps the whole hit.
+pub fn detect_transients(samples: &[f32], sample_rate: u32) -> Vec<usize> {
+    let frame_length = ((ENVELOPE_FRAME_SECONDS * sample_rate.max(1) as f32) as usize).max(1);
+    let envelope = envelope_db(samples, frame_length);
+
+    let peak_db = envelope.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if peak_db < SILENCE_DB {
+        return Vec::new();
+    }
+
+    let min_gap = ((MIN_SLICE_SECONDS / ENVELOPE_FRAME_SECONDS) as usize).max(1);
+    let margin = (START_MARGIN_SECONDS * sample_rate as f32) as usize;
+    find_onsets(&envelope, peak_db, TRANSIENT_RISE_DB, min_gap)
+        .into_iter()
+        .map(|onset| {
+            // The first sample 3 dB above the loudest sample of the frames before, or above the audible level
+            let from = onset.saturating_sub(1) * frame_length;
+            let before = samples[onset.saturating_sub(4) * frame_length..from]
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            let threshold =
+                (before * 10f32.powf(3.0 / 20.0)).max(10f32.powf((peak_db + AUDIBLE_DB) / 20.0));
+
+            let to = ((onset + 1) * frame_length).min(samples.len());
+            let start = samples[from..to]
+                .iter()
+                .position(|sample| sample.abs() > threshold)
+                .map_or(onset * frame_length, |i| from + i);
+            start.saturating_sub(margin)
+        })
+        .collect()
+}
+
+/// Decode the file at `path` and detect its transients, see [`detect_transients`]. Returns the frames at which
+/// they start and the length of the file in frames, or `None` if it cannot be decoded.
+pub fn detect_file_transients(path: &Path) -> Option<(Vec<usize>, usize)> {
+    let (samples, sample_rate) = decode_mono(path, MAX_SLICING_SECONDS)?;
+    Some((detect_transients(&samples, sample_rate), samples.len()))
+}
+
+/// Classify a mono signal as a one-shot or loop and, for one-shots, by drum type.
+///
+/// ACID flags take precedence over the signal, and the file `name` is used when the drum type cannot be
+/// told from the signal.
+pub fn classify(
+    samples: &[f32],
+    sample_rate: u32,
+    name: &str,
+    sampler: &SamplerInfo,
+) -> Classification {
+    let features = Features::new(samples, sample_rate);
+
+    let loop_score = if sampler.one_shot {
+        0.05
+    } else if sampler.tempo.is_some() || sampler.beats.is_some() {
+        0.95
+    } else {
+        features.loop_score(sampler.loop_points().is_some())
+    };
+
+    let (kind, kind_confidence) = match loop_score >= 0.5 {
+        true => (SampleKind::Loop, loop_score),
+        false => (SampleKind::OneShot, 1.0 - loop_score),
+    };
+
+    let drum = match kind {
+        SampleKind::OneShot => {
+            let detected = features.drum_type();
+            match (detected, DrumType::from_name(name)) {
+                // The name confirms the signal
+                (Some((drum, confidence)), Some(hint)) if drum == hint => {
+                    Some((drum, (confidence + 0.2).min(0.95)))
+                }
+                (Some((drum, confidence)), _) if confidence >= 0.5 => Some((drum, confidence)),
+                (_, Some(hint)) => Some((hint, 0.6)),
+                (detected, None) => detected,
+            }
+        }
+        SampleKind::Loop => None,
+    };
+
+    Classification { kind, kind_confidence, drum }
+}
+
+// Envelope and spectral features of a signal
+#[derive(Debug, Default)]
+struct Features {
+    // The length of the signal in seconds
+    duration: f32,
+    // The peak level of the envelope in dB
+    peak_db: f32,
+    // The number of attacks
+    onsets: usize,
+    // The level of the last tenth of the signal relative to the peak, in dB
+    tail_db: f32,
+    // The time from the peak until the level falls 20 dB below it
+    decay: f32,
+    // The proportion of the attack energy below 150 Hz
+    low: f32,
+    // The proportion of the attack energy above 5 kHz
+    high: f32,
+    // The number of zero crossings per second in the attack, high for noise
+    zero_crossings: f32,
+    // The number of bursts in the first 60 ms, as in a clap
+    bursts: usize,
+}
+
+impl Features {
+    fn new(samples: &[f32], sample_rate: u32) -> Self {
+        let sample_rate = sample_rate.max(1) as f32;
+        let frame_length = ((ENVELOPE_FRAME_SECONDS * sample_rate) as usize).max(1);
+        let envelope = envelope_db(samples, frame_length);
+
+        let Some((peak_frame, &peak_db)) =
+            envelope.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))
+        else {
+            return Self { peak_db: f32::NEG_INFINITY, ..Default::default() };
+        };
+
+        let onsets = find_onsets(&envelope, peak_db, 9.0, 10).len();
+
+        let tail_start = envelope.len() - (envelope.len() / 10).max(1);
+        let tail = &envelope[tail_start..];
+        let tail_db = tail.iter().sum::<f32>() / tail.len() as f32 - peak_db;
+
+        let decay_frames = envelope[peak_frame..]
+            .iter()
+            .position(|db| *db < peak_db - 20.0)
+            .unwrap_or(envelope.len() - peak_frame);
+
+        // Spectral balance of the attack, measured with two-pole filters
+        let start = peak_frame.saturating_sub(2) * frame_length;
+        let end = (start + (ATTACK_SECONDS * sample_rate) as usize).min(samples.len());
+        let attack = &samples[start..end];
+        let total = energy(attack).max(f32::EPSILON);
+        let low = lowpass(&lowpass(attack, 150.0, sample_rate), 150.0, sample_rate);
+        let high = highpass(&highpass(attack, 5000.0, sample_rate), 5000.0, sample_rate);
+
+        let crossings = attack.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+
+        // Claps are a few bursts of noise about 10 ms apart
+        let noise = highpass(
+            &attack[..attack.len().min((0.06 * sample_rate) as usize)],
+            1000.0,
+            sample_rate,
+        );
+        let fine = envelope_db(&noise, ((0.002 * sample_rate) as usize).max(1));
+        let fine_peak = fine.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let bursts = find_onsets(&fine, fine_peak, 6.0, 4).len();
+
+        Self {
+            duration: samples.len() as f32 / sample_rate,
+            peak_db,
+            onsets,
+            tail_db,
+            decay: (decay_frames * frame_length) as f32 / sample_rate,
+            low: (energy(&low) / total).min(1.0),
+            high: (energy(&high) / total).min(1.0),
+            zero_crossings: crossings as f32
+                / (attack.len() as f32 / sample_rate).max(f32::EPSILON),
+            bursts,
+        }
+    }
+
+    fn is_silent(&self) -> bool {
+        self.peak_db < SILENCE_DB
+    }
+
+    // The likelihood of the signal being a loop, from 0 for a one-shot to 1 for a loop.
+    fn loop_score(&self, has_loop_points: bool) -> f32 {
+        if self.is_silent() {
+            return 0.3;
+        }
+
+        let mut score: f32 = 0.5;
+
+        score += match self.duration {
+            d if d < 1.0 => -0.3,
+            d if d < 2.0 => -0.1,
+            d if d > 4.0 => 0.2,
+            _ => 0.0,
+        };
+
+        score += match self.onsets {
+            0 | 1 => -0.3,
+            2 | 3 => 0.0,
+            _ => 0.3,
+        };
+
+        // One-shots decay to silence, loops are cut at the end of a bar
+        score += match self.tail_db {
+            db if db < -40.0 => -0.25,
+            db if db > -20.0 => 0.25,
+            _ => 0.0,
+        };
+
+        if has_loop_points {
+            score += 0.15;
+        }
+
+        score.clamp(0.05, 0.95)
+    }
+
+    // The most likely drum type and its confidence.
+    fn drum_type(&self) -> Option<(DrumType, f32)> {
+        if self.is_silent() {
+            return None;
+        }
+
+        // Noise crosses zero at about half the sample rate, tones at twice their frequency
+        let noisy = ramp(self.zero_crossings, 3000.0, 10000.0);
+        let clap_factor = if self.bursts >= 2 { 1.0 } else { 0.2 };
+
+        let scores = [
+            (DrumType::Kick, ramp(self.low, 0.4, 0.8) * (1.0 - self.high)),
+            (
+                DrumType::Tom,
+                bell(self.low, 0.35, 0.3)
+                    * (1.0 - 0.7 * noisy)
+                    * if self.decay > 0.15 { 1.0 } else { 0.7 },
+            ),
+            (
+                DrumType::Snare,
+                noisy * (1.0 - ramp(self.high, 0.1, 0.2)) * (1.0 - ramp(self.low, 0.3, 0.6)),
+            ),
+            (
+                DrumType::Clap,
+                noisy * clap_factor * (1.0 - ramp(self.high, 0.15, 0.3)) * (1.0 - self.low),
+            ),
+            (
+                DrumType::HiHat,
+                ramp(self.high, 0.1, 0.2)
+                    * ramp(self.zero_crossings, 15000.0, 25000.0)
+                    * (1.0 - ramp(self.low, 0.02, 0.1)),
+            ),
+            (DrumType::Perc, 0.3),
+        ];
+
+        let mut scores = scores.map(|(drum, score)| (drum, score.clamp(0.0, 1.0)));
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let (drum, best) = scores[0];
+        let second = scores[1].1;
+        let confidence = best * (0.5 + 0.5 * (best - second) / best.max(f32::EPSILON));
+
+        Some((drum, confidence.clamp(0.05, 0.95)))
+    }
+}
+
+// The RMS level of consecutive frames in dB.
+fn envelope_db(samples: &[f32], frame_length: usize) -> Vec<f32> {
+    samples
+        .chunks(frame_length)
+        .map(|frame| {
+            let rms = (energy(frame) / frame.len() as f32).sqrt();
+            20.0 * (rms + 1e-9).log10()
+        })
+        .collect()
+}
+
+// The frames rising by `rise_db` above the quietest of the three frames before them, at most 30 dB below the
+// peak and at least `min_gap` frames apart.
+fn find_onsets(envelope: &[f32], peak_db: f32, rise_db: f32, min_gap: usize) -> Vec<usize> {
+    let mut onsets = Vec::new();
+    let mut last_onset: Option<usize> = None;
+
+    for (i, db) in envelope.iter().enumerate() {
+        // The start of the file counts as silence
+        let previous =
+            envelope[i.saturating_sub(3)..i].iter().copied().reduce(f32::min).unwrap_or(-120.0);
+
+        let gap = last_onset.is_none_or(|last| i - last >= min_gap);
+        if *db > peak_db - 30.0 && db - previous > rise_db && gap {
+            onsets.push(i);
+            last_onset = Some(i);
+        }
+    }
+
+    onsets
+}
+
+fn energy(samples: &[f32]) -> f32 {
+    samples.iter().map(|x| x * x).sum()
+}
+
+fn lowpass(samples: &[f32], cutoff: f32, sample_rate: f32) -> Vec<f32> {
+    let a = (-2.0 * PI * cutoff / sample_rate).exp();
+    let mut y = 0.0;
+    samples
+        .iter()
+        .map(|x| {
+            y = (1.0 - a) * x + a * y;
+            y
+        })
+        .collect()
+}
+
+fn highpass(samples: &[f32], cutoff: f32, sample_rate: f32) -> Vec<f32> {
+    samples.iter().zip(lowpass(samples, cutoff, sample_rate)).map(|(x, low)| x - low).collect()
+}
+
+// 0 below `from`, rising linearly to 1 at `to`.
+fn ramp(x: f32, from: f32, to: f32) -> f32 {
+    ((x - from) / (to - from)).clamp(0.0, 1.0)
+}
+
+// 1 at `center`, falling linearly to 0 at `width` from it.
+fn bell(x: f32, center: f32, width: f32) -> f32 {
+    (1.0 - (x - center).abs() / width).clamp(0.0, 1.0)
+}
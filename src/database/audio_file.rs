@@ -2,7 +2,11 @@ use std::path::PathBuf;
 
 use vizia::prelude::*;
 
-use super::{CollectionID, Database, DatabaseConnectionHandle, DatabaseError};
+use super::{
+    AudioFormat, CollectionID, Database, DatabaseConnectionHandle, DatabaseError, Pitch,
+    SampleFormat, SamplerInfo,
+};
+use rusqlite::{params, Row};
 use serde::{Deserialize, Serialize};
 
 pub type AudioFileID = usize;
@@ -11,13 +15,30 @@ pub struct AudioFile {
     pub id: AudioFileID,
     pub name: String,
     pub collection: CollectionID,
+    // The duration in seconds
     pub duration: f32,
-    pub sample_rate: f32,
-    pub bit_depth: f32,
-    pub num_channels: f32,
+    pub sample_rate: u32,
+    // The number of bits per sample, `None` for lossy codecs
+    pub bit_depth: Option<u16>,
+    pub num_channels: u16,
     pub bpm: Option<f32>,
-    pub key: Option<f32>,
-    pub size: f32,
+    pub key: Option<u8>,
+    // The size of the file on disk in bytes
+    pub size: u64,
+    // The container format
+    pub format: AudioFormat,
+    // How the samples are stored, `None` for lossy codecs
+    pub sample_format: Option<SampleFormat>,
+    pub codec: String,
+    // Root note, loop points and tempo read from `smpl` and `acid` chunks
+    pub sampler: SamplerInfo,
+    // The detected pitch of a tonal one-shot
+    pub pitch: Option<Pitch>,
+    // The correction in cents applied when auditioning the file retuned to another root
+    pub tuning_offset: Option<f32>,
+    // The frames at which the file becomes audible and falls silent, without leading silence and noise tails
+    pub audible_start: Option<u64>,
+    pub audible_end: Option<u64>,
 }
 
 impl AudioFile {
@@ -26,12 +47,15 @@ impl AudioFile {
         name: String,
         collection: CollectionID,
         duration: f32,
-        sample_rate: f32,
-        bit_depth: f32,
-        num_channels: f32,
+        sample_rate: u32,
+        bit_depth: Option<u16>,
+        num_channels: u16,
         bpm: Option<f32>,
-        key: Option<f32>,
-        size: f32,
+        key: Option<u8>,
+        size: u64,
+        format: AudioFormat,
+        sample_format: Option<SampleFormat>,
+        codec: String,
     ) -> Self {
         Self {
             id,
@@ -44,6 +68,14 @@ impl AudioFile {
             bpm,
             key,
             size,
+            format,
+            sample_format,
+            codec,
+            sampler: SamplerInfo::default(),
+            pitch: None,
+            tuning_offset: None,
+            audible_start: None,
+            audible_end: None,
         }
     }
 }
@@ -51,29 +83,25 @@ impl AudioFile {
 pub trait DatabaseAudioFileHandler {
     fn get_all_audio_files(&self) -> Result<Vec<AudioFile>, DatabaseError>;
     fn get_child_audio_files(&self, parent: CollectionID) -> Result<Vec<AudioFile>, DatabaseError>;
+    fn get_audio_file(&self, id: AudioFileID) -> Result<AudioFile, DatabaseError>;
+    fn get_audio_file_by_name(
+        &self,
+        collection: CollectionID,
+        name: &str,
+    ) -> Result<AudioFile, DatabaseError>;
+    fn next_audio_file_id(&self) -> Result<AudioFileID, DatabaseError>;
     fn insert_audio_file(&mut self, audio_file: AudioFile) -> Result<(), DatabaseError>;
+    fn update_audio_file(&mut self, audio_file: AudioFile) -> Result<(), DatabaseError>;
+    fn remove_audio_file(&mut self, id: AudioFileID) -> Result<(), DatabaseError>;
 }
 
 impl DatabaseAudioFileHandler for Database {
     fn get_all_audio_files(&self) -> Result<Vec<AudioFile>, DatabaseError> {
         if let Some(connection) = self.get_connection() {
             let mut query = connection
-                .prepare("SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size FROM audio_files")?;
-
-            let audio_files = query.query_map([], |row| {
-                Ok(AudioFile {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    collection: row.get(2)?,
-                    duration: row.get(3)?,
-                    sample_rate: row.get(4)?,
-                    bit_depth: row.get(5)?,
-                    num_channels: row.get(6)?,
-                    bpm: row.get(7)?,
-                    key: row.get(8)?,
-                    size: row.get(9)?,
-                })
-            })?;
+                .prepare("SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size, format, sample_format, codec, root_note, loop_start, loop_end, tempo, beats, one_shot, pitch_note, pitch_cents, tuning_offset, audible_start, audible_end FROM audio_files")?;
+
+            let audio_files = query.query_map([], audio_file_from_row)?;
 
             return Ok(audio_files.map(|v| v.unwrap()).collect());
         }
@@ -84,34 +112,104 @@ impl DatabaseAudioFileHandler for Database {
     fn get_child_audio_files(&self, parent: CollectionID) -> Result<Vec<AudioFile>, DatabaseError> {
         if let Some(connection) = self.get_connection() {
             let mut query = connection.prepare(
-                "SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size FROM audio_files WHERE collection = (?1)",
+                "SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size, format, sample_format, codec, root_note, loop_start, loop_end, tempo, beats, one_shot, pitch_note, pitch_cents, tuning_offset, audible_start, audible_end FROM audio_files WHERE collection = (?1)",
             )?;
 
-            let audio_files = query.query_map([parent], |row| {
-                Ok(AudioFile {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    collection: row.get(2)?,
-                    duration: row.get(3)?,
-                    sample_rate: row.get(4)?,
-                    bit_depth: row.get(5)?,
-                    num_channels: row.get(6)?,
-                    bpm: row.get(7)?,
-                    key: row.get(8)?,
-                    size: row.get(9)?,
-                })
-            })?;
+            let audio_files = query.query_map([parent], audio_file_from_row)?;
 
             return Ok(audio_files.map(|v| v.unwrap()).collect());
         }
         Err(DatabaseError::ConnectionClosed)
     }
 
+    fn get_audio_file(&self, id: AudioFileID) -> Result<AudioFile, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size, format, sample_format, codec, root_note, loop_start, loop_end, tempo, beats, one_shot, pitch_note, pitch_cents, tuning_offset, audible_start, audible_end FROM audio_files WHERE id = (?1)",
+            )?;
+
+            let audio_file = query.query_row([id], audio_file_from_row)?;
+
+            return Ok(audio_file);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn get_audio_file_by_name(
+        &self,
+        collection: CollectionID,
+        name: &str,
+    ) -> Result<AudioFile, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare(
+                "SELECT id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size, format, sample_format, codec, root_note, loop_start, loop_end, tempo, beats, one_shot, pitch_note, pitch_cents, tuning_offset, audible_start, audible_end FROM audio_files WHERE collection = (?1) AND name = (?2)",
+            )?;
+
+            let audio_file = query.query_row((collection, name), audio_file_from_row)?;
+
+            return Ok(audio_file);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn next_audio_file_id(&self) -> Result<AudioFileID, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let id = connection.query_row(
+                "SELECT COALESCE(MAX(id) + 1, 0) FROM audio_files",
+                [],
+                |row| row.get(0),
+            )?;
+
+            return Ok(id);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
     fn insert_audio_file(&mut self, audio_file: AudioFile) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "INSERT INTO audio_files (id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size, format, sample_format, codec, root_note, loop_start, loop_end, tempo, beats, one_shot, pitch_note, pitch_cents, tuning_offset, audible_start, audible_end) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+            )?;
+
+            query.execute(params![
+                audio_file.id,
+                audio_file.name,
+                audio_file.collection,
+                audio_file.duration,
+                audio_file.sample_rate,
+                audio_file.bit_depth,
+                audio_file.num_channels,
+                audio_file.bpm,
+                audio_file.key,
+                audio_file.size,
+                audio_file.format,
+                audio_file.sample_format,
+                audio_file.codec,
+                audio_file.sampler.root_note,
+                audio_file.sampler.loop_start,
+                audio_file.sampler.loop_end,
+                audio_file.sampler.tempo,
+                audio_file.sampler.beats,
+                audio_file.sampler.one_shot,
+                audio_file.pitch.map(|pitch| pitch.note),
+                audio_file.pitch.map(|pitch| pitch.cents),
+                audio_file.tuning_offset,
+                audio_file.audible_start,
+                audio_file.audible_end,
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    fn update_audio_file(&mut self, audio_file: AudioFile) -> Result<(), DatabaseError> {
         if let Some(connection) = self.get_connection() {
             connection.execute(
-                "INSERT INTO audio_files (id, name, collection, duration, sample_rate, bit_depth, num_channels, bpm, key, size) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                (
+                "UPDATE audio_files SET name = (?2), collection = (?3), duration = (?4), sample_rate = (?5), bit_depth = (?6), num_channels = (?7), bpm = (?8), key = (?9), size = (?10), format = (?11), sample_format = (?12), codec = (?13), root_note = (?14), loop_start = (?15), loop_end = (?16), tempo = (?17), beats = (?18), one_shot = (?19), pitch_note = (?20), pitch_cents = (?21), tuning_offset = (?22), audible_start = (?23), audible_end = (?24) WHERE id = (?1)",
+                params![
                     audio_file.id,
                     audio_file.name,
                     audio_file.collection,
@@ -122,12 +220,73 @@ impl DatabaseAudioFileHandler for Database {
                     audio_file.bpm,
                     audio_file.key,
                     audio_file.size,
-                ),
+                    audio_file.format,
+                    audio_file.sample_format,
+                    audio_file.codec,
+                    audio_file.sampler.root_note,
+                    audio_file.sampler.loop_start,
+                    audio_file.sampler.loop_end,
+                    audio_file.sampler.tempo,
+                    audio_file.sampler.beats,
+                    audio_file.sampler.one_shot,
+                    audio_file.pitch.map(|pitch| pitch.note),
+                    audio_file.pitch.map(|pitch| pitch.cents),
+                    audio_file.tuning_offset,
+                    audio_file.audible_start,
+                    audio_file.audible_end,
+                ],
             )?;
         }
 
         Ok(())
     }
+
+    fn remove_audio_file(&mut self, id: AudioFileID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute("DELETE FROM audio_files_tags WHERE audio_file = (?1)", [id])?;
+            connection.execute("DELETE FROM audio_files_metadata WHERE audio_file = (?1)", [id])?;
+            connection.execute("DELETE FROM audio_files_features WHERE audio_file = (?1)", [id])?;
+            connection
+                .execute("UPDATE kits_slots SET audio_file = NULL WHERE audio_file = (?1)", [id])?;
+            connection.execute("DELETE FROM audio_files WHERE id = (?1)", [id])?;
+        }
+
+        self.invalidate_feature_index();
+        Ok(())
+    }
+}
+
+fn audio_file_from_row(row: &Row) -> rusqlite::Result<AudioFile> {
+    Ok(AudioFile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        collection: row.get(2)?,
+        duration: row.get(3)?,
+        sample_rate: row.get(4)?,
+        bit_depth: row.get(5)?,
+        num_channels: row.get(6)?,
+        bpm: row.get(7)?,
+        key: row.get(8)?,
+        size: row.get(9)?,
+        format: row.get(10)?,
+        sample_format: row.get(11)?,
+        codec: row.get(12)?,
+        sampler: SamplerInfo {
+            root_note: row.get(13)?,
+            loop_start: row.get(14)?,
+            loop_end: row.get(15)?,
+            tempo: row.get(16)?,
+            beats: row.get(17)?,
+            one_shot: row.get(18)?,
+        },
+        pitch: match (row.get(19)?, row.get(20)?) {
+            (Some(note), Some(cents)) => Some(Pitch { note, cents }),
+            _ => None,
+        },
+        tuning_offset: row.get(21)?,
+        audible_start: row.get(22)?,
+        audible_end: row.get(23)?,
+    })
 }
 
 impl From<AudioFile> for usize {
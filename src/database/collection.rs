@@ -41,11 +41,15 @@ impl Collection {
 
 pub trait DatabaseCollectionHandler {
     fn get_collection(&self, id: CollectionID) -> Result<Collection, DatabaseError>;
+    fn get_collection_by_path(&self, path: &Path) -> Result<Collection, DatabaseError>;
     fn get_root_collection(&self) -> Result<Collection, DatabaseError>;
     fn get_all_collections(&self) -> Result<Vec<Collection>, DatabaseError>;
     fn get_child_collections(&self, parent: CollectionID)
         -> Result<Vec<Collection>, DatabaseError>;
+    fn next_collection_id(&self) -> Result<CollectionID, DatabaseError>;
     fn insert_collection(&mut self, collection: Collection) -> Result<(), DatabaseError>;
+    fn update_collection(&mut self, collection: Collection) -> Result<(), DatabaseError>;
+    fn remove_collection(&mut self, id: CollectionID) -> Result<(), DatabaseError>;
 }
 
 impl DatabaseCollectionHandler for Database {
@@ -71,6 +75,28 @@ impl DatabaseCollectionHandler for Database {
         Err(DatabaseError::ConnectionClosed)
     }
 
+    fn get_collection_by_path(&self, path: &Path) -> Result<Collection, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare(
+                "SELECT id, parent_collection, name, path FROM collections WHERE path = (?1)",
+            )?;
+
+            let collection = query.query_row([path.to_str().unwrap()], |row| {
+                let path: String = row.get(3)?;
+                Ok(Collection::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    Path::new(&path).to_path_buf(),
+                ))
+            })?;
+
+            return Ok(collection);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
     fn get_root_collection(&self) -> Result<Collection, DatabaseError> {
         if let Some(connection) = self.get_connection() {
             let mut query = connection.prepare(
@@ -144,14 +170,83 @@ impl DatabaseCollectionHandler for Database {
 
     fn insert_collection(&mut self, collection: Collection) -> Result<(), DatabaseError> {
         if let Some(connection) = self.get_connection() {
-            connection.execute(
+            let mut query = connection.prepare_cached(
                 "INSERT INTO collections (id, parent_collection, name, path) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+
+            query.execute((
+                collection.id,
+                collection.parent_collection,
+                collection.name,
+                collection.path.to_str().unwrap(),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    fn next_collection_id(&self) -> Result<CollectionID, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let id = connection.query_row(
+                "SELECT COALESCE(MAX(id) + 1, 0) FROM collections",
+                [],
+                |row| row.get(0),
+            )?;
+
+            return Ok(id);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn update_collection(&mut self, collection: Collection) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "UPDATE collections SET parent_collection = (?2), name = (?3), path = (?4) WHERE id = (?1)",
                 (collection.id, collection.parent_collection, collection.name, collection.path.to_str().unwrap()),
             )?;
         }
 
         Ok(())
     }
+
+    fn remove_collection(&mut self, id: CollectionID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            // Foreign keys are not enforced, so remove the whole subtree explicitly
+            connection.execute_batch(&format!(
+                "
+            CREATE TEMP TABLE removed_collections AS
+                WITH RECURSIVE subtree(id) AS (
+                    SELECT {id}
+                    UNION ALL
+                    SELECT collections.id FROM collections
+                        JOIN subtree ON collections.parent_collection = subtree.id
+                )
+                SELECT id FROM subtree;
+
+            DELETE FROM audio_files_tags WHERE audio_file IN (
+                SELECT id FROM audio_files WHERE collection IN (SELECT id FROM removed_collections)
+            );
+            DELETE FROM audio_files_metadata WHERE audio_file IN (
+                SELECT id FROM audio_files WHERE collection IN (SELECT id FROM removed_collections)
+            );
+            DELETE FROM audio_files_features WHERE audio_file IN (
+                SELECT id FROM audio_files WHERE collection IN (SELECT id FROM removed_collections)
+            );
+            UPDATE kits_slots SET audio_file = NULL WHERE audio_file IN (
+                SELECT id FROM audio_files WHERE collection IN (SELECT id FROM removed_collections)
+            );
+            DELETE FROM audio_files WHERE collection IN (SELECT id FROM removed_collections);
+            DELETE FROM collections WHERE id IN (SELECT id FROM removed_collections);
+
+            DROP TABLE removed_collections;
+            "
+            ))?;
+        }
+
+        self.invalidate_feature_index();
+        Ok(())
+    }
 }
 
 impl From<Collection> for usize {
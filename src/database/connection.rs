@@ -2,7 +2,7 @@ use std::fs::File;
 
 use rusqlite::Connection;
 
-use super::{Database, DatabaseError, DatabaseStore};
+use super::{create_schema, migrate, Database, DatabaseError, DatabaseStore};
 
 pub trait DatabaseConnectionHandle {
     fn get_connection(&self) -> Option<&Connection>;
@@ -22,8 +22,14 @@ impl DatabaseConnectionHandle for Database {
             self.conn = Some(Connection::open(self.get_database_path())?);
         }
 
-        if !database_exists {
-            self.get_connection().unwrap().execute_batch(include_str!("sqls/schema.sql")).unwrap();
+        let connection = self.get_connection().ok_or(DatabaseError::ConnectionClosed)?;
+        // The rows of removed files and kits are removed with them through the foreign keys of the schema, which
+        // SQLite only enforces when asked to
+        connection.pragma_update(None, "foreign_keys", true)?;
+        if database_exists {
+            migrate(connection)?;
+        } else {
+            create_schema(connection)?;
         }
 
         Ok(())
@@ -0,0 +1,627 @@
+//! Reading of the metadata embedded in audio files by recorders and sound library tools.
+//!
+//! Supports the broadcast-wave `bext` chunk, iXML, RIFF `LIST/INFO`, AIFF text chunks, ID3v2.3/2.4 tags and
+//! Vorbis comments in FLAC and Ogg files, as well as the `smpl` and `acid` chunks of sampler and loop files.
+
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+use vizia::prelude::*;
+
+// Chunks larger than this are skipped, metadata chunks are much smaller
+const MAX_METADATA_CHUNK_SIZE: u64 = 1 << 20;
+
+// The number of bytes read from an Ogg stream to find its comment header
+const MAX_OGG_HEADER_SIZE: usize = 1 << 20;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// The playback information stored for samplers in the `smpl` and `acid` chunks of a wave file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Data, Lens)]
+pub struct SamplerInfo {
+    // The MIDI note at which the file plays at its original pitch
+    pub root_note: Option<u8>,
+    // The first frame of the loop
+    pub loop_start: Option<u64>,
+    // The frame after the last frame of the loop
+    pub loop_end: Option<u64>,
+    // The tempo of an acidized loop
+    pub tempo: Option<f32>,
+    // The length of an acidized loop in beats
+    pub beats: Option<u32>,
+    // Whether the file is marked as a one-shot rather than a loop
+    pub one_shot: bool,
+}
+
+impl SamplerInfo {
+    /// The loop points, if the file has a loop which is not empty.
+    pub fn loop_points(&self) -> Option<(u64, u64)> {
+        match (self.loop_start, self.loop_end) {
+            (Some(start), Some(end)) if end > start => Some((start, end)),
+            _ => None,
+        }
+    }
+}
+
+/// The name of a MIDI note, with middle C (60) as `C4`.
+pub fn note_name(note: u8) -> String {
+    format!("{}{}", NOTE_NAMES[note as usize % 12], note as i32 / 12 - 1)
+}
+
+/// The name of a key stored as a pitch class, with C as 0.
+pub fn key_name(key: u8) -> &'static str {
+    NOTE_NAMES[key as usize % 12]
+}
+
+/// Parse a note name such as `F#`, `Bb1` or `C-1`, ignoring case, into its pitch class and, when it has an
+/// octave, its MIDI note.
+pub fn parse_note_name(name: &str) -> Option<(u8, Option<u8>)> {
+    let name = name.trim().to_ascii_uppercase();
+    let (letter, rest) = name.split_at_checked(1)?;
+    let mut pitch_class = NOTE_NAMES.iter().position(|note| *note == letter)? as i32;
+
+    let rest = if let Some(rest) = rest.strip_prefix('#') {
+        pitch_class += 1;
+        rest
+    } else if let Some(rest) = rest.strip_prefix('B') {
+        pitch_class -= 1;
+        rest
+    } else {
+        rest
+    };
+
+    let note = match rest {
+        "" => None,
+        octave => {
+            let note = (octave.parse::<i32>().ok()? + 1) * 12 + pitch_class;
+            Some(u8::try_from(note).ok().filter(|note| *note < 128)?)
+        }
+    };
+
+    Some((pitch_class.rem_euclid(12) as u8, note))
+}
+
+/// Read the metadata embedded in the file at `path`.
+///
+/// Files which cannot be read or contain malformed metadata yield whatever could be parsed before the error.
+pub fn read_embedded_metadata(path: &Path, format: AudioFormat) -> Vec<MetadataEntry> {
+    let Ok(file) = File::open(path) else { return Vec::new() };
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::new();
+
+    let _ = match format {
+        AudioFormat::Wav => read_riff(&mut reader, &mut entries),
+        AudioFormat::Aiff => read_aiff(&mut reader, &mut entries),
+        AudioFormat::Flac => read_flac(&mut reader, &mut entries),
+        AudioFormat::Mp3 => read_id3_header(&mut reader, &mut entries),
+        AudioFormat::Ogg => read_ogg(&mut reader, &mut entries),
+        AudioFormat::M4a | AudioFormat::Caf => Ok(()),
+    };
+
+    entries.dedup();
+    entries
+}
+
+fn push(entries: &mut Vec<MetadataEntry>, source: MetadataSource, key: &str, value: &str) {
+    let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if !value.is_empty() {
+        entries.push(MetadataEntry::new(source, key.to_string(), value.to_string()));
+    }
+}
+
+fn read_chunk<R: Read>(reader: &mut R, size: u64) -> std::io::Result<Vec<u8>> {
+    let mut data = vec![0; size as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// RIFF/RF64 wave files, little-endian chunks
+fn read_riff<R: Read + Seek>(
+    reader: &mut R,
+    entries: &mut Vec<MetadataEntry>,
+) -> std::io::Result<()> {
+    for_each_riff_chunk(reader, &[b"bext", b"iXML", b"LIST", b"id3 ", b"ID3 "], |id, data| match id
+    {
+        b"bext" => parse_bext(&data, entries),
+        b"iXML" => parse_ixml(&String::from_utf8_lossy(&data), entries),
+        b"LIST" if data.starts_with(b"INFO") => parse_info(&data[4..], entries),
+        b"id3 " | b"ID3 " => parse_id3(&data, entries),
+        _ => {}
+    })
+}
+
+// Call `f` with the contents of the chunks of a wave file with one of the given ids.
+fn for_each_riff_chunk<R: Read + Seek>(
+    reader: &mut R,
+    ids: &[&[u8; 4]],
+    mut f: impl FnMut(&[u8], Vec<u8>),
+) -> std::io::Result<()> {
+    let header = read_chunk(reader, 12)?;
+    if &header[8..12] != b"WAVE" {
+        return Ok(());
+    }
+
+    // RF64 files store the size of the data chunk in the `ds64` chunk
+    let mut data_size_64 = None;
+
+    loop {
+        let header = read_chunk(reader, 8)?;
+        let id = &header[0..4];
+        let mut size = read_u32_le(&header[4..8]) as u64;
+
+        if id == b"data" && size == u32::MAX as u64 {
+            let Some(data_size) = data_size_64 else { return Ok(()) };
+            size = data_size;
+        }
+
+        if id == b"ds64" && size <= MAX_METADATA_CHUNK_SIZE {
+            let data = read_chunk(reader, size)?;
+            if data.len() >= 16 {
+                data_size_64 = Some(u64::from_le_bytes(data[8..16].try_into().unwrap()));
+            }
+        } else if ids.iter().any(|chunk_id| chunk_id.as_slice() == id)
+            && size <= MAX_METADATA_CHUNK_SIZE
+        {
+            f(id, read_chunk(reader, size)?);
+        } else {
+            reader.seek(SeekFrom::Current(size as i64))?;
+        }
+
+        // Chunks are padded to an even size
+        if size % 2 == 1 {
+            reader.seek(SeekFrom::Current(1))?;
+        }
+    }
+}
+
+/// Read the sampler information of the wave file at `path` from its `smpl` and `acid` chunks.
+pub fn read_sampler_info(path: &Path) -> SamplerInfo {
+    read_sampler_chunks(path).0
+}
+
+/// Read the sampler information of the wave file at `path` along with the root note of its `acid` chunk, only
+/// set when the chunk marks the file as having one.
+pub fn read_sampler_chunks(path: &Path) -> (SamplerInfo, Option<u8>) {
+    let mut info = SamplerInfo::default();
+    let mut acid_root_note = None;
+    let Ok(file) = File::open(path) else { return (info, acid_root_note) };
+
+    let _ =
+        for_each_riff_chunk(&mut BufReader::new(file), &[b"smpl", b"acid"], |id, data| match id {
+            b"smpl" => parse_smpl(&data, &mut info),
+            _ => acid_root_note = parse_acid(&data, &mut info),
+        });
+
+    (info, acid_root_note)
+}
+
+// The sampler chunk, of which the unity note and the first loop are used
+fn parse_smpl(data: &[u8], info: &mut SamplerInfo) {
+    if data.len() < 36 {
+        return;
+    }
+
+    // The ACID root note takes precedence, it is only set when valid
+    if info.root_note.is_none() {
+        info.root_note = u8::try_from(read_u32_le(&data[12..16])).ok().filter(|note| *note < 128);
+    }
+
+    // Each loop is 24 bytes: cue point id, type, start, end, fraction and play count
+    let num_loops = read_u32_le(&data[28..32]);
+    if let Some(first_loop) = data.get(36..60).filter(|_| num_loops > 0) {
+        let start = read_u32_le(&first_loop[8..12]) as u64;
+        let end = read_u32_le(&first_loop[12..16]) as u64;
+        // The loop end is the last frame played
+        if end >= start {
+            info.loop_start = Some(start);
+            info.loop_end = Some(end + 1);
+        }
+    }
+}
+
+// The ACID chunk: flags, root note, two reserved fields, the number of beats, the meter and the tempo. Returns
+// the root note if the flags mark the file as having one.
+fn parse_acid(data: &[u8], info: &mut SamplerInfo) -> Option<u8> {
+    if data.len() < 24 {
+        return None;
+    }
+
+    let flags = read_u32_le(&data[0..4]);
+    info.one_shot = flags & 0x01 != 0;
+
+    let mut root_note = None;
+    if flags & 0x02 != 0 {
+        let note = u16::from_le_bytes([data[4], data[5]]);
+        root_note = u8::try_from(note).ok().filter(|note| *note < 128);
+        info.root_note = root_note;
+    }
+
+    let beats = read_u32_le(&data[12..16]);
+    info.beats = (beats > 0).then_some(beats);
+
+    let tempo = f32::from_le_bytes(data[20..24].try_into().unwrap());
+    info.tempo = (tempo.is_finite() && tempo > 0.0).then_some(tempo);
+
+    root_note
+}
+
+// The broadcast-wave extension chunk, see EBU Tech 3285
+fn parse_bext(data: &[u8], entries: &mut Vec<MetadataEntry>) {
+    let text = |range: std::ops::Range<usize>| {
+        data.get(range).map(|bytes| String::from_utf8_lossy(bytes).into_owned()).unwrap_or_default()
+    };
+
+    push(entries, MetadataSource::Bext, "description", &text(0..256));
+    push(entries, MetadataSource::Bext, "originator", &text(256..288));
+    push(entries, MetadataSource::Bext, "originator_reference", &text(288..320));
+    push(entries, MetadataSource::Bext, "origination_date", &text(320..330));
+    push(entries, MetadataSource::Bext, "origination_time", &text(330..338));
+    push(entries, MetadataSource::Bext, "coding_history", &text(602..data.len().max(602)));
+}
+
+// The iXML chunk. Only the top-level fields and the `USER` fields written by library tools are kept, the
+// rest describes the recording setup.
+fn parse_ixml(xml: &str, entries: &mut Vec<MetadataEntry>) {
+    let mut stack: Vec<&str> = Vec::new();
+    // The last element opened and the position of its text
+    let mut open: Option<(&str, usize)> = None;
+
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let offset = xml.len() - rest.len();
+        let Some(end) = rest[start..].find('>') else { break };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            if let Some((open_name, text_start)) = open.take() {
+                let parent = stack.iter().rev().nth(1).copied();
+                if open_name == name && matches!(parent, Some("BWFXML") | Some("USER")) {
+                    let value = unescape_xml(&xml[text_start..offset + start]);
+                    push(entries, MetadataSource::Ixml, &name.to_lowercase(), &value);
+                }
+            }
+            if stack.last() == Some(&name) {
+                stack.pop();
+            }
+        } else {
+            let name = tag.split_whitespace().next().unwrap_or_default();
+            stack.push(name);
+            open = Some((name, offset + start + end + 1));
+        }
+    }
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// The subchunks of a RIFF `LIST/INFO` chunk
+fn parse_info(mut data: &[u8], entries: &mut Vec<MetadataEntry>) {
+    while data.len() >= 8 {
+        let id = &data[0..4];
+        let size = read_u32_le(&data[4..8]) as usize;
+        let Some(value) = data.get(8..8 + size) else { break };
+
+        let key = match id {
+            b"INAM" => "title",
+            b"IART" => "artist",
+            b"IPRD" => "album",
+            b"ICMT" => "comment",
+            b"IGNR" => "genre",
+            b"IKEY" => "keywords",
+            b"ISBJ" => "subject",
+            b"ICOP" => "copyright",
+            b"ICRD" => "date",
+            b"IENG" => "engineer",
+            b"ISFT" => "software",
+            b"ISRC" => "source",
+            _ => "",
+        };
+
+        if !key.is_empty() {
+            push(entries, MetadataSource::Info, key, &String::from_utf8_lossy(value));
+        }
+
+        data = data.get(8 + size + size % 2..).unwrap_or_default();
+    }
+}
+
+// AIFF and AIFF-C files, big-endian chunks
+fn read_aiff<R: Read + Seek>(
+    reader: &mut R,
+    entries: &mut Vec<MetadataEntry>,
+) -> std::io::Result<()> {
+    let header = read_chunk(reader, 12)?;
+    if &header[8..12] != b"AIFF" && &header[8..12] != b"AIFC" {
+        return Ok(());
+    }
+
+    loop {
+        let header = read_chunk(reader, 8)?;
+        let id = &header[0..4];
+        let size = read_u32_be(&header[4..8]) as u64;
+
+        let key = match id {
+            b"NAME" => "title",
+            b"AUTH" => "artist",
+            b"(c) " => "copyright",
+            b"ANNO" => "comment",
+            _ => "",
+        };
+
+        if (!key.is_empty() || id == b"ID3 " || id == b"id3 ") && size <= MAX_METADATA_CHUNK_SIZE {
+            let data = read_chunk(reader, size)?;
+            if key.is_empty() {
+                parse_id3(&data, entries);
+            } else {
+                push(entries, MetadataSource::Aiff, key, &String::from_utf8_lossy(&data));
+            }
+        } else {
+            reader.seek(SeekFrom::Current(size as i64))?;
+        }
+
+        if size % 2 == 1 {
+            reader.seek(SeekFrom::Current(1))?;
+        }
+    }
+}
+
+// An ID3v2 tag at the start of a file
+fn read_id3_header<R: Read>(
+    reader: &mut R,
+    entries: &mut Vec<MetadataEntry>,
+) -> std::io::Result<()> {
+    let header = read_chunk(reader, 10)?;
+    if !header.starts_with(b"ID3") {
+        return Ok(());
+    }
+
+    let size = syncsafe(&header[6..10]) as u64;
+    if size <= MAX_METADATA_CHUNK_SIZE {
+        let mut tag = header;
+        tag.extend(read_chunk(reader, size)?);
+        parse_id3(&tag, entries);
+    }
+
+    Ok(())
+}
+
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |size, byte| (size << 7) | (*byte as u32 & 0x7F))
+}
+
+// An ID3v2.3 or ID3v2.4 tag, including its header
+fn parse_id3(tag: &[u8], entries: &mut Vec<MetadataEntry>) {
+    if tag.len() < 10 || !tag.starts_with(b"ID3") {
+        return;
+    }
+
+    let version = tag[3];
+    let flags = tag[5];
+    // Unsynchronised tags are rare and not supported
+    if !(version == 3 || version == 4) || flags & 0x80 != 0 {
+        return;
+    }
+
+    let end = (10 + syncsafe(&tag[6..10]) as usize).min(tag.len());
+    let mut position = 10;
+
+    // Skip the extended header
+    if flags & 0x40 != 0 {
+        let Some(size) = tag.get(10..14) else { return };
+        position += match version {
+            3 => 4 + read_u32_be(size) as usize,
+            _ => syncsafe(size) as usize,
+        };
+    }
+
+    while position + 10 <= end {
+        let id = &tag[position..position + 4];
+        if id[0] == 0 {
+            break;
+        }
+
+        let size = match version {
+            3 => read_u32_be(&tag[position + 4..position + 8]),
+            _ => syncsafe(&tag[position + 4..position + 8]),
+        } as usize;
+
+        let Some(frame) = tag.get(position + 10..position + 10 + size) else { break };
+        position += 10 + size;
+
+        let Some((&encoding, text)) = frame.split_first() else { continue };
+
+        match id {
+            b"TXXX" => {
+                let (description, value) = split_id3_text(encoding, text);
+                let key = decode_id3_text(encoding, description).to_lowercase();
+                push(entries, MetadataSource::Id3, &key, &decode_id3_text(encoding, value));
+            }
+
+            b"COMM" if text.len() > 3 => {
+                let (_, value) = split_id3_text(encoding, &text[3..]);
+                push(entries, MetadataSource::Id3, "comment", &decode_id3_text(encoding, value));
+            }
+
+            _ => {
+                let key = match id {
+                    b"TIT1" => "grouping",
+                    b"TIT2" => "title",
+                    b"TIT3" => "subtitle",
+                    b"TPE1" => "artist",
+                    b"TALB" => "album",
+                    b"TCON" => "genre",
+                    b"TCOM" => "composer",
+                    b"TYER" | b"TDRC" => "date",
+                    b"TBPM" => "bpm",
+                    b"TKEY" => "key",
+                    b"TCOP" => "copyright",
+                    b"TSSE" => "software",
+                    _ => continue,
+                };
+
+                // Multiple values are separated by terminators
+                let value = decode_id3_text(encoding, text).replace('\0', "; ");
+                push(entries, MetadataSource::Id3, key, &value);
+            }
+        }
+    }
+}
+
+// Split ID3 text at its first terminator, which is two bytes wide in UTF-16 encodings.
+fn split_id3_text(encoding: u8, text: &[u8]) -> (&[u8], &[u8]) {
+    let position = match encoding {
+        1 | 2 => text.chunks(2).position(|c| c == [0, 0]).map(|i| (i * 2, i * 2 + 2)),
+        _ => text.iter().position(|b| *b == 0).map(|i| (i, i + 1)),
+    };
+
+    match position {
+        Some((end, start)) => (&text[..end], &text[start..]),
+        None => (text, &[]),
+    }
+}
+
+fn decode_id3_text(encoding: u8, text: &[u8]) -> String {
+    match encoding {
+        // ISO-8859-1
+        0 => text.iter().map(|b| *b as char).collect(),
+        // UTF-16 with a byte order mark, or big-endian without one
+        1 | 2 => {
+            let (big_endian, text) = match text {
+                [0xFF, 0xFE, rest @ ..] => (false, rest),
+                [0xFE, 0xFF, rest @ ..] => (true, rest),
+                _ => (encoding == 2, text),
+            };
+
+            let units = text.chunks_exact(2).map(|c| match big_endian {
+                true => u16::from_be_bytes([c[0], c[1]]),
+                false => u16::from_le_bytes([c[0], c[1]]),
+            });
+
+            char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+        }
+        _ => String::from_utf8_lossy(text).into_owned(),
+    }
+}
+
+// FLAC metadata blocks
+fn read_flac<R: Read + Seek>(
+    reader: &mut R,
+    entries: &mut Vec<MetadataEntry>,
+) -> std::io::Result<()> {
+    if read_chunk(reader, 4)? != b"fLaC" {
+        return Ok(());
+    }
+
+    loop {
+        let header = read_chunk(reader, 4)?;
+        let last = header[0] & 0x80 != 0;
+        let size = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+
+        match header[0] & 0x7F {
+            // VORBIS_COMMENT
+            4 if size <= MAX_METADATA_CHUNK_SIZE => {
+                parse_vorbis_comments(&read_chunk(reader, size)?, entries);
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(size as i64))?;
+            }
+        }
+
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+// The comment header of a Vorbis or Opus stream, which is the second packet of the first logical stream
+fn read_ogg<R: Read>(reader: &mut R, entries: &mut Vec<MetadataEntry>) -> std::io::Result<()> {
+    let mut serial = None;
+    let mut packets: Vec<Vec<u8>> = vec![Vec::new()];
+    let mut read = 0;
+
+    while packets.len() < 3 && read < MAX_OGG_HEADER_SIZE {
+        let header = read_chunk(reader, 27)?;
+        if !header.starts_with(b"OggS") {
+            return Ok(());
+        }
+
+        let page_serial = read_u32_le(&header[14..18]);
+        let segments = read_chunk(reader, header[26] as u64)?;
+        let data = read_chunk(reader, segments.iter().map(|s| *s as u64).sum())?;
+        read += 27 + segments.len() + data.len();
+
+        if *serial.get_or_insert(page_serial) != page_serial {
+            continue;
+        }
+
+        let mut position = 0;
+        for segment in segments {
+            let segment = segment as usize;
+            packets.last_mut().unwrap().extend_from_slice(&data[position..position + segment]);
+            position += segment;
+
+            // A segment shorter than 255 bytes ends a packet
+            if segment < 255 {
+                packets.push(Vec::new());
+            }
+        }
+    }
+
+    if let Some(packet) = packets.get(1) {
+        if let Some(comments) = packet.strip_prefix(b"\x03vorbis") {
+            parse_vorbis_comments(comments, entries);
+        } else if let Some(comments) = packet.strip_prefix(b"OpusTags") {
+            parse_vorbis_comments(comments, entries);
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_vorbis_comments(data: &[u8], entries: &mut Vec<MetadataEntry>) {
+    let mut fields = data;
+    let mut next = |len: usize| -> Option<&[u8]> {
+        let (field, rest) = (fields.get(..len)?, fields.get(len..)?);
+        fields = rest;
+        Some(field)
+    };
+
+    let Some(vendor_length) = next(4).map(read_u32_le) else { return };
+    if next(vendor_length as usize).is_none() {
+        return;
+    }
+
+    let Some(count) = next(4).map(read_u32_le) else { return };
+    for _ in 0..count {
+        let Some(length) = next(4).map(read_u32_le) else { return };
+        let Some(comment) = next(length as usize) else { return };
+
+        let comment = String::from_utf8_lossy(comment);
+        if let Some((key, value)) = comment.split_once('=') {
+            push(entries, MetadataSource::Vorbis, &key.to_lowercase(), value);
+        }
+    }
+}
@@ -4,6 +4,8 @@ pub enum DatabaseError {
     PathNotDirectory,
     RusqliteError(rusqlite::Error),
     IOError(std::io::Error),
+    WatchError(notify::Error),
+    InvalidPattern(globset::Error),
 }
 
 impl From<rusqlite::Error> for DatabaseError {
@@ -17,3 +19,15 @@ impl From<std::io::Error> for DatabaseError {
         Self::IOError(value)
     }
 }
+
+impl From<notify::Error> for DatabaseError {
+    fn from(value: notify::Error) -> Self {
+        Self::WatchError(value)
+    }
+}
+
+impl From<globset::Error> for DatabaseError {
+    fn from(value: globset::Error) -> Self {
+        Self::InvalidPattern(value)
+    }
+}
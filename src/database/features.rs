@@ -0,0 +1,393 @@
+//! Feature vectors describing the timbre and envelope of audio files, used to find similar sounds.
+
+use super::*;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::{f32::consts::PI, ops::Range};
+
+// The number of cepstral coefficients, without the first one which only measures loudness
+const NUM_MFCC: usize = 12;
+
+// The number of bands of the mel filterbank
+const NUM_MEL_BANDS: usize = 26;
+
+// Frames quieter than the loudest frame by more than this are left out of the spectral features
+const FRAME_RANGE_DB: f32 = 50.0;
+
+/// The number of values in a feature vector.
+pub const FEATURE_LEN: usize = 2 * NUM_MFCC + 4;
+
+// The ranges of values compared as one feature when looking for similar sounds
+const FEATURE_GROUPS: [Range<usize>; 6] = [
+    0..NUM_MFCC,
+    NUM_MFCC..2 * NUM_MFCC,
+    2 * NUM_MFCC..2 * NUM_MFCC + 1,
+    2 * NUM_MFCC + 1..2 * NUM_MFCC + 2,
+    2 * NUM_MFCC + 2..2 * NUM_MFCC + 3,
+    2 * NUM_MFCC + 3..FEATURE_LEN,
+];
+
+/// A compact description of a sound: the means and standard deviations of its MFCCs, followed by its
+/// spectral centroid, spectral flatness, attack time and duration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureVector(pub [f32; FEATURE_LEN]);
+
+impl FeatureVector {
+    /// Compute the features of a mono signal.
+    pub fn new(samples: &[f32], sample_rate: u32) -> Self {
+        let sample_rate = sample_rate.max(1) as f32;
+        let frame_len = ((sample_rate * 0.03) as usize).next_power_of_two().max(256);
+        let hop = frame_len / 2;
+
+        let window: Vec<f32> = (0..frame_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / frame_len as f32).cos())
+            .collect();
+        let filterbank = mel_filterbank(frame_len, sample_rate);
+        let fft = FftPlanner::new().plan_fft_forward(frame_len);
+
+        // The power spectrum and level of every frame
+        let mut spectra = Vec::new();
+        let mut buffer = vec![Complex::default(); frame_len];
+        let mut start = 0;
+        while start < samples.len() {
+            for (i, value) in buffer.iter_mut().enumerate() {
+                let sample = samples.get(start + i).copied().unwrap_or(0.0);
+                *value = Complex::new(sample * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+
+            let power: Vec<f32> =
+                buffer[..frame_len / 2 + 1].iter().map(|c| c.norm_sqr()).collect();
+            let level = 10.0 * (power.iter().sum::<f32>() + 1e-12).log10();
+            spectra.push((power, level));
+            start += hop;
+        }
+
+        let loudest = spectra.iter().map(|(_, level)| *level).fold(f32::NEG_INFINITY, f32::max);
+        let frames: Vec<&Vec<f32>> = spectra
+            .iter()
+            .filter(|(_, level)| *level > loudest - FRAME_RANGE_DB)
+            .map(|(power, _)| power)
+            .collect();
+
+        let mut values = [0.0; FEATURE_LEN];
+        let count = frames.len().max(1) as f32;
+
+        // MFCC means and standard deviations
+        let mfccs: Vec<[f32; NUM_MFCC]> =
+            frames.iter().map(|power| mfcc(power, &filterbank)).collect();
+        for coefficients in mfccs.iter() {
+            for (k, c) in coefficients.iter().enumerate() {
+                values[k] += c / count;
+            }
+        }
+        for coefficients in mfccs.iter() {
+            for (k, c) in coefficients.iter().enumerate() {
+                values[NUM_MFCC + k] += (c - values[k]).powi(2) / count;
+            }
+        }
+        for value in values[NUM_MFCC..2 * NUM_MFCC].iter_mut() {
+            *value = value.sqrt();
+        }
+
+        // The spectral centroid in octaves and the flatness between 0 (tonal) and 1 (noise)
+        let bin_width = sample_rate / frame_len as f32;
+        let mut centroid = 0.0;
+        let mut flatness = 0.0;
+        for power in frames.iter() {
+            let total = power.iter().sum::<f32>() + 1e-12;
+            let mean = power.iter().enumerate().map(|(i, p)| i as f32 * bin_width * p).sum::<f32>();
+            centroid += (mean / total).max(20.0).log2() / count;
+
+            let log_mean = power.iter().map(|p| (p + 1e-12).ln()).sum::<f32>() / power.len() as f32;
+            flatness += log_mean.exp() / (total / power.len() as f32) / count;
+        }
+
+        values[2 * NUM_MFCC] = centroid;
+        values[2 * NUM_MFCC + 1] = flatness;
+        values[2 * NUM_MFCC + 2] = attack_time(samples, sample_rate).max(0.001).log10();
+        values[2 * NUM_MFCC + 3] = (samples.len() as f32 / sample_rate).max(0.001).log10();
+
+        Self(values)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+}
+
+impl ToSql for FeatureVector {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_bytes()))
+    }
+}
+
+impl FromSql for FeatureVector {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let bytes = value.as_blob()?;
+        if bytes.len() != FEATURE_LEN * 4 {
+            return Err(FromSqlError::InvalidBlobSize {
+                expected_size: FEATURE_LEN * 4,
+                blob_size: bytes.len(),
+            });
+        }
+
+        let mut values = [0.0; FEATURE_LEN];
+        for (value, bytes) in values.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Ok(Self(values))
+    }
+}
+
+// Triangular filters spaced evenly on the mel scale, as weights of the bins of a power spectrum.
+fn mel_filterbank(frame_len: usize, sample_rate: f32) -> Vec<Vec<(usize, f32)>> {
+    let mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+    let hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+
+    let low = mel(20.0);
+    let high = mel((sample_rate / 2.0).min(16000.0));
+    let bin_width = sample_rate / frame_len as f32;
+    let edges: Vec<f32> = (0..NUM_MEL_BANDS + 2)
+        .map(|i| hz(low + (high - low) * i as f32 / (NUM_MEL_BANDS + 1) as f32) / bin_width)
+        .collect();
+
+    edges
+        .windows(3)
+        .map(|edge| {
+            let (left, center, right) = (edge[0], edge[1], edge[2]);
+            (left.floor() as usize..=right.ceil() as usize)
+                .filter_map(|bin| {
+                    let x = bin as f32;
+                    let weight = if x < center {
+                        (x - left) / (center - left).max(f32::EPSILON)
+                    } else {
+                        (right - x) / (right - center).max(f32::EPSILON)
+                    };
+                    (weight > 0.0 && bin <= frame_len / 2).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The cepstral coefficients of a power spectrum, skipping the first.
+fn mfcc(power: &[f32], filterbank: &[Vec<(usize, f32)>]) -> [f32; NUM_MFCC] {
+    let energies: Vec<f32> = filterbank
+        .iter()
+        .map(|filter| {
+            let energy: f32 = filter.iter().map(|(bin, weight)| power[*bin] * weight).sum();
+            (energy + 1e-10).ln()
+        })
+        .collect();
+
+    let mut coefficients = [0.0; NUM_MFCC];
+    for (k, coefficient) in coefficients.iter_mut().enumerate() {
+        *coefficient = energies
+            .iter()
+            .enumerate()
+            .map(|(n, e)| e * (PI * (k + 1) as f32 * (n as f32 + 0.5) / NUM_MEL_BANDS as f32).cos())
+            .sum::<f32>()
+            / NUM_MEL_BANDS as f32;
+    }
+    coefficients
+}
+
+// The time in seconds from the first sound to the peak of the 5 ms envelope.
+fn attack_time(samples: &[f32], sample_rate: f32) -> f32 {
+    let frame_len = ((0.005 * sample_rate) as usize).max(1);
+    let envelope: Vec<f32> = samples
+        .chunks(frame_len)
+        .map(|frame| frame.iter().map(|s| s.abs()).fold(0.0, f32::max))
+        .collect();
+
+    let Some((peak, peak_level)) =
+        envelope.iter().copied().enumerate().max_by(|a, b| a.1.total_cmp(&b.1))
+    else {
+        return 0.0;
+    };
+
+    // The first frame within 40 dB of the peak
+    let onset = envelope.iter().position(|level| *level > peak_level * 0.01).unwrap_or(peak);
+    (peak - onset) as f32 * frame_len as f32 / sample_rate
+}
+
+/// The feature vectors of a library, each scaled by the spread of its values over the library so that the
+/// plain distance between two vectors weighs every feature the same.
+///
+/// Kept by the database between searches and dropped whenever features are added or removed.
+#[derive(Debug, Default)]
+pub struct FeatureIndex {
+    vectors: Vec<(AudioFileID, FeatureVector)>,
+}
+
+impl FeatureIndex {
+    fn new(mut vectors: Vec<(AudioFileID, FeatureVector)>) -> Self {
+        // The inverse of the total variance of the group of every value
+        let n = vectors.len() as f32;
+        let mut mean = [0.0; FEATURE_LEN];
+        let mut variance = [0.0; FEATURE_LEN];
+        for (_, features) in vectors.iter() {
+            for (mean, value) in mean.iter_mut().zip(features.0) {
+                *mean += value / n;
+            }
+        }
+        for (_, features) in vectors.iter() {
+            for (k, value) in features.0.iter().enumerate() {
+                variance[k] += (value - mean[k]).powi(2) / n;
+            }
+        }
+
+        let mut scale = [0.0; FEATURE_LEN];
+        for group in FEATURE_GROUPS {
+            let total: f32 = variance[group.clone()].iter().sum();
+            if total > 1e-12 {
+                scale[group].fill(1.0 / total.sqrt());
+            }
+        }
+
+        for (_, features) in vectors.iter_mut() {
+            for (value, scale) in features.0.iter_mut().zip(scale) {
+                *value *= scale;
+            }
+        }
+
+        Self { vectors }
+    }
+
+    // The `count` vectors nearest to the vector of `audio_file`, closest first
+    fn nearest(&self, audio_file: AudioFileID, count: usize) -> Vec<SimilarAudioFile> {
+        let Some((_, target)) = self.vectors.iter().find(|(id, _)| *id == audio_file) else {
+            return Vec::new();
+        };
+
+        let mut similar: Vec<SimilarAudioFile> = self
+            .vectors
+            .iter()
+            .filter(|(id, _)| *id != audio_file)
+            .map(|(id, features)| {
+                let distance = features
+                    .0
+                    .iter()
+                    .zip(target.0)
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+                SimilarAudioFile { id: *id, distance }
+            })
+            .collect();
+
+        // Only the nearest files are sorted
+        if count < similar.len() {
+            similar.select_nth_unstable_by(count, |a, b| a.distance.total_cmp(&b.distance));
+            similar.truncate(count);
+        }
+        similar.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        similar
+    }
+}
+
+/// An audio file similar to another and its distance from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarAudioFile {
+    pub id: AudioFileID,
+    pub distance: f32,
+}
+
+pub trait DatabaseFeatureHandler {
+    fn get_features(&self, audio_file: AudioFileID)
+        -> Result<Option<FeatureVector>, DatabaseError>;
+    fn insert_features(
+        &mut self,
+        audio_file: AudioFileID,
+        features: &FeatureVector,
+    ) -> Result<(), DatabaseError>;
+    fn remove_features(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError>;
+    /// The `count` audio files nearest to `audio_file`, closest first.
+    ///
+    /// Each feature is scaled by its spread over the library, so that the MFCC means, MFCC deviations,
+    /// centroid, flatness, attack time and duration all weigh the same.
+    fn find_similar_audio_files(
+        &self,
+        audio_file: AudioFileID,
+        count: usize,
+    ) -> Result<Vec<SimilarAudioFile>, DatabaseError>;
+}
+
+impl DatabaseFeatureHandler for Database {
+    fn get_features(
+        &self,
+        audio_file: AudioFileID,
+    ) -> Result<Option<FeatureVector>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "SELECT features FROM audio_files_features WHERE audio_file = (?1)",
+            )?;
+
+            let mut rows = query.query_map([audio_file], |row| row.get(0))?;
+            return Ok(rows.next().transpose()?);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn insert_features(
+        &mut self,
+        audio_file: AudioFileID,
+        features: &FeatureVector,
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "INSERT OR REPLACE INTO audio_files_features (audio_file, features) VALUES (?1, ?2)",
+            )?;
+            query.execute((audio_file, features))?;
+        }
+
+        self.invalidate_feature_index();
+        Ok(())
+    }
+
+    fn remove_features(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "DELETE FROM audio_files_features WHERE audio_file = (?1)",
+                [audio_file],
+            )?;
+        }
+
+        self.invalidate_feature_index();
+        Ok(())
+    }
+
+    fn find_similar_audio_files(
+        &self,
+        audio_file: AudioFileID,
+        count: usize,
+    ) -> Result<Vec<SimilarAudioFile>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            // The features are read and scaled once, searches then scan the vectors in memory. See
+            // `bench_find_similar_audio_files` for the time taken by both.
+            let mut index = self.feature_index.borrow_mut();
+            if index.is_none() {
+                let mut query =
+                    connection.prepare("SELECT audio_file, features FROM audio_files_features")?;
+                let rows = query.query_map([], |row| {
+                    Ok((row.get::<_, AudioFileID>(0)?, row.get::<_, FeatureVector>(1)?))
+                })?;
+                *index = Some(FeatureIndex::new(rows.filter_map(Result::ok).collect()));
+            }
+
+            return Ok(index.as_ref().map_or(Vec::new(), |index| index.nearest(audio_file, count)));
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+}
+
+impl Database {
+    // Drop the feature vectors kept for searches after features have changed.
+    pub(super) fn invalidate_feature_index(&mut self) {
+        *self.feature_index.get_mut() = None;
+    }
+}
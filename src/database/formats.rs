@@ -0,0 +1,306 @@
+//! The audio formats which can be indexed and the filters deciding which files of a library are indexed.
+
+use super::*;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+use symphonia::core::codecs::*;
+use vizia::prelude::*;
+
+// The number of bytes read from the start of a file to recognise its format
+const MAGIC_SIZE: usize = 12;
+
+/// A container format which can be decoded by Symphonia.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Data,
+    EnumIter,
+    EnumString,
+    AsRefStr,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum AudioFormat {
+    Wav,
+    Aiff,
+    Flac,
+    Mp3,
+    Ogg,
+    M4a,
+    Caf,
+}
+
+impl AudioFormat {
+    /// The file extensions used by the format, in lowercase.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            AudioFormat::Wav => &["wav", "wave", "bwf"],
+            AudioFormat::Aiff => &["aif", "aiff", "aifc"],
+            AudioFormat::Flac => &["flac"],
+            AudioFormat::Mp3 => &["mp3"],
+            AudioFormat::Ogg => &["ogg", "oga"],
+            AudioFormat::M4a => &["m4a", "mp4", "alac"],
+            AudioFormat::Caf => &["caf"],
+        }
+    }
+
+    /// The format using the extension of `path`, if any.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        AudioFormat::iter().find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// Recognise a format from the first bytes of a file.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..]
+            | [b'R', b'F', b'6', b'4', _, _, _, _, b'W', b'A', b'V', b'E', ..]
+            | [b'B', b'W', b'6', b'4', ..] => Some(AudioFormat::Wav),
+            [b'F', b'O', b'R', b'M', _, _, _, _, b'A', b'I', b'F', b'F' | b'C', ..] => {
+                Some(AudioFormat::Aiff)
+            }
+            [b'f', b'L', b'a', b'C', ..] => Some(AudioFormat::Flac),
+            [b'O', b'g', b'g', b'S', ..] => Some(AudioFormat::Ogg),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(AudioFormat::M4a),
+            [b'c', b'a', b'f', b'f', ..] => Some(AudioFormat::Caf),
+            // An ID3 tag or the sync word of an MPEG audio frame
+            [b'I', b'D', b'3', ..] => Some(AudioFormat::Mp3),
+            [0xFF, second, ..] if second & 0xE0 == 0xE0 => Some(AudioFormat::Mp3),
+            _ => None,
+        }
+    }
+
+    /// Recognise the format of the file at `path` from its first bytes.
+    pub fn detect(path: &Path) -> Result<Self, IndexError> {
+        let mut bytes = Vec::with_capacity(MAGIC_SIZE);
+        File::open(path)
+            .and_then(|file| file.take(MAGIC_SIZE as u64).read_to_end(&mut bytes))
+            .map_err(|err| IndexError::new(path, err))?;
+
+        AudioFormat::from_magic(&bytes)
+            .ok_or_else(|| IndexError::new(path, "unrecognised file header"))
+    }
+}
+
+impl std::fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref().to_uppercase())
+    }
+}
+
+impl ToSql for AudioFormat {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_ref()))
+    }
+}
+
+impl FromSql for AudioFormat {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        AudioFormat::from_str(value.as_str()?).map_err(|err| FromSqlError::Other(Box::new(err)))
+    }
+}
+
+/// How the samples of a lossless file are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Data, EnumString, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum SampleFormat {
+    Int,
+    Float,
+}
+
+impl std::fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl ToSql for SampleFormat {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_ref()))
+    }
+}
+
+impl FromSql for SampleFormat {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        SampleFormat::from_str(value.as_str()?).map_err(|err| FromSqlError::Other(Box::new(err)))
+    }
+}
+
+/// The display name of a codec and the format of its samples, which is `None` for lossy codecs.
+pub fn describe_codec(codec: CodecType) -> (&'static str, Option<SampleFormat>) {
+    match codec {
+        CODEC_TYPE_PCM_F32LE
+        | CODEC_TYPE_PCM_F32LE_PLANAR
+        | CODEC_TYPE_PCM_F32BE
+        | CODEC_TYPE_PCM_F32BE_PLANAR
+        | CODEC_TYPE_PCM_F64LE
+        | CODEC_TYPE_PCM_F64LE_PLANAR
+        | CODEC_TYPE_PCM_F64BE
+        | CODEC_TYPE_PCM_F64BE_PLANAR => ("PCM", Some(SampleFormat::Float)),
+        CODEC_TYPE_PCM_S32LE
+        | CODEC_TYPE_PCM_S32LE_PLANAR
+        | CODEC_TYPE_PCM_S32BE
+        | CODEC_TYPE_PCM_S32BE_PLANAR
+        | CODEC_TYPE_PCM_S24LE
+        | CODEC_TYPE_PCM_S24LE_PLANAR
+        | CODEC_TYPE_PCM_S24BE
+        | CODEC_TYPE_PCM_S24BE_PLANAR
+        | CODEC_TYPE_PCM_S16LE
+        | CODEC_TYPE_PCM_S16LE_PLANAR
+        | CODEC_TYPE_PCM_S16BE
+        | CODEC_TYPE_PCM_S16BE_PLANAR
+        | CODEC_TYPE_PCM_S8
+        | CODEC_TYPE_PCM_S8_PLANAR
+        | CODEC_TYPE_PCM_U32LE
+        | CODEC_TYPE_PCM_U32LE_PLANAR
+        | CODEC_TYPE_PCM_U32BE
+        | CODEC_TYPE_PCM_U32BE_PLANAR
+        | CODEC_TYPE_PCM_U24LE
+        | CODEC_TYPE_PCM_U24LE_PLANAR
+        | CODEC_TYPE_PCM_U24BE
+        | CODEC_TYPE_PCM_U24BE_PLANAR
+        | CODEC_TYPE_PCM_U16LE
+        | CODEC_TYPE_PCM_U16LE_PLANAR
+        | CODEC_TYPE_PCM_U16BE
+        | CODEC_TYPE_PCM_U16BE_PLANAR
+        | CODEC_TYPE_PCM_U8
+        | CODEC_TYPE_PCM_U8_PLANAR => ("PCM", Some(SampleFormat::Int)),
+        CODEC_TYPE_PCM_ALAW => ("A-law", Some(SampleFormat::Int)),
+        CODEC_TYPE_PCM_MULAW => ("μ-law", Some(SampleFormat::Int)),
+        CODEC_TYPE_ADPCM_G722
+        | CODEC_TYPE_ADPCM_G726
+        | CODEC_TYPE_ADPCM_G726LE
+        | CODEC_TYPE_ADPCM_MS
+        | CODEC_TYPE_ADPCM_IMA_WAV
+        | CODEC_TYPE_ADPCM_IMA_QT => ("ADPCM", None),
+        CODEC_TYPE_FLAC => ("FLAC", Some(SampleFormat::Int)),
+        CODEC_TYPE_ALAC => ("ALAC", Some(SampleFormat::Int)),
+        CODEC_TYPE_WAVPACK => ("WavPack", Some(SampleFormat::Int)),
+        CODEC_TYPE_MP1 => ("MP1", None),
+        CODEC_TYPE_MP2 => ("MP2", None),
+        CODEC_TYPE_MP3 => ("MP3", None),
+        CODEC_TYPE_VORBIS => ("Vorbis", None),
+        CODEC_TYPE_OPUS => ("Opus", None),
+        CODEC_TYPE_AAC => ("AAC", None),
+        _ => ("Unknown", None),
+    }
+}
+
+/// The files of a library which are indexed, saved in the configuration.
+///
+/// Patterns are globs matched against both the name of an entry and its path relative to the library root.
+/// Files must match one of the `include` patterns, if there are any, and files and directories matching one
+/// of the `exclude` patterns are skipped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryFilter {
+    // The formats which are indexed
+    pub formats: BTreeSet<AudioFormat>,
+    // The patterns of the files to index
+    pub include: Vec<String>,
+    // The patterns of the files and directories to skip
+    pub exclude: Vec<String>,
+}
+
+impl Default for LibraryFilter {
+    fn default() -> Self {
+        Self {
+            formats: AudioFormat::iter().collect(),
+            include: Vec::new(),
+            exclude: vec![String::from("__MACOSX"), String::from("*.reapeaks")],
+        }
+    }
+}
+
+impl LibraryFilter {
+    /// Compile the patterns of the filter for the library at `root`.
+    pub fn compile(&self, root: &Path) -> Result<FileFilter, DatabaseError> {
+        Ok(FileFilter {
+            root: root.to_path_buf(),
+            formats: self.formats.clone(),
+            include: build_glob_set(&self.include)?,
+            exclude: build_glob_set(&self.exclude)?,
+        })
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, DatabaseError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// A compiled [`LibraryFilter`], used to reject files before they are probed.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    root: PathBuf,
+    formats: BTreeSet<AudioFormat>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl Default for FileFilter {
+    fn default() -> Self {
+        LibraryFilter::default().compile(Path::new("")).unwrap()
+    }
+}
+
+impl FileFilter {
+    fn is_excluded(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.exclude.is_match(relative)
+            || path.file_name().is_some_and(|n| self.exclude.is_match(n))
+    }
+
+    /// Whether the contents of the directory at `path` are indexed.
+    pub fn accepts_directory(&self, path: &Path) -> bool {
+        !self.is_excluded(path)
+    }
+
+    /// The format of the file at `path` if it should be indexed, judging by its path only.
+    pub fn accepts_file(&self, path: &Path) -> Option<AudioFormat> {
+        if self.is_excluded(path) {
+            return None;
+        }
+
+        if !self.include.is_empty() {
+            let relative = path.strip_prefix(&self.root).unwrap_or(path);
+            let included = self.include.is_match(relative)
+                || path.file_name().is_some_and(|n| self.include.is_match(n));
+            if !included {
+                return None;
+            }
+        }
+
+        AudioFormat::from_extension(path).filter(|format| self.formats.contains(format))
+    }
+
+    /// Check the header of a file accepted by [`FileFilter::accepts_file`] and return its actual format.
+    pub fn check_header(&self, path: &Path) -> Result<AudioFormat, IndexError> {
+        let format = AudioFormat::detect(path)?;
+        if !self.formats.contains(&format) {
+            return Err(IndexError::new(path, format!("{} files are not indexed", format)));
+        }
+
+        Ok(format)
+    }
+}
@@ -17,13 +17,15 @@ use std::{
 use vizia::prelude::*;
 
 pub const DATABASE_FILE_NAME: &str = ".database.vsb";
-pub const AUDIO_FILE_EXTENSIONS: [&'static str; 1] = ["wav"];
 
 #[derive(Debug, Lens)]
 pub struct Database {
     pub(super) path: PathBuf,
     pub(super) conn: Option<Connection>,
     pub(super) meta: DatabaseMetadata,
+    pub(super) filter: FileFilter,
+    // The scaled feature vectors used to find similar files, loaded on the first search
+    pub(super) feature_index: RefCell<Option<FeatureIndex>>,
 }
 
 impl Database {
@@ -34,14 +36,27 @@ impl Database {
         }
 
         // Open connection
-        let mut s = Self { path, conn: None, meta: DatabaseMetadata::new() };
+        let filter = LibraryFilter::default().compile(&path)?;
+        let mut s = Self {
+            path,
+            conn: None,
+            meta: DatabaseMetadata::new(),
+            filter,
+            feature_index: RefCell::default(),
+        };
         s.initialize_or_create_stores()?;
 
         // let database_exists = File::open(s.get_database_path()).is_ok();
 
         s.open_connection()?;
 
-        s.initialize_empty_database();
+        // Without the fingerprints of the last indexing job every file is probed again
+        if s.meta_exists() {
+            let _ = s.retrieve_metadata();
+        }
+
+        // Files are indexed separately by an indexing job, see `index_library`
+        s.insert_default_tags();
         // if !database_exists {
         // } else {
         //     s.update_database();
@@ -54,100 +69,8 @@ impl Database {
         self.get_connection().unwrap().execute_batch(include_str!("sqls/clear.sql")).unwrap();
     }
 
-    fn initialize_empty_database(&mut self) {
-        let audio_file_count = AtomicUsize::new(0);
-        let collection_count = AtomicUsize::new(0);
-        let tags_count = AtomicUsize::new(0);
-
-        let collections: Rc<RefCell<HashMap<PathBuf, usize>>> =
-            Rc::new(RefCell::new(HashMap::new()));
-
-        let connection = Rc::new(RefCell::new(self.get_connection().unwrap()));
-
-        let path = self.path.clone();
-
-        // Recursively check each directory under the root
-        recursive_directory_closure(self, &path, None, |db, path, parent_path, files| {
-            let mut colls = collections.borrow_mut();
-
-            let name = path.file_name().unwrap().to_str().unwrap().to_string();
-            if name == ".vsb-meta" {
-                return;
-            }
-
-            let id = collection_count.load(std::sync::atomic::Ordering::Relaxed);
-            collection_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            let parent_id = match parent_path.is_none() {
-                true => None,
-                false => Some(*colls.get(parent_path.unwrap()).unwrap()),
-            };
-
-            // Insert collection
-            let collection = Collection::new(id, parent_id, name, path.clone());
-
-            db.insert_collection(collection);
-
-            colls.insert(path.clone(), id);
-            drop(colls);
-
-            // Insert each non-directory child
-            for child_file in files {
-                let p = child_file.path();
-                let extension = p.extension().map(|v| v.to_str().unwrap()).unwrap_or("");
-
-                // if !AUDIO_FILE_EXTENSIONS.contains(&extension) {
-                //     continue;
-                // }
-
-                let file_id = audio_file_count.load(std::sync::atomic::Ordering::Relaxed);
-
-                let name = child_file.file_name().to_str().unwrap().to_string();
-
-                if let Ok((_, file_info)) = SymphoniaDecoder::new(p, 0, 0, ()) {
-                    let sample_rate = file_info.sample_rate.unwrap_or(41000);
-                    let duration = file_info.num_frames as f32 / sample_rate as f32;
-
-                    let audio_file = AudioFile::new(
-                        file_id,
-                        name,
-                        id,
-                        duration,
-                        sample_rate as f32,
-                        0.0,
-                        file_info.num_channels as f32,
-                        None,
-                        None,
-                        0.0,
-                    );
-
-                    db.insert_audio_file(audio_file);
-
-                    audio_file_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                }
-
-                // let mut reader = WavReader::open(p).unwrap();
-                // let spec = reader.spec();
-
-                // let duration = reader.duration() as f32 / spec.sample_rate as f32;
-
-                // let audio_file = AudioFile::new(
-                //     file_id,
-                //     name,
-                //     id,
-                //     duration,
-                //     spec.sample_rate as f32,
-                //     spec.bits_per_sample as f32,
-                //     spec.channels as f32,
-                //     None,
-                //     None,
-                //     reader.duration() as f32 * spec.channels as f32 * spec.bits_per_sample as f32
-                //         / 8.0,
-                // );
-
-                // db.insert_audio_file(audio_file);
-            }
-        });
-
+    // Insert the tags available in a new database.
+    fn insert_default_tags(&mut self) {
         self.insert_tag(Tag {
             id: 4,
             name: String::from("UpTempo"),
@@ -424,6 +347,32 @@ impl Database {
             color: String::from("purple"),
             number: 0,
         });
+
+        // Tags suggested by the analysis of the indexed files
+        self.insert_tag(Tag {
+            id: 55,
+            name: String::from("Clap"),
+            color: String::from("pink"),
+            number: 0,
+        });
+        self.insert_tag(Tag {
+            id: 56,
+            name: String::from("Tom"),
+            color: String::from("red"),
+            number: 0,
+        });
+        self.insert_tag(Tag {
+            id: 57,
+            name: String::from("OneShot"),
+            color: String::from("blue"),
+            number: 0,
+        });
+        self.insert_tag(Tag {
+            id: 58,
+            name: String::from("Loop"),
+            color: String::from("green"),
+            number: 0,
+        });
     }
 
     pub fn from_connection(path: &str, connection: Option<Connection>) -> Self {
@@ -431,23 +380,88 @@ impl Database {
             path: Path::new(path).to_path_buf(),
             conn: connection,
             meta: DatabaseMetadata::new(),
+            filter: FileFilter::default(),
+            feature_index: RefCell::default(),
         }
     }
 
-    pub fn close_database(&mut self) {
-        self.store_metadata();
-        self.close_connection().unwrap();
+    /// Set the filter deciding which files of the library are indexed.
+    pub fn set_filter(&mut self, filter: &LibraryFilter) -> Result<(), DatabaseError> {
+        self.filter = filter.compile(&self.path)?;
+        Ok(())
+    }
+
+    pub fn close_database(&mut self) -> Result<(), DatabaseError> {
+        self.store_metadata()?;
+        self.close_connection()
     }
 }
 
+// The meta directory holds the index and the kits of the library, so it is kept when the library is closed
 impl Drop for Database {
     fn drop(&mut self) {
-        let meta_dir = self.get_meta_directory_path();
-        std::fs::remove_dir_all(meta_dir);
+        let _ = self.close_connection();
     }
 }
 
-fn recursive_directory_closure<F>(
+/// Read the stream info of the file at `path` and build the audio file row describing it.
+///
+/// The header of the file is checked against `filter` first so that files which are not audio are rejected
+/// without being decoded.
+pub(super) fn probe_audio_file(
+    id: AudioFileID,
+    collection: CollectionID,
+    path: &Path,
+    filter: &FileFilter,
+) -> Result<AudioFile, IndexError> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| IndexError::new(path, "invalid file name"))?
+        .to_string();
+
+    let format = filter.check_header(path)?;
+
+    let (_, file_info) = SymphoniaDecoder::new(path.to_path_buf(), 0, 0, ())
+        .map_err(|err| IndexError::new(path, err))?;
+
+    let size = std::fs::metadata(path).map_err(|err| IndexError::new(path, err))?.len();
+
+    let sample_rate = file_info.sample_rate.unwrap_or(41000);
+    let duration = file_info.num_frames as f32 / sample_rate as f32;
+
+    // Lossy codecs decode to floats but have no meaningful bit depth
+    let codec_params = &file_info.params.codec_params;
+    let (codec, sample_format) = describe_codec(codec_params.codec);
+    let bit_depth = sample_format.and(codec_params.bits_per_sample).map(|bits| bits as u16);
+
+    // Sampler chunks fill in the tempo until the file is analysed. Only an ACID root note gives the key of the
+    // file, the unity note of a `smpl` chunk is just the note at which it plays unpitched.
+    let (sampler, acid_root_note) = match format {
+        AudioFormat::Wav => read_sampler_chunks(path),
+        _ => (SamplerInfo::default(), None),
+    };
+
+    let audio_file = AudioFile::new(
+        id,
+        name,
+        collection,
+        duration,
+        sample_rate,
+        bit_depth,
+        file_info.num_channels,
+        sampler.tempo,
+        acid_root_note.map(|note| note % 12),
+        size,
+        format,
+        sample_format,
+        codec.to_string(),
+    );
+
+    Ok(AudioFile { sampler, ..audio_file })
+}
+
+pub(super) fn recursive_directory_closure<F>(
     db: &mut Database,
     path: &PathBuf,
     parent_path: Option<&PathBuf>,
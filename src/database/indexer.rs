@@ -0,0 +1,719 @@
+//! Background indexing of a library directory into the database.
+
+use super::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::read_dir,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use vizia::prelude::*;
+
+// The minimum time between two progress updates of an indexing job
+pub const INDEX_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+// The maximum number of rows written to the database in a single transaction
+pub const INDEX_BATCH_SIZE: usize = 1000;
+
+// The maximum number of probed files waiting to be written to the database
+const INDEX_CHANNEL_CAPACITY: usize = 4 * INDEX_BATCH_SIZE;
+
+/// Shared state of an indexing job, used to follow its progress and to cancel it from another thread.
+#[derive(Debug, Default)]
+pub struct IndexJob {
+    cancelled: AtomicBool,
+    scanned: AtomicUsize,
+    total: AtomicUsize,
+    directories: AtomicUsize,
+}
+
+impl IndexJob {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The number of files which have been probed so far.
+    pub fn scanned(&self) -> usize {
+        self.scanned.load(Ordering::Relaxed)
+    }
+
+    /// The number of files found in the library so far.
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// The number of directories which have been added to the database so far.
+    pub fn directories(&self) -> usize {
+        self.directories.load(Ordering::Relaxed)
+    }
+}
+
+/// A file or directory which could not be indexed.
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct IndexError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl IndexError {
+    pub fn new(path: &Path, message: impl ToString) -> Self {
+        Self { path: path.to_path_buf(), message: message.to_string() }
+    }
+}
+
+/// The outcome of an indexing job.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexReport {
+    // The number of files which were probed
+    pub scanned: usize,
+    // The number of audio files added to the database or updated because their content changed
+    pub indexed: usize,
+    // The number of audio files and directories removed from the database because they are gone from the disk
+    pub removed: usize,
+    // Whether the job was cancelled before it finished
+    pub cancelled: bool,
+    // The files and directories which could not be indexed
+    pub errors: Vec<IndexError>,
+}
+
+/// The number of worker threads used to index a library, one per available core.
+pub fn default_index_workers() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+// A unit of work for the indexing workers
+enum IndexTask {
+    // List the entries of a directory, identified by a key until the writer gives it a collection id
+    Directory { key: usize, parent: Option<usize>, path: PathBuf },
+    // Probe a file in the directory with the given key
+    File { directory: usize, path: PathBuf },
+}
+
+// A result sent by the indexing workers to the writer
+enum IndexMessage {
+    // A directory has been found, its parent is always sent before it
+    Directory { key: usize, parent: Option<usize>, path: PathBuf },
+    // A file has not changed since it was last indexed, it is always sent after its directory
+    Unchanged { path: PathBuf },
+    // A file has been probed, it is always sent after its directory
+    File { directory: usize, path: PathBuf, file: Box<ProbedFile> },
+    // An entry of the library could not be read
+    Error(IndexError),
+}
+
+// A file probed, read and analysed by an indexing worker, and its fingerprint
+pub(super) struct ProbedFile {
+    pub(super) result: Result<AudioFile, IndexError>,
+    pub(super) metadata: Vec<MetadataEntry>,
+    pub(super) analysis: Option<Box<Analysis>>,
+    pub(super) hash: Option<Hash>,
+}
+
+impl ProbedFile {
+    // Probe, read and analyse the file at `path`, whose fingerprint is `hash`. The audio file row has no ID
+    // or collection yet.
+    fn probe(path: &Path, filter: &FileFilter, hash: Option<Hash>) -> Self {
+        let mut result = probe_audio_file(0, 0, path, filter);
+        let (metadata, analysis, hash) = match &mut result {
+            Ok(audio_file) => {
+                let analysis = analyze_audio_file(path, &audio_file.sampler);
+                if let Some(analysis) = &analysis {
+                    analysis.apply(audio_file);
+                }
+
+                (read_embedded_metadata(path, audio_file.format), analysis, hash)
+            }
+            Err(_) => (Vec::new(), None, None),
+        };
+
+        Self { result, metadata, analysis: analysis.map(Box::new), hash }
+    }
+}
+
+// Probed files by path, `None` for those whose fingerprint has not changed
+pub(super) type ProbedFiles = HashMap<PathBuf, Option<ProbedFile>>;
+
+// The tasks shared by the indexing workers
+struct WorkQueue {
+    state: Mutex<WorkState>,
+    ready: Condvar,
+}
+
+struct WorkState {
+    tasks: VecDeque<IndexTask>,
+    // The number of tasks being worked on, which may still add new tasks
+    active: usize,
+    // Whether the writer has stopped and the remaining tasks should be dropped
+    closed: bool,
+}
+
+impl WorkQueue {
+    fn new(tasks: impl IntoIterator<Item = IndexTask>) -> Self {
+        Self {
+            state: Mutex::new(WorkState {
+                tasks: tasks.into_iter().collect(),
+                active: 0,
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    fn push(&self, tasks: impl IntoIterator<Item = IndexTask>) {
+        self.state.lock().unwrap().tasks.extend(tasks);
+        self.ready.notify_all();
+    }
+
+    // Take the next task, waiting while other workers may still add some. Returns `None` once all the
+    // work is done or the job has been cancelled.
+    fn next(&self, job: &IndexJob) -> Option<IndexTask> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed || job.is_cancelled() {
+                return None;
+            }
+
+            if let Some(task) = state.tasks.pop_front() {
+                state.active += 1;
+                return Some(task);
+            }
+
+            if state.active == 0 {
+                return None;
+            }
+
+            state = self.ready.wait_timeout(state, INDEX_PROGRESS_INTERVAL).unwrap().0;
+        }
+    }
+
+    fn done(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        if state.active == 0 {
+            self.ready.notify_all();
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// Index the library at the root path of `database` using [`default_index_workers`] threads.
+///
+/// See [`index_library_with_workers`].
+pub fn index_library<F>(
+    database: &Mutex<Database>,
+    job: &IndexJob,
+    on_progress: F,
+) -> Result<IndexReport, DatabaseError>
+where
+    F: FnMut(&IndexJob),
+{
+    index_library_with_workers(database, job, default_index_workers(), on_progress)
+}
+
+/// Index the library at the root path of `database`.
+///
+/// A pool of `workers` threads walks the directories and probes the files in parallel, while the calling
+/// thread writes their results to the database in transactions of up to [`INDEX_BATCH_SIZE`] rows. The
+/// database is only locked while a batch is written so that it can be queried while the job runs, and
+/// `on_progress` is called with the job at most every [`INDEX_PROGRESS_INTERVAL`] and once when the job
+/// finishes. It is called without the database locked, so that reporting progress never holds up the writes.
+/// Files which cannot be read are collected in the returned report.
+///
+/// Directories and files which are already indexed keep their IDs, files whose fingerprint has not changed
+/// since the last job, even one run before the library was last opened, are not probed again, and once the whole library has been walked the rows of the files and directories
+/// which are gone are removed.
+pub fn index_library_with_workers<F>(
+    database: &Mutex<Database>,
+    job: &IndexJob,
+    workers: usize,
+    mut on_progress: F,
+) -> Result<IndexReport, DatabaseError>
+where
+    F: FnMut(&IndexJob),
+{
+    let (root, filter, mut rows) = {
+        let db = database.lock().map_err(|_| DatabaseError::ConnectionClosed)?;
+        (db.path.clone(), db.filter.clone(), IndexedRows::load(&db)?)
+    };
+    let fingerprints = std::mem::take(&mut rows.fingerprints);
+
+    let queue = WorkQueue::new([IndexTask::Directory { key: 0, parent: None, path: root }]);
+    let next_key = AtomicUsize::new(1);
+    let (sender, receiver) = sync_channel(INDEX_CHANNEL_CAPACITY);
+
+    let result = std::thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            let worker = IndexWorker {
+                queue: &queue,
+                job,
+                filter: &filter,
+                fingerprints: &fingerprints,
+                next_key: &next_key,
+                sender: sender.clone(),
+            };
+            scope.spawn(move || worker.run());
+        }
+        drop(sender);
+
+        // The receiver is dropped when the writer returns, which stops the workers if it failed
+        let result = write_index(database, job, receiver, rows, &mut on_progress);
+        queue.close();
+        result
+    });
+
+    let mut report = result?;
+    report.cancelled = job.is_cancelled();
+    report.errors.sort_by(|a, b| a.path.cmp(&b.path));
+
+    (on_progress)(job);
+
+    Ok(report)
+}
+
+/// Probe, read and analyse the files at `paths` with a pool of [`default_index_workers`] threads, without
+/// locking the database. Files whose fingerprint is the one in `fingerprints` are not probed again.
+pub(super) fn probe_files(
+    paths: Vec<PathBuf>,
+    filter: &FileFilter,
+    fingerprints: &HashMap<PathBuf, Hash>,
+) -> ProbedFiles {
+    let workers = default_index_workers().min(paths.len());
+    let job = IndexJob::new();
+    let queue =
+        WorkQueue::new(paths.into_iter().map(|path| IndexTask::File { directory: 0, path }));
+    let next_key = AtomicUsize::new(0);
+    let (sender, receiver) = sync_channel(INDEX_CHANNEL_CAPACITY);
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let worker = IndexWorker {
+                queue: &queue,
+                job: &job,
+                filter,
+                fingerprints,
+                next_key: &next_key,
+                sender: sender.clone(),
+            };
+            scope.spawn(move || worker.run());
+        }
+        drop(sender);
+
+        receiver
+            .iter()
+            .filter_map(|message| match message {
+                IndexMessage::Unchanged { path } => Some((path, None)),
+                IndexMessage::File { path, file, .. } => Some((path, Some(*file))),
+                _ => None,
+            })
+            .collect()
+    })
+}
+
+// The state shared by a worker thread with the rest of an indexing job
+struct IndexWorker<'a> {
+    queue: &'a WorkQueue,
+    job: &'a IndexJob,
+    filter: &'a FileFilter,
+    // The fingerprints of the indexed files, which are skipped while their content is the same
+    fingerprints: &'a HashMap<PathBuf, Hash>,
+    next_key: &'a AtomicUsize,
+    sender: SyncSender<IndexMessage>,
+}
+
+impl IndexWorker<'_> {
+    fn run(&self) {
+        while let Some(task) = self.queue.next(self.job) {
+            let sent = match task {
+                IndexTask::Directory { key, parent, path } => {
+                    self.scan_directory(key, parent, path)
+                }
+
+                IndexTask::File { directory, path } => {
+                    let hash = fingerprint(&path);
+                    if hash.is_some() && self.fingerprints.get(&path) == hash.as_ref() {
+                        self.job.scanned.fetch_add(1, Ordering::Relaxed);
+                        self.send(IndexMessage::Unchanged { path })
+                    } else {
+                        self.probe_file(directory, path, hash)
+                    }
+                }
+            };
+
+            self.queue.done();
+
+            if !sent {
+                self.queue.close();
+            }
+        }
+    }
+
+    // Probe, read and analyse a file and send the results to the writer. Returns `false` if the writer has
+    // stopped.
+    fn probe_file(&self, directory: usize, path: PathBuf, hash: Option<Hash>) -> bool {
+        let file = ProbedFile::probe(&path, self.filter, hash);
+        self.job.scanned.fetch_add(1, Ordering::Relaxed);
+        self.send(IndexMessage::File { directory, path, file: Box::new(file) })
+    }
+
+    // Send a result to the writer. Returns `false` if the writer has stopped.
+    fn send(&self, message: IndexMessage) -> bool {
+        self.sender.send(message).is_ok()
+    }
+
+    // List the entries of a directory and queue the files and subdirectories accepted by the filter.
+    // Returns `false` if the writer has stopped.
+    fn scan_directory(&self, key: usize, parent: Option<usize>, path: PathBuf) -> bool {
+        let entries = match read_dir(&path) {
+            Ok(entries) => entries,
+            Err(err) => return self.send(IndexMessage::Error(IndexError::new(&path, err))),
+        };
+
+        let mut child_directories = Vec::new();
+        let mut files = Vec::new();
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    if !self.send(IndexMessage::Error(IndexError::new(&path, err))) {
+                        return false;
+                    }
+                    continue;
+                }
+            };
+
+            if entry.file_name() == DATABASE_META_NAME {
+                continue;
+            }
+
+            let entry_path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    if self.filter.accepts_directory(&entry_path) {
+                        child_directories.push(entry_path);
+                    }
+                }
+                Ok(_) => {
+                    if self.filter.accepts_file(&entry_path).is_some() {
+                        files.push(entry_path);
+                    }
+                }
+                Err(err) => {
+                    if !self.send(IndexMessage::Error(IndexError::new(&entry_path, err))) {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        files.sort();
+        child_directories.sort();
+
+        // The directory must reach the writer before any of its files or subdirectories
+        if !self.send(IndexMessage::Directory { key, parent, path }) {
+            return false;
+        }
+
+        self.job.total.fetch_add(files.len(), Ordering::Relaxed);
+
+        let files = files.into_iter().map(|path| IndexTask::File { directory: key, path });
+        let directories = child_directories.into_iter().map(|path| IndexTask::Directory {
+            key: self.next_key.fetch_add(1, Ordering::Relaxed),
+            parent: Some(key),
+            path,
+        });
+        self.queue.push(files.chain(directories));
+
+        true
+    }
+}
+
+// The rows of the database when an indexing job starts, by path. Rows are taken out as their files and
+// directories are found again, so that those left once the library has been walked are gone from the disk.
+#[derive(Debug, Default)]
+struct IndexedRows {
+    collections: HashMap<PathBuf, Collection>,
+    audio_files: HashMap<PathBuf, AudioFileID>,
+    // The fingerprints of the indexed files recorded by earlier jobs
+    fingerprints: HashMap<PathBuf, Hash>,
+}
+
+impl IndexedRows {
+    fn load(db: &Database) -> Result<Self, DatabaseError> {
+        let collections: HashMap<CollectionID, Collection> =
+            db.get_all_collections()?.into_iter().map(|c| (c.id(), c)).collect();
+
+        let mut rows = IndexedRows::default();
+        for audio_file in db.get_all_audio_files()? {
+            if let Some(collection) = collections.get(&audio_file.collection) {
+                let path = collection.path().join(&audio_file.name);
+                if let Some(hash) = db.meta.map.get(&path) {
+                    rows.fingerprints.insert(path.clone(), hash.clone());
+                }
+                rows.audio_files.insert(path, audio_file.id);
+            }
+        }
+        rows.collections = collections.into_values().map(|c| (c.path().clone(), c)).collect();
+
+        Ok(rows)
+    }
+}
+
+// Receive the results of the workers and write them to the database in batches.
+fn write_index<F>(
+    database: &Mutex<Database>,
+    job: &IndexJob,
+    receiver: Receiver<IndexMessage>,
+    mut rows: IndexedRows,
+    on_progress: &mut F,
+) -> Result<IndexReport, DatabaseError>
+where
+    F: FnMut(&IndexJob),
+{
+    let mut report = IndexReport::default();
+    let mut collections: HashMap<usize, CollectionID> = HashMap::new();
+    let mut batch = Vec::with_capacity(INDEX_BATCH_SIZE);
+    let mut last_write = Instant::now();
+    let mut last_progress: Option<Instant> = None;
+
+    loop {
+        let disconnected = match receiver.recv_timeout(INDEX_PROGRESS_INTERVAL) {
+            Ok(message) => {
+                batch.push(message);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+
+        if job.is_cancelled() {
+            break;
+        }
+
+        if batch.len() >= INDEX_BATCH_SIZE
+            || last_write.elapsed() >= INDEX_PROGRESS_INTERVAL
+            || disconnected
+        {
+            last_write = Instant::now();
+
+            if !batch.is_empty() {
+                let mut db = database.lock().map_err(|_| DatabaseError::ConnectionClosed)?;
+                transaction(&mut db, |db| {
+                    write_messages(db, job, &mut batch, &mut collections, &mut rows, &mut report)
+                })?;
+            }
+
+            if last_progress.is_none_or(|last| last.elapsed() >= INDEX_PROGRESS_INTERVAL) {
+                last_progress = Some(Instant::now());
+                (on_progress)(job);
+            }
+        }
+
+        if disconnected {
+            break;
+        }
+    }
+
+    // Only a walk of the whole library tells which rows are gone
+    let mut db = database.lock().map_err(|_| DatabaseError::ConnectionClosed)?;
+    if !job.is_cancelled() {
+        transaction(&mut db, |db| remove_missing(db, rows, &mut report))?;
+    }
+
+    // The next job skips the files written so far while they are unchanged
+    db.store_metadata()?;
+
+    Ok(report)
+}
+
+// Run `write` in a single transaction, rolled back if it fails.
+pub(super) fn transaction(
+    db: &mut Database,
+    write: impl FnOnce(&mut Database) -> Result<(), DatabaseError>,
+) -> Result<(), DatabaseError> {
+    db.get_connection().ok_or(DatabaseError::ConnectionClosed)?.execute_batch("BEGIN")?;
+
+    let result = write(db);
+
+    let connection = db.get_connection().ok_or(DatabaseError::ConnectionClosed)?;
+    match &result {
+        Ok(()) => connection.execute_batch("COMMIT")?,
+        Err(_) => connection.execute_batch("ROLLBACK")?,
+    }
+
+    result
+}
+
+fn write_messages(
+    db: &mut Database,
+    job: &IndexJob,
+    batch: &mut Vec<IndexMessage>,
+    collections: &mut HashMap<usize, CollectionID>,
+    rows: &mut IndexedRows,
+    report: &mut IndexReport,
+) -> Result<(), DatabaseError> {
+    let mut collection_id = db.next_collection_id()?;
+    let mut file_id = db.next_audio_file_id()?;
+
+    for message in batch.drain(..) {
+        match message {
+            IndexMessage::Directory { key, parent, path } => {
+                let parent_id = parent.and_then(|p| collections.get(&p)).copied();
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                let collection = match rows.collections.remove(&path) {
+                    Some(indexed) => {
+                        let collection =
+                            Collection::new(indexed.id(), parent_id, name.to_string(), path);
+                        if collection != indexed {
+                            db.update_collection(collection)?;
+                        }
+                        indexed.id()
+                    }
+                    None => {
+                        let id = collection_id;
+                        collection_id += 1;
+                        db.insert_collection(Collection::new(
+                            id,
+                            parent_id,
+                            name.to_string(),
+                            path,
+                        ))?;
+                        job.directories.fetch_add(1, Ordering::Relaxed);
+                        id
+                    }
+                };
+                collections.insert(key, collection);
+            }
+
+            IndexMessage::Unchanged { path } => {
+                report.scanned += 1;
+                rows.audio_files.remove(&path);
+            }
+
+            IndexMessage::File { directory, path, file } => {
+                report.scanned += 1;
+
+                let ProbedFile { result, metadata, analysis, hash } = *file;
+                match (result, collections.get(&directory)) {
+                    (Ok(audio_file), Some(&collection)) => {
+                        let audio_file = AudioFile { collection, ..audio_file };
+                        match rows.audio_files.remove(&path) {
+                            Some(id) => {
+                                let indexed = db.get_audio_file(id)?;
+                                db.reindex_audio_file(
+                                    &indexed,
+                                    audio_file,
+                                    &metadata,
+                                    analysis.as_deref(),
+                                )?;
+                            }
+                            None => {
+                                db.insert_audio_file(AudioFile { id: file_id, ..audio_file })?;
+                                db.insert_metadata(file_id, &metadata)?;
+                                if let Some(analysis) = analysis {
+                                    db.suggest_tags(file_id, &analysis.classification.tags())?;
+                                    db.insert_features(file_id, &analysis.features)?;
+                                }
+                                file_id += 1;
+                            }
+                        }
+                        report.indexed += 1;
+
+                        if let Some(hash) = hash {
+                            db.meta.map.insert(path, hash);
+                        }
+                    }
+
+                    (Ok(_), None) => {
+                        report.errors.push(IndexError::new(&path, "directory not indexed"))
+                    }
+
+                    (Err(err), _) => report.errors.push(err),
+                }
+            }
+
+            IndexMessage::Error(err) => report.errors.push(err),
+        }
+    }
+
+    Ok(())
+}
+
+// Remove the rows of the files and directories which were not found again. Rows below a path which could not
+// be read are kept, as their files may still be there.
+fn remove_missing(
+    db: &mut Database,
+    rows: IndexedRows,
+    report: &mut IndexReport,
+) -> Result<(), DatabaseError> {
+    let unreadable = |path: &Path| report.errors.iter().any(|error| path.starts_with(&error.path));
+
+    let mut removed = 0;
+    for (path, id) in rows.audio_files {
+        if !unreadable(&path) {
+            db.remove_audio_file(id)?;
+            db.meta.map.remove(&path);
+            removed += 1;
+        }
+    }
+
+    for (path, collection) in rows.collections {
+        if !unreadable(&path) {
+            db.remove_collection(collection.id())?;
+            db.meta.map.retain(|p, _| !p.starts_with(&path));
+            removed += 1;
+        }
+    }
+
+    report.removed += removed;
+    Ok(())
+}
+
+impl Database {
+    /// Replace the stream info, embedded metadata and analysis of an indexed audio file with those read from
+    /// its new content, keeping its ID and the tags assigned to it. `probed` must already hold the results of
+    /// `analysis`.
+    pub(super) fn reindex_audio_file(
+        &mut self,
+        indexed: &AudioFile,
+        probed: AudioFile,
+        metadata: &[MetadataEntry],
+        analysis: Option<&Analysis>,
+    ) -> Result<(), DatabaseError> {
+        self.remove_metadata(indexed.id)?;
+        self.insert_metadata(indexed.id, metadata)?;
+
+        self.remove_suggested_tags(indexed.id)?;
+        self.remove_features(indexed.id)?;
+        if let Some(analysis) = analysis {
+            self.suggest_tags(indexed.id, &analysis.classification.tags())?;
+            self.insert_features(indexed.id, &analysis.features)?;
+        }
+
+        // Keep analysis results, which take precedence over the sampler chunks
+        self.update_audio_file(AudioFile {
+            id: indexed.id,
+            collection: indexed.collection,
+            bpm: indexed.bpm.or(probed.bpm),
+            key: indexed.key.or(probed.key),
+            ..probed
+        })
+    }
+}
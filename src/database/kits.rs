@@ -0,0 +1,189 @@
+//! Drum kits assembled from audio files, each a named list of slots filled with at most one file.
+
+use vizia::prelude::*;
+
+use super::prelude::{AudioFileID, CollectionID, Database, DatabaseError};
+use crate::database::prelude::*;
+
+pub type KitID = usize;
+
+/// The slots of a new kit.
+pub const DEFAULT_KIT_SLOTS: [&str; 8] =
+    ["Kick", "Snare", "Clap", "Closed Hat", "Open Hat", "Tom", "Cymbal", "Percussion"];
+
+/// The audio file filling a slot of a kit.
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct KitSample {
+    pub id: AudioFileID,
+    pub collection: CollectionID,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct KitSlot {
+    pub name: String,
+    // The audio file in the slot, `None` while the slot is empty
+    pub sample: Option<KitSample>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Data, Lens)]
+pub struct Kit {
+    pub id: KitID,
+    pub name: String,
+    pub slots: Vec<KitSlot>,
+}
+
+pub trait DatabaseKitHandler {
+    /// All kits in the order they were created, with their slots in order.
+    fn get_kits(&self) -> Result<Vec<Kit>, DatabaseError>;
+    /// Create a kit with empty slots with the given names. Returns the id of the kit.
+    fn insert_kit(&mut self, name: &str, slots: &[&str]) -> Result<KitID, DatabaseError>;
+    fn rename_kit(&mut self, kit: KitID, name: &str) -> Result<(), DatabaseError>;
+    fn remove_kit(&mut self, kit: KitID) -> Result<(), DatabaseError>;
+    /// Add an empty slot after the last slot of a kit.
+    fn add_kit_slot(&mut self, kit: KitID, name: &str) -> Result<(), DatabaseError>;
+    fn rename_kit_slot(&mut self, kit: KitID, slot: usize, name: &str)
+        -> Result<(), DatabaseError>;
+    /// Remove a slot of a kit, moving the slots after it up by one.
+    fn remove_kit_slot(&mut self, kit: KitID, slot: usize) -> Result<(), DatabaseError>;
+    /// Fill a slot of a kit with an audio file, or empty it.
+    fn set_kit_slot(
+        &mut self,
+        kit: KitID,
+        slot: usize,
+        audio_file: Option<AudioFileID>,
+    ) -> Result<(), DatabaseError>;
+}
+
+impl DatabaseKitHandler for Database {
+    fn get_kits(&self) -> Result<Vec<Kit>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            // Slots whose audio file has been removed from the index read as empty
+            let mut query = connection.prepare(
+                "
+            SELECT kits.id, kits.name, kits_slots.name, audio_files.id, audio_files.collection,
+                audio_files.name
+                FROM kits
+                LEFT JOIN kits_slots ON kits_slots.kit = kits.id
+                LEFT JOIN audio_files ON audio_files.id = kits_slots.audio_file
+                ORDER BY kits.id, kits_slots.slot
+            ",
+            )?;
+
+            let mut kits: Vec<Kit> = Vec::new();
+            let mut rows = query.query([])?;
+            while let Some(row) = rows.next()? {
+                let id: KitID = row.get(0)?;
+                if kits.last().map(|kit| kit.id) != Some(id) {
+                    kits.push(Kit { id, name: row.get(1)?, slots: Vec::new() });
+                }
+
+                let Some(name) = row.get::<_, Option<String>>(2)? else {
+                    continue;
+                };
+                let sample = match row.get::<_, Option<AudioFileID>>(3)? {
+                    Some(id) => Some(KitSample { id, collection: row.get(4)?, name: row.get(5)? }),
+                    None => None,
+                };
+                kits.last_mut().unwrap().slots.push(KitSlot { name, sample });
+            }
+
+            return Ok(kits);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn insert_kit(&mut self, name: &str, slots: &[&str]) -> Result<KitID, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute("INSERT INTO kits (name) VALUES (?1)", [name])?;
+            let kit = connection.last_insert_rowid() as KitID;
+
+            let mut query = connection
+                .prepare_cached("INSERT INTO kits_slots (kit, slot, name) VALUES (?1, ?2, ?3)")?;
+            for (slot, name) in slots.iter().enumerate() {
+                query.execute((kit, slot, name))?;
+            }
+
+            return Ok(kit);
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn rename_kit(&mut self, kit: KitID, name: &str) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute("UPDATE kits SET name = (?2) WHERE id = (?1)", (kit, name))?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_kit(&mut self, kit: KitID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute("DELETE FROM kits_slots WHERE kit = (?1)", [kit])?;
+            connection.execute("DELETE FROM kits WHERE id = (?1)", [kit])?;
+        }
+
+        Ok(())
+    }
+
+    fn add_kit_slot(&mut self, kit: KitID, name: &str) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "
+            INSERT INTO kits_slots (kit, slot, name)
+                SELECT (?1), COUNT(*), (?2) FROM kits_slots WHERE kit = (?1)
+            ",
+                (kit, name),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn rename_kit_slot(
+        &mut self,
+        kit: KitID,
+        slot: usize,
+        name: &str,
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "UPDATE kits_slots SET name = (?3) WHERE kit = (?1) AND slot = (?2)",
+                (kit, slot, name),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_kit_slot(&mut self, kit: KitID, slot: usize) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection
+                .execute("DELETE FROM kits_slots WHERE kit = (?1) AND slot = (?2)", (kit, slot))?;
+            connection.execute(
+                "UPDATE kits_slots SET slot = slot - 1 WHERE kit = (?1) AND slot > (?2)",
+                (kit, slot),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn set_kit_slot(
+        &mut self,
+        kit: KitID,
+        slot: usize,
+        audio_file: Option<AudioFileID>,
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "UPDATE kits_slots SET audio_file = (?3) WHERE kit = (?1) AND slot = (?2)",
+                (kit, slot, audio_file),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! The metadata embedded in audio files, stored as searchable key/value pairs.
+
+use super::*;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use strum::{AsRefStr, EnumString};
+use vizia::prelude::*;
+
+/// The kind of chunk or tag an entry was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Data, EnumString, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum MetadataSource {
+    Bext,
+    Ixml,
+    Info,
+    Aiff,
+    Id3,
+    Vorbis,
+}
+
+impl std::fmt::Display for MetadataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataSource::Bext => write!(f, "BWF"),
+            MetadataSource::Ixml => write!(f, "iXML"),
+            MetadataSource::Info => write!(f, "INFO"),
+            MetadataSource::Aiff => write!(f, "AIFF"),
+            MetadataSource::Id3 => write!(f, "ID3"),
+            MetadataSource::Vorbis => write!(f, "Vorbis"),
+        }
+    }
+}
+
+impl ToSql for MetadataSource {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_ref()))
+    }
+}
+
+impl FromSql for MetadataSource {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        MetadataSource::from_str(value.as_str()?).map_err(|err| FromSqlError::Other(Box::new(err)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Data, Lens)]
+pub struct MetadataEntry {
+    pub source: MetadataSource,
+    // Lowercase field name, e.g. `description` or `artist`
+    pub key: String,
+    pub value: String,
+}
+
+impl MetadataEntry {
+    pub fn new(source: MetadataSource, key: String, value: String) -> Self {
+        Self { source, key, value }
+    }
+}
+
+pub trait DatabaseMetadataHandler {
+    fn get_metadata_for_audio_file(
+        &self,
+        audio_file: AudioFileID,
+    ) -> Result<Vec<MetadataEntry>, DatabaseError>;
+    fn insert_metadata(
+        &mut self,
+        audio_file: AudioFileID,
+        entries: &[MetadataEntry],
+    ) -> Result<(), DatabaseError>;
+    fn remove_metadata(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError>;
+    /// The audio files whose name or embedded metadata contains `text`, ignoring ASCII case, or whose detected
+    /// pitch or root note is the note named by `text`, such as `F#` or `A1`.
+    fn search_audio_files(&self, text: &str) -> Result<Vec<AudioFileID>, DatabaseError>;
+}
+
+impl DatabaseMetadataHandler for Database {
+    fn get_metadata_for_audio_file(
+        &self,
+        audio_file: AudioFileID,
+    ) -> Result<Vec<MetadataEntry>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare(
+                "SELECT source, key, value FROM audio_files_metadata WHERE audio_file = (?1) ORDER BY rowid",
+            )?;
+
+            let entries = query.query_map([audio_file], |row| {
+                Ok(MetadataEntry { source: row.get(0)?, key: row.get(1)?, value: row.get(2)? })
+            })?;
+
+            return Ok(entries.map(|v| v.unwrap()).collect());
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn insert_metadata(
+        &mut self,
+        audio_file: AudioFileID,
+        entries: &[MetadataEntry],
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "INSERT INTO audio_files_metadata (audio_file, source, key, value) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+
+            for entry in entries {
+                query.execute((audio_file, entry.source, &entry.key, &entry.value))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_metadata(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "DELETE FROM audio_files_metadata WHERE audio_file = (?1)",
+                [audio_file],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn search_audio_files(&self, text: &str) -> Result<Vec<AudioFileID>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare(
+                "
+            SELECT id FROM audio_files WHERE name LIKE (?1) ESCAPE '\\'
+            UNION
+            SELECT audio_file FROM audio_files_metadata WHERE value LIKE (?1) ESCAPE '\\'
+            UNION
+            SELECT id FROM audio_files WHERE COALESCE(pitch_note, root_note) % 12 = (?2)
+                AND COALESCE(pitch_note, root_note) = COALESCE((?3), COALESCE(pitch_note, root_note))
+            ",
+            )?;
+
+            let pattern =
+                format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            let (pitch_class, note) = match parse_note_name(text) {
+                Some((pitch_class, note)) => (Some(pitch_class), note),
+                None => (None, None),
+            };
+            let ids = query.query_map((pattern, pitch_class, note), |row| row.get(0))?;
+
+            return Ok(ids.map(|v| v.unwrap()).collect());
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+}
@@ -0,0 +1,188 @@
+//! Upgrades of the schema of libraries created by earlier versions, tracked with `PRAGMA user_version`.
+//!
+//! `sqls/schema.sql` always creates the latest schema. Each change to it needs a migration here which brings
+//! a library from the previous version up to it, and [`SCHEMA_VERSION`] raised to match.
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::{AudioFormat, DatabaseError};
+
+/// The version of the schema created by `sqls/schema.sql`.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// A change of the schema from one version to the next
+type Migration = fn(&Connection) -> Result<(), DatabaseError>;
+
+// The migration from each version to the next, starting from the first schema which had no version. Libraries
+// indexed by development builds, or migrated before the changes were split into versions, may already have some
+// of the changes, so each migration only makes those which are missing.
+const MIGRATIONS: [Migration; 9] = [
+    migrate_format,
+    migrate_stream_info,
+    migrate_metadata,
+    migrate_sampler,
+    migrate_classification,
+    migrate_features,
+    migrate_pitch,
+    migrate_audible_range,
+    migrate_kits,
+];
+
+/// Create the latest schema in a new database.
+pub fn create_schema(connection: &Connection) -> Result<(), DatabaseError> {
+    connection.execute_batch(include_str!("sqls/schema.sql"))?;
+    connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(())
+}
+
+/// Bring the schema of an existing database up to [`SCHEMA_VERSION`], one migration at a time. Each migration
+/// runs in its own transaction along with the update of the version, so that a failed migration leaves the
+/// database as it was.
+pub fn migrate(connection: &Connection) -> Result<(), DatabaseError> {
+    let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let transaction = connection.unchecked_transaction()?;
+        migration(&transaction)?;
+        transaction.pragma_update(None, "user_version", index as u32 + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+// Version 1 records the format of the files, filled in from their extensions.
+fn migrate_format(connection: &Connection) -> Result<(), DatabaseError> {
+    if add_columns(connection, "audio_files", &[("format", "nvarchar(8) DEFAULT 'wav'")])? {
+        fill_formats(connection)?;
+    }
+    Ok(())
+}
+
+// Version 2 records the sample format and codec of the files. The integer types of the first schema
+// accept the real durations and tempos stored since.
+fn migrate_stream_info(connection: &Connection) -> Result<(), DatabaseError> {
+    add_columns(
+        connection,
+        "audio_files",
+        &[("sample_format", "nvarchar(8) NULL"), ("codec", "nvarchar(16) DEFAULT ''")],
+    )?;
+    Ok(())
+}
+
+// Version 3 stores the metadata embedded in the files.
+fn migrate_metadata(connection: &Connection) -> Result<(), DatabaseError> {
+    connection.execute_batch(include_str!("sqls/migrations/metadata.sql"))?;
+    Ok(())
+}
+
+// Version 4 records the root note, loop points and tempo read from the sampler chunks of the files.
+fn migrate_sampler(connection: &Connection) -> Result<(), DatabaseError> {
+    add_columns(
+        connection,
+        "audio_files",
+        &[
+            ("root_note", "integer NULL"),
+            ("loop_start", "integer NULL"),
+            ("loop_end", "integer NULL"),
+            ("tempo", "real NULL"),
+            ("beats", "integer NULL"),
+            ("one_shot", "boolean DEFAULT 0"),
+        ],
+    )?;
+    Ok(())
+}
+
+// Version 5 records the confidence of the tags suggested by the classification of the files.
+fn migrate_classification(connection: &Connection) -> Result<(), DatabaseError> {
+    add_columns(connection, "audio_files_tags", &[("confidence", "real NULL")])?;
+    Ok(())
+}
+
+// Version 6 stores the feature vectors of the files.
+fn migrate_features(connection: &Connection) -> Result<(), DatabaseError> {
+    connection.execute_batch(include_str!("sqls/migrations/features.sql"))?;
+    Ok(())
+}
+
+// Version 7 records the detected pitch and tuning offset of the files.
+fn migrate_pitch(connection: &Connection) -> Result<(), DatabaseError> {
+    add_columns(
+        connection,
+        "audio_files",
+        &[
+            ("pitch_note", "integer NULL"),
+            ("pitch_cents", "real NULL"),
+            ("tuning_offset", "real NULL"),
+        ],
+    )?;
+    Ok(())
+}
+
+// Version 8 records the audible start and end of the files.
+fn migrate_audible_range(connection: &Connection) -> Result<(), DatabaseError> {
+    add_columns(
+        connection,
+        "audio_files",
+        &[("audible_start", "integer NULL"), ("audible_end", "integer NULL")],
+    )?;
+    Ok(())
+}
+
+// Version 9 adds kits.
+fn migrate_kits(connection: &Connection) -> Result<(), DatabaseError> {
+    connection.execute_batch(include_str!("sqls/migrations/kits.sql"))?;
+    Ok(())
+}
+
+// Fill in the format of the existing files from their extensions.
+fn fill_formats(connection: &Connection) -> Result<(), DatabaseError> {
+    let mut query = connection.prepare("SELECT id, name FROM audio_files")?;
+    let formats = query
+        .query_map([], |row| Ok((row.get::<_, usize>(0)?, row.get::<_, String>(1)?)))?
+        .filter_map(|row| {
+            let (id, name) = row.ok()?;
+            Some((id, AudioFormat::from_extension(Path::new(&name))?))
+        })
+        .collect::<Vec<_>>();
+
+    let mut update = connection.prepare("UPDATE audio_files SET format = (?2) WHERE id = (?1)")?;
+    for (id, format) in formats {
+        update.execute((id, format))?;
+    }
+
+    Ok(())
+}
+
+// Add the columns missing from `table`, with their definitions. Rows of existing files get the default until
+// they are indexed again. Returns whether any column was added.
+fn add_columns(
+    connection: &Connection,
+    table: &str,
+    columns: &[(&str, &str)],
+) -> Result<bool, DatabaseError> {
+    let mut added = false;
+    for (column, definition) in columns {
+        if !has_column(connection, table, column)? {
+            connection
+                .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"))?;
+            added = true;
+        }
+    }
+
+    Ok(added)
+}
+
+fn has_column(connection: &Connection, table: &str, column: &str) -> Result<bool, DatabaseError> {
+    let mut query = connection.prepare(&format!("PRAGMA table_info({table})"))?;
+    let mut rows = query.query([])?;
+    while let Some(row) = rows.next()? {
+        if row.get::<_, String>(1)? == column {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
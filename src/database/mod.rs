@@ -23,16 +23,56 @@ pub use audio_file::*;
 pub mod tags;
 pub use tags::*;
 
+pub mod watcher;
+pub use watcher::*;
+
+pub mod indexer;
+pub use indexer::*;
+
+pub mod formats;
+pub use formats::*;
+
+pub mod metadata;
+pub use metadata::*;
+
+pub mod embedded;
+pub use embedded::*;
+
+pub mod analysis;
+pub use analysis::*;
+
+pub mod features;
+pub use features::*;
+
+pub mod pitch;
+pub use pitch::*;
+
+pub mod kits;
+pub use kits::*;
+
+pub mod migrations;
+pub use migrations::*;
+
 mod tests;
 
 pub mod prelude {
+    pub use super::analysis::*;
     pub use super::audio_file::*;
     pub use super::collection::*;
     pub use super::connection::*;
+    pub use super::embedded::*;
     pub use super::error::*;
+    pub use super::features::*;
+    pub use super::formats::*;
     pub use super::handler::*;
+    pub use super::indexer::*;
+    pub use super::kits::*;
+    pub use super::metadata::*;
+    pub use super::migrations::*;
+    pub use super::pitch::*;
     pub use super::store::*;
     pub use super::tags::*;
+    pub use super::watcher::*;
     pub use rusqlite::*;
 }
 
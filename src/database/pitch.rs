@@ -0,0 +1,118 @@
+//! Detection of the fundamental frequency of tonal one-shots, such as bass hits, plucks, 808s and vocal chops.
+
+use serde::{Deserialize, Serialize};
+use vizia::prelude::*;
+
+// The range of detected fundamentals, from sub bass to the top of a vocal chop
+const MIN_FREQUENCY: f32 = 30.0;
+const MAX_FREQUENCY: f32 = 2000.0;
+
+// The signal is decimated to at most this sample rate before detection
+const DETECTION_SAMPLE_RATE: u32 = 16000;
+
+// The normalised difference below which a lag is taken as the period, from the YIN paper
+const YIN_THRESHOLD: f32 = 0.15;
+
+// The number of frames measured after the attack
+const MAX_FRAMES: usize = 8;
+
+/// A detected pitch, as the nearest MIDI note and its deviation from it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Data, Lens)]
+pub struct Pitch {
+    pub note: u8,
+    // The deviation from the note in cents, between -50 and 50
+    pub cents: f32,
+}
+
+impl Pitch {
+    pub fn from_frequency(frequency: f32) -> Option<Self> {
+        let midi = 69.0 + 12.0 * (frequency / 440.0).log2();
+        let note = midi.round();
+        (0.0..128.0)
+            .contains(&note)
+            .then_some(Self { note: note as u8, cents: (midi - note) * 100.0 })
+    }
+
+    pub fn frequency(&self) -> f32 {
+        440.0 * ((self.note as f32 + self.cents / 100.0 - 69.0) / 12.0).exp2()
+    }
+}
+
+/// The shift in cents which plays a sample of the given pitch at the nearest `root` pitch class, after
+/// applying its `tuning_offset` in cents.
+pub fn retune_cents(note: u8, tuning_offset: f32, root: u8) -> f32 {
+    let semitones = (root as i32 - note as i32).rem_euclid(12);
+    let semitones = if semitones > 6 { semitones - 12 } else { semitones };
+    semitones as f32 * 100.0 + tuning_offset
+}
+
+/// Detect the pitch of a mono signal with the YIN algorithm, or `None` if it is not tonal.
+///
+/// The attack is skipped, and the median of the pitches of the frames following it is taken so that a short
+/// glide, as in an 808, does not shift the result.
+pub fn detect_pitch(samples: &[f32], sample_rate: u32) -> Option<Pitch> {
+    let factor = (sample_rate / DETECTION_SAMPLE_RATE).max(1) as usize;
+    let sample_rate = sample_rate as f32 / factor as f32;
+    let samples: Vec<f32> = samples
+        .chunks(factor)
+        .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+        .collect();
+
+    let min_lag = ((sample_rate / MAX_FREQUENCY) as usize).max(2);
+    let max_lag = (sample_rate / MIN_FREQUENCY) as usize;
+    let window = max_lag;
+
+    let (peak, peak_level) =
+        samples.iter().map(|sample| sample.abs()).enumerate().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if peak_level < 1e-4 {
+        return None;
+    }
+
+    let mut start = peak + (0.02 * sample_rate) as usize;
+    let mut frames = 0;
+    let mut frequencies = Vec::with_capacity(MAX_FRAMES);
+    while frames < MAX_FRAMES && start + window + max_lag <= samples.len() {
+        let frame = &samples[start..start + window + max_lag];
+
+        // The tail has faded out
+        let rms = (frame[..window].iter().map(|s| s * s).sum::<f32>() / window as f32).sqrt();
+        if rms < peak_level * 0.01 {
+            break;
+        }
+
+        frequencies.extend(yin(frame, window, min_lag, max_lag).map(|lag| sample_rate / lag));
+        frames += 1;
+        start += window / 2;
+    }
+
+    // Most frames must be periodic
+    if frames == 0 || frequencies.len() * 2 <= frames {
+        return None;
+    }
+
+    frequencies.sort_by(f32::total_cmp);
+    Pitch::from_frequency(frequencies[frequencies.len() / 2])
+}
+
+// The period of a frame in samples, interpolated between lags.
+fn yin(frame: &[f32], window: usize, min_lag: usize, max_lag: usize) -> Option<f32> {
+    // The cumulative mean normalised difference function
+    let mut difference = vec![1.0; max_lag + 1];
+    let mut total = 0.0;
+    for lag in 1..=max_lag {
+        let d: f32 = (0..window).map(|i| (frame[i] - frame[i + lag]).powi(2)).sum();
+        total += d;
+        difference[lag] = if total > 0.0 { d * lag as f32 / total } else { 1.0 };
+    }
+
+    // The first dip below the threshold, followed down to its minimum
+    let mut lag = (min_lag..max_lag).find(|lag| difference[*lag] < YIN_THRESHOLD)?;
+    while lag + 1 < max_lag && difference[lag + 1] < difference[lag] {
+        lag += 1;
+    }
+
+    let (a, b, c) = (difference[lag - 1], difference[lag], difference[lag + 1]);
+    let curvature = a - 2.0 * b + c;
+    let shift = if curvature > 0.0 { 0.5 * (a - c) / curvature } else { 0.0 };
+    Some(lag as f32 + shift.clamp(-0.5, 0.5))
+}
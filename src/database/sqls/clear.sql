@@ -1,4 +1,8 @@
 DELETE TABLE collections;
 DELETE TABLE audio_files;
 DELETE TABLE tags;
-DELETE TABLE audio_files_tags;
\ No newline at end of file
+DELETE TABLE audio_files_tags;
+DELETE TABLE audio_files_metadata;
+DELETE TABLE audio_files_features;
+DELETE TABLE kits;
+DELETE TABLE kits_slots;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS audio_files_features (
+    audio_file          integer PRIMARY KEY,
+    features            blob NOT NULL,
+
+    CONSTRAINT fk_aff_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE CASCADE
+);
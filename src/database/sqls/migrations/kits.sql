@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS kits (
+    id                  integer PRIMARY KEY,
+    name                nvarchar(255)
+);
+
+CREATE TABLE IF NOT EXISTS kits_slots (
+    kit                 integer,
+    slot                integer,
+    name                nvarchar(255),
+    audio_file          integer NULL,
+
+    CONSTRAINT fk_ks_kit 
+        FOREIGN KEY(kit) 
+        REFERENCES kits(id) 
+        ON DELETE CASCADE
+    CONSTRAINT fk_ks_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ks_kit ON kits_slots (kit);
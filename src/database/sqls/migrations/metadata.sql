@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS audio_files_metadata (
+    audio_file          integer,
+    source              nvarchar(8),
+    key                 nvarchar(255),
+    value               text,
+
+    CONSTRAINT fk_afm_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_afm_af ON audio_files_metadata (audio_file);
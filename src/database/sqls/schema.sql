@@ -14,13 +14,27 @@ CREATE TABLE audio_files (
     id                  integer UNIQUE PRIMARY KEY,
     name                nvarchar(255),
     collection          integer,
-    duration            integer,
+    duration            real,
     sample_rate         integer,
-    bit_depth           integer,
+    bit_depth           integer NULL,
     num_channels        integer,
-    bpm                 integer NULL,
+    bpm                 real NULL,
     key                 integer NULL,
     size                integer,
+    format              nvarchar(8),
+    sample_format       nvarchar(8) NULL,
+    codec               nvarchar(16),
+    root_note           integer NULL,
+    loop_start          integer NULL,
+    loop_end            integer NULL,
+    tempo               real NULL,
+    beats               integer NULL,
+    one_shot            boolean DEFAULT 0,
+    pitch_note          integer NULL,
+    pitch_cents         real NULL,
+    tuning_offset       real NULL,
+    audible_start       integer NULL,
+    audible_end         integer NULL,
 
     CONSTRAINT fk_af_coll 
         FOREIGN KEY(collection) 
@@ -38,6 +52,7 @@ CREATE TABLE tags (
 CREATE TABLE audio_files_tags (
     audio_file          integer,
     tag                 integer,
+    confidence          real NULL,
 
     PRIMARY KEY (audio_file, tag)
 
@@ -49,4 +64,51 @@ CREATE TABLE audio_files_tags (
         FOREIGN KEY(tag) 
         REFERENCES tags(id) 
         ON DELETE CASCADE
-);
\ No newline at end of file
+);
+
+CREATE TABLE audio_files_metadata (
+    audio_file          integer,
+    source              nvarchar(8),
+    key                 nvarchar(255),
+    value               text,
+
+    CONSTRAINT fk_afm_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_afm_af ON audio_files_metadata (audio_file);
+
+CREATE TABLE audio_files_features (
+    audio_file          integer PRIMARY KEY,
+    features            blob NOT NULL,
+
+    CONSTRAINT fk_aff_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE CASCADE
+);
+
+CREATE TABLE kits (
+    id                  integer PRIMARY KEY,
+    name                nvarchar(255)
+);
+
+CREATE TABLE kits_slots (
+    kit                 integer,
+    slot                integer,
+    name                nvarchar(255),
+    audio_file          integer NULL,
+
+    CONSTRAINT fk_ks_kit 
+        FOREIGN KEY(kit) 
+        REFERENCES kits(id) 
+        ON DELETE CASCADE
+    CONSTRAINT fk_ks_af 
+        FOREIGN KEY(audio_file) 
+        REFERENCES audio_files(id) 
+        ON DELETE SET NULL
+);
+
+CREATE INDEX idx_ks_kit ON kits_slots (kit);
\ No newline at end of file
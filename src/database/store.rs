@@ -1,9 +1,11 @@
 use super::{Database, DatabaseError};
+use base64ct::{Base64, Encoding};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     collections::HashMap,
     fs::{create_dir, File},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use vizia::prelude::*;
 
@@ -13,6 +15,19 @@ pub const DATABASE_META_NAME: &str = ".vsb-meta";
 
 pub type Hash = String;
 
+/// Compute a content fingerprint of a file from all of its bytes.
+///
+/// Used to skip files which have not changed since they were indexed, and to recognise a file that was moved
+/// by a delete followed by a create.
+pub fn fingerprint(path: &Path) -> Option<Hash> {
+    let mut file = File::open(path).ok()?;
+
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+
+    Some(Base64::encode_string(&hasher.finalize()))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Lens, PartialEq)]
 pub struct DatabaseMetadata {
     pub(super) map: HashMap<PathBuf, Hash>,
@@ -76,8 +91,10 @@ pub trait DatabaseStore {
         Ok(())
     }
 
-    fn store_metadata(&self);
-    fn retreive_metadata(&mut self);
+    /// Save the fingerprints of the indexed files next to the database.
+    fn store_metadata(&self) -> Result<(), DatabaseError>;
+    /// Load the fingerprints saved by [`DatabaseStore::store_metadata`].
+    fn retrieve_metadata(&mut self) -> Result<(), DatabaseError>;
 }
 
 impl DatabaseStore for Database {
@@ -85,13 +102,16 @@ impl DatabaseStore for Database {
         &self.path
     }
 
-    fn store_metadata(&self) {
-        let to_store =
-            ron::ser::to_string_pretty(&self.meta, ron::ser::PrettyConfig::default()).unwrap();
-        std::fs::write(self.get_meta_path(), to_store).unwrap()
+    fn store_metadata(&self) -> Result<(), DatabaseError> {
+        let to_store = ron::ser::to_string_pretty(&self.meta, ron::ser::PrettyConfig::default())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        std::fs::write(self.get_meta_path(), to_store)?;
+        Ok(())
     }
 
-    fn retreive_metadata(&mut self) {
-        self.meta = ron::from_str(&std::fs::read_to_string(self.get_meta_path()).unwrap()).unwrap()
+    fn retrieve_metadata(&mut self) -> Result<(), DatabaseError> {
+        self.meta = ron::from_str(&std::fs::read_to_string(self.get_meta_path())?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        Ok(())
     }
 }
@@ -19,6 +19,16 @@ impl Tag {
     }
 }
 
+/// The suggestions of a tag waiting to be accepted or rejected.
+#[derive(Debug, Clone, PartialEq, Data, Lens)]
+pub struct TagSuggestion {
+    pub tag: Tag,
+    // The number of audio files the tag is suggested for
+    pub count: u32,
+    // The mean confidence of the suggestions
+    pub confidence: f32,
+}
+
 pub trait DatabaseTagHandler {
     fn get_all_tags(&self) -> Result<Vec<Tag>, DatabaseError>;
     fn get_tags_for_audio_file(&self, audio_file: AudioFileID) -> Result<Vec<Tag>, DatabaseError>;
@@ -28,6 +38,22 @@ pub trait DatabaseTagHandler {
         tag: TagID,
         audio_file: AudioFileID,
     ) -> Result<(), DatabaseError>;
+    /// Suggest the tags with the given names for an audio file. Tags already assigned are kept as they are.
+    fn suggest_tags(
+        &mut self,
+        audio_file: AudioFileID,
+        tags: &[(&str, f32)],
+    ) -> Result<(), DatabaseError>;
+    fn remove_suggested_tags(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError>;
+    fn get_tag_suggestions(&self) -> Result<Vec<TagSuggestion>, DatabaseError>;
+    /// Assign a tag to the files it is suggested for with at least `min_confidence`.
+    fn accept_suggested_tags(
+        &mut self,
+        tag: TagID,
+        min_confidence: f32,
+    ) -> Result<(), DatabaseError>;
+    /// Remove the remaining suggestions of a tag.
+    fn reject_suggested_tags(&mut self, tag: TagID) -> Result<(), DatabaseError>;
 }
 
 impl DatabaseTagHandler for Database {
@@ -57,7 +83,7 @@ impl DatabaseTagHandler for Database {
             SELECT id, name, color, number FROM tags
                 WHERE id IN (
                     SELECT tag FROM audio_files_tags
-                        WHERE audio_file = (?1)
+                        WHERE audio_file = (?1) AND confidence IS NULL
                 )
             ",
             )?;
@@ -94,14 +120,107 @@ impl DatabaseTagHandler for Database {
         audio_file: AudioFileID,
     ) -> Result<(), DatabaseError> {
         if let Some(connection) = self.get_connection() {
+            // Assigning a suggested tag accepts it
             connection.execute(
-                "INSERT INTO audio_files_tags (audio_file, tags) VALUES (?1, ?2)",
+                "
+            INSERT INTO audio_files_tags (audio_file, tag) VALUES (?1, ?2)
+                ON CONFLICT (audio_file, tag) DO UPDATE SET confidence = NULL
+            ",
                 (audio_file, tag),
             )?;
         }
 
         Ok(())
     }
+
+    fn suggest_tags(
+        &mut self,
+        audio_file: AudioFileID,
+        tags: &[(&str, f32)],
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare_cached(
+                "
+            INSERT OR IGNORE INTO audio_files_tags (audio_file, tag, confidence)
+                SELECT (?1), id, (?3) FROM tags WHERE name = (?2)
+            ",
+            )?;
+
+            for (name, confidence) in tags {
+                query.execute((audio_file, name, confidence))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_suggested_tags(&mut self, audio_file: AudioFileID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "DELETE FROM audio_files_tags WHERE audio_file = (?1) AND confidence IS NOT NULL",
+                [audio_file],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn get_tag_suggestions(&self) -> Result<Vec<TagSuggestion>, DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            let mut query = connection.prepare(
+                "
+            SELECT tags.id, tags.name, tags.color, tags.number, COUNT(*), AVG(confidence)
+                FROM audio_files_tags JOIN tags ON tags.id = audio_files_tags.tag
+                WHERE confidence IS NOT NULL
+                GROUP BY tags.id
+                ORDER BY tags.name
+            ",
+            )?;
+
+            let suggestions = query.query_map([], |row| {
+                Ok(TagSuggestion {
+                    tag: Tag {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        color: row.get(2)?,
+                        number: row.get(3)?,
+                    },
+                    count: row.get(4)?,
+                    confidence: row.get(5)?,
+                })
+            })?;
+
+            return Ok(suggestions.map(|v| v.unwrap()).collect());
+        }
+
+        Err(DatabaseError::ConnectionClosed)
+    }
+
+    fn accept_suggested_tags(
+        &mut self,
+        tag: TagID,
+        min_confidence: f32,
+    ) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "UPDATE audio_files_tags SET confidence = NULL WHERE tag = (?1) AND confidence >= (?2)",
+                (tag, min_confidence),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn reject_suggested_tags(&mut self, tag: TagID) -> Result<(), DatabaseError> {
+        if let Some(connection) = self.get_connection() {
+            connection.execute(
+                "DELETE FROM audio_files_tags WHERE tag = (?1) AND confidence IS NOT NULL",
+                [tag],
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 struct AudioFilesTag {
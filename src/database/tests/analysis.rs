@@ -0,0 +1,100 @@
+use std::sync::Mutex;
+
+use crate::database::{
+    prelude::*,
+    tests::{hat, hit, kick, sine, snare, test_library, write_float_wav, SAMPLE_RATE},
+};
+
+#[test]
+fn classify_one_shots() {
+    let none = SamplerInfo::default();
+
+    let kick = classify(&kick(), SAMPLE_RATE, "hit.wav", &none);
+    assert_eq!(kick.kind, SampleKind::OneShot);
+    assert!(kick.kind_confidence > 0.8);
+    assert_eq!(kick.drum.map(|(drum, _)| drum), Some(DrumType::Kick));
+
+    let hat = classify(&hat(), SAMPLE_RATE, "hit.wav", &none);
+    assert_eq!(hat.kind, SampleKind::OneShot);
+    assert_eq!(hat.drum.map(|(drum, _)| drum), Some(DrumType::HiHat));
+
+    let snare = classify(&snare(), SAMPLE_RATE, "hit.wav", &none);
+    assert_eq!(snare.drum.map(|(drum, _)| drum), Some(DrumType::Snare));
+}
+
+#[test]
+fn classify_loops() {
+    // Four bars of kicks at 120 BPM
+    let samples: Vec<f32> = (0..16).flat_map(|_| hit(0.5, 0.2, sine(55.0))).collect();
+
+    let classification = classify(&samples, SAMPLE_RATE, "beat.wav", &SamplerInfo::default());
+    assert_eq!(classification.kind, SampleKind::Loop);
+    assert_eq!(classification.drum, None);
+
+    // ACID flags override the signal
+    let one_shot = SamplerInfo { one_shot: true, ..Default::default() };
+    let classification = classify(&samples, SAMPLE_RATE, "beat.wav", &one_shot);
+    assert_eq!(classification.kind, SampleKind::OneShot);
+
+    let acidized = SamplerInfo { tempo: Some(120.0), ..Default::default() };
+    let classification = classify(&kick(), SAMPLE_RATE, "hit.wav", &acidized);
+    assert_eq!(classification.kind, SampleKind::Loop);
+}
+
+#[test]
+fn classify_from_name() {
+    // A mid-range tone cannot be told apart from its signal
+    let samples = hit(0.4, 0.05, sine(900.0));
+    let none = SamplerInfo::default();
+
+    let classification = classify(&samples, SAMPLE_RATE, "Big_Clap_01.wav", &none);
+    assert_eq!(classification.drum, Some((DrumType::Clap, 0.6)));
+
+    let classification = classify(&kick(), SAMPLE_RATE, "BD_Deep.wav", &none);
+    let (drum, confidence) = classification.drum.unwrap();
+    assert_eq!(drum, DrumType::Kick);
+    assert!(confidence > 0.8);
+
+    // Silence is never classified by drum type
+    let classification = classify(&[0.0; 4410], SAMPLE_RATE, "silence.wav", &none);
+    assert_eq!(classification.kind, SampleKind::OneShot);
+    assert_eq!(classification.drum, None);
+}
+
+#[test]
+fn index_library_suggests_tags() {
+    let root = test_library("analysis-index");
+    write_float_wav(&root.join("drums/kick.wav"), &kick());
+    write_float_wav(&root.join("drums/hat.wav"), &hat());
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let mut db = database.lock().unwrap();
+    let suggestions = db.get_tag_suggestions().unwrap();
+    let names: Vec<_> = suggestions.iter().map(|s| (s.tag.name.as_str(), s.count)).collect();
+    assert_eq!(names, vec![("HiHat", 1), ("Kick", 1), ("OneShot", 2)]);
+
+    let collection = db.get_collection_by_path(&root.join("drums")).unwrap().id();
+    let kick = db.get_audio_file_by_name(collection, "kick.wav").unwrap();
+    let one_shot = suggestions.iter().find(|s| s.tag.name == "OneShot").unwrap().tag.id;
+    let kick_tag = suggestions.iter().find(|s| s.tag.name == "Kick").unwrap().tag.id;
+
+    // Accepted suggestions become assigned tags, rejected ones are removed
+    db.accept_suggested_tags(one_shot, 0.5).unwrap();
+    db.reject_suggested_tags(kick_tag).unwrap();
+
+    let names: Vec<_> = db.get_tag_suggestions().unwrap().into_iter().map(|s| s.tag.name).collect();
+    assert_eq!(names, vec!["HiHat"]);
+    let tags: Vec<_> =
+        db.get_tags_for_audio_file(kick.id).unwrap().into_iter().map(|tag| tag.name).collect();
+    assert_eq!(tags, vec!["OneShot"]);
+
+    // Assigned tags are kept when the file is analysed again
+    db.remove_suggested_tags(kick.id).unwrap();
+    db.suggest_tags(kick.id, &[("OneShot", 0.7), ("Kick", 0.9)]).unwrap();
+    let tags: Vec<_> =
+        db.get_tags_for_audio_file(kick.id).unwrap().into_iter().map(|tag| tag.name).collect();
+    assert_eq!(tags, vec!["OneShot"]);
+    assert_eq!(db.get_tag_suggestions().unwrap().len(), 2);
+}
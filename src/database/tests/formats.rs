@@ -0,0 +1,71 @@
+use std::{path::Path, sync::Mutex};
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+#[test]
+fn detect_format_from_magic() {
+    assert_eq!(AudioFormat::from_magic(b"RIFF\0\0\0\0WAVEfmt "), Some(AudioFormat::Wav));
+    assert_eq!(AudioFormat::from_magic(b"FORM\0\0\0\0AIFC"), Some(AudioFormat::Aiff));
+    assert_eq!(AudioFormat::from_magic(b"fLaC\0\0\0\x22"), Some(AudioFormat::Flac));
+    assert_eq!(AudioFormat::from_magic(b"ID3\x04\0\0"), Some(AudioFormat::Mp3));
+    assert_eq!(AudioFormat::from_magic(&[0xFF, 0xFB, 0x90, 0x64]), Some(AudioFormat::Mp3));
+    assert_eq!(AudioFormat::from_magic(b"OggS\0\x02"), Some(AudioFormat::Ogg));
+    assert_eq!(AudioFormat::from_magic(b"\0\0\0\x20ftypM4A "), Some(AudioFormat::M4a));
+    assert_eq!(AudioFormat::from_magic(b"RIFF\0\0\0\0AVI "), None);
+    assert_eq!(AudioFormat::from_magic(b"\x89PNG\r\n"), None);
+}
+
+#[test]
+fn filter_files() {
+    let root = Path::new("/library");
+    let filter = LibraryFilter::default().compile(root).unwrap();
+
+    assert_eq!(filter.accepts_file(&root.join("drums/kick.WAV")), Some(AudioFormat::Wav));
+    assert_eq!(filter.accepts_file(&root.join("drums/kick.aif")), Some(AudioFormat::Aiff));
+    assert_eq!(filter.accepts_file(&root.join("drums/kick.wav.asd")), None);
+    assert_eq!(filter.accepts_file(&root.join("drums/kick.wav.reapeaks")), None);
+    assert_eq!(filter.accepts_file(&root.join("drums/notes.txt")), None);
+    assert!(!filter.accepts_directory(&root.join("drums/__MACOSX")));
+    assert!(filter.accepts_directory(&root.join("drums")));
+
+    let filter = LibraryFilter {
+        formats: [AudioFormat::Flac].into_iter().collect(),
+        include: vec![String::from("loops/**")],
+        exclude: vec![String::from("*_old.*")],
+    }
+    .compile(root)
+    .unwrap();
+
+    assert_eq!(filter.accepts_file(&root.join("loops/a/beat.flac")), Some(AudioFormat::Flac));
+    assert_eq!(filter.accepts_file(&root.join("loops/beat_old.flac")), None);
+    assert_eq!(filter.accepts_file(&root.join("loops/beat.wav")), None);
+    assert_eq!(filter.accepts_file(&root.join("one-shots/hit.flac")), None);
+}
+
+#[test]
+fn index_library_filters_files() {
+    let root = test_library("formats-filter");
+    std::fs::create_dir(root.join("drums/__MACOSX")).unwrap();
+    write_wav(&root.join("drums/snare.wav"), 100);
+    write_wav(&root.join("drums/__MACOSX/snare.wav"), 100);
+    std::fs::write(root.join("drums/snare.wav.reapeaks"), b"RPKS").unwrap();
+    std::fs::write(root.join("drums/readme.txt"), b"hello").unwrap();
+    std::fs::write(root.join("drums/cover.wav"), b"\x89PNG\r\n\x1a\n\0\0\0\0").unwrap();
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    let job = IndexJob::new();
+    let report = index_library(&database, &job, |_| {}).unwrap();
+
+    assert_eq!(report.scanned, 2);
+    assert_eq!(report.indexed, 1);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].path, root.join("drums/cover.wav"));
+
+    let db = database.lock().unwrap();
+    assert!(db.get_collection_by_path(&root.join("drums/__MACOSX")).is_err());
+    let audio_files = db.get_all_audio_files().unwrap();
+    assert_eq!(audio_files[0].format, AudioFormat::Wav);
+}
@@ -13,25 +13,31 @@ pub fn get_all_audio_files() {
                 0,
                 "Audio File 0".to_string(),
                 0,
-                0.,
-                0.,
-                0.,
-                0.,
-                Some(0.),
-                Some(0.),
-                0.
+                1.5,
+                44100,
+                Some(16),
+                1,
+                None,
+                None,
+                132344,
+                AudioFormat::Wav,
+                Some(SampleFormat::Int),
+                "PCM".to_string()
             ),
             AudioFile::new(
                 1,
                 "Audio File 1".to_string(),
                 1,
-                0.,
-                0.,
-                0.,
-                0.,
-                Some(0.),
-                Some(0.),
-                0.
+                2.0,
+                48000,
+                None,
+                2,
+                Some(120.),
+                Some(9),
+                32000,
+                AudioFormat::Mp3,
+                None,
+                "MP3".to_string()
             ),
         ]
     );
@@ -49,13 +55,16 @@ pub fn get_child_audio_files() {
             0,
             "Audio File 0".to_string(),
             0,
-            0.,
-            0.,
-            0.,
-            0.,
-            Some(0.),
-            Some(0.),
-            0.
+            1.5,
+            44100,
+            Some(16),
+            1,
+            None,
+            None,
+            132344,
+            AudioFormat::Wav,
+            Some(SampleFormat::Int),
+            "PCM".to_string()
         )],
     );
 }
@@ -0,0 +1,226 @@
+use std::{path::Path, sync::Mutex, time::Instant};
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+#[test]
+fn index_library_collects_errors() {
+    let root = test_library("indexer-errors");
+    std::fs::create_dir(root.join("drums/kicks")).unwrap();
+    write_wav(&root.join("drums/snare.wav"), 100);
+    write_wav(&root.join("drums/kicks/kick.wav"), 100);
+    std::fs::write(root.join("drums/broken.wav"), b"RIFF").unwrap();
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    let job = IndexJob::new();
+    let mut num_progress = 0;
+    let report = index_library(&database, &job, |_| num_progress += 1).unwrap();
+
+    assert_eq!(report.scanned, 3);
+    assert_eq!(report.indexed, 2);
+    assert!(!report.cancelled);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].path, root.join("drums/broken.wav"));
+    assert_eq!((job.scanned(), job.total(), job.directories()), (3, 3, 3));
+    assert!(num_progress >= 1);
+
+    let db = database.lock().unwrap();
+    let kicks = db.get_collection_by_path(&root.join("drums/kicks")).unwrap();
+    let drums = db.get_collection_by_path(&root.join("drums")).unwrap();
+    assert_eq!(kicks.parent_collection(), Some(drums.id()));
+    assert_eq!(db.get_child_audio_files(kicks.id()).unwrap().len(), 1);
+}
+
+#[test]
+fn index_library_again_keeps_ids() {
+    let root = test_library("indexer-again");
+    std::fs::create_dir(root.join("drums/kicks")).unwrap();
+    write_wav(&root.join("drums/snare.wav"), 100);
+    write_wav(&root.join("drums/hat.wav"), 100);
+    write_wav(&root.join("drums/kicks/kick.wav"), 100);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let ids = |database: &Mutex<Database>| {
+        let db = database.lock().unwrap();
+        let mut collections: Vec<_> = db
+            .get_all_collections()
+            .unwrap()
+            .into_iter()
+            .map(|collection| (collection.id(), collection.path().clone()))
+            .collect();
+        let mut audio_files: Vec<_> = db
+            .get_all_audio_files()
+            .unwrap()
+            .into_iter()
+            .map(|audio_file| (audio_file.id, audio_file.name))
+            .collect();
+        collections.sort();
+        audio_files.sort();
+        (collections, audio_files)
+    };
+    let (collections, audio_files) = ids(&database);
+    assert_eq!((collections.len(), audio_files.len()), (3, 3));
+
+    let snare = audio_files.iter().find(|(_, name)| name == "snare.wav").unwrap().0;
+    database.lock().unwrap().assign_tag_to_audio_file(32, snare).unwrap();
+
+    // Nothing has changed, so no file is probed again, even once the library has been opened again
+    drop(database);
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    let report = index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+    assert_eq!((report.scanned, report.indexed, report.removed), (3, 0, 0));
+    assert_eq!(ids(&database), (collections.clone(), audio_files.clone()));
+
+    // A changed file keeps its ID and tags, and a removed file loses its row
+    write_wav(&root.join("drums/snare.wav"), 200);
+    std::fs::remove_file(root.join("drums/hat.wav")).unwrap();
+    let report = index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+    assert_eq!((report.scanned, report.indexed, report.removed), (2, 1, 1));
+
+    let (new_collections, new_audio_files) = ids(&database);
+    assert_eq!(new_collections, collections);
+    assert_eq!(
+        new_audio_files,
+        audio_files.into_iter().filter(|(_, name)| name != "hat.wav").collect::<Vec<_>>()
+    );
+
+    let db = database.lock().unwrap();
+    assert_eq!(db.get_audio_file(snare).unwrap().duration, 200.0 / 44100.0);
+    assert_eq!(db.get_tags_for_audio_file(snare).unwrap().len(), 1);
+}
+
+#[test]
+fn index_library_cancelled() {
+    let root = test_library("indexer-cancelled");
+    write_wav(&root.join("drums/snare.wav"), 100);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    let job = IndexJob::new();
+    job.cancel();
+    let report = index_library(&database, &job, |_| {}).unwrap();
+
+    assert!(report.cancelled);
+    assert_eq!(report.indexed, 0);
+    assert!(database.lock().unwrap().get_all_audio_files().unwrap().is_empty());
+}
+
+#[test]
+fn index_library_parallel() {
+    let root = test_library("indexer-parallel");
+    write_synthetic_library(&root, 8, 16);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    let job = IndexJob::new();
+    let report = index_library_with_workers(&database, &job, 4, |_| {}).unwrap();
+
+    assert_eq!(report.scanned, 8 * 16);
+    assert_eq!(report.indexed, 8 * 16);
+    assert!(report.errors.is_empty());
+
+    // Every directory is indexed once, under its parent
+    let db = database.lock().unwrap();
+    let drums = db.get_collection_by_path(&root.join("drums")).unwrap();
+    for d in 0..8 {
+        let collection = db.get_collection_by_path(&root.join(format!("drums/{}", d))).unwrap();
+        assert_eq!(collection.parent_collection(), Some(drums.id()));
+        assert_eq!(db.get_child_audio_files(collection.id()).unwrap().len(), 16);
+    }
+    assert_eq!(db.get_all_collections().unwrap().len(), 8 + 2);
+}
+
+// Run with `cargo test --release bench_index_library -- --ignored --nocapture`. The size of the generated
+// library can be set with the `VSB_BENCH_DIRECTORIES` and `VSB_BENCH_FILES` environment variables.
+#[test]
+#[ignore]
+fn bench_index_library() {
+    let var = |name: &str, default: usize| {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    };
+    let (num_directories, num_files) =
+        (var("VSB_BENCH_DIRECTORIES", 100), var("VSB_BENCH_FILES", 200));
+
+    let root = test_library("indexer-bench");
+    write_synthetic_library(&root, num_directories, num_files);
+
+    let mut workers = vec![1];
+    if default_index_workers() > 1 {
+        workers.push(default_index_workers());
+    }
+
+    for workers in workers {
+        let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+        let job = IndexJob::new();
+
+        let start = Instant::now();
+        let report = index_library_with_workers(&database, &job, workers, |_| {}).unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(report.indexed, num_directories * num_files);
+        println!(
+            "indexed {} files with {} workers in {:.2?} ({:.0} files/s)",
+            report.indexed,
+            workers,
+            elapsed,
+            report.indexed as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
+
+// Fill the `drums` directory of a test library with `num_directories` directories of `num_files` files.
+fn write_synthetic_library(root: &Path, num_directories: usize, num_files: usize) {
+    for d in 0..num_directories {
+        let directory = root.join(format!("drums/{}", d));
+        std::fs::create_dir(&directory).unwrap();
+        for f in 0..num_files {
+            write_wav(&directory.join(format!("{}.wav", f)), 64);
+        }
+    }
+}
+
+#[test]
+fn index_library_records_metadata() {
+    let root = test_library("indexer-metadata");
+
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 48000,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(root.join("drums/kick.wav"), spec).unwrap();
+    for i in 0..4800 {
+        writer.write_sample(i).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let spec =
+        hound::WavSpec { bits_per_sample: 32, sample_format: hound::SampleFormat::Float, ..spec };
+    let mut writer = hound::WavWriter::create(root.join("drums/pad.wav"), spec).unwrap();
+    for _ in 0..4800 {
+        writer.write_sample(0.5f32).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let db = database.lock().unwrap();
+    let drums = db.get_collection_by_path(&root.join("drums")).unwrap();
+
+    let kick = db.get_audio_file_by_name(drums.id(), "kick.wav").unwrap();
+    assert_eq!(kick.sample_rate, 48000);
+    assert_eq!(kick.num_channels, 2);
+    assert_eq!(kick.bit_depth, Some(24));
+    assert_eq!(kick.sample_format, Some(SampleFormat::Int));
+    assert_eq!(kick.codec, "PCM");
+    assert_eq!(kick.size, std::fs::metadata(root.join("drums/kick.wav")).unwrap().len());
+    assert_eq!(kick.duration, 0.05);
+
+    let pad = db.get_audio_file_by_name(drums.id(), "pad.wav").unwrap();
+    assert_eq!(pad.bit_depth, Some(32));
+    assert_eq!(pad.sample_format, Some(SampleFormat::Float));
+}
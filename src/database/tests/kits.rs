@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+
+use crate::database::{
+    prelude::*,
+    tests::{init_test_database, test_library, write_wav},
+};
+
+fn slots(kit: &Kit) -> Vec<(&str, Option<&str>)> {
+    kit.slots
+        .iter()
+        .map(|slot| (slot.name.as_str(), slot.sample.as_ref().map(|sample| sample.name.as_str())))
+        .collect()
+}
+
+#[test]
+fn kits_keep_their_slots_in_order() {
+    let mut handle: Database = init_test_database();
+
+    let drums = handle.insert_kit("Drums", &["Kick", "Snare", "Hat"]).unwrap();
+    let empty = handle.insert_kit("Empty", &[]).unwrap();
+    handle.set_kit_slot(drums, 0, Some(0)).unwrap();
+    handle.set_kit_slot(drums, 2, Some(1)).unwrap();
+
+    let kits = handle.get_kits().unwrap();
+    assert_eq!(
+        kits.iter().map(|kit| (kit.id, kit.name.as_str())).collect::<Vec<_>>(),
+        [(drums, "Drums"), (empty, "Empty")]
+    );
+    assert_eq!(
+        slots(&kits[0]),
+        [("Kick", Some("Audio File 0")), ("Snare", None), ("Hat", Some("Audio File 1"))]
+    );
+    assert_eq!(
+        kits[0].slots[2].sample,
+        Some(KitSample { id: 1, collection: 1, name: String::from("Audio File 1") })
+    );
+    assert!(kits[1].slots.is_empty());
+
+    // Slots after a removed slot move up, and new slots go after the last
+    handle.remove_kit_slot(drums, 1).unwrap();
+    handle.add_kit_slot(drums, "Clap").unwrap();
+    handle.rename_kit_slot(drums, 0, "Sub Kick").unwrap();
+    handle.set_kit_slot(drums, 2, Some(0)).unwrap();
+    handle.rename_kit(drums, "Break").unwrap();
+
+    let kits = handle.get_kits().unwrap();
+    assert_eq!(kits[0].name, "Break");
+    assert_eq!(
+        slots(&kits[0]),
+        [
+            ("Sub Kick", Some("Audio File 0")),
+            ("Hat", Some("Audio File 1")),
+            ("Clap", Some("Audio File 0"))
+        ]
+    );
+
+    handle.remove_kit(empty).unwrap();
+    assert_eq!(handle.get_kits().unwrap().len(), 1);
+}
+
+#[test]
+fn removed_files_leave_their_slots_empty() {
+    let mut handle: Database = init_test_database();
+
+    let kit = handle.insert_kit("Drums", &DEFAULT_KIT_SLOTS).unwrap();
+    handle.set_kit_slot(kit, 0, Some(0)).unwrap();
+    handle.set_kit_slot(kit, 1, Some(1)).unwrap();
+
+    handle.remove_audio_file(0).unwrap();
+    handle.remove_collection(1).unwrap();
+
+    let kits = handle.get_kits().unwrap();
+    assert_eq!(kits[0].slots.len(), DEFAULT_KIT_SLOTS.len());
+    assert!(kits[0].slots.iter().all(|slot| slot.sample.is_none()));
+}
+
+#[test]
+fn libraries_enforce_foreign_keys() {
+    let root = test_library("kits-foreign-keys");
+    write_wav(&root.join("drums/kick.wav"), 100);
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let mut db = database.into_inner().unwrap();
+    let kick = db.get_all_audio_files().unwrap()[0].id;
+    let kit = db.insert_kit("Drums", &["Kick"]).unwrap();
+    db.set_kit_slot(kit, 0, Some(kick)).unwrap();
+
+    // Removing the row of the file alone empties the slot filled with it
+    let connection = db.get_connection().unwrap();
+    connection.execute("DELETE FROM audio_files WHERE id = (?1)", [kick]).unwrap();
+    let slot: Option<AudioFileID> = connection
+        .query_row("SELECT audio_file FROM kits_slots WHERE kit = (?1)", [kit], |row| row.get(0))
+        .unwrap();
+    assert_eq!(slot, None);
+}
+
+#[test]
+fn kits_are_kept_when_the_library_is_closed() {
+    let root = test_library("kits-reopen");
+    let mut db = Database::from_directory(root.clone()).unwrap();
+    db.insert_kit("Drums", &DEFAULT_KIT_SLOTS).unwrap();
+    drop(db);
+
+    let db = Database::from_directory(root).unwrap();
+    assert_eq!(db.get_kits().unwrap()[0].name, "Drums");
+}
@@ -0,0 +1,194 @@
+use std::{path::Path, sync::Mutex};
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+fn entry(source: MetadataSource, key: &str, value: &str) -> MetadataEntry {
+    MetadataEntry::new(source, key.to_string(), value.to_string())
+}
+
+fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend((data.len() as u32).to_le_bytes());
+    chunk.extend(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn padded(text: &str, len: usize) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(len, 0);
+    bytes
+}
+
+// Append metadata chunks to a wave file written by `write_wav`.
+fn write_tagged_wav(path: &Path) {
+    write_wav(path, 100);
+
+    let mut bext = padded("Door slam, heavy", 256);
+    bext.extend(padded("Field Recorder", 32));
+    bext.extend(padded("REF-0001", 32));
+    bext.extend(padded("2024-05-01", 10));
+    bext.extend(padded("12:30:00", 8));
+    bext.resize(602, 0);
+    bext.extend(b"A=PCM,F=44100,W=16,M=mono\r\n");
+
+    let mut info = b"INFO".to_vec();
+    info.extend(chunk(b"IART", b"Sound Designer\0"));
+    info.extend(chunk(b"IKEY", b"door; wood\0"));
+    info.extend(chunk(b"IXYZ", b"unknown\0"));
+
+    let ixml = "<?xml version=\"1.0\"?><BWFXML><PROJECT>Foley &amp; FX</PROJECT>\
+        <TRACK_LIST><TRACK><NAME>Boom</NAME></TRACK></TRACK_LIST>\
+        <USER><CATEGORY>DOORS</CATEGORY></USER></BWFXML>";
+
+    let mut wav = std::fs::read(path).unwrap();
+    wav.extend(chunk(b"bext", &bext));
+    wav.extend(chunk(b"LIST", &info));
+    wav.extend(chunk(b"iXML", ixml.as_bytes()));
+    let riff_size = (wav.len() - 8) as u32;
+    wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    std::fs::write(path, wav).unwrap();
+}
+
+fn id3_frame(id: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut frame = id.to_vec();
+    frame.extend((data.len() as u32).to_be_bytes());
+    frame.extend([0, 0]);
+    frame.extend(data);
+    frame
+}
+
+#[test]
+fn read_wav_metadata() {
+    let root = test_library("metadata-wav");
+    let path = root.join("drums/door.wav");
+    write_tagged_wav(&path);
+
+    assert_eq!(
+        read_embedded_metadata(&path, AudioFormat::Wav),
+        vec![
+            entry(MetadataSource::Bext, "description", "Door slam, heavy"),
+            entry(MetadataSource::Bext, "originator", "Field Recorder"),
+            entry(MetadataSource::Bext, "originator_reference", "REF-0001"),
+            entry(MetadataSource::Bext, "origination_date", "2024-05-01"),
+            entry(MetadataSource::Bext, "origination_time", "12:30:00"),
+            entry(MetadataSource::Bext, "coding_history", "A=PCM,F=44100,W=16,M=mono"),
+            entry(MetadataSource::Info, "artist", "Sound Designer"),
+            entry(MetadataSource::Info, "keywords", "door; wood"),
+            entry(MetadataSource::Ixml, "project", "Foley & FX"),
+            entry(MetadataSource::Ixml, "category", "DOORS"),
+        ]
+    );
+}
+
+#[test]
+fn read_id3_metadata() {
+    let root = test_library("metadata-id3");
+    let path = root.join("drums/riser.mp3");
+
+    // UTF-16 with a byte order mark
+    let mut title = vec![1, 0xFF, 0xFE];
+    title.extend("Risér".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+
+    let mut frames = id3_frame(b"TIT2", &title);
+    frames.extend(id3_frame(b"TBPM", b"\x00128"));
+    frames.extend(id3_frame(b"TXXX", b"\x03Library\0Cinematic"));
+    frames.extend(id3_frame(b"COMM", b"\x00engDesc\0Long build"));
+    frames.extend(id3_frame(b"APIC", b"\x00image/png\0\x03\0\x89PNG"));
+
+    let mut tag = b"ID3\x03\0\0".to_vec();
+    let size = frames.len() as u32;
+    tag.extend([
+        (size >> 21) as u8 & 0x7F,
+        (size >> 14) as u8 & 0x7F,
+        (size >> 7) as u8 & 0x7F,
+        size as u8 & 0x7F,
+    ]);
+    tag.extend(frames);
+    tag.extend([0xFF, 0xFB, 0x90, 0x64]);
+    std::fs::write(&path, tag).unwrap();
+
+    assert_eq!(
+        read_embedded_metadata(&path, AudioFormat::Mp3),
+        vec![
+            entry(MetadataSource::Id3, "title", "Risér"),
+            entry(MetadataSource::Id3, "bpm", "128"),
+            entry(MetadataSource::Id3, "library", "Cinematic"),
+            entry(MetadataSource::Id3, "comment", "Long build"),
+        ]
+    );
+}
+
+#[test]
+fn read_flac_metadata() {
+    let root = test_library("metadata-flac");
+    let path = root.join("drums/hat.flac");
+
+    let mut comments = Vec::new();
+    comments.extend(4u32.to_le_bytes());
+    comments.extend(b"test");
+    comments.extend(2u32.to_le_bytes());
+    for comment in ["ARTIST=Drummer", "GENRE=Funk"] {
+        comments.extend((comment.len() as u32).to_le_bytes());
+        comments.extend(comment.as_bytes());
+    }
+
+    let mut flac = b"fLaC".to_vec();
+    // An empty STREAMINFO block, then the last block
+    flac.extend([0, 0, 0, 0]);
+    flac.extend([0x84, 0, 0, comments.len() as u8]);
+    flac.extend(comments);
+    std::fs::write(&path, flac).unwrap();
+
+    assert_eq!(
+        read_embedded_metadata(&path, AudioFormat::Flac),
+        vec![
+            entry(MetadataSource::Vorbis, "artist", "Drummer"),
+            entry(MetadataSource::Vorbis, "genre", "Funk"),
+        ]
+    );
+}
+
+#[test]
+fn read_malformed_metadata() {
+    let root = test_library("metadata-malformed");
+    let path = root.join("drums/broken.wav");
+    std::fs::write(&path, b"RIFF\xff\xff\xff\xffWAVEbext\x10\0\0\0Trunc").unwrap();
+
+    assert!(read_embedded_metadata(&path, AudioFormat::Wav).is_empty());
+    assert!(read_embedded_metadata(&root.join("drums/missing.wav"), AudioFormat::Wav).is_empty());
+}
+
+#[test]
+fn index_library_searches_metadata() {
+    let root = test_library("metadata-search");
+    write_tagged_wav(&root.join("drums/door.wav"));
+    write_wav(&root.join("drums/kick_100%.wav"), 100);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let mut db = database.lock().unwrap();
+    let door = db
+        .get_audio_file_by_name(
+            db.get_collection_by_path(&root.join("drums")).unwrap().id(),
+            "door.wav",
+        )
+        .unwrap();
+    assert_eq!(db.get_metadata_for_audio_file(door.id).unwrap().len(), 10);
+
+    assert_eq!(db.search_audio_files("slam").unwrap(), vec![door.id]);
+    assert_eq!(db.search_audio_files("DOORS").unwrap(), vec![door.id]);
+    assert_eq!(db.search_audio_files("door").unwrap(), vec![door.id]);
+    assert_eq!(db.search_audio_files("100%").unwrap().len(), 1);
+    assert!(db.search_audio_files("k%1").unwrap().is_empty());
+
+    db.remove_audio_file(door.id).unwrap();
+    assert!(db.get_metadata_for_audio_file(door.id).unwrap().is_empty());
+    assert!(db.search_audio_files("slam").unwrap().is_empty());
+}
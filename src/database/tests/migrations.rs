@@ -0,0 +1,96 @@
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+#[test]
+fn open_library_with_first_schema() {
+    let root = test_library("migrations-first");
+    write_wav(&root.join("drums/kick.wav"), 100);
+
+    // A library indexed before the schema had a version
+    let meta_directory = root.join(DATABASE_META_DIRECTORY_NAME);
+    std::fs::create_dir(&meta_directory).unwrap();
+    let connection = Connection::open(meta_directory.join(DATABASE_DATABASE_NAME)).unwrap();
+    connection.execute_batch(include_str!("../sqls/test_baseline.sql")).unwrap();
+    connection
+        .execute(
+            "INSERT INTO collections (id, parent_collection, name, path) VALUES (0, NULL, 'root', ?1)",
+            [root.to_str().unwrap()],
+        )
+        .unwrap();
+    connection
+        .execute(
+            "INSERT INTO collections (id, parent_collection, name, path) VALUES (1, 0, 'drums', ?1)",
+            [root.join("drums").to_str().unwrap()],
+        )
+        .unwrap();
+    connection
+        .execute_batch(
+            "
+        INSERT INTO audio_files (id, name, collection, duration, sample_rate, bit_depth, num_channels, size)
+            VALUES (0, 'kick.wav', 1, 0, 44100, 16, 1, 244);
+        INSERT INTO tags (id, name, color, number) VALUES (31, 'Kick', 'yellow', 0);
+        INSERT INTO audio_files_tags (audio_file, tag) VALUES (0, 31);
+        ",
+        )
+        .unwrap();
+    drop(connection);
+
+    let mut db = Database::from_directory(root.clone()).unwrap();
+    let version: u32 = db
+        .get_connection()
+        .unwrap()
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+
+    // Existing rows read with the defaults of the added columns
+    let kick = db.get_audio_file(0).unwrap();
+    assert_eq!(kick.format, AudioFormat::Wav);
+    assert_eq!(kick.codec, "");
+    assert_eq!(kick.pitch, None);
+    assert_eq!(db.get_tags_for_audio_file(0).unwrap().len(), 1);
+
+    // The added tables can be used
+    let kit = db.insert_kit("Drums", &DEFAULT_KIT_SLOTS).unwrap();
+    db.set_kit_slot(kit, 0, Some(0)).unwrap();
+    assert!(db.get_metadata_for_audio_file(0).unwrap().is_empty());
+
+    // Indexing fills in the added columns of the existing rows
+    let database = Mutex::new(db);
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let db = database.lock().unwrap();
+    let kick = db.get_audio_file(0).unwrap();
+    assert_eq!(kick.codec, "PCM");
+    assert_eq!(kick.sample_format, Some(SampleFormat::Int));
+    assert!(db.get_features(0).unwrap().is_some());
+    assert_eq!(db.get_kits().unwrap()[0].slots[0].sample.as_ref().map(|sample| sample.id), Some(0));
+}
+
+#[test]
+fn migrate_is_idempotent() {
+    let connection = Connection::open_in_memory().unwrap();
+    create_schema(&connection).unwrap();
+    migrate(&connection).unwrap();
+
+    let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+}
+
+#[test]
+fn migrations_skip_changes_already_made() {
+    // Libraries migrated before the changes were split into versions have the latest schema at version 1
+    let connection = Connection::open_in_memory().unwrap();
+    create_schema(&connection).unwrap();
+    connection.pragma_update(None, "user_version", 1).unwrap();
+    migrate(&connection).unwrap();
+
+    let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+}
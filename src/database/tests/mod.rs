@@ -1,9 +1,26 @@
+use std::{
+    f32::consts::PI,
+    path::{Path, PathBuf},
+};
+
 use super::prelude::Database;
 
+pub mod analysis;
+pub mod formats;
 pub mod get_audio_files;
 pub mod get_collections;
 pub mod get_tags;
+pub mod indexer;
 pub mod insert;
+pub mod kits;
+pub mod metadata;
+pub mod migrations;
+pub mod pitch;
+pub mod sampler;
+pub mod similarity;
+pub mod slicing;
+pub mod trim;
+pub mod watcher;
 
 const TEST_DIRECTORY: &str = "test_files/";
 const TEST_META_DIRECTORY: &str = "test_files/.vsb-meta/";
@@ -32,3 +49,88 @@ pub fn check_meta_exists() -> bool {
 pub fn check_database_exists() -> bool {
     std::fs::read(TEST_DATABASE).is_ok()
 }
+
+// Create an empty library directory with a `drums` subdirectory in the system temp directory.
+fn test_library(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vsb-{}", name));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("drums")).unwrap();
+    path
+}
+
+// Write a mono 16-bit test file.
+fn write_wav(path: &Path, num_samples: usize) {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 44100,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for i in 0..num_samples {
+        writer.write_sample((i % 100) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+const SAMPLE_RATE: u32 = 44100;
+
+// Deterministic white noise between -1 and 1.
+fn noise(len: usize) -> Vec<f32> {
+    let mut state = 0x12345678u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        })
+        .collect()
+}
+
+// A hit decaying exponentially with the given time constant in seconds.
+fn hit(seconds: f32, decay: f32, signal: impl Fn(usize, f32) -> f32) -> Vec<f32> {
+    let len = (seconds * SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            signal(i, t) * (-t / decay).exp() * 0.8
+        })
+        .collect()
+}
+
+fn sine(frequency: f32) -> impl Fn(usize, f32) -> f32 {
+    move |_, t| (2.0 * PI * frequency * t).sin()
+}
+
+fn kick() -> Vec<f32> {
+    hit(0.5, 0.08, sine(55.0))
+}
+
+fn hat() -> Vec<f32> {
+    // Differentiated noise has most of its energy at high frequencies
+    let noise = noise(SAMPLE_RATE as usize);
+    hit(0.3, 0.03, move |i, _| noise[i + 1] - noise[i])
+}
+
+fn snare() -> Vec<f32> {
+    let noise = noise(SAMPLE_RATE as usize);
+    let body = sine(180.0);
+    hit(0.4, 0.06, move |i, t| noise[i] + 0.5 * body(i, t))
+}
+
+fn write_float_wav(path: &Path, samples: &[f32]) {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for sample in samples {
+        writer.write_sample(*sample).unwrap();
+    }
+    writer.finalize().unwrap();
+}
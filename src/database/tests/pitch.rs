@@ -0,0 +1,95 @@
+use std::{f32::consts::PI, sync::Mutex};
+
+use crate::database::{
+    prelude::*,
+    tests::{hat, hit, kick, sine, test_library, write_float_wav, SAMPLE_RATE},
+};
+
+fn detect(samples: &[f32]) -> Option<(u8, f32)> {
+    detect_pitch(samples, SAMPLE_RATE).map(|pitch| (pitch.note, pitch.cents))
+}
+
+#[test]
+fn detect_tonal_one_shots() {
+    // A sine and a plucked sawtooth
+    let (note, cents) = detect(&hit(0.5, 0.2, sine(220.0))).unwrap();
+    assert_eq!(note, 57);
+    assert!(cents.abs() < 3.0, "{cents}");
+
+    let d3 = 146.83;
+    let pluck = hit(0.5, 0.1, |_, t| {
+        (1..12).map(|k| (2.0 * PI * d3 * k as f32 * t).sin() / k as f32).sum()
+    });
+    assert_eq!(detect(&pluck).map(|(note, _)| note), Some(50));
+
+    // Detuned by 30 cents
+    let (note, cents) = detect(&hit(0.5, 0.2, sine(110.0 * (0.3f32 / 12.0).exp2()))).unwrap();
+    assert_eq!(note, 45);
+    assert!((cents - 30.0).abs() < 3.0, "{cents}");
+
+    // An 808 gliding down to A1
+    let glide = hit(0.8, 0.3, |_, t| {
+        let phase = 55.0 * t + 25.0 * 0.03 * (1.0 - (-t / 0.03).exp());
+        (2.0 * PI * phase).sin()
+    });
+    assert_eq!(detect(&glide).map(|(note, _)| note), Some(33));
+    assert_eq!(detect(&kick()).map(|(note, _)| note), Some(33));
+
+    // Noise and silence have no pitch
+    assert_eq!(detect(&hat()), None);
+    assert_eq!(detect(&[0.0; 44100]), None);
+    assert_eq!(detect(&[]), None);
+}
+
+#[test]
+fn note_names_and_tuning() {
+    assert_eq!(parse_note_name("A1"), Some((9, Some(33))));
+    assert_eq!(parse_note_name("f#"), Some((6, None)));
+    assert_eq!(parse_note_name("Bb3"), Some((10, Some(58))));
+    assert_eq!(parse_note_name("C-1"), Some((0, Some(0))));
+    assert_eq!(parse_note_name("Cb4"), Some((11, Some(59))));
+    assert_eq!(parse_note_name("G9"), Some((7, Some(127))));
+    assert_eq!(parse_note_name("A9"), None);
+    assert_eq!(parse_note_name("kick"), None);
+    assert_eq!(parse_note_name(""), None);
+
+    let pitch = Pitch::from_frequency(440.0).unwrap();
+    assert_eq!((pitch.note, pitch.cents), (69, 0.0));
+    assert!((Pitch { note: 57, cents: 50.0 }.frequency() - 226.45).abs() < 0.01);
+
+    // To the nearest root, up to a tritone up or down, after the correction
+    assert_eq!(retune_cents(69, 0.0, 9), 0.0);
+    assert_eq!(retune_cents(69, -20.0, 0), 300.0 - 20.0);
+    assert_eq!(retune_cents(69, 0.0, 5), -400.0);
+    assert_eq!(retune_cents(60, 0.0, 6), 600.0);
+}
+
+#[test]
+fn index_library_detects_pitch() {
+    let root = test_library("pitch-index");
+    write_float_wav(
+        &root.join("drums/sub.wav"),
+        &hit(0.8, 0.3, sine(36.71 * (0.2f32 / 12.0).exp2())),
+    );
+    write_float_wav(&root.join("drums/hat.wav"), &hat());
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let db = database.lock().unwrap();
+    let collection = db.get_collection_by_path(&root.join("drums")).unwrap().id();
+    let sub = db.get_audio_file_by_name(collection, "sub.wav").unwrap();
+    let pitch = sub.pitch.unwrap();
+    assert_eq!(pitch.note, 26);
+    assert!((pitch.cents - 20.0).abs() < 3.0, "{}", pitch.cents);
+    assert_eq!(sub.tuning_offset, Some(-pitch.cents));
+
+    let hat = db.get_audio_file_by_name(collection, "hat.wav").unwrap();
+    assert_eq!((hat.pitch, hat.tuning_offset), (None, None));
+
+    // Notes are searched with or without their octave
+    assert_eq!(db.search_audio_files("D1").unwrap(), vec![sub.id]);
+    assert_eq!(db.search_audio_files("d").unwrap(), vec![sub.id]);
+    assert!(db.search_audio_files("D2").unwrap().is_empty());
+    assert!(db.search_audio_files("G#").unwrap().is_empty());
+}
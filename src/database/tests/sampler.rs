@@ -0,0 +1,100 @@
+use std::{path::Path, sync::Mutex};
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+// Append `smpl` and `acid` chunks to a wave file written by `write_wav`.
+fn write_loop_wav(path: &Path, root_note: u32, acid_flags: u32) {
+    write_wav(path, 1000);
+
+    let mut smpl = Vec::new();
+    for value in [0, 0, 22675, root_note, 0, 0, 0, 1, 0] {
+        smpl.extend(u32::to_le_bytes(value));
+    }
+    // Cue point id, type, start, end, fraction and play count
+    for value in [0, 0, 100, 899, 0, 0] {
+        smpl.extend(u32::to_le_bytes(value));
+    }
+
+    let mut acid = Vec::new();
+    acid.extend(acid_flags.to_le_bytes());
+    acid.extend(62u16.to_le_bytes());
+    acid.extend([0; 6]);
+    acid.extend(4u32.to_le_bytes());
+    acid.extend(4u16.to_le_bytes());
+    acid.extend(4u16.to_le_bytes());
+    acid.extend(128.0f32.to_le_bytes());
+
+    let mut wav = std::fs::read(path).unwrap();
+    for (id, data) in [(b"smpl", smpl), (b"acid", acid)] {
+        wav.extend(id);
+        wav.extend((data.len() as u32).to_le_bytes());
+        wav.extend(data);
+    }
+    let riff_size = (wav.len() - 8) as u32;
+    wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    std::fs::write(path, wav).unwrap();
+}
+
+#[test]
+fn read_sampler_chunks() {
+    let root = test_library("sampler-chunks");
+    let path = root.join("drums/loop.wav");
+
+    // The ACID root note is only used when its flag is set
+    write_loop_wav(&path, 60, 0);
+    assert_eq!(
+        read_sampler_info(&path),
+        SamplerInfo {
+            root_note: Some(60),
+            loop_start: Some(100),
+            loop_end: Some(900),
+            tempo: Some(128.0),
+            beats: Some(4),
+            one_shot: false,
+        }
+    );
+
+    write_loop_wav(&path, 60, 0x03);
+    let info = read_sampler_info(&path);
+    assert_eq!(info.root_note, Some(62));
+    assert!(info.one_shot);
+    assert_eq!(info.loop_points(), Some((100, 900)));
+
+    write_wav(&path, 1000);
+    assert_eq!(read_sampler_info(&path), SamplerInfo::default());
+}
+
+#[test]
+fn note_names() {
+    assert_eq!(note_name(60), "C4");
+    assert_eq!(note_name(69), "A4");
+    assert_eq!(note_name(13), "C#0");
+    assert_eq!(key_name(62 % 12), "D");
+}
+
+#[test]
+fn index_library_records_sampler_info() {
+    let root = test_library("sampler-index");
+    write_loop_wav(&root.join("drums/acid.wav"), 69, 0x02);
+    write_loop_wav(&root.join("drums/loop.wav"), 69, 0);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let db = database.lock().unwrap();
+    let mut audio_files = db.get_all_audio_files().unwrap();
+    audio_files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // The key is only taken from an ACID root note
+    assert_eq!(audio_files[0].key, Some(2));
+    assert_eq!(audio_files[0].sampler.root_note, Some(62));
+
+    let audio_file = &audio_files[1];
+    assert_eq!(audio_file.bpm, Some(128.0));
+    assert_eq!(audio_file.key, None);
+    assert_eq!(audio_file.sampler.root_note, Some(69));
+    assert_eq!(audio_file.sampler.loop_points(), Some((100, 900)));
+}
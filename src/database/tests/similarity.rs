@@ -0,0 +1,120 @@
+use std::{sync::Mutex, time::Instant};
+
+use crate::database::{
+    prelude::*,
+    tests::{
+        hat, hit, init_test_database, kick, noise, sine, snare, test_library, write_float_wav,
+        SAMPLE_RATE,
+    },
+};
+
+#[test]
+fn features_of_signals() {
+    let kick = FeatureVector::new(&kick(), SAMPLE_RATE);
+    let hat = FeatureVector::new(&hat(), SAMPLE_RATE);
+
+    // Centroid and flatness follow the MFCC means and deviations
+    let (kick_centroid, hat_centroid) = (kick.0[24], hat.0[24]);
+    assert!(kick_centroid < 7.0, "{kick_centroid}");
+    assert!(hat_centroid > 12.0, "{hat_centroid}");
+    assert!(hat.0[25] > kick.0[25]);
+
+    // Duration is stored in log10 seconds
+    assert!((kick.0[27] - 0.5f32.log10()).abs() < 0.01);
+
+    // Silence gives finite values
+    assert!(FeatureVector::new(&[0.0; 4410], SAMPLE_RATE).0.iter().all(|v| v.is_finite()));
+    assert!(FeatureVector::new(&[], SAMPLE_RATE).0.iter().all(|v| v.is_finite()));
+}
+
+#[test]
+fn find_similar_sounds() {
+    let root = test_library("similarity");
+    let noise = noise(SAMPLE_RATE as usize);
+    write_float_wav(&root.join("drums/kick_a.wav"), &kick());
+    write_float_wav(&root.join("drums/kick_b.wav"), &hit(0.6, 0.1, sine(50.0)));
+    write_float_wav(&root.join("drums/kick_c.wav"), &hit(0.4, 0.07, sine(62.0)));
+    write_float_wav(&root.join("drums/hat_a.wav"), &hat());
+    write_float_wav(
+        &root.join("drums/hat_b.wav"),
+        &hit(0.25, 0.02, move |i, _| noise[i + 2] - noise[i + 1]),
+    );
+    write_float_wav(&root.join("drums/snare.wav"), &snare());
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let mut db = database.lock().unwrap();
+    let collection = db.get_collection_by_path(&root.join("drums")).unwrap().id();
+    let id = |db: &Database, name: &str| db.get_audio_file_by_name(collection, name).unwrap().id;
+    let names = |db: &Database, similar: Vec<SimilarAudioFile>| {
+        similar
+            .into_iter()
+            .map(|similar| db.get_audio_file(similar.id).unwrap().name)
+            .collect::<Vec<_>>()
+    };
+
+    let kick = id(&db, "kick_a.wav");
+    let mut similar = names(&db, db.find_similar_audio_files(kick, 2).unwrap());
+    similar.sort();
+    assert_eq!(similar, vec!["kick_b.wav", "kick_c.wav"]);
+
+    let similar = db.find_similar_audio_files(id(&db, "hat_a.wav"), 10).unwrap();
+    assert_eq!(similar.len(), 5);
+    assert!(similar.windows(2).all(|pair| pair[0].distance <= pair[1].distance));
+    assert_eq!(names(&db, similar)[0], "hat_b.wav");
+
+    // Features are stored as they were computed and removed with their file
+    let features = db.get_features(kick).unwrap().unwrap();
+    assert_eq!(features, FeatureVector::new(&crate::database::tests::kick(), SAMPLE_RATE));
+
+    db.remove_audio_file(kick).unwrap();
+    assert_eq!(db.get_features(kick).unwrap(), None);
+    assert!(db.find_similar_audio_files(kick, 2).unwrap().is_empty());
+}
+
+// Run with `cargo test --release bench_find_similar_audio_files -- --ignored --nocapture`. The number of
+// feature vectors can be set with the `VSB_BENCH_FILES` environment variable.
+#[test]
+#[ignore]
+fn bench_find_similar_audio_files() {
+    let num_files =
+        std::env::var("VSB_BENCH_FILES").ok().and_then(|v| v.parse().ok()).unwrap_or(100_000);
+
+    let mut db = init_test_database();
+    db.get_connection()
+        .unwrap()
+        .execute(
+            "
+        WITH RECURSIVE ids(id) AS (SELECT 2 UNION ALL SELECT id + 1 FROM ids WHERE id + 1 < (?1))
+        INSERT INTO audio_files (id, name, collection, format, codec)
+            SELECT id, 'Audio File ' || id, 0, 'wav', 'PCM' FROM ids
+        ",
+            [num_files],
+        )
+        .unwrap();
+
+    let values = noise(num_files * FEATURE_LEN);
+    db.get_connection().unwrap().execute_batch("BEGIN").unwrap();
+    for (id, values) in values.chunks_exact(FEATURE_LEN).enumerate() {
+        db.insert_features(id, &FeatureVector(values.try_into().unwrap())).unwrap();
+    }
+    db.get_connection().unwrap().execute_batch("COMMIT").unwrap();
+
+    // The first search reads and scales the features
+    let start = Instant::now();
+    assert_eq!(db.find_similar_audio_files(0, 100).unwrap().len(), 100);
+    let first = start.elapsed();
+
+    let num_searches = 20;
+    let start = Instant::now();
+    for id in 0..num_searches {
+        assert_eq!(db.find_similar_audio_files(id, 100).unwrap().len(), 100);
+    }
+    println!(
+        "searched {} files in {:.2?} the first time, then in {:.2?}",
+        num_files,
+        first,
+        start.elapsed() / num_searches as u32
+    );
+}
@@ -0,0 +1,54 @@
+use crate::database::{
+    prelude::*,
+    tests::{hat, kick, snare, test_library, write_float_wav, SAMPLE_RATE},
+};
+
+// A break with the hits at the given times in seconds, each ringing into the next.
+fn drum_break(hits: &[(f32, Vec<f32>)]) -> Vec<f32> {
+    let mut samples = vec![0.0; 2 * SAMPLE_RATE as usize];
+    for (time, hit) in hits {
+        let start = (time * SAMPLE_RATE as f32) as usize;
+        for (sample, hit) in samples[start..].iter_mut().zip(hit) {
+            *sample += hit;
+        }
+    }
+    samples
+}
+
+fn frame(seconds: f32) -> usize {
+    (seconds * SAMPLE_RATE as f32) as usize
+}
+
+#[test]
+fn detect_break_transients() {
+    let hits = [(0.1, kick()), (0.35, hat()), (0.5, snare()), (0.75, hat()), (1.0, kick())];
+    let transients = detect_transients(&drum_break(&hits), SAMPLE_RATE);
+    assert_eq!(transients.len(), hits.len(), "{transients:?}");
+
+    // Each slice starts up to a millisecond before its hit
+    for (transient, (time, _)) in transients.iter().zip(&hits) {
+        let onset = frame(*time);
+        assert!(*transient <= onset && onset - transient <= 44, "{transient} {onset}");
+    }
+}
+
+#[test]
+fn detect_no_transients_in_silence_or_a_tail() {
+    assert!(detect_transients(&vec![0.0; 44100], SAMPLE_RATE).is_empty());
+
+    // The decay of a hit is not sliced
+    assert_eq!(detect_transients(&kick(), SAMPLE_RATE), vec![0]);
+}
+
+#[test]
+fn detect_file_transients_with_length() {
+    let path = test_library("slicing").join("drums").join("break.wav");
+    let samples = drum_break(&[(0.0, kick()), (0.5, snare())]);
+    write_float_wav(&path, &samples);
+
+    let (transients, num_frames) = detect_file_transients(&path).unwrap();
+    assert_eq!(num_frames, samples.len());
+    assert_eq!(transients.len(), 2);
+    assert!(frame(0.5) - transients[1] <= 44);
+    assert!(detect_file_transients(&path.with_file_name("missing.wav")).is_none());
+}
@@ -0,0 +1,61 @@
+use std::sync::Mutex;
+
+use crate::database::{
+    prelude::*,
+    tests::{kick, noise, test_library, write_float_wav, SAMPLE_RATE},
+};
+
+// A kick after `silence` seconds of silence, followed by `tail` seconds of quiet noise.
+fn padded_kick(silence: f32, tail: f32, tail_level: f32) -> Vec<f32> {
+    let silence = vec![0.0; (silence * SAMPLE_RATE as f32) as usize];
+    let tail = noise((tail * SAMPLE_RATE as f32) as usize);
+    silence
+        .into_iter()
+        .chain(kick())
+        .chain(tail.into_iter().map(|sample| sample * tail_level))
+        .collect()
+}
+
+#[test]
+fn find_audible_range() {
+    let kick_len = kick().len();
+
+    // Leading silence is skipped up to a millisecond before the attack
+    let (start, end) = audible_range(&padded_kick(0.3, 0.0, 0.0), SAMPLE_RATE).unwrap();
+    let onset = (0.3 * SAMPLE_RATE as f32) as usize;
+    assert!(start <= onset && onset - start <= 44, "{start}");
+    assert!(end <= onset + kick_len && end > onset + kick_len / 2, "{end}");
+
+    // A noise tail 50 dB down is cut where the kick decays into it
+    let (_, end) = audible_range(&padded_kick(0.0, 2.0, 0.003), SAMPLE_RATE).unwrap();
+    assert!(end <= kick_len, "{end}");
+
+    // A file which is loud throughout is not trimmed
+    let loud = noise(SAMPLE_RATE as usize);
+    assert_eq!(audible_range(&loud, SAMPLE_RATE), Some((0, loud.len())));
+
+    assert_eq!(audible_range(&[0.0; 4410], SAMPLE_RATE), None);
+    assert_eq!(audible_range(&[], SAMPLE_RATE), None);
+}
+
+#[test]
+fn index_library_stores_audible_range() {
+    let root = test_library("trim-index");
+    write_float_wav(&root.join("drums/kick.wav"), &padded_kick(0.5, 1.0, 0.003));
+    write_float_wav(&root.join("drums/silence.wav"), &[0.0; 4410]);
+
+    let database = Mutex::new(Database::from_directory(root.clone()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+
+    let db = database.lock().unwrap();
+    let collection = db.get_collection_by_path(&root.join("drums")).unwrap().id();
+
+    let kick = db.get_audio_file_by_name(collection, "kick.wav").unwrap();
+    let onset = SAMPLE_RATE as u64 / 2;
+    let start = kick.audible_start.unwrap();
+    assert!(start <= onset && onset - start <= 44, "{start}");
+    assert!(kick.audible_end.unwrap() <= onset + crate::database::tests::kick().len() as u64);
+
+    let silence = db.get_audio_file_by_name(collection, "silence.wav").unwrap();
+    assert_eq!((silence.audible_start, silence.audible_end), (None, None));
+}
@@ -0,0 +1,183 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use notify::{
+    event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
+    Event, EventKind,
+};
+
+use crate::database::{
+    prelude::*,
+    tests::{test_library, write_wav},
+};
+
+fn open_library(root: &Path) -> Mutex<Database> {
+    let database = Mutex::new(Database::from_directory(root.to_path_buf()).unwrap());
+    index_library(&database, &IndexJob::new(), |_| {}).unwrap();
+    database
+}
+
+fn event(kind: EventKind, paths: &[&str]) -> Event {
+    paths.iter().fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)))
+}
+
+#[test]
+fn coalesce_transient_files() {
+    let events = vec![
+        event(EventKind::Create(CreateKind::File), &["/lib/tmp.wav"]),
+        event(EventKind::Modify(ModifyKind::Any), &["/lib/tmp.wav"]),
+        event(EventKind::Remove(RemoveKind::File), &["/lib/tmp.wav"]),
+        event(EventKind::Create(CreateKind::File), &["/lib/.vsb-meta/.vsb-database"]),
+    ];
+
+    assert_eq!(coalesce_events(Path::new("/lib"), &events), vec![]);
+}
+
+#[test]
+fn coalesce_renames() {
+    let events = vec![
+        event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &["/lib/a.wav"]),
+        event(EventKind::Modify(ModifyKind::Name(RenameMode::To)), &["/lib/b.wav"]),
+        event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/lib/a.wav", "/lib/b.wav"]),
+        event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/lib/b.wav", "/lib/c.wav"]),
+    ];
+
+    assert_eq!(
+        coalesce_events(Path::new("/lib"), &events),
+        vec![LibraryChange::Renamed(PathBuf::from("/lib/a.wav"), PathBuf::from("/lib/c.wav"))]
+    );
+}
+
+#[test]
+fn coalesce_replaced_file() {
+    let events = vec![
+        event(EventKind::Remove(RemoveKind::File), &["/lib/a.wav"]),
+        event(EventKind::Create(CreateKind::File), &["/lib/a.wav"]),
+    ];
+
+    assert_eq!(
+        coalesce_events(Path::new("/lib"), &events),
+        vec![LibraryChange::Modified(PathBuf::from("/lib/a.wav"))]
+    );
+}
+
+#[test]
+fn apply_created_and_removed() {
+    let root = test_library("watcher-created");
+    write_wav(&root.join("drums/kick.wav"), 100);
+
+    let database = open_library(&root);
+    assert_eq!(database.lock().unwrap().get_all_audio_files().unwrap().len(), 1);
+
+    std::fs::create_dir(root.join("loops")).unwrap();
+    write_wav(&root.join("loops/break.wav"), 200);
+    std::fs::remove_file(root.join("drums/kick.wav")).unwrap();
+
+    apply_library_changes(
+        &database,
+        &[
+            LibraryChange::Created(root.join("loops")),
+            LibraryChange::Created(root.join("loops/break.wav")),
+            LibraryChange::Removed(root.join("drums/kick.wav")),
+        ],
+    )
+    .unwrap();
+
+    let loops = database.lock().unwrap().get_collection_by_path(&root.join("loops")).unwrap();
+    let audio_files = database.lock().unwrap().get_all_audio_files().unwrap();
+    assert_eq!(audio_files.len(), 1);
+    assert_eq!(audio_files[0].name, "break.wav");
+    assert_eq!(audio_files[0].collection, loops.id());
+
+    apply_library_changes(&database, &[LibraryChange::Removed(root.join("loops"))]).unwrap();
+    assert!(database.lock().unwrap().get_collection_by_path(&root.join("loops")).is_err());
+    assert!(database.lock().unwrap().get_all_audio_files().unwrap().is_empty());
+}
+
+#[test]
+fn apply_rename_keeps_tags() {
+    let root = test_library("watcher-rename");
+    write_wav(&root.join("drums/kick.wav"), 100);
+
+    let database = open_library(&root);
+    let kick = database.lock().unwrap().get_all_audio_files().unwrap().remove(0);
+    database.lock().unwrap().assign_tag_to_audio_file(31, kick.id).unwrap();
+
+    std::fs::rename(root.join("drums"), root.join("percussion")).unwrap();
+    apply_library_changes(
+        &database,
+        &[LibraryChange::Renamed(root.join("drums"), root.join("percussion"))],
+    )
+    .unwrap();
+
+    // Moved by delete and create rather than a rename
+    std::fs::create_dir(root.join("kicks")).unwrap();
+    std::fs::copy(root.join("percussion/kick.wav"), root.join("kicks/kick 01.wav")).unwrap();
+    std::fs::remove_file(root.join("percussion/kick.wav")).unwrap();
+    apply_library_changes(
+        &database,
+        &[
+            LibraryChange::Removed(root.join("percussion/kick.wav")),
+            LibraryChange::Created(root.join("kicks")),
+        ],
+    )
+    .unwrap();
+
+    let kicks = database.lock().unwrap().get_collection_by_path(&root.join("kicks")).unwrap();
+    let audio_files = database.lock().unwrap().get_all_audio_files().unwrap();
+    assert_eq!(audio_files.len(), 1);
+    assert_eq!(audio_files[0].id, kick.id);
+    assert_eq!(audio_files[0].name, "kick 01.wav");
+    assert_eq!(audio_files[0].collection, kicks.id());
+    assert_eq!(database.lock().unwrap().get_tags_for_audio_file(kick.id).unwrap().len(), 1);
+}
+
+#[test]
+fn apply_ambiguous_move_drops_tags() {
+    let root = test_library("watcher-ambiguous");
+    write_wav(&root.join("drums/kick.wav"), 100);
+    write_wav(&root.join("drums/kick copy.wav"), 100);
+
+    let database = open_library(&root);
+    let kicks = database.lock().unwrap().get_all_audio_files().unwrap();
+    for kick in kicks.iter() {
+        database.lock().unwrap().assign_tag_to_audio_file(31, kick.id).unwrap();
+    }
+
+    // Either removed file may be the one which reappeared
+    std::fs::copy(root.join("drums/kick.wav"), root.join("kick.wav")).unwrap();
+    std::fs::remove_file(root.join("drums/kick.wav")).unwrap();
+    std::fs::remove_file(root.join("drums/kick copy.wav")).unwrap();
+    apply_library_changes(
+        &database,
+        &[
+            LibraryChange::Removed(root.join("drums/kick.wav")),
+            LibraryChange::Removed(root.join("drums/kick copy.wav")),
+            LibraryChange::Created(root.join("kick.wav")),
+        ],
+    )
+    .unwrap();
+
+    let audio_files = database.lock().unwrap().get_all_audio_files().unwrap();
+    assert_eq!(audio_files.len(), 1);
+    assert!(kicks.iter().all(|kick| kick.id != audio_files[0].id));
+    assert!(database
+        .lock()
+        .unwrap()
+        .get_tags_for_audio_file(audio_files[0].id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn fingerprint_covers_the_whole_file() {
+    let root = test_library("watcher-fingerprint");
+    let mut bytes = vec![0; 100_000];
+    std::fs::write(root.join("a.bin"), &bytes).unwrap();
+    bytes[99_999] = 1;
+    std::fs::write(root.join("b.bin"), &bytes).unwrap();
+
+    assert_ne!(fingerprint(&root.join("a.bin")), fingerprint(&root.join("b.bin")));
+}
@@ -0,0 +1,612 @@
+//! Filesystem watching used to keep the index of an open library in sync with the disk.
+
+use super::*;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event, EventKind, RecursiveMode, Watcher,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::read_dir,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+// How long the filesystem must be quiet before a batch of changes is applied
+pub const WATCHER_DEBOUNCE: Duration = Duration::from_millis(500);
+// The longest a change can wait while a burst of events is still arriving
+pub const WATCHER_MAX_LATENCY: Duration = Duration::from_secs(3);
+// How often the watch loop checks whether it should stop
+const WATCHER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A change to a file or directory inside a library.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryChange {
+    Created(PathBuf),
+    Removed(PathBuf),
+    Renamed(PathBuf, PathBuf),
+    Modified(PathBuf),
+}
+
+/// Handle to a running library watch loop. The loop stops when the handle is dropped.
+#[derive(Debug, Default)]
+pub struct LibraryWatcher {
+    running: Arc<AtomicBool>,
+}
+
+impl LibraryWatcher {
+    pub fn new() -> Self {
+        Self { running: Arc::new(AtomicBool::new(true)) }
+    }
+
+    /// The flag passed to [`watch_library`], cleared when the watcher is stopped.
+    pub fn running(&self) -> Arc<AtomicBool> {
+        self.running.clone()
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for LibraryWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Watch the library at `root` recursively, calling `on_changes` with each debounced batch of changes and
+/// `on_error` with the errors reported by the filesystem watcher.
+///
+/// Blocks until `running` is cleared.
+pub fn watch_library<F, E>(
+    root: &Path,
+    running: &AtomicBool,
+    mut on_changes: F,
+    mut on_error: E,
+) -> Result<(), DatabaseError>
+where
+    F: FnMut(Vec<LibraryChange>),
+    E: FnMut(IndexError),
+{
+    let (tx, rx) = channel();
+
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    let mut pending: Vec<Event> = Vec::new();
+    let mut first_event = Instant::now();
+    let mut last_event = Instant::now();
+
+    while running.load(Ordering::Relaxed) {
+        match rx.recv_timeout(WATCHER_POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                if pending.is_empty() {
+                    first_event = Instant::now();
+                }
+                last_event = Instant::now();
+                pending.push(event);
+            }
+            Ok(Err(err)) => {
+                let path = err.paths.first().map_or(root, |path| path.as_path());
+                (on_error)(IndexError::new(path, &err));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if !pending.is_empty()
+            && (last_event.elapsed() >= WATCHER_DEBOUNCE
+                || first_event.elapsed() >= WATCHER_MAX_LATENCY)
+        {
+            let changes = coalesce_events(root, &pending);
+            pending.clear();
+
+            if !changes.is_empty() {
+                (on_changes)(changes);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reduce a burst of raw filesystem events to the net change for each path.
+///
+/// Files created and removed within the same batch are dropped, and paired rename events are merged.
+pub fn coalesce_events(root: &Path, events: &[Event]) -> Vec<LibraryChange> {
+    let meta_directory = root.join(DATABASE_META_DIRECTORY_NAME);
+
+    let mut changes: Vec<Option<LibraryChange>> = Vec::new();
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+
+    let push = |changes: &mut Vec<Option<LibraryChange>>,
+                index: &mut HashMap<PathBuf, usize>,
+                change: LibraryChange| {
+        let path = match &change {
+            LibraryChange::Created(p)
+            | LibraryChange::Removed(p)
+            | LibraryChange::Modified(p)
+            | LibraryChange::Renamed(_, p) => p.clone(),
+        };
+        index.insert(path, changes.len());
+        changes.push(Some(change));
+    };
+
+    for event in events {
+        if event.paths.iter().any(|p| p.starts_with(&meta_directory)) {
+            continue;
+        }
+
+        let change = match (&event.kind, event.paths.as_slice()) {
+            (EventKind::Create(_), [path]) => LibraryChange::Created(path.clone()),
+            (EventKind::Remove(_), [path]) => LibraryChange::Removed(path.clone()),
+            (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) => {
+                LibraryChange::Renamed(from.clone(), to.clone())
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::From)), [path]) => {
+                LibraryChange::Removed(path.clone())
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::To)), [path]) => {
+                LibraryChange::Created(path.clone())
+            }
+            (EventKind::Modify(ModifyKind::Name(_)), [path]) => match path.exists() {
+                true => LibraryChange::Created(path.clone()),
+                false => LibraryChange::Removed(path.clone()),
+            },
+            (EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any), [path]) => {
+                LibraryChange::Modified(path.clone())
+            }
+            _ => continue,
+        };
+
+        match change {
+            LibraryChange::Created(path) => match index.get(&path).map(|i| changes[*i].take()) {
+                // Replaced within the batch
+                Some(Some(LibraryChange::Removed(_))) => {
+                    push(&mut changes, &mut index, LibraryChange::Modified(path))
+                }
+                _ => push(&mut changes, &mut index, LibraryChange::Created(path)),
+            },
+
+            LibraryChange::Removed(path) => match index.get(&path).map(|i| changes[*i].take()) {
+                // Created and removed within the batch
+                Some(Some(LibraryChange::Created(_))) => {
+                    index.remove(&path);
+                }
+                Some(Some(LibraryChange::Renamed(from, _))) => {
+                    push(&mut changes, &mut index, LibraryChange::Removed(from))
+                }
+                _ => push(&mut changes, &mut index, LibraryChange::Removed(path)),
+            },
+
+            LibraryChange::Modified(path) => {
+                // A created or renamed file is probed anyway, so only record untouched paths
+                if !matches!(index.get(&path).map(|i| changes[*i].is_some()), Some(true)) {
+                    push(&mut changes, &mut index, LibraryChange::Modified(path));
+                }
+            }
+
+            LibraryChange::Renamed(from, to) => {
+                // A rename may already have been reported as separate remove and create events
+                if let Some(i) = index.get(&to) {
+                    if let Some(LibraryChange::Created(_)) = changes[*i] {
+                        changes[*i] = None;
+                    }
+                }
+
+                match index.get(&from).map(|i| changes[*i].take()) {
+                    Some(Some(LibraryChange::Created(_))) => {
+                        index.remove(&from);
+                        push(&mut changes, &mut index, LibraryChange::Created(to))
+                    }
+                    Some(Some(LibraryChange::Renamed(origin, _))) => {
+                        push(&mut changes, &mut index, LibraryChange::Renamed(origin, to))
+                    }
+                    _ => push(&mut changes, &mut index, LibraryChange::Renamed(from, to)),
+                }
+            }
+        }
+    }
+
+    changes.into_iter().flatten().collect()
+}
+
+// Files removed in the current batch, by fingerprint, which may reappear elsewhere. `None` marks a fingerprint
+// shared by several removed or created files, which cannot tell which file was moved where.
+type MovedFiles = HashMap<Hash, Option<PathBuf>>;
+
+// The state of a batch of changes while it is written to the index
+#[derive(Default)]
+struct ChangeBatch {
+    moved: MovedFiles,
+    // The files created or modified in the batch, probed before the database was locked
+    probed: ProbedFiles,
+}
+
+/// Apply a batch of filesystem changes to the collections and audio files tables of `database`.
+///
+/// The created and modified files are probed and analysed by a pool of indexing workers while the database is
+/// unlocked, then the whole batch is written in a single transaction.
+pub fn apply_library_changes(
+    database: &Mutex<Database>,
+    changes: &[LibraryChange],
+) -> Result<(), DatabaseError> {
+    let (roots, filter, fingerprints) = {
+        let db = database.lock().map_err(|_| DatabaseError::ConnectionClosed)?;
+        let roots = db.paths_to_probe(changes);
+        let fingerprints: HashMap<PathBuf, Hash> = db
+            .meta
+            .map
+            .iter()
+            .filter(|(path, _)| roots.iter().any(|root| path.starts_with(root)))
+            .map(|(path, hash)| (path.clone(), hash.clone()))
+            .collect();
+        (roots, db.filter.clone(), fingerprints)
+    };
+
+    let mut paths = Vec::new();
+    for root in roots.iter() {
+        collect_files(root, &filter, &mut paths);
+    }
+    paths.sort();
+    paths.dedup();
+
+    let probed = probe_files(paths, &filter, &fingerprints);
+
+    let mut db = database.lock().map_err(|_| DatabaseError::ConnectionClosed)?;
+    transaction(&mut db, |db| db.write_library_changes(changes, probed))?;
+    db.store_metadata()
+}
+
+// Collect the files accepted by `filter` at or below `path`.
+fn collect_files(path: &Path, filter: &FileFilter, files: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        if filter.accepts_file(path).is_some() {
+            files.push(path.to_path_buf());
+        }
+        return;
+    }
+
+    if path.file_name().is_some_and(|name| name == DATABASE_META_NAME)
+        || !filter.accepts_directory(path)
+    {
+        return;
+    }
+
+    if let Ok(entries) = read_dir(path) {
+        for entry in entries.flatten() {
+            collect_files(&entry.path(), filter, files);
+        }
+    }
+}
+
+impl Database {
+    // The paths below which a batch of changes may index files. A path whose parent directory is not indexed
+    // is widened to its topmost ancestor which is not, as that ancestor is indexed along with it.
+    fn paths_to_probe(&self, changes: &[LibraryChange]) -> Vec<PathBuf> {
+        let indexed_directory = |path: &Path| self.get_collection_by_path(path).is_ok();
+
+        changes
+            .iter()
+            .filter_map(|change| match change {
+                LibraryChange::Created(path) | LibraryChange::Modified(path) => Some(path),
+                // A file or directory moved within the index keeps its rows
+                LibraryChange::Renamed(from, to) => {
+                    let moved = to.parent().is_some_and(indexed_directory)
+                        && (indexed_directory(from) || self.audio_file_at(from).is_some());
+                    (!moved).then_some(to)
+                }
+                LibraryChange::Removed(_) => None,
+            })
+            .filter(|path| path.starts_with(&self.path))
+            .map(|path| {
+                let mut path = path.as_path();
+                while let Some(parent) = path.parent() {
+                    if !parent.starts_with(&self.path) || indexed_directory(parent) {
+                        break;
+                    }
+                    path = parent;
+                }
+                path.to_path_buf()
+            })
+            .collect()
+    }
+
+    // Write a batch of changes whose files have been probed to the collections and audio files tables.
+    fn write_library_changes(
+        &mut self,
+        changes: &[LibraryChange],
+        probed: ProbedFiles,
+    ) -> Result<(), DatabaseError> {
+        // Some tools move files by deleting and recreating them. Removals are applied last so
+        // that a created file with the same fingerprint can take over the removed row, which
+        // keeps its ID and tag assignments.
+        let mut batch = ChangeBatch { moved: MovedFiles::new(), probed };
+        for change in changes.iter() {
+            if let LibraryChange::Removed(path) = change {
+                for (p, hash) in self.meta.map.iter().filter(|(p, _)| p.starts_with(path)) {
+                    batch
+                        .moved
+                        .entry(hash.clone())
+                        .and_modify(|from| *from = None)
+                        .or_insert_with(|| Some(p.clone()));
+                }
+            }
+        }
+
+        // Tags are not carried over to one of several copies of a removed file
+        let mut copies: HashMap<&Hash, usize> = HashMap::new();
+        for hash in batch.probed.values().flatten().filter_map(|file| file.hash.as_ref()) {
+            *copies.entry(hash).or_default() += 1;
+        }
+        for (hash, _) in copies.into_iter().filter(|(_, count)| *count > 1) {
+            if let Some(from) = batch.moved.get_mut(hash) {
+                *from = None;
+            }
+        }
+
+        for change in changes.iter() {
+            match change {
+                LibraryChange::Created(path) => self.index_path(path, &mut batch)?,
+                LibraryChange::Renamed(from, to) => self.move_path(from, to, &mut batch)?,
+                LibraryChange::Modified(path) => self.update_path(path, &mut batch)?,
+                LibraryChange::Removed(_) => {}
+            }
+        }
+
+        for change in changes.iter() {
+            if let LibraryChange::Removed(path) = change {
+                self.remove_path(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Add a new file or directory to the index.
+    fn index_path(&mut self, path: &Path, batch: &mut ChangeBatch) -> Result<(), DatabaseError> {
+        if !path.starts_with(&self.path) || self.get_collection_by_path(path).is_ok() {
+            return Ok(());
+        }
+
+        let Some(parent_path) = path.parent() else { return Ok(()) };
+
+        // Index the parent first if it is not known yet, which also picks up this path
+        let Ok(parent) = self.get_collection_by_path(parent_path) else {
+            return self.index_path(parent_path, batch);
+        };
+
+        if path.is_dir() {
+            if self.filter.accepts_directory(path) {
+                self.index_directory(path, parent.id(), batch)?;
+            }
+        } else {
+            self.index_file(path, parent.id(), batch)?;
+        }
+
+        Ok(())
+    }
+
+    // Add a probed file to the index, taking over the row of a removed file with the same content.
+    fn index_file(
+        &mut self,
+        path: &Path,
+        collection: CollectionID,
+        batch: &mut ChangeBatch,
+    ) -> Result<(), DatabaseError> {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { return Ok(()) };
+
+        if self.filter.accepts_file(path).is_none()
+            || self.get_audio_file_by_name(collection, name).is_ok()
+        {
+            return Ok(());
+        }
+
+        // Files which were not probed before the database was locked are left to the next indexing job
+        let Some(Some(ProbedFile { result: Ok(audio_file), metadata, analysis, hash })) =
+            batch.probed.remove(path)
+        else {
+            return Ok(());
+        };
+
+        if let Some(from) = hash.as_ref().and_then(|hash| batch.moved.remove(hash)).flatten() {
+            if let Some(mut audio_file) = self.audio_file_at(&from) {
+                audio_file.name = name.to_string();
+                audio_file.collection = collection;
+                self.update_audio_file(audio_file)?;
+
+                self.meta.map.remove(&from);
+                self.meta.map.insert(path.to_path_buf(), hash.unwrap());
+
+                return Ok(());
+            }
+        }
+
+        let id = self.next_audio_file_id()?;
+        self.insert_audio_file(AudioFile { id, collection, ..audio_file })?;
+        self.insert_metadata(id, &metadata)?;
+        if let Some(analysis) = analysis {
+            self.suggest_tags(id, &analysis.classification.tags())?;
+            self.insert_features(id, &analysis.features)?;
+        }
+
+        if let Some(hash) = hash {
+            self.meta.map.insert(path.to_path_buf(), hash);
+        }
+
+        Ok(())
+    }
+
+    // Recursively index a directory and its contents as a child of `parent`.
+    fn index_directory(
+        &mut self,
+        path: &Path,
+        parent: CollectionID,
+        batch: &mut ChangeBatch,
+    ) -> Result<(), DatabaseError> {
+        let collections: RefCell<HashMap<PathBuf, CollectionID>> = RefCell::new(HashMap::new());
+        let result: RefCell<Result<(), DatabaseError>> = RefCell::new(Ok(()));
+        let directory_batch = RefCell::new(std::mem::take(batch));
+
+        let parent_path = path.parent().map(|p| p.to_path_buf());
+        if let Some(parent_path) = &parent_path {
+            collections.borrow_mut().insert(parent_path.clone(), parent);
+        }
+
+        recursive_directory_closure(
+            self,
+            &path.to_path_buf(),
+            parent_path.as_ref(),
+            |db, path, parent_path, files| {
+                if result.borrow().is_err() {
+                    return;
+                }
+
+                let parent_id = parent_path.and_then(|p| collections.borrow().get(p).copied());
+
+                // Skip excluded directories and everything below them
+                if parent_id.is_none() || !db.filter.accepts_directory(path) {
+                    return;
+                }
+
+                *result.borrow_mut() = (|| {
+                    let id = db.next_collection_id()?;
+                    let name = path.file_name().unwrap().to_str().unwrap().to_string();
+
+                    db.insert_collection(Collection::new(id, parent_id, name, path.clone()))?;
+                    collections.borrow_mut().insert(path.clone(), id);
+
+                    for child_file in files {
+                        db.index_file(&child_file.path(), id, &mut directory_batch.borrow_mut())?;
+                    }
+
+                    Ok(())
+                })();
+            },
+        )?;
+
+        *batch = directory_batch.into_inner();
+
+        result.into_inner()
+    }
+
+    // Remove a file or directory from the index.
+    fn remove_path(&mut self, path: &Path) -> Result<(), DatabaseError> {
+        if let Ok(collection) = self.get_collection_by_path(path) {
+            self.remove_collection(collection.id())?;
+            self.meta.map.retain(|p, _| !p.starts_with(path));
+        } else if let Some(audio_file) = self.audio_file_at(path) {
+            self.remove_audio_file(audio_file.id)?;
+            self.meta.map.remove(path);
+        }
+
+        Ok(())
+    }
+
+    // Move a file or directory within the index, keeping its ID so that tag assignments survive.
+    fn move_path(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        batch: &mut ChangeBatch,
+    ) -> Result<(), DatabaseError> {
+        if !to.starts_with(&self.path) {
+            return self.remove_path(from);
+        }
+
+        let Some(parent) = to.parent().and_then(|p| self.get_collection_by_path(p).ok()) else {
+            self.remove_path(from)?;
+            return self.index_path(to, batch);
+        };
+
+        let name = to.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+
+        if let Ok(collection) = self.get_collection_by_path(from) {
+            // Update the moved collection and rewrite the paths of all its descendants
+            for descendant in self.get_all_collections()? {
+                if descendant.id() == collection.id() {
+                    self.update_collection(Collection::new(
+                        collection.id(),
+                        Some(parent.id()),
+                        name.clone(),
+                        to.to_path_buf(),
+                    ))?;
+                } else if let Ok(relative) = descendant.path().strip_prefix(from) {
+                    let path = to.join(relative);
+                    self.update_collection(Collection::new(
+                        descendant.id(),
+                        descendant.parent_collection(),
+                        descendant.name().to_string(),
+                        path,
+                    ))?;
+                }
+            }
+
+            self.meta.map = self
+                .meta
+                .map
+                .drain()
+                .map(|(p, hash)| match p.strip_prefix(from) {
+                    Ok(relative) => (to.join(relative), hash),
+                    Err(_) => (p, hash),
+                })
+                .collect();
+        } else if let Some(mut audio_file) = self.audio_file_at(from) {
+            audio_file.name = name;
+            audio_file.collection = parent.id();
+            self.update_audio_file(audio_file)?;
+
+            if let Some(hash) = self.meta.map.remove(from) {
+                self.meta.map.insert(to.to_path_buf(), hash);
+            }
+        } else {
+            self.index_path(to, batch)?;
+        }
+
+        Ok(())
+    }
+
+    // Replace the stream info, embedded metadata and analysis of a modified file with those probed for it.
+    fn update_path(&mut self, path: &Path, batch: &mut ChangeBatch) -> Result<(), DatabaseError> {
+        if path.is_dir() {
+            return Ok(());
+        }
+
+        let Some(audio_file) = self.audio_file_at(path) else {
+            return self.index_path(path, batch);
+        };
+
+        match batch.probed.remove(path) {
+            Some(Some(ProbedFile { result: Ok(probed), metadata, analysis, hash })) => {
+                self.reindex_audio_file(&audio_file, probed, &metadata, analysis.as_deref())?;
+
+                if let Some(hash) = hash {
+                    self.meta.map.insert(path.to_path_buf(), hash);
+                }
+            }
+
+            // The file can no longer be decoded
+            Some(Some(ProbedFile { result: Err(_), .. })) => self.remove_path(path)?,
+
+            // The content of the file has not changed, or it is left to the next indexing job
+            Some(None) | None => {}
+        }
+
+        Ok(())
+    }
+
+    fn audio_file_at(&self, path: &Path) -> Option<AudioFile> {
+        let collection = self.get_collection_by_path(path.parent()?).ok()?;
+        let name = path.file_name()?.to_str()?;
+        self.get_audio_file_by_name(collection.id(), name).ok()
+    }
+}
@@ -0,0 +1,168 @@
+use image::DynamicImage;
+use vizia::prelude::*;
+
+use crate::data::{AppData, ExportData, ExportEvent, SamplesData};
+use crate::engine::{BitDepth, ExportSettings};
+
+pub fn export_dialog(cx: &mut Context, icon: DynamicImage) {
+    Binding::new(cx, AppData::export_data.then(ExportData::show_dialog), move |cx, show_dialog| {
+        if show_dialog.get(cx) {
+            Window::popup(cx, true, |cx| {
+                VStack::new(cx, |cx| {
+                    ScrollView::new(cx, |cx| {
+                        Label::new(
+                            cx,
+                            Localized::new("export-files").arg(
+                                "count",
+                                AppData::samples_data
+                                    .then(SamplesData::selection)
+                                    .map(|selection| selection.len()),
+                            ),
+                        )
+                        .class("title");
+                        // Only the selected region is exported from a single file
+                        Label::new(cx, Localized::new("export-region"))
+                            .display(AppData::wave_selection.map(|selection| selection.is_some()));
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("sample-rate"));
+                            PickList::new(
+                                cx,
+                                AppData::export_data.then(ExportData::sample_rate_entries),
+                                AppData::export_data.map(|data| data.selected_sample_rate()),
+                                true,
+                            )
+                            .on_select(|cx, index| cx.emit(ExportEvent::SelectSampleRate(index)))
+                            .width(Pixels(150.0));
+                        })
+                        .class("panel");
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("bit-depth"));
+                            PickList::new(
+                                cx,
+                                AppData::export_data.then(ExportData::bit_depth_entries),
+                                AppData::export_data.map(|data| data.selected_bit_depth()),
+                                true,
+                            )
+                            .on_select(|cx, index| cx.emit(ExportEvent::SelectBitDepth(index)))
+                            .width(Pixels(150.0));
+                        })
+                        .class("panel");
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("export-dither"));
+                            Checkbox::new(cx, settings().then(ExportSettings::dither))
+                                .on_toggle(|cx| cx.emit(ExportEvent::ToggleDither))
+                                .disabled(
+                                    settings()
+                                        .then(ExportSettings::bit_depth)
+                                        .map(|bit_depth| *bit_depth == BitDepth::Float32),
+                                );
+                        })
+                        .class("panel");
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("export-normalize"));
+                            HStack::new(cx, |cx| {
+                                Textbox::new(
+                                    cx,
+                                    AppData::export_data
+                                        .then(ExportData::normalize_level)
+                                        .map(|level| format!("{:.1}", level)),
+                                )
+                                .on_submit(|cx, text, _| {
+                                    if let Ok(level) = text.trim().parse() {
+                                        cx.emit(ExportEvent::SetNormalizeLevel(level));
+                                    }
+                                })
+                                .class("normalize-level");
+                                Label::new(cx, "dBFS");
+                                Checkbox::new(
+                                    cx,
+                                    settings()
+                                        .then(ExportSettings::normalize)
+                                        .map(|normalize| normalize.is_some()),
+                                )
+                                .on_toggle(|cx| cx.emit(ExportEvent::ToggleNormalize));
+                            })
+                            .class("export-option");
+                        })
+                        .class("panel");
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("export-effects"));
+                            Checkbox::new(cx, AppData::export_data.then(ExportData::apply_effects))
+                                .on_toggle(|cx| cx.emit(ExportEvent::ToggleEffects));
+                        })
+                        .class("panel");
+
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("export-template"));
+                            Textbox::new(cx, AppData::export_data.then(ExportData::template))
+                                .on_edit(|cx, text| cx.emit(ExportEvent::SetTemplate(text)))
+                                .width(Pixels(150.0));
+                        })
+                        .class("panel");
+                        Label::new(cx, Localized::new("export-template-hint")).class("export-hint");
+
+                        // The outcome of the last export
+                        Label::new(
+                            cx,
+                            Localized::new("export-finished")
+                                .arg(
+                                    "exported",
+                                    AppData::export_data
+                                        .then(ExportData::exported)
+                                        .map(|exported| exported.unwrap_or_default()),
+                                )
+                                .arg(
+                                    "failed",
+                                    AppData::export_data
+                                        .then(ExportData::errors)
+                                        .map(|errors| errors.len()),
+                                ),
+                        )
+                        .display(
+                            AppData::export_data
+                                .then(ExportData::exported)
+                                .map(|exported| exported.is_some()),
+                        );
+                        List::new(
+                            cx,
+                            AppData::export_data.then(ExportData::errors),
+                            |cx, _, error| {
+                                Label::new(cx, error).class("export-error");
+                            },
+                        );
+                    })
+                    .class("settings");
+
+                    HStack::new(cx, |cx| {
+                        Button::new(cx, |cx| Label::new(cx, Localized::new("close")))
+                            .on_press(|cx| cx.emit(ExportEvent::HideDialog));
+                        Button::new(cx, |cx| Label::new(cx, Localized::new("export")))
+                            .on_press(|cx| cx.emit(ExportEvent::Export))
+                            .disabled(AppData::export_data.then(ExportData::exporting))
+                            .class("accent");
+                    })
+                    .class("dialog-buttons");
+                });
+            })
+            .on_close(|cx| {
+                cx.emit(ExportEvent::HideDialog);
+            })
+            .class("dialog")
+            .title("Export")
+            .inner_size((500, 560))
+            .anchor(Anchor::Center)
+            .enabled_window_buttons(WindowButtons::CLOSE)
+            .icon(icon.width(), icon.height(), icon.clone().into_bytes());
+        }
+    });
+}
+
+// The conversion settings of the export
+fn settings() -> impl Lens<Target = ExportSettings> {
+    AppData::export_data.then(ExportData::settings)
+}
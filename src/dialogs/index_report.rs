@@ -0,0 +1,58 @@
+use image::DynamicImage;
+use vizia::prelude::*;
+
+use crate::data::{AppData, IndexingData, IndexingEvent};
+use crate::database::prelude::IndexError;
+
+pub fn index_report_dialog(cx: &mut Context, icon: DynamicImage) {
+    Binding::new(
+        cx,
+        AppData::indexing_data.then(IndexingData::show_report),
+        move |cx, show_report| {
+            if show_report.get(cx) {
+                Window::popup(cx, true, |cx| {
+                    VStack::new(cx, |cx| {
+                        ScrollView::new(cx, |cx| {
+                            List::new(
+                                cx,
+                                AppData::indexing_data.then(IndexingData::errors),
+                                |cx, _, error| {
+                                    VStack::new(cx, |cx| {
+                                        Label::new(
+                                            cx,
+                                            error
+                                                .then(IndexError::path)
+                                                .map(|path| path.display().to_string()),
+                                        )
+                                        .class("path");
+                                        Label::new(cx, error.then(IndexError::message))
+                                            .class("message");
+                                    })
+                                    .class("index-error");
+                                },
+                            );
+                        });
+
+                        HStack::new(cx, |cx| {
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("clear-report")))
+                                .on_press(|cx| cx.emit(IndexingEvent::ClearReport));
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("close")))
+                                .on_press(|cx| cx.emit(IndexingEvent::HideReport))
+                                .class("accent");
+                        })
+                        .class("dialog-buttons");
+                    });
+                })
+                .on_close(|cx| {
+                    cx.emit(IndexingEvent::HideReport);
+                })
+                .class("dialog")
+                .title("Indexing Report")
+                .inner_size((500, 400))
+                .anchor(Anchor::Center)
+                .enabled_window_buttons(WindowButtons::CLOSE)
+                .icon(icon.width(), icon.height(), icon.clone().into_bytes());
+            }
+        },
+    );
+}
@@ -0,0 +1,139 @@
+use image::DynamicImage;
+use vizia::prelude::*;
+
+use crate::data::{AppData, InstrumentData, InstrumentEvent, ZoneRow};
+
+pub fn instrument_dialog(cx: &mut Context, icon: DynamicImage) {
+    Binding::new(
+        cx,
+        AppData::instrument_data.then(InstrumentData::show_dialog),
+        move |cx, show_dialog| {
+            if show_dialog.get(cx) {
+                Window::popup(cx, true, |cx| {
+                    VStack::new(cx, |cx| {
+                        Label::new(
+                            cx,
+                            Localized::new("export-instrument-zones")
+                                .arg("count", rows().map(|rows| rows.len())),
+                        )
+                        .class("title");
+
+                        // The mapping preview
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, Localized::new("instrument-sample")).class("name");
+                            Label::new(cx, Localized::new("instrument-root"));
+                            Label::new(cx, Localized::new("instrument-keys"));
+                            Label::new(cx, Localized::new("instrument-velocities"));
+                            Label::new(cx, Localized::new("instrument-loop"));
+                        })
+                        .class("zone-header");
+                        ScrollView::new(cx, |cx| {
+                            List::new(cx, rows(), |cx, _, row| {
+                                HStack::new(cx, |cx| {
+                                    Label::new(cx, row.then(ZoneRow::name)).class("name");
+                                    Label::new(cx, row.then(ZoneRow::root));
+                                    Label::new(cx, row.then(ZoneRow::keys));
+                                    Label::new(cx, row.then(ZoneRow::velocities));
+                                    Label::new(
+                                        cx,
+                                        row.then(ZoneRow::looped).map(|looped| {
+                                            if *looped {
+                                                "✓"
+                                            } else {
+                                                ""
+                                            }
+                                        }),
+                                    );
+                                })
+                                .class("zone");
+                            })
+                            .selectable(Selectable::None);
+                        })
+                        .class("zones");
+
+                        // The samples without a root note
+                        Label::new(
+                            cx,
+                            Localized::new("instrument-unmapped").arg(
+                                "names",
+                                AppData::instrument_data
+                                    .then(InstrumentData::unmapped)
+                                    .map(|names| names.join(", ")),
+                            ),
+                        )
+                        .display(
+                            AppData::instrument_data
+                                .then(InstrumentData::unmapped)
+                                .map(|names| !names.is_empty()),
+                        )
+                        .class("export-error");
+
+                        HStack::new(cx, |cx| {
+                            Checkbox::new(
+                                cx,
+                                AppData::instrument_data.then(InstrumentData::with_dspreset),
+                            )
+                            .on_toggle(|cx| cx.emit(InstrumentEvent::ToggleDspreset));
+                            Label::new(cx, Localized::new("instrument-dspreset"));
+                        })
+                        .class("export-option");
+
+                        // The outcome of the last export
+                        Label::new(
+                            cx,
+                            Localized::new("instrument-written").arg(
+                                "files",
+                                AppData::instrument_data
+                                    .then(InstrumentData::written)
+                                    .map(|written| written.join(", ")),
+                            ),
+                        )
+                        .display(
+                            AppData::instrument_data
+                                .then(InstrumentData::written)
+                                .map(|written| !written.is_empty()),
+                        )
+                        .class("export-hint");
+                        Label::new(
+                            cx,
+                            AppData::instrument_data
+                                .then(InstrumentData::error)
+                                .map(|error| error.clone().unwrap_or_default()),
+                        )
+                        .display(
+                            AppData::instrument_data
+                                .then(InstrumentData::error)
+                                .map(Option::is_some),
+                        )
+                        .class("export-error");
+
+                        HStack::new(cx, |cx| {
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("close")))
+                                .on_press(|cx| cx.emit(InstrumentEvent::HideDialog));
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("export")))
+                                .on_press(|cx| cx.emit(InstrumentEvent::Export))
+                                .disabled(rows().map(|rows| rows.is_empty()))
+                                .class("accent");
+                        })
+                        .class("dialog-buttons");
+                    })
+                    .class("instrument");
+                })
+                .on_close(|cx| {
+                    cx.emit(InstrumentEvent::HideDialog);
+                })
+                .class("dialog")
+                .title("Export as Instrument")
+                .inner_size((600, 480))
+                .anchor(Anchor::Center)
+                .enabled_window_buttons(WindowButtons::CLOSE)
+                .icon(icon.width(), icon.height(), icon.clone().into_bytes());
+            }
+        },
+    );
+}
+
+// The rows of the mapping preview
+fn rows() -> impl Lens<Target = Vec<ZoneRow>> {
+    AppData::instrument_data.then(InstrumentData::rows)
+}
@@ -3,3 +3,12 @@ pub use about::*;
 
 pub mod settings;
 pub use settings::*;
+
+pub mod index_report;
+pub use index_report::*;
+
+pub mod export;
+pub use export::*;
+
+pub mod instrument;
+pub use instrument::*;
@@ -1,10 +1,13 @@
 use std::str::FromStr;
 
 use image::DynamicImage;
-use strum::VariantNames;
+use strum::{IntoEnumIterator, VariantNames};
 use vizia::prelude::*;
 
-use crate::data::{AppData, AppEvent, SettingsData, SettingsEvent, SettingsPage};
+use crate::data::{
+    AppData, AppEvent, Config, ConfigEvent, SettingsData, SettingsEvent, SettingsPage,
+};
+use crate::database::prelude::AudioFormat;
 
 pub fn settings_dialog<L: Lens<Target = SettingsData>>(
     cx: &mut Context,
@@ -36,7 +39,24 @@ pub fn settings_dialog<L: Lens<Target = SettingsData>>(
                         move |cx, selected_page| match selected_page.get(cx) {
                             SettingsPage::General => {
                                 ScrollView::new(cx, |cx| {
-                                    //
+                                    Label::new(cx, Localized::new("indexed-formats"))
+                                        .class("title");
+
+                                    for format in AudioFormat::iter() {
+                                        HStack::new(cx, move |cx| {
+                                            Checkbox::new(
+                                                cx,
+                                                AppData::config.then(Config::library_filter).map(
+                                                    move |filter| filter.formats.contains(&format),
+                                                ),
+                                            )
+                                            .on_toggle(move |cx| {
+                                                cx.emit(ConfigEvent::ToggleFormat(format))
+                                            });
+                                            Label::new(cx, format.to_string());
+                                        })
+                                        .class("panel");
+                                    }
                                 })
                                 .class("settings");
                             }
@@ -50,7 +70,6 @@ pub fn settings_dialog<L: Lens<Target = SettingsData>>(
 
                             SettingsPage::Audio => {
                                 ScrollView::new(cx, move |cx| {
-                                    //
                                     HStack::new(cx, move |cx| {
                                         Label::new(cx, Localized::new("audio-driver"));
                                         PickList::new(
@@ -59,33 +78,60 @@ pub fn settings_dialog<L: Lens<Target = SettingsData>>(
                                             lens.then(SettingsData::selected_audio_driver),
                                             true,
                                         )
+                                        .on_select(|cx, index| {
+                                            cx.emit(AppEvent::SelectAudioDriver(index))
+                                        })
                                         .width(Pixels(150.0));
                                     })
                                     .class("panel");
 
                                     HStack::new(cx, move |cx| {
-                                        Label::new(cx, Localized::new("input-device"));
+                                        Label::new(cx, Localized::new("output-device"));
                                         PickList::new(
                                             cx,
-                                            lens.then(SettingsData::input_device),
-                                            lens.then(SettingsData::selected_input_device),
+                                            lens.then(SettingsData::output_device),
+                                            lens.then(SettingsData::selected_output_device),
                                             true,
                                         )
+                                        .on_select(|cx, index| {
+                                            cx.emit(AppEvent::SelectOutputDevice(index))
+                                        })
                                         .width(Pixels(150.0));
                                     })
                                     .class("panel");
 
                                     HStack::new(cx, move |cx| {
-                                        Label::new(cx, Localized::new("output-device"));
+                                        Label::new(cx, Localized::new("sample-rate"));
                                         PickList::new(
                                             cx,
-                                            lens.then(SettingsData::output_device),
-                                            lens.then(SettingsData::selected_output_device),
+                                            lens.then(SettingsData::sample_rate),
+                                            lens.then(SettingsData::selected_sample_rate),
                                             true,
                                         )
+                                        .on_select(|cx, index| {
+                                            cx.emit(AppEvent::SelectSampleRate(index))
+                                        })
                                         .width(Pixels(150.0));
                                     })
                                     .class("panel");
+
+                                    HStack::new(cx, move |cx| {
+                                        Label::new(cx, Localized::new("buffer-size"));
+                                        PickList::new(
+                                            cx,
+                                            lens.then(SettingsData::buffer_size),
+                                            lens.then(SettingsData::selected_buffer_size),
+                                            true,
+                                        )
+                                        .on_select(|cx, index| {
+                                            cx.emit(AppEvent::SelectBufferSize(index))
+                                        })
+                                        .width(Pixels(150.0));
+                                    })
+                                    .class("panel");
+
+                                    Label::new(cx, lens.then(SettingsData::output_status))
+                                        .class("output-status");
                                 })
                                 .class("settings");
                             }
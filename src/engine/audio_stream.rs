@@ -1,9 +1,17 @@
-use crate::utils::interleave;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::Stream;
+use cpal::{BufferSize, Device, Host, SampleRate, Stream, SupportedBufferSize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use super::Process;
 
+// The sample rates and buffer sizes offered for a device which supports a range of them
+const SAMPLE_RATES: [u32; 6] = [44100, 48000, 88200, 96000, 176400, 192000];
+const BUFFER_SIZES: [u32; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+
 /// The playback context is used by the audio callback to map data from the audio
 /// file to the playback buffer.
 pub struct PlaybackContext<'a> {
@@ -21,66 +29,234 @@ impl<'a> PlaybackContext<'a> {
     }
 }
 
-/// Start the audio stream
-pub fn audio_stream(mut main_callback: impl FnMut(PlaybackContext) + Send + 'static) -> Stream {
-    let host = cpal::default_host();
-    let output_device = host.default_output_device().expect("no output found");
-    let config = output_device.default_output_config().expect("no default output config").config();
+/// The output chosen in the settings, each part being `None` to use the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputSettings {
+    pub host: Option<String>,
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub buffer_size: Option<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum OutputError {
+    #[error("no output device found")]
+    NoDevice,
+    #[error(transparent)]
+    Config(#[from] cpal::DefaultStreamConfigError),
+    #[error(transparent)]
+    Build(#[from] cpal::BuildStreamError),
+    #[error(transparent)]
+    Play(#[from] cpal::PlayStreamError),
+}
+
+/// The device and configuration of an open output stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputInfo {
+    pub device: String,
+    pub sample_rate: u32,
+    pub num_channels: u16,
+    // Whether the chosen device was not found, so the default device was opened instead
+    pub fallback: bool,
+}
+
+/// A backend which runs the sample player, on an audio device or offline.
+pub trait AudioBackend {
+    /// Start running the sample player on the chosen output, returning the device and configuration used.
+    fn open(&mut self, settings: &OutputSettings) -> Result<OutputInfo, OutputError>;
+
+    /// Stop running the sample player.
+    fn close(&mut self);
+
+    /// The device and configuration of the output, `None` if it is not running or its device has been lost.
+    fn info(&self) -> Option<&OutputInfo>;
+
+    /// Whether the output should be reopened with the chosen settings.
+    fn needs_reopen(&self, _settings: &OutputSettings) -> bool {
+        self.info().is_none()
+    }
+}
+
+/// The backend playing through a cpal output stream, which is reopened when the chosen output changes or its
+/// device disappears.
+pub struct CpalBackend {
+    process: Arc<Mutex<Process>>,
+    stream: Option<Stream>,
+    info: Option<OutputInfo>,
+    // Set from the audio thread when the device of the stream is no longer available
+    failed: Arc<AtomicBool>,
+}
 
-    let sample_rate = config.sample_rate.0 as f64;
-    let num_channels = config.channels as usize;
-    let mut output_buffer = vec![];
-    let mut input_buffer = vec![];
+impl CpalBackend {
+    pub fn new(process: Process) -> Self {
+        Self {
+            process: Arc::new(Mutex::new(process)),
+            stream: None,
+            info: None,
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    /// Open a stream on the chosen output, falling back to the default device of the host when the chosen device
+    /// is not available, and to the default configuration of the device for an unsupported sample rate or buffer
+    /// size. The current stream is closed even if no stream can be opened.
+    fn open(&mut self, settings: &OutputSettings) -> Result<OutputInfo, OutputError> {
+        // The player is only used by one stream at a time
+        self.close();
 
-    output_buffer.resize_with(1 << 16, || 0.0);
-    input_buffer.resize_with(1 << 16, || 0.0);
+        let host = find_host(settings.host.as_deref());
+        let chosen = settings.device.as_deref().and_then(|name| find_device(&host, name));
+        let fallback = settings.device.is_some() && chosen.is_none();
+        let device =
+            chosen.or_else(|| host.default_output_device()).ok_or(OutputError::NoDevice)?;
 
-    let callback = move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-        let buffer_size = data.len() / num_channels;
-        output_buffer.resize(data.len(), 0.0);
-        for sample in data.iter_mut() {
-            *sample = 0.0;
+        let mut config = device.default_output_config()?.config();
+        if let Some(sample_rate) = settings.sample_rate {
+            if sample_rates(&device).contains(&sample_rate) {
+                config.sample_rate = SampleRate(sample_rate);
+            }
         }
-        for sample in &mut output_buffer.iter_mut() {
-            *sample = 0.0;
+        if let Some(buffer_size) = settings.buffer_size {
+            if buffer_sizes(&device).contains(&buffer_size) {
+                config.buffer_size = BufferSize::Fixed(buffer_size);
+            }
         }
 
-        let context =
-            PlaybackContext { buffer_size, num_channels, sample_rate, output_buffer: data };
+        let sample_rate = config.sample_rate.0 as f64;
+        let num_channels = config.channels as usize;
+        let process = self.process.clone();
+        let callback = move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            // The lock is only held elsewhere while a previous stream is closing
+            match process.try_lock() {
+                Ok(mut process) => {
+                    let buffer_size = data.len() / num_channels;
+                    process.process(PlaybackContext {
+                        buffer_size,
+                        num_channels,
+                        sample_rate,
+                        output_buffer: data,
+                    });
+                }
+                Err(_) => data.fill(0.0),
+            }
+        };
 
-        main_callback(context);
-        // interleave(&output_buffer, data, num_channels);
-    };
+        let failed = self.failed.clone();
+        let error_callback = move |err: cpal::StreamError| {
+            eprintln!("{}", err);
+            if let cpal::StreamError::DeviceNotAvailable = err {
+                failed.store(true, Ordering::SeqCst);
+            }
+        };
+
+        let stream = device.build_output_stream(&config, callback, error_callback, None)?;
+        stream.play()?;
+        self.stream = Some(stream);
+
+        let info = OutputInfo {
+            device: device.name().unwrap_or_default(),
+            sample_rate: config.sample_rate.0,
+            num_channels: config.channels,
+            fallback,
+        };
+        self.info = Some(info.clone());
+        Ok(info)
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+        self.info = None;
+        self.failed.store(false, Ordering::SeqCst);
+    }
+
+    fn info(&self) -> Option<&OutputInfo> {
+        if self.failed.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        self.info.as_ref()
+    }
+
+    /// The stream is also reopened when it fell back to the default device and the chosen device has become
+    /// available.
+    fn needs_reopen(&self, settings: &OutputSettings) -> bool {
+        match (self.info(), &settings.device) {
+            (None, _) => true,
+            (Some(info), Some(device)) if info.fallback => {
+                output_device_names(settings.host.as_deref()).contains(device)
+            }
+            _ => false,
+        }
+    }
+}
 
-    output_device
-        .build_output_stream(&config, callback, |err| eprintln!("{}", err), None)
-        .expect("failed to open stream")
+/// The names of the audio hosts of this platform, e.g. WASAPI and ASIO on Windows.
+pub fn host_names() -> Vec<String> {
+    cpal::available_hosts().into_iter().map(|id| id.name().to_owned()).collect()
 }
 
-// pub fn spawn_cpal_stream(process: Process) -> cpal::Stream {
-//     // Setup cpal audio output
+/// The names of the output devices of the named host, or of the default host.
+pub fn output_device_names(host: Option<&str>) -> Vec<String> {
+    find_host(host)
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
 
-//     let host = cpal::default_host();
+/// The sample rates and buffer sizes which can be chosen for the output, those of the chosen device or of the
+/// default device.
+pub fn output_options(settings: &OutputSettings) -> (Vec<u32>, Vec<u32>) {
+    let host = find_host(settings.host.as_deref());
+    let device = settings
+        .device
+        .as_deref()
+        .and_then(|name| find_device(&host, name))
+        .or_else(|| host.default_output_device());
 
-//     let device = host.default_output_device().expect("no output device available");
+    device.map(|device| (sample_rates(&device), buffer_sizes(&device))).unwrap_or_default()
+}
 
-//     let sample_rate = device.default_output_config().unwrap().sample_rate();
+// The named host, or the default host if it is not available.
+fn find_host(name: Option<&str>) -> Host {
+    cpal::available_hosts()
+        .into_iter()
+        .find(|id| Some(id.name()) == name)
+        .and_then(|id| cpal::host_from_id(id).ok())
+        .unwrap_or_else(cpal::default_host)
+}
 
-//     let config =
-//         cpal::StreamConfig { channels: 2, sample_rate, buffer_size: cpal::BufferSize::Default };
+fn find_device(host: &Host, name: &str) -> Option<Device> {
+    host.output_devices().ok()?.find(|device| device.name().is_ok_and(|device| device == name))
+}
 
-//     let stream = device
-//         .build_output_stream(
-//             &config,
-//             move |data: &mut [f32], _: &cpal::OutputCallbackInfo| process.process(data),
-//             move |err| {
-//                 eprintln!("{}", err);
-//             },
-//             None,
-//         )
-//         .unwrap();
+// The common sample rates supported by the device for 32-bit float output.
+fn sample_rates(device: &Device) -> Vec<u32> {
+    let Ok(configs) = device.supported_output_configs() else {
+        return Vec::new();
+    };
 
-//     stream.play().unwrap();
+    let ranges: Vec<_> = configs
+        .filter(|config| config.sample_format() == cpal::SampleFormat::F32)
+        .map(|config| config.min_sample_rate().0..=config.max_sample_rate().0)
+        .collect();
+    SAMPLE_RATES
+        .into_iter()
+        .filter(|rate| ranges.iter().any(|range| range.contains(rate)))
+        .collect()
+}
 
-//     stream
-// }
+// The buffer sizes in frames supported by the device, all of them if it does not report a range.
+fn buffer_sizes(device: &Device) -> Vec<u32> {
+    let Ok(config) = device.default_output_config() else {
+        return Vec::new();
+    };
+
+    match config.buffer_size() {
+        SupportedBufferSize::Range { min, max } => {
+            BUFFER_SIZES.into_iter().filter(|size| (*min..=*max).contains(size)).collect()
+        }
+        SupportedBufferSize::Unknown => BUFFER_SIZES.to_vec(),
+    }
+}
@@ -0,0 +1,132 @@
+//! Mapping the channels of a file to the stereo signal of the player, and of the player to the output device.
+//!
+//! Files are downmixed to stereo when read, so that the effects of the player only process two channels, and
+//! the stereo signal is mapped to the channels of the device when output.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use vizia::prelude::*;
+
+/// The layout of the channels of a file, in the channel order of WAV files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    Mono,
+    Stereo,
+    // Front left and right, back left and right
+    Quad,
+    // Front left, right and centre, LFE, back left and right
+    Surround51,
+    // Front left, right and centre, LFE, back left and right, side left and right
+    Surround71,
+    // First order ambisonics in the AmbiX format, with the W, Y, Z and X channels
+    Ambisonic,
+    // Channels without a known layout, which are sent alternately to the left and right
+    Discrete(usize),
+}
+
+impl ChannelLayout {
+    /// The layout of a file with `num_channels` channels, four channels being first order ambisonics rather than
+    /// quad when `ambisonic` is set.
+    pub fn new(num_channels: usize, ambisonic: bool) -> Self {
+        match num_channels {
+            1 => Self::Mono,
+            2 => Self::Stereo,
+            4 if ambisonic => Self::Ambisonic,
+            4 => Self::Quad,
+            6 => Self::Surround51,
+            8 => Self::Surround71,
+            _ => Self::Discrete(num_channels),
+        }
+    }
+
+    /// The gains of the `channel` in the left and right channels of the stereo downmix.
+    pub fn stereo_gains(&self, channel: usize) -> [f32; 2] {
+        // Centre and surround channels are mixed at -3 dB, and the LFE is dropped
+        const HALF: f32 = FRAC_1_SQRT_2;
+        match (self, channel) {
+            (Self::Mono, 0) => [1.0, 1.0],
+            (Self::Stereo | Self::Quad | Self::Surround51 | Self::Surround71, 0) => [1.0, 0.0],
+            (Self::Stereo | Self::Quad | Self::Surround51 | Self::Surround71, 1) => [0.0, 1.0],
+            (Self::Quad, 2) => [HALF, 0.0],
+            (Self::Quad, 3) => [0.0, HALF],
+            (Self::Surround51 | Self::Surround71, 2) => [HALF, HALF],
+            (Self::Surround51 | Self::Surround71, 4 | 6) => [HALF, 0.0],
+            (Self::Surround51 | Self::Surround71, 5 | 7) => [0.0, HALF],
+            // Two virtual cardioid microphones pointing left and right
+            (Self::Ambisonic, 0) => [0.5, 0.5],
+            (Self::Ambisonic, 1) => [0.5, -0.5],
+            (Self::Discrete(num_channels), channel) if channel < *num_channels => {
+                let gain = 1.0 / num_channels.div_ceil(2) as f32;
+                if channel % 2 == 0 {
+                    [gain, 0.0]
+                } else {
+                    [0.0, gain]
+                }
+            }
+            _ => [0.0, 0.0],
+        }
+    }
+
+    /// Mix the `channels` of a file into the `left` and `right` channels, which are as long as the channels.
+    pub fn downmix<'a>(
+        &self,
+        channels: impl Iterator<Item = &'a [f32]>,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        left.fill(0.0);
+        right.fill(0.0);
+
+        for (channel, samples) in channels.enumerate() {
+            let [left_gain, right_gain] = self.stereo_gains(channel);
+            if left_gain == 0.0 && right_gain == 0.0 {
+                continue;
+            }
+
+            for ((sample, left), right) in samples.iter().zip(left.iter_mut()).zip(right.iter_mut())
+            {
+                *left += sample * left_gain;
+                *right += sample * right_gain;
+            }
+        }
+    }
+}
+
+/// The part of the stereo signal which is previewed on both channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Data)]
+pub enum ChannelSolo {
+    #[default]
+    Both,
+    Left,
+    Right,
+    // The sum of the channels
+    Mid,
+    // The difference of the channels
+    Side,
+}
+
+impl ChannelSolo {
+    pub fn apply(&self, left: f32, right: f32) -> (f32, f32) {
+        match self {
+            Self::Both => (left, right),
+            Self::Left => (left, left),
+            Self::Right => (right, right),
+            Self::Mid => ((left + right) * 0.5, (left + right) * 0.5),
+            Self::Side => ((left - right) * 0.5, (left - right) * 0.5),
+        }
+    }
+}
+
+/// Write a stereo frame to a `frame` of the output device, averaging the channels on a mono device and leaving
+/// the channels after the front left and right silent on a surround device.
+pub fn write_frame(left: f32, right: f32, frame: &mut [f32]) {
+    match frame {
+        [] => {}
+        [mono] => *mono = (left + right) * 0.5,
+        [front_left, front_right, rest @ ..] => {
+            *front_left = left;
+            *front_right = right;
+            rest.fill(0.0);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Non-destructive effects applied to previews: reverse, a low- or high-pass filter and fades.
+
+use std::f32::consts::PI;
+
+use vizia::prelude::*;
+
+// The range of the filter cutoff in Hz
+pub const MIN_CUTOFF: f32 = 20.0;
+pub const MAX_CUTOFF: f32 = 20000.0;
+
+// The longest fade in seconds
+pub const MAX_FADE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum FilterType {
+    LowPass,
+    HighPass,
+}
+
+/// The effects applied to the preview of a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct PreviewEffects {
+    pub reverse: bool,
+    pub filter: Option<FilterType>,
+    // The cutoff of the filter in Hz
+    pub cutoff: f32,
+    // The length of the fades at the start and end of the sample in seconds
+    pub fade_in: f32,
+    pub fade_out: f32,
+}
+
+impl Default for PreviewEffects {
+    fn default() -> Self {
+        Self { reverse: false, filter: None, cutoff: 1000.0, fade_in: 0.0, fade_out: 0.0 }
+    }
+}
+
+/// A 12 dB per octave low- or high-pass filter of a stereo signal.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    filter_type: FilterType,
+    cutoff: f32,
+    // The sample rate for which the coefficients were computed
+    sample_rate: f32,
+    b: [f32; 3],
+    a: [f32; 2],
+    // The last two inputs and outputs of each channel
+    state: [[f32; 4]; 2],
+}
+
+impl Filter {
+    pub fn new(filter_type: FilterType, cutoff: f32) -> Self {
+        Self {
+            filter_type,
+            cutoff,
+            sample_rate: 0.0,
+            b: [1.0, 0.0, 0.0],
+            a: [0.0; 2],
+            state: [[0.0; 4]; 2],
+        }
+    }
+
+    /// Change the type and cutoff, keeping the state so that the change does not click.
+    pub fn set(&mut self, filter_type: FilterType, cutoff: f32) {
+        if (filter_type, cutoff) != (self.filter_type, self.cutoff) {
+            self.filter_type = filter_type;
+            self.cutoff = cutoff;
+            self.sample_rate = 0.0;
+        }
+    }
+
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32], sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.update_coefficients(sample_rate);
+        }
+
+        let (b, a) = (self.b, self.a);
+        for (samples, state) in [left, right].into_iter().zip(self.state.iter_mut()) {
+            let [x1, x2, y1, y2] = state;
+            for sample in samples.iter_mut() {
+                let y = b[0] * *sample + b[1] * *x1 + b[2] * *x2 - a[0] * *y1 - a[1] * *y2;
+                (*x2, *x1, *y2, *y1) = (*x1, *sample, *y1, y);
+                *sample = y;
+            }
+        }
+    }
+
+    // The coefficients of a Butterworth biquad, from the Audio EQ Cookbook.
+    fn update_coefficients(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        let cutoff = self.cutoff.clamp(MIN_CUTOFF, 0.45 * sample_rate);
+        let omega = 2.0 * PI * cutoff / sample_rate;
+        let alpha = omega.sin() / 2.0f32.sqrt();
+        let cos = omega.cos();
+
+        let a0 = 1.0 + alpha;
+        let b = match self.filter_type {
+            FilterType::LowPass => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            FilterType::HighPass => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+        };
+        self.b = b.map(|b| b / a0);
+        self.a = [-2.0 * cos / a0, (1.0 - alpha) / a0];
+    }
+}
+
+/// The gain of a frame `from_start` frames after the start of a sample and `to_end` frames before its end,
+/// with linear fades of the given lengths in frames.
+pub fn fade_gain(from_start: usize, to_end: usize, fade_in: usize, fade_out: usize) -> f32 {
+    let ramp = |frames: usize, length: usize| match length {
+        0 => 1.0,
+        _ => (frames as f32 / length as f32).min(1.0),
+    };
+    ramp(from_start, fade_in) * ramp(to_end, fade_out)
+}
@@ -0,0 +1,413 @@
+//! Offline export of files and regions of files to WAV, converted to another sample rate and bit depth,
+//! optionally normalized and with the preview effects applied.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use creek::{ReadDiskStream, SymphoniaDecoder};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rubato::{FftFixedIn, Resampler};
+use symphonia::core::codecs::*;
+use thiserror::Error;
+use vizia::prelude::*;
+
+use super::effects::{fade_gain, Filter, PreviewEffects};
+
+// The most frames read from the disk stream at once
+const MAX_READ_FRAMES: usize = 4096;
+
+// The number of input frames resampled at once
+const RESAMPLE_CHUNK: usize = 1024;
+
+/// The sample rates offered for exports.
+pub const EXPORT_SAMPLE_RATES: [u32; 5] = [22050, 44100, 48000, 88200, 96000];
+
+/// The sample format of exported files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Data)]
+pub enum BitDepth {
+    Int16,
+    #[default]
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    pub fn bits(&self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+}
+
+/// How files are converted when exported.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct ExportSettings {
+    // The sample rate of the exported files, `None` to keep that of each file
+    pub sample_rate: Option<u32>,
+    pub bit_depth: BitDepth,
+    // Whether integer samples are dithered when the bit depth is reduced, as it is for floating point and lossy
+    // files and for files which are resampled, normalized or have effects applied
+    pub dither: bool,
+    // The peak level in dBFS to which the files are normalized, `None` to keep their level
+    pub normalize: Option<f32>,
+    // The preview effects applied to the files, `None` to export them dry
+    pub effects: Option<PreviewEffects>,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate: None,
+            bit_depth: BitDepth::default(),
+            dither: true,
+            normalize: None,
+            effects: None,
+        }
+    }
+}
+
+/// A file to export, or the frames between `start` and `end` of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportSource {
+    pub path: PathBuf,
+    pub region: Option<(usize, usize)>,
+}
+
+impl ExportSource {
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), region: None }
+    }
+
+    pub fn region(path: impl Into<PathBuf>, start: usize, end: usize) -> Self {
+        Self { path: path.into(), region: Some((start, end)) }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("could not decode {0}")]
+    Decode(PathBuf),
+    #[error("nothing to export in {0}")]
+    Empty(PathBuf),
+    #[error("{0} would overwrite the file it is exported from")]
+    Overwrite(PathBuf),
+    #[error("could not resample: {0}")]
+    Resample(String),
+    #[error(transparent)]
+    Write(#[from] hound::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// Audio decoded for export, one buffer per channel, and the bit depth of the integer samples it was decoded
+// from, `None` once they are changed or if they were not integers
+struct ExportAudio {
+    channels: Vec<Vec<f32>>,
+    sample_rate: u32,
+    bits: Option<u16>,
+}
+
+impl ExportAudio {
+    fn len(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+}
+
+/// Export `source` to a WAV file at `destination` with the given settings, replacing any file there other than
+/// the source.
+pub fn export(
+    source: &ExportSource,
+    destination: &Path,
+    settings: &ExportSettings,
+) -> Result<(), ExportError> {
+    if is_same_file(&source.path, destination) {
+        return Err(ExportError::Overwrite(destination.to_path_buf()));
+    }
+
+    let audio = render(source, settings)?;
+    write_wav(&audio, destination, settings)
+}
+
+/// Export each of `sources` to a WAV file in `directory` named after `template`, see [`file_name`]. Files already
+/// in the directory are kept, the exported file being numbered instead. Returns the path of each exported file or
+/// the error which stopped its export, in the order of the sources.
+pub fn export_batch(
+    sources: &[ExportSource],
+    directory: &Path,
+    template: &str,
+    settings: &ExportSettings,
+) -> Vec<Result<PathBuf, ExportError>> {
+    let mut used = HashSet::new();
+    sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| {
+            let audio = render(source, settings)?;
+            let stem = source.path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            let name = file_name(template, stem, index + 1, sources.len(), audio.sample_rate);
+            let name = unique_name(&mut used, directory, name);
+
+            std::fs::create_dir_all(directory)?;
+            let destination = directory.join(format!("{}.wav", name));
+            write_wav(&audio, &destination, settings)?;
+            Ok(destination)
+        })
+        .collect()
+}
+
+/// The name without extension of the file exported from the file named `stem`, the `index`th of `count` files
+/// exported at `sample_rate`. The template may contain `{name}` for the name of the file, `{index}` for its
+/// position in the batch, counting from 1 and padded to the width of the count, and `{rate}` for the sample
+/// rate.
+pub fn file_name(
+    template: &str,
+    stem: &str,
+    index: usize,
+    count: usize,
+    sample_rate: u32,
+) -> String {
+    let width = count.to_string().len();
+    let name = template
+        .replace("{name}", stem)
+        .replace("{index}", &format!("{:0width$}", index, width = width))
+        .replace("{rate}", &sample_rate.to_string())
+        .replace(['/', '\\'], "_");
+
+    match name.trim() {
+        "" => stem.to_string(),
+        name => name.to_string(),
+    }
+}
+
+// `name`, followed by a number if a file of the batch or a file in `directory` already has that name
+fn unique_name(used: &mut HashSet<String>, directory: &Path, name: String) -> String {
+    let mut unique = name.clone();
+    let mut number = 2;
+    while used.contains(&unique) || directory.join(format!("{}.wav", unique)).exists() {
+        unique = format!("{}-{}", name, number);
+        number += 1;
+    }
+    used.insert(unique.clone());
+    unique
+}
+
+// Decode `source` and apply the effects, sample rate conversion and normalization of `settings`.
+fn render(source: &ExportSource, settings: &ExportSettings) -> Result<ExportAudio, ExportError> {
+    let mut audio = decode(source).ok_or_else(|| ExportError::Decode(source.path.clone()))?;
+    if audio.len() == 0 {
+        return Err(ExportError::Empty(source.path.clone()));
+    }
+
+    if let Some(effects) = settings.effects {
+        apply_effects(&mut audio, &effects);
+        audio.bits = None;
+    }
+
+    if let Some(sample_rate) = settings.sample_rate {
+        if sample_rate != audio.sample_rate {
+            audio = resample(&audio, sample_rate)?;
+        }
+    }
+
+    if let Some(level) = settings.normalize {
+        normalize(&mut audio, level);
+        audio.bits = None;
+    }
+
+    Ok(audio)
+}
+
+// Decode the region of the source, or the whole file, keeping all of its channels.
+fn decode(source: &ExportSource) -> Option<ExportAudio> {
+    let (start, end) = source.region.unwrap_or((0, usize::MAX));
+    let mut read_stream =
+        ReadDiskStream::<SymphoniaDecoder>::new(&source.path, start, Default::default()).ok()?;
+    let info = read_stream.info();
+    let (num_channels, sample_rate) =
+        (info.num_channels as usize, info.sample_rate.unwrap_or(44100));
+    let end = end.min(info.num_frames);
+    let bits = integer_bits(&info.params.codec_params);
+
+    let mut audio = ExportAudio { channels: vec![Vec::new(); num_channels], sample_rate, bits };
+    let mut position = start;
+    while position < end {
+        read_stream.block_until_ready().ok()?;
+        let read_data = read_stream.read(MAX_READ_FRAMES.min(end - position)).ok()?;
+        if read_data.num_frames() == 0 {
+            break;
+        }
+
+        for (channel, samples) in audio.channels.iter_mut().enumerate() {
+            samples.extend_from_slice(read_data.read_channel(channel));
+        }
+        position += read_data.num_frames();
+    }
+
+    Some(audio)
+}
+
+// Reverse, filter and fade the audio like the preview.
+fn apply_effects(audio: &mut ExportAudio, effects: &PreviewEffects) {
+    if effects.reverse {
+        audio.channels.iter_mut().for_each(|samples| samples.reverse());
+    }
+
+    if let Some(filter_type) = effects.filter {
+        // Filter the channels in pairs, a mono or last odd channel on its own
+        for pair in audio.channels.chunks_mut(2) {
+            let (left, right) = pair.split_at_mut(1);
+            let right = right.first_mut().map_or(&mut [][..], |right| right.as_mut_slice());
+            Filter::new(filter_type, effects.cutoff).process(
+                &mut left[0],
+                right,
+                audio.sample_rate as f32,
+            );
+        }
+    }
+
+    let len = audio.len();
+    let fade_frames = |seconds: f32| (seconds * audio.sample_rate as f32) as usize;
+    let (fade_in, fade_out) = (fade_frames(effects.fade_in), fade_frames(effects.fade_out));
+    if fade_in > 0 || fade_out > 0 {
+        for samples in audio.channels.iter_mut() {
+            for (frame, sample) in samples.iter_mut().enumerate() {
+                *sample *= fade_gain(frame, len - 1 - frame, fade_in, fade_out);
+            }
+        }
+    }
+}
+
+// Convert the audio to `sample_rate`, keeping its length in seconds.
+fn resample(audio: &ExportAudio, sample_rate: u32) -> Result<ExportAudio, ExportError> {
+    let num_channels = audio.channels.len();
+    let mut resampler = FftFixedIn::<f32>::new(
+        audio.sample_rate as usize,
+        sample_rate as usize,
+        RESAMPLE_CHUNK,
+        2,
+        num_channels,
+    )
+    .map_err(|err| ExportError::Resample(err.to_string()))?;
+
+    // The resampler delays its output, so it is fed with silence past the end until the delayed end is out
+    let len = audio.len();
+    let output_len = (len as u64 * sample_rate as u64).div_ceil(audio.sample_rate as u64) as usize;
+    let delay = resampler.output_delay();
+    let mut channels = vec![Vec::with_capacity(output_len + delay); num_channels];
+    let mut position = 0;
+    while channels[0].len() < output_len + delay {
+        let end = (position + resampler.input_frames_next()).min(len);
+        let input: Vec<&[f32]> =
+            audio.channels.iter().map(|samples| &samples[position..end]).collect();
+        let input = (end > position).then_some(input.as_slice());
+        let output = resampler
+            .process_partial(input, None)
+            .map_err(|err| ExportError::Resample(err.to_string()))?;
+        for (samples, output) in channels.iter_mut().zip(output) {
+            samples.extend_from_slice(&output);
+        }
+        position = end;
+    }
+
+    for samples in channels.iter_mut() {
+        samples.drain(..delay);
+        samples.truncate(output_len);
+    }
+    Ok(ExportAudio { channels, sample_rate, bits: None })
+}
+
+// Scale the audio so that its peak is at `level` dBFS, leaving silence silent.
+fn normalize(audio: &mut ExportAudio, level: f32) {
+    let peak = audio.channels.iter().flatten().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    if peak > 0.0 {
+        let gain = 10.0f32.powf(level / 20.0) / peak;
+        audio.channels.iter_mut().flatten().for_each(|sample| *sample *= gain);
+    }
+}
+
+fn write_wav(
+    audio: &ExportAudio,
+    destination: &Path,
+    settings: &ExportSettings,
+) -> Result<(), ExportError> {
+    let spec = WavSpec {
+        channels: audio.channels.len() as u16,
+        sample_rate: audio.sample_rate,
+        bits_per_sample: settings.bit_depth.bits(),
+        sample_format: match settings.bit_depth {
+            BitDepth::Float32 => SampleFormat::Float,
+            _ => SampleFormat::Int,
+        },
+    };
+
+    let mut writer = WavWriter::create(destination, spec)?;
+    let scale = ((1u32 << (spec.bits_per_sample - 1)) - 1) as f32;
+    let reduced = audio.bits.is_none_or(|bits| bits > spec.bits_per_sample);
+    let mut dither = Dither::default();
+    for frame in 0..audio.len() {
+        for samples in audio.channels.iter() {
+            let sample = samples[frame];
+            match settings.bit_depth {
+                BitDepth::Float32 => writer.write_sample(sample)?,
+                _ => {
+                    let noise = if settings.dither && reduced { dither.noise() } else { 0.0 };
+                    let value = (sample * scale + noise).round().clamp(-scale - 1.0, scale);
+                    writer.write_sample(value as i32)?
+                }
+            }
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+// The bit depth of the integer samples of a file, `None` for floating point and lossy files.
+fn integer_bits(codec_params: &CodecParameters) -> Option<u16> {
+    match codec_params.codec {
+        CODEC_TYPE_PCM_F32LE
+        | CODEC_TYPE_PCM_F32LE_PLANAR
+        | CODEC_TYPE_PCM_F32BE
+        | CODEC_TYPE_PCM_F32BE_PLANAR
+        | CODEC_TYPE_PCM_F64LE
+        | CODEC_TYPE_PCM_F64LE_PLANAR
+        | CODEC_TYPE_PCM_F64BE
+        | CODEC_TYPE_PCM_F64BE_PLANAR => None,
+        _ => codec_params.bits_per_sample.map(|bits| bits as u16),
+    }
+}
+
+// Whether `destination` is the file at `path`, under another name or not.
+fn is_same_file(path: &Path, destination: &Path) -> bool {
+    match (path.canonicalize(), destination.canonicalize()) {
+        (Ok(path), Ok(destination)) => path == destination,
+        _ => false,
+    }
+}
+
+// Triangular dither of one least significant bit of peak amplitude, from a xorshift generator so that exports
+// are reproducible.
+struct Dither {
+    state: u32,
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self { state: 0x9e37_79b9 }
+    }
+}
+
+impl Dither {
+    fn noise(&mut self) -> f32 {
+        self.random() - self.random()
+    }
+
+    // A random number between 0 and 1
+    fn random(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f32 / u32::MAX as f32
+    }
+}
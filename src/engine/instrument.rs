@@ -0,0 +1,158 @@
+//! Sampler instrument definitions, in the SFZ and Decent Sampler formats, mapping samples across the keyboard
+//! by their root notes.
+
+use std::path::{Component, Path, PathBuf};
+
+/// A sample to map into an instrument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentSample {
+    pub path: PathBuf,
+    // The MIDI note at which the sample plays at its original pitch
+    pub root_note: u8,
+    // The correction in cents which brings the sample in tune with its root note
+    pub tune: i32,
+    // The first frame of the loop and the frame after its last frame
+    pub loop_points: Option<(u64, u64)>,
+    // The peak level of the sample, from 0 to 1
+    pub level: f32,
+}
+
+/// A sample mapped to a range of keys and velocities, both inclusive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentZone {
+    pub sample: InstrumentSample,
+    pub keys: (u8, u8),
+    pub velocities: (u8, u8),
+}
+
+/// Map the samples across the keyboard. Each root note plays up to halfway to the next, the lowest and highest
+/// extending to the ends of the keyboard, and samples sharing a root note split the velocity range into layers
+/// from the softest to the loudest, by their levels.
+pub fn map_instrument(mut samples: Vec<InstrumentSample>) -> Vec<InstrumentZone> {
+    samples.sort_by(|a, b| {
+        a.root_note
+            .cmp(&b.root_note)
+            .then_with(|| a.level.total_cmp(&b.level))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut roots: Vec<u16> = samples.iter().map(|sample| sample.root_note as u16).collect();
+    roots.dedup();
+
+    let mut zones = Vec::with_capacity(samples.len());
+    let layers = samples.chunk_by(|a, b| a.root_note == b.root_note);
+    for (index, layer) in layers.enumerate() {
+        let root = roots[index];
+        let low_key = index.checked_sub(1).map_or(0, |previous| (roots[previous] + root) / 2 + 1);
+        let high_key = roots.get(index + 1).map_or(127, |next| (root + next) / 2);
+
+        let count = layer.len();
+        for (layer_index, sample) in layer.iter().enumerate() {
+            let low_velocity = if layer_index == 0 { 1 } else { 127 * layer_index / count + 1 };
+            let high_velocity = 127 * (layer_index + 1) / count;
+            zones.push(InstrumentZone {
+                sample: sample.clone(),
+                keys: (low_key as u8, high_key as u8),
+                velocities: (low_velocity as u8, high_velocity as u8),
+            });
+        }
+    }
+    zones
+}
+
+/// The SFZ definition of the zones, referring to the samples relative to `directory`, where the definition is
+/// written. Loop ends are written inclusive, as SFZ expects.
+pub fn sfz(zones: &[InstrumentZone], directory: &Path) -> String {
+    let mut text = String::from("// Generated by Vizia Sample Browser\n\n<group>\n");
+    for zone in zones {
+        let sample = &zone.sample;
+        text.push_str("\n<region>\n");
+        text.push_str(&format!("sample={}\n", relative_path(&sample.path, directory)));
+        text.push_str(&format!("pitch_keycenter={}\n", sample.root_note));
+        text.push_str(&format!("lokey={} hikey={}\n", zone.keys.0, zone.keys.1));
+        text.push_str(&format!("lovel={} hivel={}\n", zone.velocities.0, zone.velocities.1));
+        if sample.tune != 0 {
+            text.push_str(&format!("tune={}\n", sample.tune));
+        }
+        if let Some((start, end)) = sample.loop_points {
+            text.push_str("loop_mode=loop_continuous\n");
+            text.push_str(&format!("loop_start={} loop_end={}\n", start, end - 1));
+        }
+    }
+    text
+}
+
+/// The Decent Sampler preset of the zones, referring to the samples relative to `directory`, where the preset
+/// is written. Tunings are written in semitones and loop ends inclusive.
+pub fn dspreset(zones: &[InstrumentZone], directory: &Path) -> String {
+    let mut text = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<DecentSampler minVersion=\"1.0.0\">\n  <groups>\n    <group>\n",
+    );
+    for zone in zones {
+        let sample = &zone.sample;
+        text.push_str(&format!(
+            "      <sample path=\"{}\" rootNote=\"{}\" loNote=\"{}\" hiNote=\"{}\" loVel=\"{}\" hiVel=\"{}\"",
+            escape_xml(&relative_path(&sample.path, directory)),
+            sample.root_note,
+            zone.keys.0,
+            zone.keys.1,
+            zone.velocities.0,
+            zone.velocities.1,
+        ));
+        if sample.tune != 0 {
+            text.push_str(&format!(" tuning=\"{:.2}\"", sample.tune as f32 / 100.0));
+        }
+        if let Some((start, end)) = sample.loop_points {
+            text.push_str(&format!(
+                " loopEnabled=\"true\" loopStart=\"{}\" loopEnd=\"{}\"",
+                start,
+                end - 1
+            ));
+        }
+        text.push_str("/>\n");
+    }
+    text.push_str("    </group>\n  </groups>\n</DecentSampler>\n");
+    text
+}
+
+/// Write the SFZ definition of the zones to `path` and, if `with_dspreset` is set, a Decent Sampler preset
+/// with the same name next to it. Returns the paths of the written files.
+pub fn export_instrument(
+    zones: &[InstrumentZone],
+    path: &Path,
+    with_dspreset: bool,
+) -> std::io::Result<Vec<PathBuf>> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let path = path.with_extension("sfz");
+    std::fs::write(&path, sfz(zones, directory))?;
+    let mut written = vec![path.clone()];
+
+    if with_dspreset {
+        let preset = path.with_extension("dspreset");
+        std::fs::write(&preset, dspreset(zones, directory))?;
+        written.push(preset);
+    }
+    Ok(written)
+}
+
+// The path of `path` relative to `directory` with forward slashes, or the whole path if they share no root.
+fn relative_path(path: &Path, directory: &Path) -> String {
+    let common = path.components().zip(directory.components()).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+
+    let parents = directory.components().skip(common).filter(|c| *c != Component::CurDir).count();
+    std::iter::repeat_n(String::from(".."), parents)
+        .chain(path.components().skip(common).map(|c| c.as_os_str().to_string_lossy().into_owned()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
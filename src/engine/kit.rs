@@ -0,0 +1,124 @@
+//! Export of drum kits as a folder of files named after their slots, with a manifest describing the kit.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::export::{export, BitDepth, ExportError, ExportSettings, ExportSource};
+
+/// The name of the manifest written into the folder of an exported kit.
+pub const KIT_MANIFEST_NAME: &str = "kit.ron";
+
+/// A slot of a kit to export, and the file filling it if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KitExportSlot {
+    pub name: String,
+    pub path: Option<PathBuf>,
+}
+
+/// The description of an exported kit, written next to its files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KitManifest {
+    pub name: String,
+    // The sample rate of the files, `None` if each kept its own
+    pub sample_rate: Option<u32>,
+    pub bit_depth: u16,
+    pub float: bool,
+    // The peak level in dBFS to which the files were normalized, `None` if they kept their level
+    pub normalize: Option<f32>,
+    pub slots: Vec<KitManifestSlot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KitManifestSlot {
+    pub name: String,
+    // The name of the exported file in the folder of the kit, `None` for an empty slot or one which failed to
+    // export
+    pub file: Option<String>,
+    // The file the slot was exported from
+    pub source: Option<PathBuf>,
+}
+
+/// The outcome of the export of a kit.
+#[derive(Debug)]
+pub struct KitExport {
+    // The folder the kit was written into
+    pub folder: PathBuf,
+    // The name of each slot which failed to export, and the error which stopped it
+    pub errors: Vec<(String, ExportError)>,
+}
+
+/// Export the kit `name` into a new folder named after it in `directory`, each filled slot converted with the
+/// given settings to a file named after its position and name, such as `01 Kick.wav`, along with a manifest.
+/// The folder is numbered if one with that name already exists. A slot which fails to export is left out of the
+/// folder, and only errors creating the folder or writing the manifest stop the export.
+pub fn export_kit(
+    name: &str,
+    slots: &[KitExportSlot],
+    directory: &Path,
+    settings: &ExportSettings,
+) -> Result<KitExport, ExportError> {
+    let folder = new_folder(directory, &sanitize(name, "Kit"));
+    std::fs::create_dir_all(&folder)?;
+
+    let width = slots.len().to_string().len().max(2);
+    let mut manifest = KitManifest {
+        name: name.to_string(),
+        sample_rate: settings.sample_rate,
+        bit_depth: settings.bit_depth.bits(),
+        float: settings.bit_depth == BitDepth::Float32,
+        normalize: settings.normalize,
+        slots: Vec::with_capacity(slots.len()),
+    };
+    let mut errors = Vec::new();
+    for (index, slot) in slots.iter().enumerate() {
+        let file = match &slot.path {
+            Some(path) => {
+                let file = format!(
+                    "{:0width$} {}.wav",
+                    index + 1,
+                    sanitize(&slot.name, "Slot"),
+                    width = width
+                );
+                match export(&ExportSource::file(path), &folder.join(&file), settings) {
+                    Ok(()) => Some(file),
+                    Err(err) => {
+                        errors.push((slot.name.clone(), err));
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        manifest.slots.push(KitManifestSlot {
+            name: slot.name.clone(),
+            file,
+            source: slot.path.clone(),
+        });
+    }
+
+    let text = ron::ser::to_string_pretty(&manifest, ron::ser::PrettyConfig::default())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    std::fs::write(folder.join(KIT_MANIFEST_NAME), text)?;
+    Ok(KitExport { folder, errors })
+}
+
+// The folder named `name` in `directory`, followed by a number if a file or folder already has that name
+fn new_folder(directory: &Path, name: &str) -> PathBuf {
+    let mut folder = directory.join(name);
+    let mut number = 2;
+    while folder.exists() {
+        folder = directory.join(format!("{}-{}", name, number));
+        number += 1;
+    }
+    folder
+}
+
+// `name` made safe to use as a file name, or `fallback` if nothing is left of it
+fn sanitize(name: &str, fallback: &str) -> String {
+    let name = name.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+    match name.trim() {
+        "" => fallback.to_string(),
+        name => name.to_string(),
+    }
+}
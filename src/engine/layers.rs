@@ -0,0 +1,76 @@
+//! Samples layered over the preview, so that several samples can be auditioned together.
+
+use basedrop::Owned;
+use vizia::prelude::*;
+
+use super::DecodedAudio;
+
+/// The most samples layered over the preview at once.
+pub const MAX_LAYERS: usize = 8;
+
+// The range of the gain of a layer in dB
+pub const MIN_LAYER_GAIN: f32 = -24.0;
+pub const MAX_LAYER_GAIN: f32 = 6.0;
+
+/// How a layer is mixed with the preview.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct LayerMix {
+    // The gain in dB
+    pub gain: f32,
+    // The balance between the channels, from -1 for the left channel only to 1 for the right channel only
+    pub pan: f32,
+    pub muted: bool,
+}
+
+impl Default for LayerMix {
+    fn default() -> Self {
+        Self { gain: 0.0, pan: 0.0, muted: false }
+    }
+}
+
+impl LayerMix {
+    /// The linear gains of the left and right channels of the layer.
+    pub fn channel_gains(&self) -> [f32; 2] {
+        if self.muted {
+            return [0.0, 0.0];
+        }
+
+        let gain = 10.0f32.powf(self.gain / 20.0);
+        [gain * (1.0 - self.pan).min(1.0), gain * (1.0 + self.pan).min(1.0)]
+    }
+}
+
+/// A sample layered over the preview, played once from memory from its start each time the preview starts.
+pub struct Layer {
+    audio: Owned<DecodedAudio>,
+    pub mix: LayerMix,
+    // The position in frames of the sample, which is played at its own sample rate
+    position: f64,
+}
+
+impl Layer {
+    pub fn new(audio: Owned<DecodedAudio>, mix: LayerMix) -> Self {
+        Self { audio, mix, position: 0.0 }
+    }
+
+    pub fn restart(&mut self) {
+        self.position = 0.0;
+    }
+
+    /// Add the next frames of the sample to the interleaved stereo `buffer`, resampled to the output
+    /// `sample_rate` by linear interpolation. Muted layers keep their position, so that they stay in time.
+    pub fn mix_into(&mut self, buffer: &mut [f32], sample_rate: f64) {
+        let [left_gain, right_gain] = self.mix.channel_gains();
+        let step = self.audio.sample_rate as f64 / sample_rate;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            let Some((left, right)) = self.audio.frame_at(self.position) else {
+                break;
+            };
+
+            frame[0] += left * left_gain;
+            frame[1] += right * right_gain;
+            self.position += step;
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! The levels and spectrum of the output, published from the audio thread to the UI without locking.
+
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use ringbuf::traits::{Consumer, Observer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+// The length of the FFT of the spectrum analyzer
+const SPECTRUM_SIZE: usize = 2048;
+
+// The number of samples queued for the spectrum analyzer before new ones are dropped
+const TAP_CAPACITY: usize = 8192;
+
+/// The number of log-spaced bands of the spectrum, from `MIN_BAND_FREQUENCY` to the Nyquist frequency.
+pub const SPECTRUM_BANDS: usize = 32;
+const MIN_BAND_FREQUENCY: f32 = 30.0;
+
+/// The lowest level shown by the meters and the spectrum in dB, to which silence is clamped.
+pub const MIN_LEVEL_DB: f32 = -60.0;
+
+/// The peak and RMS level of each output channel over the last block, stored as the bits of `f32`s.
+#[derive(Debug, Default)]
+pub struct Levels {
+    peak: [AtomicU32; 2],
+    rms: [AtomicU32; 2],
+}
+
+impl Levels {
+    /// Store the levels of a block of interleaved stereo frames.
+    pub fn measure(&self, buffer: &[f32]) {
+        for channel in 0..2 {
+            let samples = buffer.iter().skip(channel).step_by(2);
+            let (peak, sum) = samples.fold((0.0f32, 0.0f32), |(peak, sum), sample| {
+                (peak.max(sample.abs()), sum + sample * sample)
+            });
+            let rms = (sum / (buffer.len() / 2).max(1) as f32).sqrt();
+
+            self.peak[channel].store(peak.to_bits(), Ordering::Relaxed);
+            self.rms[channel].store(rms.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn reset(&self) {
+        for level in self.peak.iter().chain(self.rms.iter()) {
+            level.store(0.0f32.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// The linear peak and RMS levels of the left and right channels.
+    pub fn get(&self) -> ([f32; 2], [f32; 2]) {
+        let load = |level: &AtomicU32| f32::from_bits(level.load(Ordering::Relaxed));
+        ([load(&self.peak[0]), load(&self.peak[1])], [load(&self.rms[0]), load(&self.rms[1])])
+    }
+}
+
+/// A level in dB, clamped to `MIN_LEVEL_DB`.
+pub fn level_db(level: f32) -> f32 {
+    (20.0 * level.log10()).max(MIN_LEVEL_DB)
+}
+
+/// Create the queue through which the player sends its mono output to a spectrum analyzer.
+pub fn spectrum_tap() -> (HeapProd<f32>, SpectrumAnalyzer) {
+    let (producer, consumer) = HeapRb::new(TAP_CAPACITY).split();
+    (producer, SpectrumAnalyzer::new(consumer))
+}
+
+/// Computes the spectrum of the latest output of the player, on the UI thread.
+pub struct SpectrumAnalyzer {
+    consumer: HeapCons<f32>,
+    // The latest samples, oldest first
+    history: Vec<f32>,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+}
+
+impl SpectrumAnalyzer {
+    fn new(consumer: HeapCons<f32>) -> Self {
+        let window = (0..SPECTRUM_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_SIZE as f32).cos())
+            .collect();
+
+        Self {
+            consumer,
+            history: vec![0.0; SPECTRUM_SIZE],
+            fft: FftPlanner::new().plan_fft_forward(SPECTRUM_SIZE),
+            window,
+            buffer: vec![Complex::default(); SPECTRUM_SIZE],
+        }
+    }
+
+    /// Discard the queued samples and forget the latest ones.
+    pub fn clear(&mut self) {
+        self.consumer.clear();
+        self.history.fill(0.0);
+    }
+
+    /// Read the samples sent since the last call and return the level in dB of each band of the spectrum of
+    /// the latest samples.
+    pub fn analyze(&mut self, sample_rate: f32) -> Vec<f32> {
+        let new = self.consumer.occupied_len().min(SPECTRUM_SIZE);
+        self.consumer.skip(self.consumer.occupied_len() - new);
+        self.history.drain(..new);
+        self.history.extend(self.consumer.pop_iter().take(new));
+
+        for ((value, sample), window) in self.buffer.iter_mut().zip(&self.history).zip(&self.window)
+        {
+            *value = Complex::new(sample * window, 0.0);
+        }
+        self.fft.process(&mut self.buffer);
+
+        // A full scale sine is at 0 dB, the window halving its amplitude
+        let scale = 4.0 / SPECTRUM_SIZE as f32;
+        let bin_frequency = sample_rate / SPECTRUM_SIZE as f32;
+        let nyquist = sample_rate / 2.0;
+        (0..SPECTRUM_BANDS)
+            .map(|band| {
+                let edge = |band: usize| {
+                    let position = band as f32 / SPECTRUM_BANDS as f32;
+                    MIN_BAND_FREQUENCY * (nyquist / MIN_BAND_FREQUENCY).powf(position)
+                };
+                let low = ((edge(band) / bin_frequency) as usize).min(SPECTRUM_SIZE / 2 - 1);
+                let high =
+                    ((edge(band + 1) / bin_frequency) as usize).clamp(low + 1, SPECTRUM_SIZE / 2);
+
+                let peak =
+                    self.buffer[low..high].iter().map(|value| value.norm()).fold(0.0f32, f32::max);
+                level_db(peak * scale)
+            })
+            .collect()
+    }
+}
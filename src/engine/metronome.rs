@@ -0,0 +1,97 @@
+//! The metronome, which keeps the bars and beats of the project tempo on the output, clicking on each beat when
+//! enabled.
+
+use std::f64::consts::TAU;
+
+// The length and decay time of a click in seconds
+const CLICK_LENGTH: f64 = 0.03;
+const CLICK_DECAY: f64 = 0.006;
+
+// The level of the clicks, and their frequency on the first and the other beats of the bar
+const CLICK_LEVEL: f32 = 0.5;
+const DOWNBEAT_FREQUENCY: f64 = 1760.0;
+const BEAT_FREQUENCY: f64 = 880.0;
+
+/// The clock of the metronome, advanced by each output frame whether or not it clicks.
+pub struct Metronome {
+    // The tempo in quarter notes per minute
+    tempo: f64,
+    beats_per_bar: u32,
+    // The note value of a beat, e.g. 8 for eighth notes
+    beat_unit: u32,
+    click: bool,
+    // The beat of the bar, and the position in frames in the beat
+    beat: u32,
+    position: f64,
+}
+
+impl Default for Metronome {
+    fn default() -> Self {
+        Self { tempo: 120.0, beats_per_bar: 4, beat_unit: 4, click: false, beat: 0, position: 0.0 }
+    }
+}
+
+impl Metronome {
+    /// Set the tempo in quarter notes per minute and the time signature, keeping the position in the bar.
+    pub fn set(&mut self, tempo: f64, beats_per_bar: u32, beat_unit: u32, click: bool) {
+        self.tempo = tempo.max(1.0);
+        self.beats_per_bar = beats_per_bar.max(1);
+        self.beat_unit = beat_unit.max(1);
+        self.beat %= self.beats_per_bar;
+        self.click = click;
+    }
+
+    pub fn clicks(&self) -> bool {
+        self.click
+    }
+
+    /// Start a bar at the next frame.
+    pub fn restart(&mut self) {
+        self.beat = 0;
+        self.position = 0.0;
+    }
+
+    // The length of a beat in frames
+    fn beat_frames(&self, sample_rate: f64) -> f64 {
+        60.0 / self.tempo * 4.0 / self.beat_unit as f64 * sample_rate
+    }
+
+    /// The number of frames before the next bar starts, 0 if the next frame starts a bar.
+    pub fn frames_to_next_bar(&self, sample_rate: f64) -> usize {
+        if self.beat == 0 && self.position == 0.0 {
+            return 0;
+        }
+
+        let beats_left = (self.beats_per_bar - self.beat) as f64;
+        (beats_left * self.beat_frames(sample_rate) - self.position).ceil().max(0.0) as usize
+    }
+
+    /// The click of the next frame, which is silent when the metronome does not click, advancing the clock by
+    /// a frame.
+    pub fn next_frame(&mut self, sample_rate: f64) -> f32 {
+        let click = if self.click {
+            let frequency = if self.beat == 0 { DOWNBEAT_FREQUENCY } else { BEAT_FREQUENCY };
+            click(self.position / sample_rate, frequency)
+        } else {
+            0.0
+        };
+
+        self.position += 1.0;
+        let beat_frames = self.beat_frames(sample_rate);
+        if self.position >= beat_frames {
+            self.position -= beat_frames;
+            self.beat = (self.beat + 1) % self.beats_per_bar;
+        }
+
+        click
+    }
+}
+
+// The click of a beat at `time` seconds after the beat, a decaying sine
+fn click(time: f64, frequency: f64) -> f32 {
+    if time >= CLICK_LENGTH {
+        return 0.0;
+    }
+
+    ((TAU * frequency * time).sin() * (-time / CLICK_DECAY).exp()) as f32 * CLICK_LEVEL
+}
@@ -0,0 +1,94 @@
+//! MIDI input driving the pads. Inputs are polled by the sample player on the audio thread, so that a hardware
+//! controller plays the pads with the latency of the output. A virtual input receives messages sent from the
+//! app, and loops them back in tests.
+
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+
+use super::NUM_PADS;
+
+/// The note of the first pad, the kick drum of the General MIDI drum map, the other pads following it
+/// chromatically.
+pub const FIRST_PAD_NOTE: u8 = 36;
+
+// The number of messages queued in a virtual input before new ones are dropped
+const VIRTUAL_CAPACITY: usize = 256;
+
+/// A MIDI message which plays the pads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8 },
+}
+
+impl MidiMessage {
+    /// Parse a message from its bytes, a note on with a velocity of 0 being a note off. Messages other than
+    /// notes are `None`.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (&status, data) = bytes.split_first()?;
+        let channel = status & 0x0f;
+        match (status & 0xf0, data) {
+            (0x90, &[note, velocity, ..]) if velocity & 0x7f > 0 => {
+                Some(Self::NoteOn { channel, note: note & 0x7f, velocity: velocity & 0x7f })
+            }
+            (0x80 | 0x90, &[note, _, ..]) => Some(Self::NoteOff { channel, note: note & 0x7f }),
+            _ => None,
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; 3] {
+        match self {
+            Self::NoteOn { channel, note, velocity } => [0x90 | channel, note, velocity],
+            Self::NoteOff { channel, note } => [0x80 | channel, note, 0],
+        }
+    }
+
+    /// The pad played by the note of the message, on any channel, if it is one of the pads.
+    pub fn pad(&self) -> Option<usize> {
+        let (Self::NoteOn { note, .. } | Self::NoteOff { note, .. }) = self;
+        let pad = note.checked_sub(FIRST_PAD_NOTE)? as usize;
+        (pad < NUM_PADS).then_some(pad)
+    }
+}
+
+/// A source of MIDI messages, polled on the audio thread, so that polling must not block.
+pub trait MidiInput: Send {
+    /// The name of the input shown to the user, e.g. of its device.
+    fn name(&self) -> &str;
+
+    /// The next message received, or `None` if no more have been received.
+    fn poll(&mut self) -> Option<MidiMessage>;
+}
+
+/// Create a virtual MIDI input, and the output through which messages are sent to it.
+pub fn virtual_midi() -> (VirtualMidiOutput, VirtualMidiInput) {
+    let (tx, rx) = HeapRb::new(VIRTUAL_CAPACITY).split();
+    (VirtualMidiOutput { tx }, VirtualMidiInput { rx })
+}
+
+/// Sends messages to a virtual MIDI input.
+pub struct VirtualMidiOutput {
+    tx: HeapProd<MidiMessage>,
+}
+
+impl VirtualMidiOutput {
+    /// Send the message in `bytes`, returning false if it does not play the pads or the input is full.
+    pub fn send(&mut self, bytes: &[u8]) -> bool {
+        MidiMessage::parse(bytes).is_some_and(|message| self.tx.try_push(message).is_ok())
+    }
+}
+
+/// A MIDI input receiving the messages sent through a `VirtualMidiOutput`.
+pub struct VirtualMidiInput {
+    rx: HeapCons<MidiMessage>,
+}
+
+impl MidiInput for VirtualMidiInput {
+    fn name(&self) -> &str {
+        "Virtual"
+    }
+
+    fn poll(&mut self) -> Option<MidiMessage> {
+        self.rx.try_pop()
+    }
+}
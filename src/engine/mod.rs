@@ -7,8 +7,45 @@ pub use utils::*;
 pub mod audio_stream;
 pub use audio_stream::*;
 
+pub mod channels;
+pub use channels::*;
+
+pub mod effects;
+pub use effects::*;
+
+pub mod export;
+pub use export::*;
+
+pub mod instrument;
+pub use instrument::*;
+
+pub mod kit;
+pub use kit::*;
+
+pub mod layers;
+pub use layers::*;
+
+pub mod meter;
+pub use meter::*;
+
+pub mod metronome;
+pub use metronome::*;
+
+pub mod midi;
+pub use midi::*;
+
+pub mod offline;
+pub use offline::*;
+
+pub mod pads;
+pub use pads::*;
+
 pub mod sample_player;
 pub use sample_player::*;
 
+pub mod time_stretch;
+
 pub mod waveform;
 pub use waveform::*;
+
+mod tests;
@@ -0,0 +1,88 @@
+use super::audio_stream::{AudioBackend, OutputError, OutputInfo, OutputSettings, PlaybackContext};
+use super::Process;
+
+/// The backend running the sample player offline, without an audio device, which renders its output in blocks
+/// of a fixed size on demand. Reads from disk are waited for, so the output does not depend on timing.
+pub struct OfflineBackend {
+    process: Process,
+    info: Option<OutputInfo>,
+    // The number of frames rendered by each call of the player
+    block_size: usize,
+    block: Vec<f32>,
+}
+
+impl OfflineBackend {
+    pub fn new(
+        mut process: Process,
+        sample_rate: u32,
+        num_channels: u16,
+        block_size: usize,
+    ) -> Self {
+        process.set_wait_for_disk(true);
+
+        Self {
+            process,
+            info: Some(OutputInfo {
+                device: String::from("Offline"),
+                sample_rate,
+                num_channels,
+                fallback: false,
+            }),
+            block_size,
+            block: vec![0.0; block_size * num_channels as usize],
+        }
+    }
+
+    /// Render at least `frames` frames of interleaved output, rounded up to whole blocks. Nothing is rendered
+    /// when the backend is closed.
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let Some(info) = &self.info else {
+            return Vec::new();
+        };
+
+        let (sample_rate, num_channels) = (info.sample_rate as f64, info.num_channels as usize);
+        let blocks = frames.div_ceil(self.block_size);
+        let mut output = Vec::with_capacity(blocks * self.block.len());
+        for _ in 0..blocks {
+            self.process.process(PlaybackContext {
+                buffer_size: self.block_size,
+                sample_rate,
+                num_channels,
+                output_buffer: &mut self.block,
+            });
+            output.extend_from_slice(&self.block);
+        }
+
+        output
+    }
+}
+
+impl AudioBackend for OfflineBackend {
+    /// The sample rate and buffer size of the settings replace those of the backend, and the host and device are
+    /// ignored.
+    fn open(&mut self, settings: &OutputSettings) -> Result<OutputInfo, OutputError> {
+        let mut info = self.info.take().unwrap_or_else(|| OutputInfo {
+            device: String::from("Offline"),
+            sample_rate: 44100,
+            num_channels: 2,
+            fallback: false,
+        });
+        info.sample_rate = settings.sample_rate.unwrap_or(info.sample_rate);
+
+        if let Some(buffer_size) = settings.buffer_size {
+            self.block_size = buffer_size as usize;
+        }
+        self.block.resize(self.block_size * info.num_channels as usize, 0.0);
+
+        self.info = Some(info.clone());
+        Ok(info)
+    }
+
+    fn close(&mut self) {
+        self.info = None;
+    }
+
+    fn info(&self) -> Option<&OutputInfo> {
+        self.info.as_ref()
+    }
+}
@@ -0,0 +1,161 @@
+//! Pads to which samples are pinned and triggered like a drum machine, each trigger playing a voice over the
+//! preview.
+
+use basedrop::Owned;
+use vizia::prelude::*;
+
+use super::DecodedAudio;
+
+/// The number of pads, in a grid of four by four.
+pub const NUM_PADS: usize = 16;
+
+/// The number of choke groups. Triggering a pad cuts off the voices of the pads in its group, group 0 being no
+/// group.
+pub const CHOKE_GROUPS: u8 = 4;
+
+// The most voices played at once, the oldest being cut off for new ones
+const MAX_VOICES: usize = 32;
+
+// The length in seconds of the fade out of a voice when it is released or choked, to avoid clicks
+const RELEASE_TIME: f32 = 0.005;
+
+/// How a pad plays its sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum PadMode {
+    /// The whole sample is played each time the pad is triggered.
+    OneShot,
+    /// The sample is played while the pad is held.
+    Gate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct PadSettings {
+    pub mode: PadMode,
+    // The choke group of the pad, 0 for none
+    pub choke_group: u8,
+}
+
+impl Default for PadSettings {
+    fn default() -> Self {
+        Self { mode: PadMode::OneShot, choke_group: 0 }
+    }
+}
+
+// A sample playing after its pad was triggered
+struct Voice {
+    pad: usize,
+    // The position in frames of the sample, which is played at its own sample rate
+    position: f64,
+    velocity: f32,
+    // The gain of the fade out once released, from 1 to 0
+    level: f32,
+    released: bool,
+}
+
+/// The samples pinned to the pads and the voices playing them, on the audio thread.
+pub struct Pads {
+    samples: Vec<Option<Owned<DecodedAudio>>>,
+    settings: [PadSettings; NUM_PADS],
+    voices: Vec<Voice>,
+}
+
+impl Default for Pads {
+    fn default() -> Self {
+        Self {
+            samples: (0..NUM_PADS).map(|_| None).collect(),
+            settings: [PadSettings::default(); NUM_PADS],
+            voices: Vec::with_capacity(MAX_VOICES),
+        }
+    }
+}
+
+impl Pads {
+    /// Pin the decoded `audio` to the pad at `index`, or clear the pad if `None`, cutting off its voices.
+    pub fn set_sample(&mut self, index: usize, audio: Option<Owned<DecodedAudio>>) {
+        if let Some(sample) = self.samples.get_mut(index) {
+            *sample = audio;
+            self.voices.retain(|voice| voice.pad != index);
+        }
+    }
+
+    pub fn set_settings(&mut self, index: usize, settings: PadSettings) {
+        if let Some(pad) = self.settings.get_mut(index) {
+            *pad = settings;
+        }
+    }
+
+    /// Start a voice of the pad at `index` at the linear gain `velocity`, releasing the voices of the pads in
+    /// its choke group. Retriggering a gate pad releases its previous voices, while those of a one-shot pad
+    /// play on unless it is in a choke group.
+    pub fn trigger(&mut self, index: usize, velocity: f32) {
+        let Some(Some(_)) = self.samples.get(index) else {
+            return;
+        };
+
+        let settings = self.settings[index];
+        for voice in &mut self.voices {
+            let choked = settings.choke_group != 0
+                && self.settings[voice.pad].choke_group == settings.choke_group;
+            if choked || (voice.pad == index && settings.mode == PadMode::Gate) {
+                voice.released = true;
+            }
+        }
+
+        if self.voices.len() == MAX_VOICES {
+            self.voices.remove(0);
+        }
+        self.voices.push(Voice {
+            pad: index,
+            position: 0.0,
+            velocity,
+            level: 1.0,
+            released: false,
+        });
+    }
+
+    /// Release the voices of the pad at `index` when it is a gate pad.
+    pub fn release(&mut self, index: usize) {
+        if self.settings.get(index).is_some_and(|settings| settings.mode == PadMode::Gate) {
+            for voice in self.voices.iter_mut().filter(|voice| voice.pad == index) {
+                voice.released = true;
+            }
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.voices.is_empty()
+    }
+
+    /// The next stereo frame of the voices, resampled to the output `sample_rate` by linear interpolation.
+    pub fn next_frame(&mut self, sample_rate: f64) -> (f32, f32) {
+        let release_step = 1.0 / (RELEASE_TIME * sample_rate as f32);
+
+        let (mut left, mut right) = (0.0, 0.0);
+        for voice in &mut self.voices {
+            let Some(audio) = &self.samples[voice.pad] else {
+                voice.level = 0.0;
+                continue;
+            };
+            let Some(frame) = audio.frame_at(voice.position) else {
+                voice.level = 0.0;
+                continue;
+            };
+
+            let gain = voice.velocity * voice.level;
+            left += frame.0 * gain;
+            right += frame.1 * gain;
+
+            voice.position += audio.sample_rate as f64 / sample_rate;
+            if voice.released {
+                voice.level = (voice.level - release_step).max(0.0);
+            }
+        }
+
+        self.voices.retain(|voice| voice.level > 0.0);
+        (left, right)
+    }
+}
@@ -1,5 +1,13 @@
 use super::audio_data::AudioData;
 use super::audio_stream::PlaybackContext;
+use super::channels::{write_frame, ChannelLayout, ChannelSolo};
+use super::effects::{fade_gain, Filter, FilterType};
+use super::layers::{Layer, LayerMix, MAX_LAYERS};
+use super::meter::{spectrum_tap, Levels, SpectrumAnalyzer};
+use super::metronome::Metronome;
+use super::midi::{MidiInput, MidiMessage};
+use super::pads::{PadSettings, Pads};
+use super::time_stretch::TimeStretch;
 use basedrop::{Collector, Handle, Owned, Shared, SharedCell};
 use creek::read::ReadError;
 use creek::{Decoder, ReadDiskStream, SeekMode, SymphoniaDecoder};
@@ -9,11 +17,32 @@ use rubato::{
     Resampler, SincFixedIn, SincFixedOut, SincInterpolationParameters, SincInterpolationType,
     WindowFunction,
 };
+use std::f32::consts::FRAC_PI_2;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use vizia::prelude::*;
 
+// The largest factor by which the resampler changes the sample rate, up or down
+const MAX_RATIO: f64 = 8.0;
+
+// The most frames read from the disk stream at once, and the most reads for one block of output
+const MAX_READ_FRAMES: usize = 4096;
+const MAX_READS: usize = 64;
+
+// The longest file decoded into memory to be played in reverse, about three minutes at 44.1 kHz
+const MAX_DECODED_FRAMES: usize = 1 << 23;
+
+/// The longest crossfade between the outgoing and incoming file in seconds.
+pub const MAX_CROSSFADE: f32 = 0.1;
+
+// The most frames of the outgoing file kept for a crossfade, enough for the longest crossfade at 192 kHz and a
+// large buffer
+const MAX_TAIL_FRAMES: usize = 32768;
+
+// The most output rendered ahead of a crossfade in one buffer, in buffers, besides the output of the buffer
+const CAPTURE_BUFFERS: usize = 2;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayerState {
     Playing,
@@ -29,6 +58,88 @@ enum PlayerAction {
     UseStream(Owned<ReadDiskStream<SymphoniaDecoder>>),
     //Volume(f32),
     SetLoop { start: usize, end: usize },
+    SetPlaybackRate(f64),
+    SetTimeStretch(f64),
+    SetRegion { start: usize, end: usize },
+    UseReversed(Option<Owned<DecodedAudio>>),
+    SetFilter(Option<(FilterType, f32)>),
+    SetFades { fade_in: f32, fade_out: f32 },
+    SetChannelSolo(ChannelSolo),
+    SetAmbisonic(bool),
+    SetCrossfade(f32),
+    AddLayer(Owned<DecodedAudio>, LayerMix),
+    RemoveLayer(usize),
+    SetLayerMix(usize, LayerMix),
+    SetPad(usize, Option<Owned<DecodedAudio>>),
+    SetPadSettings(usize, PadSettings),
+    TriggerPad(usize, f32),
+    ReleasePad(usize),
+    SetMidiInput(Option<Owned<Box<dyn MidiInput>>>),
+    SetMetronome { tempo: f64, beats_per_bar: u32, beat_unit: u32, click: bool },
+    SetQuantize(bool),
+}
+
+/// A file decoded into memory, which is played backwards when previews are reversed.
+pub struct DecodedAudio {
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl DecodedAudio {
+    /// Decode the file at `path` downmixed to stereo, or `None` if it cannot be read or is too long. Four
+    /// channel files are decoded as first order ambisonics when `ambisonic` is set.
+    pub fn read(path: &Path, ambisonic: bool) -> Option<Self> {
+        let mut read_stream =
+            ReadDiskStream::<SymphoniaDecoder>::new(path, 0, Default::default()).ok()?;
+        let _ = read_stream.cache(0, 0);
+        read_stream.seek(0, Default::default()).ok()?;
+        let num_frames = read_stream.info().num_frames;
+        if num_frames > MAX_DECODED_FRAMES {
+            return None;
+        }
+
+        let mut audio = Self {
+            left: Vec::with_capacity(num_frames),
+            right: Vec::with_capacity(num_frames),
+            sample_rate: read_stream.info().sample_rate.unwrap_or(44100),
+        };
+        while audio.left.len() < num_frames {
+            read_stream.block_until_ready().ok()?;
+            let read_data =
+                read_stream.read(MAX_READ_FRAMES.min(num_frames - audio.left.len())).ok()?;
+            if read_data.num_frames() == 0 {
+                break;
+            }
+
+            let (start, frames) = (audio.left.len(), read_data.num_frames());
+            audio.left.resize(start + frames, 0.0);
+            audio.right.resize(start + frames, 0.0);
+            ChannelLayout::new(read_data.num_channels(), ambisonic).downmix(
+                (0..read_data.num_channels()).map(|channel| read_data.read_channel(channel)),
+                &mut audio.left[start..],
+                &mut audio.right[start..],
+            );
+        }
+
+        Some(audio)
+    }
+
+    /// The frame at the fractional frame `position`, interpolated linearly, or `None` past the end.
+    pub fn frame_at(&self, position: f64) -> Option<(f32, f32)> {
+        let index = position as usize;
+        if index >= self.left.len() {
+            return None;
+        }
+
+        let next = (index + 1).min(self.left.len() - 1);
+        let fraction = (position - index as f64) as f32;
+        let (left, right) = (&self.left, &self.right);
+        Some((
+            left[index] + (left[next] - left[index]) * fraction,
+            right[index] + (right[next] - right[index]) * fraction,
+        ))
+    }
 }
 
 #[derive(Lens)]
@@ -39,6 +150,13 @@ pub struct SamplePlayerController {
     collector: Handle,
     pub playhead: Arc<AtomicUsize>,
     pub should_loop: Arc<AtomicBool>,
+    // The levels of the output, and its spectrum when enabled
+    #[lens(ignore)]
+    levels: Arc<Levels>,
+    #[lens(ignore)]
+    spectrum_enabled: Arc<AtomicBool>,
+    #[lens(ignore)]
+    spectrum: SpectrumAnalyzer,
     pub play_state: PlayerState,
     pub sample_rate: Option<f64>,
     pub num_channels: Option<usize>,
@@ -58,9 +176,12 @@ pub fn sample_player(c: &Collector) -> (Process, SamplePlayerController) {
         window: WindowFunction::BlackmanHarris2,
     };
 
-    let resampler = SincFixedOut::new(1.0, 2.0, params, 512, 2).unwrap();
+    let resampler = SincFixedOut::new(1.0, MAX_RATIO, params, 512, 2).unwrap();
 
     let (tx, rx) = HeapRb::new(2048).split();
+    let levels = Arc::new(Levels::default());
+    let spectrum_enabled = Arc::new(AtomicBool::new(false));
+    let (spectrum_tx, spectrum) = spectrum_tap();
 
     (
         Process {
@@ -72,18 +193,55 @@ pub fn sample_player(c: &Collector) -> (Process, SamplePlayerController) {
             resample_buffer_in: resampler.input_buffer_allocate(true),
             resample_buffer_out: resampler.output_buffer_allocate(true),
             resampler,
+            time_stretch: TimeStretch::new(),
+            scratch: [vec![0.0; MAX_READ_FRAMES], vec![0.0; MAX_READ_FRAMES]],
             playhead: playhead.clone(),
             should_loop: should_loop.clone(),
+            levels: levels.clone(),
+            spectrum_enabled: spectrum_enabled.clone(),
+            spectrum: spectrum_tx,
             playback_state: PlayerState::Stopped,
+            finishing: false,
             had_cache_miss_last_cycle: false,
             loop_start: 0,
             loop_end: 0,
+            start: 0,
+            end: 0,
+            reversed: None,
+            reverse_playhead: 0,
+            filter: None,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            solo: ChannelSolo::Both,
+            ambisonic: false,
+            crossfade: 0.0,
+            tail: vec![0.0; MAX_TAIL_FRAMES * 2 + 1024],
+            tail_len: 0,
+            tail_position: 0,
+            fade_start: None,
+            pending_stream: None,
+            capture: vec![0.0; MAX_TAIL_FRAMES * 2 + 1024],
+            capture_len: 0,
+            capture_position: 0,
+            capture_target: 0,
+            layers: Vec::with_capacity(MAX_LAYERS),
+            pads: Pads::default(),
+            midi_input: None,
+            metronome: Metronome::default(),
+            quantize: false,
+            start_delay: 0,
+            was_playing: false,
+            wait_for_disk: false,
+            playback_rate: 1.0,
             fatal_error: false,
         },
         SamplePlayerController {
             tx,
             playhead: playhead.clone(),
             should_loop: should_loop.clone(),
+            levels,
+            spectrum_enabled,
+            spectrum,
             collector: c.handle(),
             sample_rate: None,
             num_channels: None,
@@ -134,6 +292,127 @@ impl SamplePlayerController {
         self.send_msg(PlayerAction::UseStream(audio_file));
     }
 
+    /// Loop playback between the frames `start` and `end` instead of over the whole file.
+    pub fn set_loop(&mut self, start: usize, end: usize) {
+        self.send_msg(PlayerAction::SetLoop { start, end });
+    }
+
+    /// Play faster or slower than the file's sample rate, which also shifts the pitch, e.g. 2.0 plays an
+    /// octave higher.
+    pub fn set_playback_rate(&mut self, rate: f64) {
+        self.send_msg(PlayerAction::SetPlaybackRate(rate));
+    }
+
+    /// Play faster or slower without changing the pitch, e.g. 1.5 plays a 90 BPM loop at 135 BPM.
+    pub fn set_time_stretch(&mut self, speed: f64) {
+        self.send_msg(PlayerAction::SetTimeStretch(speed));
+    }
+
+    /// Start playback from the frame `start` after stopping, instead of from the start of the file. Fades
+    /// are applied from `start` and up to `end`, or up to the end of the file if `end` is 0.
+    pub fn set_region(&mut self, start: usize, end: usize) {
+        self.send_msg(PlayerAction::SetRegion { start, end });
+    }
+
+    /// Play the file backwards from the decoded `audio`, or forwards from the disk stream if `None`.
+    pub fn set_reversed(&mut self, audio: Option<Owned<DecodedAudio>>) {
+        self.send_msg(PlayerAction::UseReversed(audio));
+    }
+
+    /// Filter the output with the given filter type and cutoff in Hz.
+    pub fn set_filter(&mut self, filter: Option<(FilterType, f32)>) {
+        self.send_msg(PlayerAction::SetFilter(filter));
+    }
+
+    /// Fade the start and end of the file in and out over the given lengths in seconds.
+    pub fn set_fades(&mut self, fade_in: f32, fade_out: f32) {
+        self.send_msg(PlayerAction::SetFades { fade_in, fade_out });
+    }
+
+    /// Preview only the left or right channel, or the mid or side signal, on both channels.
+    pub fn set_channel_solo(&mut self, solo: ChannelSolo) {
+        self.send_msg(PlayerAction::SetChannelSolo(solo));
+    }
+
+    /// Downmix four channel files as first order ambisonics rather than quad.
+    pub fn set_ambisonic(&mut self, ambisonic: bool) {
+        self.send_msg(PlayerAction::SetAmbisonic(ambisonic));
+    }
+
+    /// Crossfade from the playing file to the next loaded file over `seconds`, up to `MAX_CROSSFADE`, or cut
+    /// between them if 0.
+    pub fn set_crossfade(&mut self, seconds: f32) {
+        self.send_msg(PlayerAction::SetCrossfade(seconds.clamp(0.0, MAX_CROSSFADE)));
+    }
+
+    /// Layer the decoded `audio` over the preview, up to `MAX_LAYERS` layers. Layers are played from their
+    /// start each time the preview starts.
+    pub fn add_layer(&mut self, audio: Owned<DecodedAudio>, mix: LayerMix) {
+        self.send_msg(PlayerAction::AddLayer(audio, mix));
+    }
+
+    /// Remove the layer at `index`, the following layers moving down.
+    pub fn remove_layer(&mut self, index: usize) {
+        self.send_msg(PlayerAction::RemoveLayer(index));
+    }
+
+    pub fn set_layer_mix(&mut self, index: usize, mix: LayerMix) {
+        self.send_msg(PlayerAction::SetLayerMix(index, mix));
+    }
+
+    /// Pin the decoded `audio` to the pad at `index`, or clear the pad if `None`.
+    pub fn set_pad(&mut self, index: usize, audio: Option<Owned<DecodedAudio>>) {
+        self.send_msg(PlayerAction::SetPad(index, audio));
+    }
+
+    pub fn set_pad_settings(&mut self, index: usize, settings: PadSettings) {
+        self.send_msg(PlayerAction::SetPadSettings(index, settings));
+    }
+
+    /// Play the sample of the pad at `index` over the preview at the linear gain `velocity`.
+    pub fn trigger_pad(&mut self, index: usize, velocity: f32) {
+        self.send_msg(PlayerAction::TriggerPad(index, velocity));
+    }
+
+    /// Stop the sample of the pad at `index` if it is a gate pad.
+    pub fn release_pad(&mut self, index: usize) {
+        self.send_msg(PlayerAction::ReleasePad(index));
+    }
+
+    /// Set the tempo of the metronome in quarter notes per minute and its time signature, and whether it
+    /// clicks on each beat.
+    pub fn set_metronome(&mut self, tempo: f64, beats_per_bar: u32, beat_unit: u32, click: bool) {
+        self.send_msg(PlayerAction::SetMetronome { tempo, beats_per_bar, beat_unit, click });
+    }
+
+    /// Start playback on the next bar of the metronome. When the metronome is silent and nothing was playing,
+    /// a bar starts with playback instead.
+    pub fn set_quantize(&mut self, quantize: bool) {
+        self.send_msg(PlayerAction::SetQuantize(quantize));
+    }
+
+    /// Play the pads from the notes received by the MIDI `input`, replacing the previous input.
+    pub fn set_midi_input(&mut self, input: Option<Box<dyn MidiInput>>) {
+        let input = input.map(|input| Owned::new(&self.collector, input));
+        self.send_msg(PlayerAction::SetMidiInput(input));
+    }
+
+    /// The linear peak and RMS levels of the left and right channels of the output.
+    pub fn levels(&self) -> ([f32; 2], [f32; 2]) {
+        self.levels.get()
+    }
+
+    /// Send the output to the spectrum analyzer, which is otherwise idle.
+    pub fn set_spectrum_enabled(&mut self, enabled: bool) {
+        self.spectrum.clear();
+        self.spectrum_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// The level in dB of each band of the spectrum of the latest output.
+    pub fn spectrum(&mut self, sample_rate: f32) -> Vec<f32> {
+        self.spectrum.analyze(sample_rate)
+    }
+
     pub fn toggle_looping(&mut self) {
         self.should_loop.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| Some(!x));
     }
@@ -152,16 +431,82 @@ pub struct Process {
     resample_buffer_in: Vec<Vec<f32>>,
     resample_buffer_out: Vec<Vec<f32>>,
     resampler: SincFixedOut<f32>,
+    time_stretch: TimeStretch,
+    // The frames read before they are passed to the time stretcher
+    scratch: [Vec<f32>; 2],
 
     playhead: Arc<AtomicUsize>,
     should_loop: Arc<AtomicBool>,
 
+    levels: Arc<Levels>,
+    spectrum_enabled: Arc<AtomicBool>,
+    spectrum: HeapProd<f32>,
+
     playback_state: PlayerState,
+    // Whether playback stopped at the end of the file, the queued output still being played
+    finishing: bool,
     had_cache_miss_last_cycle: bool,
 
     loop_start: usize,
     loop_end: usize,
 
+    // The frame to which playback returns when stopped, and the end of the faded region, 0 for the end of the
+    // file
+    start: usize,
+    end: usize,
+
+    // The decoded file when playing in reverse, and the frame before which it is read
+    reversed: Option<Owned<DecodedAudio>>,
+    reverse_playhead: usize,
+
+    filter: Option<Filter>,
+    // The length of the fades in seconds
+    fade_in: f32,
+    fade_out: f32,
+
+    solo: ChannelSolo,
+    // Whether four channel files are first order ambisonics
+    ambisonic: bool,
+
+    // The length in seconds of the crossfade when a file is loaded during playback
+    crossfade: f32,
+    // The interleaved output of the outgoing file, of which `tail_len` samples are faded out from
+    // `tail_position`, the fade starting at `fade_start` once the incoming file is output
+    tail: Vec<f32>,
+    tail_len: usize,
+    tail_position: usize,
+    fade_start: Option<usize>,
+    // The incoming file while the output of the playing file is rendered ahead into `capture`, a few blocks
+    // each buffer, until `capture_target` samples are queued there to become the tail. The output is played
+    // from `capture_position` meanwhile, and the messages following the file wait.
+    pending_stream: Option<Owned<ReadDiskStream<SymphoniaDecoder>>>,
+    capture: Vec<f32>,
+    capture_len: usize,
+    capture_position: usize,
+    capture_target: usize,
+
+    // The samples layered over the preview, mixed after its effects
+    layers: Vec<Layer>,
+
+    // The pads and the MIDI input playing them, which are mixed with the output whether or not the preview is
+    // playing
+    pads: Pads,
+    midi_input: Option<Owned<Box<dyn MidiInput>>>,
+
+    // The bars and beats of the output, and whether playback starts on the next bar, the number of frames of
+    // output before it starts, and whether a file was playing before the file was loaded
+    metronome: Metronome,
+    quantize: bool,
+    start_delay: usize,
+    was_playing: bool,
+
+    // Whether to wait for the disk stream instead of outputting silence when it is not ready, when rendering
+    // offline
+    wait_for_disk: bool,
+
+    // The speed at which the file is played, relative to its sample rate
+    playback_rate: f64,
+
     fatal_error: bool,
 }
 
@@ -169,18 +514,25 @@ unsafe impl Send for Process {}
 unsafe impl Sync for Process {}
 
 impl Process {
+    /// Wait for the disk stream when it is not ready instead of outputting silence. This blocks, so it is only
+    /// used when rendering offline.
+    pub fn set_wait_for_disk(&mut self, wait_for_disk: bool) {
+        self.wait_for_disk = wait_for_disk;
+    }
+
     pub fn process(&mut self, context: PlaybackContext) {
         if self.fatal_error {
             silence(context.output_buffer);
             return;
         }
 
-        if let Err(e) = self.try_process(context.output_buffer, context.sample_rate) {
+        if let Err(e) =
+            self.try_process(context.output_buffer, context.sample_rate, context.num_channels)
+        {
             if matches!(e, ReadError::FatalError(_)) {
                 self.fatal_error = true;
             }
 
-            println!("{:?}", e);
             silence(context.output_buffer);
         }
     }
@@ -189,16 +541,40 @@ impl Process {
         &mut self,
         mut data: &mut [f32],
         sample_rate: f64,
+        num_channels: usize,
     ) -> Result<(), ReadError<<SymphoniaDecoder as Decoder>::FatalError>> {
-        // Process messages from GUI.
-        while let Some(msg) = self.rx.try_pop() {
+        // The player renders stereo, which is mapped to the channels of the device when output
+        let num_channels = num_channels.max(1);
+        let frames = data.len() / num_channels;
+
+        // Process messages from GUI. While the tail of a crossfade is rendered, the messages following the
+        // incoming file wait for it to be loaded.
+        loop {
+            if self.pending_stream.is_some() {
+                self.continue_capture(sample_rate, frames)?;
+                if self.pending_stream.is_some() {
+                    break;
+                }
+            }
+            let Some(msg) = self.rx.try_pop() else {
+                break;
+            };
+
             match msg {
                 PlayerAction::UseStream(read_disk_stream) => {
-                    self.playback_state = PlayerState::Stopped;
-                    self.loop_start = 0;
-                    self.loop_end = 0;
-
-                    self.read_disk_stream = Some(read_disk_stream);
+                    // Keep the output of the playing file to fade it out, lasting at least until the
+                    // incoming file is output after the next buffer
+                    if self.playback_state == PlayerState::Playing
+                        && self.crossfade > 0.0
+                        && self.read_disk_stream.is_some()
+                    {
+                        let crossfade = (self.crossfade * sample_rate as f32) as usize;
+                        self.start_capture(crossfade + frames);
+                        self.pending_stream = Some(read_disk_stream);
+                    } else {
+                        let was_playing = self.playback_state == PlayerState::Playing;
+                        self.use_stream(read_disk_stream, was_playing);
+                    }
                 }
                 PlayerAction::SetLoop { start, end } => {
                     self.loop_start = start;
@@ -211,29 +587,138 @@ impl Process {
                         }
                     }
                 }
+                PlayerAction::SetPlaybackRate(rate) => {
+                    self.playback_rate = rate;
+                }
+                PlayerAction::SetTimeStretch(speed) => {
+                    self.time_stretch.set_speed(speed);
+                }
+                PlayerAction::SetRegion { start, end } => {
+                    self.start = start;
+                    self.end = end;
+                }
+                PlayerAction::UseReversed(reversed) => {
+                    self.time_stretch.reset();
+                    self.reversed = reversed;
+                    self.reverse_playhead = self.reverse_bounds().1;
+                }
+                PlayerAction::SetFilter(Some((filter_type, cutoff))) => match &mut self.filter {
+                    Some(filter) => filter.set(filter_type, cutoff),
+                    None => self.filter = Some(Filter::new(filter_type, cutoff)),
+                },
+                PlayerAction::SetFilter(None) => {
+                    self.filter = None;
+                }
+                PlayerAction::SetFades { fade_in, fade_out } => {
+                    self.fade_in = fade_in;
+                    self.fade_out = fade_out;
+                }
+                PlayerAction::SetChannelSolo(solo) => {
+                    self.solo = solo;
+                }
+                PlayerAction::SetAmbisonic(ambisonic) => {
+                    self.ambisonic = ambisonic;
+                }
+                PlayerAction::SetCrossfade(crossfade) => {
+                    self.crossfade = crossfade;
+                }
+                PlayerAction::AddLayer(audio, mix) => {
+                    // The layers are allocated up front, so that none are allocated here
+                    if self.layers.len() < MAX_LAYERS {
+                        self.layers.push(Layer::new(audio, mix));
+                    }
+                }
+                PlayerAction::RemoveLayer(index) => {
+                    if index < self.layers.len() {
+                        self.layers.remove(index);
+                    }
+                }
+                PlayerAction::SetLayerMix(index, mix) => {
+                    if let Some(layer) = self.layers.get_mut(index) {
+                        layer.mix = mix;
+                    }
+                }
+                PlayerAction::SetPad(index, audio) => {
+                    self.pads.set_sample(index, audio);
+                }
+                PlayerAction::SetPadSettings(index, settings) => {
+                    self.pads.set_settings(index, settings);
+                }
+                PlayerAction::TriggerPad(index, velocity) => {
+                    self.pads.trigger(index, velocity);
+                }
+                PlayerAction::ReleasePad(index) => {
+                    self.pads.release(index);
+                }
+                PlayerAction::SetMidiInput(input) => {
+                    self.midi_input = input;
+                }
+                PlayerAction::SetMetronome { tempo, beats_per_bar, beat_unit, click } => {
+                    self.metronome.set(tempo, beats_per_bar, beat_unit, click);
+                }
+                PlayerAction::SetQuantize(quantize) => {
+                    self.quantize = quantize;
+                    if !quantize {
+                        self.start_delay = 0;
+                    }
+                }
                 PlayerAction::Play => {
+                    // Without a click or a file playing to keep time with, the bar starts with playback
+                    if self.quantize && self.playback_state == PlayerState::Stopped {
+                        if self.metronome.clicks() || self.was_playing {
+                            self.start_delay = self.metronome.frames_to_next_bar(sample_rate);
+                        } else {
+                            self.metronome.restart();
+                        }
+                    }
+                    self.was_playing = false;
                     self.playback_state = PlayerState::Playing;
+
+                    // Playing in reverse again after reaching the start
+                    let (lower, upper) = self.reverse_bounds();
+                    if self.reverse_playhead <= lower {
+                        self.reverse_playhead = upper;
+                    }
                 }
                 PlayerAction::Pause => {
                     self.playback_state = PlayerState::Stopped;
+                    self.finishing = false;
+                    self.tail_len = 0;
+                    self.start_delay = 0;
+                    self.was_playing = false;
                 }
                 PlayerAction::Stop => {
                     self.playback_state = PlayerState::Stopped;
+                    self.tail_len = 0;
+                    self.start_delay = 0;
+                    self.was_playing = false;
+                    self.discard_output();
+                    self.restart_layers();
+                    self.pads.stop_all();
+                    self.reverse_playhead = self.reverse_bounds().1;
 
                     if let Some(read_disk_stream) = &mut self.read_disk_stream {
-                        read_disk_stream.seek(self.loop_start, SeekMode::Auto)?;
+                        read_disk_stream.seek(self.start, SeekMode::Auto)?;
 
                         self.playhead.store(read_disk_stream.playhead(), Ordering::SeqCst);
                     }
+                    if self.reversed.is_some() {
+                        self.playhead.store(self.reverse_playhead, Ordering::SeqCst);
+                    }
                 }
                 PlayerAction::Restart => {
                     self.playback_state = PlayerState::Playing;
+                    self.discard_output();
+                    self.restart_layers();
+                    self.reverse_playhead = self.reverse_bounds().1;
 
                     if let Some(read_disk_stream) = &mut self.read_disk_stream {
                         read_disk_stream.seek(self.loop_start, SeekMode::Auto)?;
                     }
                 }
                 PlayerAction::Seek(pos) => {
+                    self.discard_output();
+                    self.reverse_playhead = pos;
                     if let Some(read_disk_stream) = &mut self.read_disk_stream {
                         read_disk_stream.seek(pos, SeekMode::Auto)?;
                     }
@@ -241,160 +726,463 @@ impl Process {
             }
         }
 
-        silence(data);
-
-        self.input_count += data.len();
-
-        if self.input_count >= 1024 {
-            let mut buffer = self.process_buffer.as_mut_slice();
-
-            let mut cache_missed_this_cycle = false;
-            if let Some(read_disk_stream) = &mut self.read_disk_stream {
-                let file_sample_rate = read_disk_stream.info().sample_rate.unwrap_or(41000) as f64;
+        if let Some(midi_input) = &mut self.midi_input {
+            while let Some(message) = midi_input.poll() {
+                match (message, message.pad()) {
+                    (MidiMessage::NoteOn { velocity, .. }, Some(pad)) => {
+                        self.pads.trigger(pad, velocity as f32 / 127.0);
+                    }
+                    (MidiMessage::NoteOff { .. }, Some(pad)) => self.pads.release(pad),
+                    _ => {}
+                }
+            }
+        }
 
-                // Update client and check if it is ready.
+        silence(data);
 
-                if !read_disk_stream.is_ready()? {
-                    cache_missed_this_cycle = true;
-                    // We can choose to either continue reading (which will return silence),
-                    // or pause playback until the buffer is filled. This demo uses the former.
+        // Nothing is rendered while a quantized start waits for the next bar. Once the bar is in this buffer,
+        // enough output is queued to start on it.
+        let delay = self.start_delay.min(frames);
+        self.start_delay -= delay;
+        let capturing = self.pending_stream.is_some();
+        if capturing {
+            // The output is played from the capture
+        } else if self.playback_state == PlayerState::Playing && delay > 0 {
+            self.input_count = 0;
+            if delay < frames {
+                let capacity = self.output_buffer.capacity().get();
+                let needed = ((frames - delay) * 2 + 1024).min(capacity);
+                while self.output_buffer.occupied_len() < needed {
+                    if !self.queue_block(sample_rate)? {
+                        break;
+                    }
                 }
+            }
+        } else {
+            self.input_count += frames * 2;
 
-                if let PlayerState::Stopped = self.playback_state {
-                    self.input_count = 0;
-                    // Paused, do nothing.
-                    silence(buffer);
-                    return Ok(());
+            while self.input_count >= 1024 {
+                if !self.queue_block(sample_rate)? {
+                    break;
                 }
 
-                let num_frames = read_disk_stream.info().num_frames;
-                let num_channels = usize::from(read_disk_stream.info().num_channels);
+                self.input_count -= 1024;
+            }
+        }
 
-                // Keep reading data until output buffer is filled.
+        // Nothing is output while paused, but the output queued before the end of the file is played. The
+        // outgoing file of a crossfade plays alone until the output of the incoming file starts. The pads and
+        // the metronome are played without the delay of the queue.
+        let queued = match self.playback_state {
+            _ if capturing => true,
+            PlayerState::Playing => self.output_buffer.occupied_len() >= (frames - delay) * 2,
+            PlayerState::Stopped => self.finishing && !self.output_buffer.is_empty(),
+        };
+        let pads_active = self.pads.is_active();
+        for (index, frame) in data.chunks_exact_mut(num_channels).enumerate() {
+            let queued = queued && index >= delay;
+            let (mut left, mut right) = (0.0, 0.0);
+            if capturing {
+                (left, right) = self.next_captured();
+            } else if queued {
+                left = self.output_buffer.try_pop().unwrap_or(0.0);
+                right = self.output_buffer.try_pop().unwrap_or(0.0);
+            }
+            if self.tail_position < self.tail_len {
+                (left, right) = self.mix_tail(left, right, queued);
+            }
+            if pads_active {
+                let (pad_left, pad_right) = self.pads.next_frame(sample_rate);
+                (left, right) = (left + pad_left, right + pad_right);
+            }
+            let click = self.metronome.next_frame(sample_rate);
+            write_frame(left + click, right + click, frame);
+        }
 
-                self.resampler.set_resample_ratio(sample_rate / file_sample_rate, true);
-                let read_frames = if file_sample_rate != sample_rate {
-                    self.resampler.input_frames_next()
-                } else {
-                    buffer.len() / 2
-                };
+        Ok(())
+    }
+}
 
-                let mut playhead = read_disk_stream.playhead();
+impl Process {
+    // Render the next block of output and queue it, measuring its levels and sending it to the spectrum
+    // analyzer, returning false without rendering when playback is stopped.
+    fn queue_block(
+        &mut self,
+        sample_rate: f64,
+    ) -> Result<bool, ReadError<<SymphoniaDecoder as Decoder>::FatalError>> {
+        if !self.render_block(sample_rate)? {
+            return Ok(false);
+        }
 
-                // If user seeks ahead of the loop end, continue playing until the end
-                // of the file.
-                // let loop_end = if playhead < self.loop_end { self.loop_end } else { num_frames };
-                let loop_end = num_frames;
+        self.measure_block();
+        self.output_buffer.push_slice(&self.process_buffer);
+        Ok(true)
+    }
 
-                let read_data = read_disk_stream.read(read_frames)?;
+    // Measure the levels of the rendered block and send it to the spectrum analyzer.
+    fn measure_block(&mut self) {
+        let buffer = self.process_buffer.as_slice();
+        self.levels.measure(buffer);
+        if self.spectrum_enabled.load(Ordering::Relaxed) {
+            let mid = buffer.chunks_exact(2).map(|frame| (frame[0] + frame[1]) * 0.5);
+            self.spectrum.push_iter(mid);
+        }
+    }
 
-                let output_size = if file_sample_rate != sample_rate {
-                    self.resample_buffer_in[0][0..read_data.num_frames()]
-                        .copy_from_slice(read_data.read_channel(0));
-                    if read_data.num_channels() == 2 {
-                        self.resample_buffer_in[1][0..read_data.num_frames()]
-                            .copy_from_slice(read_data.read_channel(1));
-                    }
+    // Render the next block of stereo output into the process buffer, returning false without rendering when
+    // playback is stopped.
+    fn render_block(
+        &mut self,
+        sample_rate: f64,
+    ) -> Result<bool, ReadError<<SymphoniaDecoder as Decoder>::FatalError>> {
+        let reverse_bounds = self.reverse_bounds();
+        let buffer = self.process_buffer.as_mut_slice();
 
-                    self.resampler.set_resample_ratio(sample_rate / file_sample_rate, true);
+        let mut cache_missed_this_cycle = false;
+        if let Some(read_disk_stream) = &mut self.read_disk_stream {
+            // Playing faster is the same as reading a file of a higher sample rate
+            let file_sample_rate =
+                read_disk_stream.info().sample_rate.unwrap_or(41000) as f64 * self.playback_rate;
 
-                    let (input_frames, output_frames) = self
-                        .resampler
-                        .process_into_buffer(
-                            self.resample_buffer_in.as_slice(),
-                            self.resample_buffer_out.as_mut_slice(),
-                            None,
-                        )
-                        .unwrap();
+            // Update client and check if it is ready.
 
-                    output_frames
+            if !read_disk_stream.is_ready()? {
+                if self.wait_for_disk {
+                    read_disk_stream.block_until_ready()?;
                 } else {
-                    self.resample_buffer_out[0][0..read_data.num_frames()]
-                        .copy_from_slice(read_data.read_channel(0));
-                    if read_data.num_channels() == 2 {
-                        self.resample_buffer_out[1][0..read_data.num_frames()]
-                            .copy_from_slice(read_data.read_channel(1));
-                    }
-
-                    read_data.num_frames()
-                };
-
-                playhead += read_data.num_frames();
-                if playhead >= loop_end {
-                    // Copy up to the end of the loop.
-                    let to_end_of_loop = output_size - (playhead - loop_end);
+                    cache_missed_this_cycle = true;
+                }
+                // We can choose to either continue reading (which will return silence),
+                // or pause playback until the buffer is filled. This demo uses the former.
+            }
 
-                    if read_data.num_channels() == 1 {
-                        let ch = self.resample_buffer_out[0].as_slice();
+            if let PlayerState::Stopped = self.playback_state {
+                self.input_count = 0;
+                self.levels.reset();
+                // Paused, do nothing.
+                silence(buffer);
+                return Ok(false);
+            }
 
-                        for i in 0..to_end_of_loop {
-                            buffer[i * 2] = ch[i];
-                            buffer[i * 2 + 1] = ch[i];
-                        }
-                    } else if read_data.num_channels() == 2 {
-                        let ch1 = self.resample_buffer_out[0].as_slice();
-                        let ch2 = self.resample_buffer_out[1].as_slice();
+            let num_frames = read_disk_stream.info().num_frames;
+            let should_loop = self.should_loop.load(Ordering::SeqCst);
+
+            // The resampler only supports ratios within a range of its initial ratio
+            let ratio = (sample_rate / file_sample_rate).clamp(1.0 / MAX_RATIO, MAX_RATIO);
+            let resample = file_sample_rate != sample_rate;
+            let _ = self.resampler.set_resample_ratio(ratio, true);
+            let needed =
+                if resample { self.resampler.input_frames_next() } else { buffer.len() / 2 };
+
+            // The region which is faded in and out, in frames of the file
+            let file_rate = read_disk_stream.info().sample_rate.unwrap_or(41000) as f32;
+            let fade_in = (self.fade_in * file_rate) as usize;
+            let fade_out = (self.fade_out * file_rate) as usize;
+            let region_end = if self.end == 0 { num_frames } else { self.end };
+            let region_start = self.start;
+            let ambisonic = self.ambisonic;
+
+            // Read until the time stretcher has enough frames, wrapping around the loop.
+            let mut reached_end = false;
+            let mut reads = 0;
+            while self.time_stretch.available() < needed && !reached_end && reads < MAX_READS {
+                reads += 1;
+                let wanted = self.time_stretch.input_needed(needed).min(MAX_READ_FRAMES);
+                let [left, right] = &mut self.scratch;
+
+                let frames = if let Some(reversed) = &self.reversed {
+                    // Reversed, the loop is played from its end to its start
+                    let (lower, upper) = reverse_bounds;
+                    let playhead = self.reverse_playhead.min(reversed.left.len());
+                    let frames = wanted.min(playhead.saturating_sub(lower));
+
+                    for i in 0..frames {
+                        let position = playhead - 1 - i;
+                        let gain = fade_gain(
+                            region_end.saturating_sub(position + 1),
+                            position.saturating_sub(region_start),
+                            fade_in,
+                            fade_out,
+                        );
+                        left[i] = reversed.left[position] * gain;
+                        right[i] = reversed.right[position] * gain;
+                    }
 
-                        for i in 0..to_end_of_loop {
-                            buffer[i * 2] = ch1[i];
-                            buffer[i * 2 + 1] = ch2[i];
+                    self.reverse_playhead = playhead - frames;
+                    if self.reverse_playhead <= lower {
+                        if should_loop && upper > lower {
+                            self.reverse_playhead = upper;
+                        } else {
+                            reached_end = true;
                         }
                     }
 
-                    if self.should_loop.load(Ordering::SeqCst) {
-                        read_disk_stream.seek(self.loop_start, SeekMode::Auto)?;
-                    } else {
-                        self.playback_state = PlayerState::Stopped;
-                    }
+                    frames
                 } else {
-                    // Else copy all the read data.
-                    if read_data.num_channels() == 1 {
-                        let ch = self.resample_buffer_out[0].as_slice();
+                    let playhead = read_disk_stream.playhead();
+
+                    // If user seeks ahead of the loop end, or looping is off, continue playing
+                    // until the end of the file.
+                    let loop_end = if should_loop && playhead < self.loop_end {
+                        self.loop_end
+                    } else {
+                        num_frames
+                    };
 
-                        for i in 0..output_size {
-                            buffer[i * 2] = ch[i];
-                            buffer[i * 2 + 1] = ch[i];
+                    let frames = wanted.min(loop_end.saturating_sub(playhead));
+                    if frames > 0 && self.wait_for_disk && !read_disk_stream.is_ready()? {
+                        read_disk_stream.block_until_ready()?;
+                    }
+                    let frames = if frames > 0 {
+                        let read_data = read_disk_stream.read(frames)?;
+                        let frames = read_data.num_frames();
+                        ChannelLayout::new(read_data.num_channels(), ambisonic).downmix(
+                            (0..read_data.num_channels())
+                                .map(|channel| read_data.read_channel(channel)),
+                            &mut left[..frames],
+                            &mut right[..frames],
+                        );
+
+                        for i in 0..frames {
+                            let position = playhead + i;
+                            let gain = fade_gain(
+                                position.saturating_sub(region_start),
+                                region_end.saturating_sub(position),
+                                fade_in,
+                                fade_out,
+                            );
+                            left[i] *= gain;
+                            right[i] *= gain;
                         }
-                    } else if read_data.num_channels() == 2 {
-                        let ch1 = self.resample_buffer_out[0].as_slice();
-                        let ch2 = self.resample_buffer_out[1].as_slice();
 
-                        for i in 0..output_size {
-                            buffer[i * 2] = ch1[i];
-                            buffer[i * 2 + 1] = ch2[i];
+                        frames
+                    } else {
+                        0
+                    };
+
+                    if read_disk_stream.playhead() >= loop_end {
+                        if should_loop {
+                            read_disk_stream.seek(self.loop_start, SeekMode::Auto)?;
+                        } else {
+                            reached_end = true;
                         }
                     }
-                }
 
-                self.playhead.store(read_disk_stream.playhead(), Ordering::SeqCst);
-            } else {
-                // Output silence until file is received.
-                silence(buffer);
+                    frames
+                };
+
+                self.time_stretch.push(&left[..frames], &right[..frames]);
             }
 
-            // When the cache misses, the buffer is filled with silence. So the next
-            // buffer after the cache miss is starting from silence. To avoid an audible
-            // pop, apply a ramping gain from 0 up to unity.
-            if self.had_cache_miss_last_cycle {
-                let buffer_size = buffer.len() as f32;
-                for (i, sample) in buffer.iter_mut().enumerate() {
-                    *sample *= i as f32 / buffer_size;
+            let (input_left, input_right) = self.resample_buffer_in.split_at_mut(1);
+            self.time_stretch.pop(&mut input_left[0], &mut input_right[0], needed);
+
+            let output_size = if resample {
+                let (_, output_frames) = self
+                    .resampler
+                    .process_into_buffer(
+                        self.resample_buffer_in.as_slice(),
+                        self.resample_buffer_out.as_mut_slice(),
+                        None,
+                    )
+                    .unwrap();
+
+                output_frames
+            } else {
+                for (input, output) in
+                    self.resample_buffer_in.iter().zip(self.resample_buffer_out.iter_mut())
+                {
+                    output[..needed].copy_from_slice(&input[..needed]);
                 }
+
+                needed
+            };
+
+            if let Some(filter) = &mut self.filter {
+                let (left, right) = self.resample_buffer_out.split_at_mut(1);
+                filter.process(
+                    &mut left[0][..output_size],
+                    &mut right[0][..output_size],
+                    sample_rate as f32,
+                );
             }
 
-            self.input_count -= 1024;
+            let (left, right) = (&self.resample_buffer_out[0], &self.resample_buffer_out[1]);
+            for i in 0..output_size {
+                (buffer[i * 2], buffer[i * 2 + 1]) = self.solo.apply(left[i], right[i]);
+            }
 
-            self.output_buffer.push_slice(buffer);
+            for layer in &mut self.layers {
+                layer.mix_into(&mut buffer[..output_size * 2], sample_rate);
+            }
 
-            self.had_cache_miss_last_cycle = cache_missed_this_cycle;
+            if reached_end {
+                self.playback_state = PlayerState::Stopped;
+                self.finishing = true;
+            }
+
+            let playhead = match self.reversed {
+                Some(_) => self.reverse_playhead,
+                None => read_disk_stream.playhead(),
+            };
+            self.playhead.store(playhead, Ordering::SeqCst);
+        } else {
+            // Output silence until file is received.
+            silence(buffer);
         }
 
-        if self.output_buffer.occupied_len() >= data.len() {
-            self.output_buffer.pop_slice(data);
+        // When the cache misses, the buffer is filled with silence. So the next
+        // buffer after the cache miss is starting from silence. To avoid an audible
+        // pop, apply a ramping gain from 0 up to unity.
+        if self.had_cache_miss_last_cycle {
+            let buffer_size = buffer.len() as f32;
+            for (i, sample) in buffer.iter_mut().enumerate() {
+                *sample *= i as f32 / buffer_size;
+            }
         }
 
+        self.had_cache_miss_last_cycle = cache_missed_this_cycle;
+
+        Ok(true)
+    }
+
+    // Start keeping the next `frames` frames of output of the playing file, to crossfade them with the
+    // incoming file. The queued frames are kept first, and more are rendered over the next buffers.
+    fn start_capture(&mut self, frames: usize) {
+        self.capture_target = frames.min(MAX_TAIL_FRAMES) * 2;
+        self.capture_len = 0;
+        self.capture_position = 0;
+        while self.output_buffer.occupied_len() >= 2 && self.capture_len < self.capture_target {
+            self.capture[self.capture_len] = self.output_buffer.try_pop().unwrap_or(0.0);
+            self.capture[self.capture_len + 1] = self.output_buffer.try_pop().unwrap_or(0.0);
+            self.capture_len += 2;
+        }
+        self.output_buffer.clear();
+    }
+
+    // Render the output of the playing file ahead into the capture, at most a few buffers of it so that the
+    // buffer is not late, and load the incoming file once the capture is long enough or the file has ended.
+    fn continue_capture(
+        &mut self,
+        sample_rate: f64,
+        frames: usize,
+    ) -> Result<(), ReadError<<SymphoniaDecoder as Decoder>::FatalError>> {
+        let budget = frames * 2 * (CAPTURE_BUFFERS + 1);
+        let mut rendered = 0;
+        while self.capture_len - self.capture_position < self.capture_target {
+            if rendered >= budget {
+                return Ok(());
+            }
+            if !self.render_block(sample_rate)? {
+                break;
+            }
+            self.measure_block();
+
+            // The frames already played make room for the block
+            let block = self.process_buffer.len();
+            if self.capture_len + block > self.capture.len() {
+                self.capture.copy_within(self.capture_position..self.capture_len, 0);
+                self.capture_len -= self.capture_position;
+                self.capture_position = 0;
+            }
+            self.capture[self.capture_len..self.capture_len + block]
+                .copy_from_slice(&self.process_buffer);
+            self.capture_len += block;
+            rendered += block;
+        }
+
+        // A crossfade in progress is mixed into the rest of the capture, which becomes the tail
+        let mut position = self.capture_position;
+        while position < self.capture_len && self.tail_position < self.tail_len {
+            let (left, right) = (self.capture[position], self.capture[position + 1]);
+            (self.capture[position], self.capture[position + 1]) = self.mix_tail(left, right, true);
+            position += 2;
+        }
+        std::mem::swap(&mut self.tail, &mut self.capture);
+        self.tail_len = self.capture_len;
+        self.tail_position = self.capture_position;
+        self.fade_start = None;
+
+        if let Some(read_disk_stream) = self.pending_stream.take() {
+            self.use_stream(read_disk_stream, true);
+        }
         Ok(())
     }
+
+    // The next frame of the capture, silence once it has all been played.
+    fn next_captured(&mut self) -> (f32, f32) {
+        if self.capture_position >= self.capture_len {
+            return (0.0, 0.0);
+        }
+
+        let position = self.capture_position;
+        self.capture_position += 2;
+        (self.capture[position], self.capture[position + 1])
+    }
+
+    // Replace the playing file, stopped with its settings cleared until they are sent for the new file.
+    fn use_stream(
+        &mut self,
+        read_disk_stream: Owned<ReadDiskStream<SymphoniaDecoder>>,
+        was_playing: bool,
+    ) {
+        self.was_playing = was_playing;
+        self.playback_state = PlayerState::Stopped;
+        self.loop_start = 0;
+        self.loop_end = 0;
+        self.start = 0;
+        self.end = 0;
+        self.reversed = None;
+        self.discard_output();
+        self.restart_layers();
+
+        self.read_disk_stream = Some(read_disk_stream);
+    }
+
+    // Mix the next frame of the tail of the outgoing file with a frame of the incoming file, fading from one to
+    // the other at equal power once the incoming file is output.
+    fn mix_tail(&mut self, left: f32, right: f32, incoming: bool) -> (f32, f32) {
+        let position = self.tail_position;
+        let (tail_left, tail_right) = (self.tail[position], self.tail[position + 1]);
+        self.tail_position += 2;
+        if !incoming {
+            return (tail_left, tail_right);
+        }
+
+        let start = *self.fade_start.get_or_insert(position);
+        let progress = (position - start) as f32 / (self.tail_len - start) as f32;
+        let (fade_in, fade_out) = (progress * FRAC_PI_2).sin_cos();
+        (left * fade_in + tail_left * fade_out, right * fade_in + tail_right * fade_out)
+    }
+
+    // Discard the frames queued for output, so that the output continues from a new position without delay.
+    fn discard_output(&mut self) {
+        self.time_stretch.reset();
+        self.resampler.reset();
+        self.output_buffer.clear();
+        self.input_count = 0;
+        self.finishing = false;
+    }
+
+    // Play the layers from their start with the next output of the preview.
+    fn restart_layers(&mut self) {
+        for layer in &mut self.layers {
+            layer.restart();
+        }
+    }
+
+    // The frames between which the decoded file is played in reverse, wrapping from the first to the second
+    // when looping.
+    fn reverse_bounds(&self) -> (usize, usize) {
+        let len = self.reversed.as_ref().map_or(0, |reversed| reversed.left.len());
+        let end = if self.end == 0 { len } else { self.end.min(len) };
+        if self.should_loop.load(Ordering::SeqCst) {
+            let loop_end = if self.loop_end == 0 { end } else { self.loop_end.min(len) };
+            (self.loop_start.min(loop_end), loop_end)
+        } else {
+            (0, end)
+        }
+    }
 }
 
 fn silence(data: &mut [f32]) {
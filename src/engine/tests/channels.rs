@@ -0,0 +1,74 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::engine::{
+    tests::{assert_frame, render_last_frame, write_constant_wav},
+    ChannelLayout, ChannelSolo,
+};
+
+#[test]
+fn mono_is_centred() {
+    let path = write_constant_wav("mono", &[0.5]);
+
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[0.5, 0.5]);
+    assert_frame(&render_last_frame(&path, 1, |_| {}), &[0.5]);
+    assert_frame(&render_last_frame(&path, 6, |_| {}), &[0.5, 0.5, 0.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn stereo_to_device_layouts() {
+    let path = write_constant_wav("stereo", &[0.2, 0.6]);
+
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[0.2, 0.6]);
+    assert_frame(&render_last_frame(&path, 1, |_| {}), &[0.4]);
+    assert_frame(&render_last_frame(&path, 4, |_| {}), &[0.2, 0.6, 0.0, 0.0]);
+}
+
+#[test]
+fn surround_is_downmixed() {
+    // Front left, right and centre, LFE, back left and right
+    let path = write_constant_wav("surround51", &[0.1, 0.2, 0.3, 0.4, 0.05, 0.15]);
+    let left = 0.1 + FRAC_1_SQRT_2 * (0.3 + 0.05);
+    let right = 0.2 + FRAC_1_SQRT_2 * (0.3 + 0.15);
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[left, right]);
+
+    let path = write_constant_wav("surround71", &[0.1, 0.2, 0.0, 0.4, 0.0, 0.0, 0.1, 0.2]);
+    let (left, right) = (0.1 + FRAC_1_SQRT_2 * 0.1, 0.2 + FRAC_1_SQRT_2 * 0.2);
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[left, right]);
+
+    let path = write_constant_wav("quad", &[0.1, 0.2, 0.3, 0.4]);
+    let (left, right) = (0.1 + FRAC_1_SQRT_2 * 0.3, 0.2 + FRAC_1_SQRT_2 * 0.4);
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[left, right]);
+}
+
+#[test]
+fn ambisonics_are_decoded() {
+    // W, Y, Z and X, with a source to the left
+    let path = write_constant_wav("ambisonic", &[0.5, 0.25, 0.1, 0.1]);
+
+    let frame = render_last_frame(&path, 2, |controller| controller.set_ambisonic(true));
+    assert_frame(&frame, &[0.375, 0.125]);
+}
+
+#[test]
+fn channel_solo() {
+    let path = write_constant_wav("solo", &[0.2, 0.6]);
+    let render = |solo| render_last_frame(&path, 2, |controller| controller.set_channel_solo(solo));
+
+    assert_frame(&render(ChannelSolo::Left), &[0.2, 0.2]);
+    assert_frame(&render(ChannelSolo::Right), &[0.6, 0.6]);
+    assert_frame(&render(ChannelSolo::Mid), &[0.4, 0.4]);
+    assert_frame(&render(ChannelSolo::Side), &[-0.2, -0.2]);
+}
+
+#[test]
+fn unknown_layouts_alternate_channels() {
+    let layout = ChannelLayout::new(3, false);
+    assert_eq!(layout, ChannelLayout::Discrete(3));
+    assert_eq!(layout.stereo_gains(0), [0.5, 0.0]);
+    assert_eq!(layout.stereo_gains(1), [0.0, 0.5]);
+    assert_eq!(layout.stereo_gains(2), [0.5, 0.0]);
+    assert_eq!(layout.stereo_gains(3), [0.0, 0.0]);
+
+    let path = write_constant_wav("discrete", &[0.2, 0.4, 0.6]);
+    assert_frame(&render_last_frame(&path, 2, |_| {}), &[0.4, 0.2]);
+}
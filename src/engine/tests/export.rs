@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+
+use crate::engine::{
+    export, export_batch, file_name,
+    tests::{ramp_value, write_constant_wav, write_ramp_wav, SAMPLE_RATE},
+    BitDepth, ExportError, ExportSettings, ExportSource, PreviewEffects,
+};
+
+// A path in the system temp directory for an exported file.
+fn export_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("vsb-export-{}.wav", name))
+}
+
+// The spec and the interleaved samples of the file at `path`, integer samples as they are stored.
+fn read_wav(path: &Path) -> (hound::WavSpec, Vec<f32>) {
+    let mut reader = hound::WavReader::open(path).unwrap();
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().map(Result::unwrap).collect(),
+        hound::SampleFormat::Int => {
+            reader.samples::<i32>().map(|sample| sample.unwrap() as f32).collect()
+        }
+    };
+    (spec, samples)
+}
+
+#[test]
+fn exports_a_region_to_16_bit() {
+    let source = write_ramp_wav("export-ramp", 8192);
+    let destination = export_path("region");
+    let settings =
+        ExportSettings { bit_depth: BitDepth::Int16, dither: false, ..Default::default() };
+
+    export(&ExportSource::region(&source, 1000, 3000), &destination, &settings).unwrap();
+    let (spec, samples) = read_wav(&destination);
+    assert_eq!((spec.channels, spec.sample_rate, spec.bits_per_sample), (1, SAMPLE_RATE, 16));
+    assert_eq!(samples.len(), 2000);
+    for (frame, sample) in samples.iter().enumerate() {
+        assert_eq!(*sample, (ramp_value(1000 + frame) * 32767.0).round());
+    }
+}
+
+#[test]
+fn dithers_integer_samples() {
+    let source = write_constant_wav("export-dither", &[0.25]);
+    let destination = export_path("dither");
+    let settings =
+        ExportSettings { bit_depth: BitDepth::Int16, dither: true, ..Default::default() };
+
+    // The dither spreads the samples over the nearest steps without moving their average
+    export(&ExportSource::file(&source), &destination, &settings).unwrap();
+    let (_, samples) = read_wav(&destination);
+    let exact = 0.25 * 32767.0;
+    assert!(samples.iter().all(|sample| (sample - exact).abs() <= 1.5));
+    assert!(samples.iter().any(|sample| *sample != samples[0]));
+    let mean = samples.iter().map(|sample| *sample as f64).sum::<f64>() / samples.len() as f64;
+    assert!((mean - exact as f64).abs() < 0.05);
+}
+
+#[test]
+fn resamples_keeping_the_length() {
+    let source = write_constant_wav("export-resample", &[0.5, -0.25]);
+    let destination = export_path("resample");
+    let settings = ExportSettings {
+        sample_rate: Some(48000),
+        bit_depth: BitDepth::Float32,
+        ..Default::default()
+    };
+
+    export(&ExportSource::file(&source), &destination, &settings).unwrap();
+    let (spec, samples) = read_wav(&destination);
+    assert_eq!((spec.channels, spec.sample_rate), (2, 48000));
+    assert_eq!(samples.len(), 2 * 8917);
+
+    // Away from the edges, where the resampler rings, the level is unchanged
+    for frame in samples.chunks_exact(2).skip(1000).take(6000) {
+        assert!((frame[0] - 0.5).abs() < 0.01 && (frame[1] + 0.25).abs() < 0.01);
+    }
+}
+
+#[test]
+fn normalizes_with_the_preview_effects() {
+    let source = write_ramp_wav("export-effects", 8192);
+    let destination = export_path("effects");
+    let effects = PreviewEffects { reverse: true, fade_out: 0.01, ..Default::default() };
+    let settings = ExportSettings {
+        bit_depth: BitDepth::Float32,
+        normalize: Some(-6.0),
+        effects: Some(effects),
+        ..Default::default()
+    };
+
+    // The ramp is reversed, so that its peak comes first, and fades out to silence
+    export(&ExportSource::file(&source), &destination, &settings).unwrap();
+    let (_, samples) = read_wav(&destination);
+    let peak = 10.0f32.powf(-6.0 / 20.0);
+    assert!((samples[0] - peak).abs() < 1e-6);
+    assert!(samples.iter().all(|sample| *sample <= peak + 1e-6));
+    assert_eq!(samples[samples.len() - 1], 0.0);
+}
+
+#[test]
+fn batches_are_named_after_the_template() {
+    assert_eq!(file_name("{name}-{rate}", "kick", 1, 1, 48000), "kick-48000");
+    assert_eq!(file_name("{index} {name}", "snare", 3, 12, 44100), "03 snare");
+    assert_eq!(file_name("drums/{name}", "hat", 1, 1, 44100), "drums_hat");
+    assert_eq!(file_name(" ", "hat", 1, 1, 44100), "hat");
+
+    // The same file twice and a missing file
+    let source = write_constant_wav("export-batch", &[0.5]);
+    let directory = std::env::temp_dir().join("vsb-export-batch");
+    let _ = std::fs::remove_dir_all(&directory);
+    let sources = [
+        ExportSource::file(&source),
+        ExportSource::region(&source, 0, 100),
+        ExportSource::file(directory.join("missing.wav")),
+    ];
+    let results = export_batch(&sources, &directory, "{name}", &ExportSettings::default());
+    assert_eq!(results[0].as_ref().unwrap(), &directory.join("vsb-engine-export-batch.wav"));
+    assert_eq!(results[1].as_ref().unwrap(), &directory.join("vsb-engine-export-batch-2.wav"));
+    assert!(results[2].is_err());
+    assert_eq!(read_wav(&directory.join("vsb-engine-export-batch-2.wav")).1.len(), 100);
+
+    // The files of an earlier export are kept
+    let results = export_batch(&sources[..1], &directory, "{name}", &ExportSettings::default());
+    assert_eq!(results[0].as_ref().unwrap(), &directory.join("vsb-engine-export-batch-3.wav"));
+    assert_eq!(read_wav(&directory.join("vsb-engine-export-batch-2.wav")).1.len(), 100);
+}
+
+#[test]
+fn never_overwrites_the_source() {
+    let source = write_constant_wav("export-source", &[0.5]);
+    let directory = source.parent().unwrap();
+
+    let settings = ExportSettings::default();
+    assert!(matches!(
+        export(&ExportSource::file(&source), &source, &settings),
+        Err(ExportError::Overwrite(_))
+    ));
+    let results = export_batch(&[ExportSource::file(&source)], directory, "{name}", &settings);
+    assert_ne!(results[0].as_ref().unwrap(), &source);
+    assert_eq!(read_wav(&source).0.sample_format, hound::SampleFormat::Float);
+    std::fs::remove_file(results[0].as_ref().unwrap()).unwrap();
+}
+
+#[test]
+fn keeps_integer_samples_without_reducing_the_bit_depth() {
+    let source = std::env::temp_dir().join("vsb-engine-export-16-bit.wav");
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&source, spec).unwrap();
+    for frame in 0..4096 {
+        writer.write_sample((frame % 2000) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    // Dithering is only needed when the samples lose precision
+    let destination = export_path("16-bit");
+    let settings =
+        ExportSettings { bit_depth: BitDepth::Int24, dither: true, ..Default::default() };
+    export(&ExportSource::file(&source), &destination, &settings).unwrap();
+    let (_, samples) = read_wav(&destination);
+    for (frame, sample) in samples.iter().enumerate() {
+        let exact = (frame % 2000) as f32 / 32768.0 * 8388607.0;
+        assert_eq!(*sample, exact.round());
+    }
+}
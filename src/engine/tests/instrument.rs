@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::engine::{
+    dspreset, export_instrument, map_instrument, sfz, InstrumentSample, InstrumentZone,
+};
+
+fn sample(path: &str, root_note: u8, level: f32) -> InstrumentSample {
+    InstrumentSample { path: PathBuf::from(path), root_note, tune: 0, loop_points: None, level }
+}
+
+// The samples of a small multisample, one of them looped and detuned, in folders next to each other.
+fn multisample(directory: &Path) -> Vec<InstrumentZone> {
+    let samples = directory.join("samples");
+    map_instrument(vec![
+        InstrumentSample {
+            path: samples.join("piano C3 soft.wav"),
+            root_note: 48,
+            tune: -12,
+            loop_points: Some((1000, 40000)),
+            level: 0.25,
+        },
+        InstrumentSample {
+            path: samples.join("piano C3 loud.wav"),
+            root_note: 48,
+            tune: 7,
+            loop_points: None,
+            level: 0.9,
+        },
+        InstrumentSample {
+            path: directory.join("..").join("other & \"odd\"").join("piano <C4>.wav"),
+            root_note: 60,
+            tune: 0,
+            loop_points: Some((0, 1)),
+            level: 0.5,
+        },
+    ])
+}
+
+// Remove the `.` and `..` components of a path without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// The zones as read back from a definition, which has normalized paths and no levels.
+fn as_written(zones: &[InstrumentZone]) -> Vec<InstrumentZone> {
+    zones
+        .iter()
+        .cloned()
+        .map(|mut zone| {
+            zone.sample.path = normalize(&zone.sample.path);
+            zone.sample.level = 0.0;
+            zone
+        })
+        .collect()
+}
+
+// Build a zone from the opcodes or attributes of a region, with the names used by the format.
+fn zone(values: &HashMap<String, String>, names: [&str; 9], directory: &Path) -> InstrumentZone {
+    let [path, root, low_key, high_key, low_velocity, high_velocity, tune, loop_start, loop_end] =
+        names.map(|name| values.get(name));
+    let number = |value: Option<&String>| value.unwrap().parse::<u8>().unwrap();
+    let loop_points = match (loop_start, loop_end) {
+        (Some(start), Some(end)) => Some((start.parse().unwrap(), end.parse::<u64>().unwrap() + 1)),
+        _ => None,
+    };
+
+    InstrumentZone {
+        sample: InstrumentSample {
+            path: normalize(&directory.join(path.unwrap())),
+            root_note: number(root),
+            tune: tune.map_or(0, |tune| tune.parse().unwrap()),
+            loop_points,
+            level: 0.0,
+        },
+        keys: (number(low_key), number(high_key)),
+        velocities: (number(low_velocity), number(high_velocity)),
+    }
+}
+
+// Parse the regions of an SFZ definition, whose sample opcodes take up the rest of their line.
+fn parse_sfz(text: &str, directory: &Path) -> Vec<InstrumentZone> {
+    let mut regions: Vec<HashMap<String, String>> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("//") || line.is_empty() {
+            continue;
+        }
+        if line == "<region>" {
+            regions.push(HashMap::new());
+            continue;
+        }
+        if line.starts_with('<') {
+            continue;
+        }
+
+        let region = regions.last_mut().expect("opcode outside of a region");
+        if let Some(sample) = line.strip_prefix("sample=") {
+            region.insert(String::from("sample"), sample.to_string());
+            continue;
+        }
+        for opcode in line.split_whitespace() {
+            let (name, value) = opcode.split_once('=').expect("opcode without value");
+            region.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    let names = [
+        "sample",
+        "pitch_keycenter",
+        "lokey",
+        "hikey",
+        "lovel",
+        "hivel",
+        "tune",
+        "loop_start",
+        "loop_end",
+    ];
+    regions
+        .iter()
+        .inspect(|region| {
+            let looped = region.get("loop_mode").map(String::as_str) == Some("loop_continuous");
+            assert_eq!(looped, region.contains_key("loop_start"));
+        })
+        .map(|region| zone(region, names, directory))
+        .collect()
+}
+
+// Parse the sample elements of a Decent Sampler preset, converting tunings to cents.
+fn parse_dspreset(text: &str, directory: &Path) -> Vec<InstrumentZone> {
+    assert!(text.starts_with("<?xml") && text.trim_end().ends_with("</DecentSampler>"));
+
+    let names = [
+        "path",
+        "rootNote",
+        "loNote",
+        "hiNote",
+        "loVel",
+        "hiVel",
+        "tuning",
+        "loopStart",
+        "loopEnd",
+    ];
+    text.split("<sample ")
+        .skip(1)
+        .map(|element| {
+            let element = &element[..element.find("/>").expect("unclosed sample element")];
+            let mut attributes = HashMap::new();
+            let mut rest = element.trim();
+            while let Some((name, value)) = rest.split_once("=\"") {
+                let end = value.find('"').expect("unclosed attribute");
+                let unescaped = value[..end]
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&");
+                attributes.insert(name.trim().to_string(), unescaped);
+                rest = &value[end + 1..];
+            }
+
+            if let Some(tuning) = attributes.get_mut("tuning") {
+                *tuning = ((tuning.parse::<f32>().unwrap() * 100.0).round() as i32).to_string();
+            }
+            assert_eq!(
+                attributes.get("loopEnabled").map(String::as_str) == Some("true"),
+                attributes.contains_key("loopStart")
+            );
+            zone(&attributes, names, directory)
+        })
+        .collect()
+}
+
+#[test]
+fn maps_root_notes_across_the_keyboard() {
+    let zones = map_instrument(vec![
+        sample("c4 loud.wav", 60, 0.9),
+        sample("c3.wav", 48, 0.5),
+        sample("c4 soft.wav", 60, 0.1),
+        sample("g4.wav", 67, 0.5),
+        sample("c4 medium.wav", 60, 0.4),
+    ]);
+    let mapping = zones
+        .iter()
+        .map(|zone| (zone.sample.path.to_str().unwrap(), zone.keys, zone.velocities))
+        .collect::<Vec<_>>();
+
+    // The keys are split halfway between the roots, and the layers of C4 from the softest to the loudest
+    assert_eq!(
+        mapping,
+        [
+            ("c3.wav", (0, 54), (1, 127)),
+            ("c4 soft.wav", (55, 63), (1, 42)),
+            ("c4 medium.wav", (55, 63), (43, 84)),
+            ("c4 loud.wav", (55, 63), (85, 127)),
+            ("g4.wav", (64, 127), (1, 127)),
+        ]
+    );
+}
+
+#[test]
+fn sfz_round_trips() {
+    let directory = std::env::temp_dir().join("vsb-instrument");
+    let zones = multisample(&directory);
+
+    let text = sfz(&zones, &directory);
+    assert!(text.contains("sample=samples/piano C3 loud.wav\n"));
+    assert!(text.contains("sample=../other & \"odd\"/piano <C4>.wav\n"));
+    assert_eq!(parse_sfz(&text, &directory), as_written(&zones));
+}
+
+#[test]
+fn dspreset_round_trips() {
+    let directory = std::env::temp_dir().join("vsb-instrument");
+    let zones = multisample(&directory);
+
+    let text = dspreset(&zones, &directory);
+    assert!(text.contains("path=\"../other &amp; &quot;odd&quot;/piano &lt;C4&gt;.wav\""));
+    assert!(text.contains("tuning=\"-0.12\""));
+    assert_eq!(parse_dspreset(&text, &directory), as_written(&zones));
+}
+
+#[test]
+fn exports_the_definitions_next_to_each_other() {
+    let directory = std::env::temp_dir().join("vsb-instrument-export");
+    std::fs::create_dir_all(&directory).unwrap();
+    let zones = multisample(&directory);
+
+    let written = export_instrument(&zones, &directory.join("piano.txt"), true).unwrap();
+    assert_eq!(written, [directory.join("piano.sfz"), directory.join("piano.dspreset")]);
+
+    let sfz = std::fs::read_to_string(&written[0]).unwrap();
+    let dspreset = std::fs::read_to_string(&written[1]).unwrap();
+    assert_eq!(parse_sfz(&sfz, &directory), parse_dspreset(&dspreset, &directory));
+
+    let written = export_instrument(&zones, &directory.join("keys"), false).unwrap();
+    assert_eq!(written, [directory.join("keys.sfz")]);
+}
@@ -0,0 +1,107 @@
+use crate::engine::{
+    export_kit,
+    tests::{write_constant_wav, write_ramp_wav},
+    BitDepth, ExportSettings, KitExportSlot, KitManifest, KitManifestSlot, KIT_MANIFEST_NAME,
+};
+
+#[test]
+fn exports_a_kit_with_its_manifest() {
+    let kick = write_constant_wav("kit-kick", &[0.25, -0.5]);
+    let snare = write_ramp_wav("kit-snare", 4096);
+    let slots = [
+        KitExportSlot { name: String::from("Kick"), path: Some(kick.clone()) },
+        KitExportSlot { name: String::from("Closed Hat"), path: None },
+        KitExportSlot { name: String::from("Snare/Rim"), path: Some(snare.clone()) },
+    ];
+    let settings = ExportSettings {
+        sample_rate: Some(48000),
+        bit_depth: BitDepth::Int16,
+        dither: false,
+        normalize: Some(-6.0),
+        effects: None,
+    };
+
+    let directory = std::env::temp_dir().join("vsb-kit-export");
+    let _ = std::fs::remove_dir_all(&directory);
+    let export = export_kit("Break: Funky", &slots, &directory, &settings).unwrap();
+    assert_eq!(export.folder, directory.join("Break_ Funky"));
+    assert!(export.errors.is_empty());
+    let folder = export.folder;
+
+    // Only the filled slots are written, named after their position and name
+    let mut files: Vec<String> = std::fs::read_dir(&folder)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["01 Kick.wav", "03 Snare_Rim.wav", KIT_MANIFEST_NAME]);
+
+    // The files are converted and normalized
+    let mut reader = hound::WavReader::open(folder.join("01 Kick.wav")).unwrap();
+    let spec = reader.spec();
+    assert_eq!((spec.channels, spec.sample_rate, spec.bits_per_sample), (2, 48000, 16));
+    let peak = reader.samples::<i32>().map(|sample| sample.unwrap().abs()).max().unwrap();
+    let expected = 10f32.powf(-6.0 / 20.0) * 32767.0;
+    assert!((peak as f32 - expected).abs() < 2.0, "{peak}");
+
+    let manifest = std::fs::read_to_string(folder.join(KIT_MANIFEST_NAME)).unwrap();
+    let manifest: KitManifest = ron::from_str(&manifest).unwrap();
+    assert_eq!(
+        manifest,
+        KitManifest {
+            name: String::from("Break: Funky"),
+            sample_rate: Some(48000),
+            bit_depth: 16,
+            float: false,
+            normalize: Some(-6.0),
+            slots: vec![
+                KitManifestSlot {
+                    name: String::from("Kick"),
+                    file: Some(String::from("01 Kick.wav")),
+                    source: Some(kick),
+                },
+                KitManifestSlot { name: String::from("Closed Hat"), file: None, source: None },
+                KitManifestSlot {
+                    name: String::from("Snare/Rim"),
+                    file: Some(String::from("03 Snare_Rim.wav")),
+                    source: Some(snare),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn exports_a_kit_into_a_new_folder_despite_failed_slots() {
+    let kick = write_constant_wav("kit-kick-again", &[0.25, -0.5]);
+    let missing = std::env::temp_dir().join("vsb-kit-missing.wav");
+    let slots = [
+        KitExportSlot { name: String::from("Kick"), path: Some(kick) },
+        KitExportSlot { name: String::from("Snare"), path: Some(missing.clone()) },
+    ];
+    let settings = ExportSettings {
+        sample_rate: None,
+        bit_depth: BitDepth::Int16,
+        dither: false,
+        normalize: None,
+        effects: None,
+    };
+
+    let directory = std::env::temp_dir().join("vsb-kit-export-again");
+    let _ = std::fs::remove_dir_all(&directory);
+    let first = export_kit("Kit", &slots, &directory, &settings).unwrap();
+    let second = export_kit("Kit", &slots, &directory, &settings).unwrap();
+    assert_eq!(first.folder, directory.join("Kit"));
+    assert_eq!(second.folder, directory.join("Kit-2"));
+
+    // The slot which failed is left out, and the manifest is still written
+    assert_eq!(second.errors.len(), 1);
+    assert_eq!(second.errors[0].0, "Snare");
+    let manifest = std::fs::read_to_string(second.folder.join(KIT_MANIFEST_NAME)).unwrap();
+    let manifest: KitManifest = ron::from_str(&manifest).unwrap();
+    assert_eq!(manifest.slots[0].file.as_deref(), Some("01 Kick.wav"));
+    assert_eq!(
+        manifest.slots[1],
+        KitManifestSlot { name: String::from("Snare"), file: None, source: Some(missing) }
+    );
+}
@@ -0,0 +1,55 @@
+use basedrop::Owned;
+
+use crate::engine::{
+    tests::{assert_frame, offline_player, write_constant_wav, SAMPLE_RATE},
+    DecodedAudio, LayerMix,
+};
+
+#[test]
+fn layers_are_mixed_with_the_preview() {
+    let path = write_constant_wav("layered-preview", &[0.25]);
+    let layer = write_constant_wav("layer", &[0.5]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    let audio = DecodedAudio::read(&layer, false).unwrap();
+    controller.add_layer(Owned::new(&collector.handle(), audio), LayerMix::default());
+    controller.play();
+    let output = backend.render(4096);
+    assert_frame(&output[output.len() - 2..], &[0.75, 0.75]);
+
+    // Panned right and 6 dB down
+    controller.set_layer_mix(0, LayerMix { gain: -6.0206, pan: 1.0, muted: false });
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.25, 0.5]);
+
+    controller.set_layer_mix(0, LayerMix { muted: true, ..LayerMix::default() });
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.25, 0.25]);
+
+    controller.remove_layer(0);
+    controller.set_layer_mix(0, LayerMix::default());
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.25, 0.25]);
+}
+
+#[test]
+fn layers_restart_with_the_preview() {
+    let path = write_constant_wav("restarted-preview", &[0.0]);
+    // A layer of 8192 frames, which has ended after the preview has played for longer
+    let layer = write_constant_wav("short-layer", &[0.5]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    let audio = DecodedAudio::read(&layer, false).unwrap();
+    controller.add_layer(Owned::new(&collector.handle(), audio), LayerMix::default());
+    controller.play();
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.5, 0.5]);
+
+    let output = backend.render(8192);
+    assert_frame(&output[output.len() - 2..], &[0.0, 0.0]);
+
+    controller.stop();
+    controller.play();
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.5, 0.5]);
+}
@@ -0,0 +1,45 @@
+use std::f32::consts::PI;
+
+use crate::engine::{
+    tests::{offline_player, write_constant_wav, write_float_wav, SAMPLE_RATE},
+    SPECTRUM_BANDS,
+};
+
+#[test]
+fn measures_the_output_levels() {
+    let path = write_constant_wav("levels", &[0.25, -0.5]);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.play();
+    backend.render(4096);
+    let (peak, rms) = controller.levels();
+    for (level, expected) in peak.iter().chain(&rms).zip([0.25, 0.5, 0.25, 0.5]) {
+        assert!((level - expected).abs() < 1e-4, "{:?} {:?}", peak, rms);
+    }
+
+    // The levels drop to silence once playback stops
+    controller.stop();
+    backend.render(1024);
+    assert_eq!(controller.levels(), ([0.0; 2], [0.0; 2]));
+}
+
+#[test]
+fn analyzes_the_spectrum_when_enabled() {
+    // A half scale 2 kHz sine, in the 21st band of the spectrum
+    let sine = (0..16384).map(|i| 0.5 * (2.0 * PI * 2000.0 * i as f32 / SAMPLE_RATE as f32).sin());
+    let path = write_float_wav("spectrum", 1, sine);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.play();
+    backend.render(4096);
+    let silent = controller.spectrum(SAMPLE_RATE as f32);
+    assert_eq!(silent.len(), SPECTRUM_BANDS);
+    assert!(silent.iter().all(|level| *level == silent[0]));
+
+    controller.set_spectrum_enabled(true);
+    backend.render(4096);
+    let bands = controller.spectrum(SAMPLE_RATE as f32);
+    let loudest = (0..SPECTRUM_BANDS).max_by(|a, b| bands[*a].total_cmp(&bands[*b])).unwrap();
+    assert_eq!(loudest, 20);
+    assert!((bands[20] + 6.0).abs() < 2.0, "{:?}", bands);
+}
@@ -0,0 +1,72 @@
+use crate::engine::{
+    tests::{offline_player, open_stream, write_constant_wav, SAMPLE_RATE},
+    Metronome,
+};
+
+// The frames of the left channel of stereo output which are not silent.
+fn sounding_frames(output: &[f32]) -> Vec<usize> {
+    output
+        .chunks_exact(2)
+        .enumerate()
+        .filter(|(_, frame)| frame[0] != 0.0)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[test]
+fn clicks_on_each_beat() {
+    let path = write_constant_wav("metronome-preview", &[0.0]);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    // Beats of 22050 frames at 120 BPM, the clicks lasting 30 ms, rendered up to before the third beat
+    controller.set_metronome(120.0, 4, 4, true);
+    let sounding = sounding_frames(&backend.render(32768));
+    let (first, second): (Vec<usize>, Vec<usize>) =
+        sounding.into_iter().partition(|frame| *frame < 22050);
+    assert!(first.first().is_some_and(|frame| *frame <= 1));
+    assert!(first.last().is_some_and(|frame| *frame < 1323));
+    assert!(second.first().is_some_and(|frame| *frame <= 22051));
+    assert!(second.last().is_some_and(|frame| *frame < 22050 + 1323));
+
+    controller.set_metronome(120.0, 4, 4, false);
+    assert!(sounding_frames(&backend.render(SAMPLE_RATE as usize)).is_empty());
+}
+
+#[test]
+fn counts_frames_to_the_next_bar() {
+    let sample_rate = SAMPLE_RATE as f64;
+    let mut metronome = Metronome::default();
+
+    // Bars of six eighth notes at 120 quarter notes per minute
+    metronome.set(120.0, 6, 8, false);
+    assert_eq!(metronome.frames_to_next_bar(sample_rate), 0);
+    metronome.next_frame(sample_rate);
+    assert_eq!(metronome.frames_to_next_bar(sample_rate), 66149);
+    for _ in 0..66149 {
+        metronome.next_frame(sample_rate);
+    }
+    assert_eq!(metronome.frames_to_next_bar(sample_rate), 0);
+}
+
+#[test]
+fn quantized_start_waits_for_the_next_bar() {
+    let first = write_constant_wav("quantized-first", &[0.25]);
+    let second = write_constant_wav("quantized-second", &[0.5]);
+    let (mut backend, mut controller, collector) = offline_player(&first, SAMPLE_RATE, 2);
+
+    // Nothing is playing and the metronome is silent, so the first file starts a bar
+    controller.set_metronome(120.0, 4, 4, false);
+    controller.set_quantize(true);
+    controller.play();
+    let output = backend.render(10240);
+    assert!(output.chunks_exact(2).last().is_some_and(|frame| frame[0] == 0.25));
+
+    // Selected during playback, the second file starts on the bar after 88200 frames
+    controller.load_file(open_stream(&second, &collector));
+    controller.play();
+    let output = backend.render(80000);
+    let left: Vec<f32> = output.chunks_exact(2).map(|frame| frame[0]).collect();
+    let bar = 88200 - 10240;
+    assert!(left[..bar].iter().all(|sample| *sample == 0.0));
+    assert!(left[bar..].iter().all(|sample| *sample == 0.5));
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use basedrop::{Collector, Owned};
+use creek::{ReadDiskStream, SymphoniaDecoder};
+
+use super::{sample_player, OfflineBackend, SamplePlayerController};
+
+pub mod channels;
+pub mod export;
+pub mod instrument;
+pub mod kit;
+pub mod layers;
+pub mod meter;
+pub mod metronome;
+pub mod pads;
+pub mod player;
+
+const SAMPLE_RATE: u32 = 44100;
+
+// The number of frames rendered in each call of the player
+const BLOCK_FRAMES: usize = 256;
+
+// Write a 32-bit float file to the system temp directory whose channels hold the given constant values.
+fn write_constant_wav(name: &str, values: &[f32]) -> PathBuf {
+    let frames = vec![values.to_vec(); 8192];
+    write_float_wav(name, values.len() as u16, frames.iter().flatten().copied())
+}
+
+// Write a mono 32-bit float file of `len` frames to the system temp directory, whose frame `i` has the value
+// of `ramp_value(i)`, so that the position of each output frame is known.
+fn write_ramp_wav(name: &str, len: usize) -> PathBuf {
+    write_float_wav(name, 1, (0..len).map(ramp_value))
+}
+
+fn ramp_value(frame: usize) -> f32 {
+    (frame + 1) as f32 / 65536.0
+}
+
+fn write_float_wav(name: &str, channels: u16, samples: impl Iterator<Item = f32>) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vsb-engine-{}.wav", name));
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    for sample in samples {
+        writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    path
+}
+
+// A sample player rendering offline to a device with the given sample rate and number of channels, with the
+// file at `path` loaded.
+fn offline_player(
+    path: &Path,
+    sample_rate: u32,
+    num_channels: u16,
+) -> (OfflineBackend, SamplePlayerController, Collector) {
+    let collector = Collector::new();
+    let (process, mut controller) = sample_player(&collector);
+    controller.load_file(open_stream(path, &collector));
+
+    (OfflineBackend::new(process, sample_rate, num_channels, BLOCK_FRAMES), controller, collector)
+}
+
+// Open the file at `path` as a stream ready to be loaded into a sample player.
+fn open_stream(path: &Path, collector: &Collector) -> Owned<ReadDiskStream<SymphoniaDecoder>> {
+    let mut read_stream =
+        ReadDiskStream::<SymphoniaDecoder>::new(path, 0, Default::default()).unwrap();
+    read_stream.block_until_ready().unwrap();
+    Owned::new(&collector.handle(), read_stream)
+}
+
+// Play a file through a sample player to a device with `num_channels` channels for a few blocks, after
+// configuring the player with `setup`, and return the last frame of the output.
+fn render_last_frame(
+    path: &Path,
+    num_channels: usize,
+    setup: impl FnOnce(&mut SamplePlayerController),
+) -> Vec<f32> {
+    let (mut backend, mut controller, _collector) =
+        offline_player(path, SAMPLE_RATE, num_channels as u16);
+    setup(&mut controller);
+    controller.play();
+
+    let output = backend.render(BLOCK_FRAMES * 8);
+    output[output.len() - num_channels..].to_vec()
+}
+
+// The left channel of stereo output, without the silence before playback starts.
+fn left_channel(output: &[f32]) -> Vec<f32> {
+    output.chunks_exact(2).map(|frame| frame[0]).skip_while(|sample| *sample == 0.0).collect()
+}
+
+fn assert_frame(frame: &[f32], expected: &[f32]) {
+    assert_eq!(frame.len(), expected.len());
+    for (sample, expected) in frame.iter().zip(expected) {
+        assert!((sample - expected).abs() < 1e-4, "{:?} != {:?}", frame, expected);
+    }
+}
@@ -0,0 +1,122 @@
+use basedrop::{Collector, Owned};
+
+use crate::engine::{
+    tests::{assert_frame, offline_player, write_constant_wav, SAMPLE_RATE},
+    virtual_midi, DecodedAudio, MidiMessage, PadMode, PadSettings, FIRST_PAD_NOTE,
+};
+
+// A decoded sample of `len` frames holding `value` on both channels.
+fn constant_audio(collector: &Collector, value: f32, len: usize) -> Owned<DecodedAudio> {
+    let audio =
+        DecodedAudio { left: vec![value; len], right: vec![value; len], sample_rate: SAMPLE_RATE };
+    Owned::new(&collector.handle(), audio)
+}
+
+#[test]
+fn pads_play_over_the_stopped_preview() {
+    let path = write_constant_wav("pads-preview", &[0.0]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.set_pad(0, Some(constant_audio(&collector, 0.25, 4096)));
+    controller.trigger_pad(0, 1.0);
+    let output = backend.render(1024);
+    assert_frame(&output[..2], &[0.25, 0.25]);
+    assert_frame(&output[output.len() - 2..], &[0.25, 0.25]);
+
+    // Retriggering a one-shot pad plays another voice over the first, at the given velocity
+    controller.trigger_pad(0, 0.5);
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.375, 0.375]);
+
+    // The voices end with the sample
+    let output = backend.render(4096);
+    assert_frame(&output[output.len() - 2..], &[0.0, 0.0]);
+
+    // Empty pads are silent
+    controller.trigger_pad(1, 1.0);
+    let output = backend.render(1024);
+    assert_frame(&output[..2], &[0.0, 0.0]);
+}
+
+#[test]
+fn gate_pads_stop_when_released() {
+    let path = write_constant_wav("gate-preview", &[0.0]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.set_pad(0, Some(constant_audio(&collector, 0.5, 8192)));
+    controller.set_pad(1, Some(constant_audio(&collector, 0.25, 8192)));
+    controller.set_pad_settings(0, PadSettings { mode: PadMode::Gate, choke_group: 0 });
+    controller.trigger_pad(0, 1.0);
+    controller.trigger_pad(1, 1.0);
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.75, 0.75]);
+
+    // The gate pad fades out, while the one-shot pad plays on
+    controller.release_pad(0);
+    controller.release_pad(1);
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.25, 0.25]);
+}
+
+#[test]
+fn choke_groups_cut_off_their_pads() {
+    let path = write_constant_wav("choke-preview", &[0.0]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    // An open and a closed hi-hat in the same group, and a kick in none
+    let choked = PadSettings { mode: PadMode::OneShot, choke_group: 1 };
+    for (pad, value) in [(0, 0.5), (1, 0.25), (2, 0.125)] {
+        controller.set_pad(pad, Some(constant_audio(&collector, value, 8192)));
+    }
+    controller.set_pad_settings(0, choked);
+    controller.set_pad_settings(1, choked);
+
+    controller.trigger_pad(0, 1.0);
+    controller.trigger_pad(2, 1.0);
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.625, 0.625]);
+
+    controller.trigger_pad(1, 1.0);
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.375, 0.375]);
+}
+
+#[test]
+fn midi_notes_play_the_pads() {
+    let path = write_constant_wav("midi-preview", &[0.0]);
+    let (mut backend, mut controller, collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    let (mut midi_output, midi_input) = virtual_midi();
+    controller.set_midi_input(Some(Box::new(midi_input)));
+    controller.set_pad(1, Some(constant_audio(&collector, 0.5, 8192)));
+    controller.set_pad_settings(1, PadSettings { mode: PadMode::Gate, choke_group: 0 });
+
+    // A note on the second pad at half velocity, on the tenth channel used for drums
+    assert!(midi_output.send(&[0x99, FIRST_PAD_NOTE + 1, 64]));
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.25196, 0.25196]);
+
+    // A note on with a velocity of 0 releases the pad
+    assert!(midi_output.send(&[0x99, FIRST_PAD_NOTE + 1, 0]));
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.0, 0.0]);
+
+    // Notes below the pads and other messages are ignored
+    assert!(midi_output.send(&[0x90, FIRST_PAD_NOTE - 1, 127]));
+    assert!(!midi_output.send(&[0xb0, 1, 127]));
+    let output = backend.render(1024);
+    assert_frame(&output[output.len() - 2..], &[0.0, 0.0]);
+}
+
+#[test]
+fn midi_messages_round_trip() {
+    let note_on = MidiMessage::NoteOn { channel: 9, note: FIRST_PAD_NOTE + 15, velocity: 100 };
+    assert_eq!(MidiMessage::parse(&note_on.to_bytes()), Some(note_on));
+    assert_eq!(note_on.pad(), Some(15));
+
+    let note_off = MidiMessage::NoteOff { channel: 0, note: FIRST_PAD_NOTE + 16 };
+    assert_eq!(MidiMessage::parse(&note_off.to_bytes()), Some(note_off));
+    assert_eq!(note_off.pad(), None);
+
+    assert_eq!(MidiMessage::parse(&[0x90, 60]), None);
+}
@@ -0,0 +1,168 @@
+use crate::engine::tests::{
+    left_channel, offline_player, open_stream, ramp_value, write_constant_wav, write_ramp_wav,
+    SAMPLE_RATE,
+};
+
+// Check that `samples` are the frames of a ramp file starting at `first_frame`.
+fn assert_ramp(samples: &[f32], first_frame: usize) {
+    for (i, sample) in samples.iter().enumerate() {
+        let expected = ramp_value(first_frame + i);
+        assert!((sample - expected).abs() < 1e-6, "frame {}: {} != {}", i, sample, expected);
+    }
+}
+
+// The change of a ramp file's value per output frame over the middle of `samples`.
+fn slope(samples: &[f32]) -> f32 {
+    let (start, end) = (samples.len() / 4, samples.len() * 3 / 4);
+    (samples[end] - samples[start]) / (end - start) as f32
+}
+
+#[test]
+fn plays_from_the_start() {
+    let path = write_ramp_wav("play", 16384);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    // Nothing is rendered until playback starts
+    assert!(backend.render(1024).iter().all(|sample| *sample == 0.0));
+
+    controller.play();
+    let output = backend.render(4096);
+    let left = left_channel(&output);
+    assert!(left.len() >= 3000);
+    assert_ramp(&left, 0);
+
+    // Both channels of a mono file are the same
+    assert!(output.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+}
+
+#[test]
+fn loops_between_loop_points() {
+    let path = write_ramp_wav("loop", 16384);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.set_loop(1000, 2000);
+    controller.seek(1500);
+    controller.play();
+    let left = left_channel(&backend.render(4096));
+
+    assert_ramp(&left[..500], 1500);
+    assert_ramp(&left[500..1500], 1000);
+    assert_ramp(&left[1500..2500], 1000);
+}
+
+#[test]
+fn stops_at_the_end_without_looping() {
+    let path = write_ramp_wav("end", 4096);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.toggle_looping();
+    controller.seek(3000);
+    controller.play();
+    let left = left_channel(&backend.render(4096));
+
+    assert_ramp(&left[..1096], 3000);
+    assert!(left[1096..].iter().all(|sample| *sample == 0.0));
+}
+
+#[test]
+fn seeks_during_playback() {
+    let path = write_ramp_wav("seek", 16384);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.play();
+    backend.render(1024);
+
+    // The output continues from the new position without the frames queued before seeking
+    controller.seek(5000);
+    let left = left_channel(&backend.render(2048));
+    assert!(left.len() >= 1000);
+    assert_ramp(&left, 5000);
+}
+
+#[test]
+fn pause_resumes_and_stop_rewinds() {
+    let path = write_ramp_wav("pause", 16384);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.play();
+    let played = left_channel(&backend.render(1024)).len();
+
+    controller.pause();
+    assert!(backend.render(1024).iter().all(|sample| *sample == 0.0));
+
+    controller.play();
+    let left = left_channel(&backend.render(1024));
+    assert_ramp(&left, played);
+
+    controller.stop();
+    assert!(backend.render(1024).iter().all(|sample| *sample == 0.0));
+
+    controller.play();
+    let left = left_channel(&backend.render(1024));
+    assert_ramp(&left, 0);
+}
+
+#[test]
+fn resamples_to_the_device_rate() {
+    let path = write_ramp_wav("resample", 16384);
+    let (mut backend, mut controller, _collector) = offline_player(&path, 48000, 2);
+
+    controller.play();
+    let left = left_channel(&backend.render(8192));
+    let expected = 44100.0 / 48000.0 / 65536.0;
+    assert!((slope(&left) / expected - 1.0).abs() < 0.005, "{}", slope(&left) / expected);
+}
+
+#[test]
+fn playback_rate_changes_speed() {
+    let path = write_ramp_wav("rate", 32768);
+    let (mut backend, mut controller, _collector) = offline_player(&path, SAMPLE_RATE, 2);
+
+    controller.set_playback_rate(2.0);
+    controller.play();
+    let left = left_channel(&backend.render(8192));
+    let expected = 2.0 / 65536.0;
+    assert!((slope(&left) / expected - 1.0).abs() < 0.005, "{}", slope(&left) / expected);
+}
+
+#[test]
+fn crossfades_to_the_next_file() {
+    let first = write_constant_wav("crossfade-first", &[0.5]);
+    let second = write_constant_wav("crossfade-second", &[-0.25]);
+    let (mut backend, mut controller, collector) = offline_player(&first, SAMPLE_RATE, 2);
+
+    controller.set_crossfade(0.01);
+    controller.play();
+    backend.render(2048);
+
+    // The output moves smoothly from one file to the other, without a gap
+    controller.load_file(open_stream(&second, &collector));
+    controller.play();
+    let left = left_channel(&backend.render(4096));
+    assert!((left[0] - 0.5).abs() < 1e-4);
+    assert!((left[left.len() - 1] + 0.25).abs() < 1e-4);
+    for pair in left.windows(2) {
+        assert!((pair[1] - pair[0]).abs() < 0.01, "{} -> {}", pair[0], pair[1]);
+    }
+}
+
+#[test]
+fn crossfades_longer_than_a_buffer() {
+    let first = write_constant_wav("long-crossfade-first", &[0.5]);
+    let second = write_constant_wav("long-crossfade-second", &[-0.25]);
+    let (mut backend, mut controller, collector) = offline_player(&first, SAMPLE_RATE, 2);
+
+    // The tail of the first file is rendered over several buffers before the second is loaded
+    controller.set_crossfade(0.1);
+    controller.play();
+    backend.render(2048);
+
+    controller.load_file(open_stream(&second, &collector));
+    controller.play();
+    let left = left_channel(&backend.render(8192));
+    assert!((left[0] - 0.5).abs() < 1e-4);
+    assert!((left[left.len() - 1] + 0.25).abs() < 1e-4);
+    for pair in left.windows(2) {
+        assert!((pair[1] - pair[0]).abs() < 0.01, "{} -> {}", pair[0], pair[1]);
+    }
+}
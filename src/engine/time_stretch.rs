@@ -0,0 +1,227 @@
+//! Time stretching with WSOLA, which changes the tempo of a stereo signal without changing its pitch.
+//!
+//! Grains of the input are overlap-added at a fixed output hop, while the input is advanced by the hop times
+//! the speed. Each grain is moved by up to a few milliseconds to line up with the end of the previous one, so
+//! that the overlap does not cancel out.
+
+use std::collections::VecDeque;
+
+// The length of a grain in frames, about 46 ms at 44.1 kHz
+const GRAIN: usize = 2048;
+
+// The output hop between grains, which overlap by half
+const HOP: usize = GRAIN / 2;
+
+// The farthest a grain is moved from its nominal position to line up with the previous one
+const TOLERANCE: usize = 512;
+
+// The length of the overlap compared when lining up a grain, and the stride of the comparison
+const OVERLAP: usize = 512;
+const OVERLAP_STRIDE: usize = 4;
+
+// Frames are queued without allocating up to this length
+const QUEUE_CAPACITY: usize = 16384;
+
+pub struct TimeStretch {
+    // The input tempo divided by the output tempo, 1.0 passes the input through unchanged
+    speed: f64,
+
+    // The input frames which are not consumed yet
+    input: [VecDeque<f32>; 2],
+    // The nominal start of the next grain in `input`
+    position: f64,
+    // Where the input continues from the end of the last grain, `None` before the first grain
+    continuation: Option<usize>,
+
+    // The overlap-added grains, of which the first hop is complete
+    accumulator: [Vec<f32>; 2],
+    output: [VecDeque<f32>; 2],
+
+    window: Vec<f32>,
+    // The sum of the channels of the input, used to line up the grains
+    mix: Vec<f32>,
+}
+
+impl Default for TimeStretch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeStretch {
+    pub fn new() -> Self {
+        // A periodic Hann window, whose halves sum to one
+        let window = (0..GRAIN)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / GRAIN as f32).cos())
+            .collect();
+
+        Self {
+            speed: 1.0,
+            input: [
+                VecDeque::with_capacity(QUEUE_CAPACITY),
+                VecDeque::with_capacity(QUEUE_CAPACITY),
+            ],
+            position: 0.0,
+            continuation: None,
+            accumulator: [vec![0.0; GRAIN], vec![0.0; GRAIN]],
+            output: [
+                VecDeque::with_capacity(QUEUE_CAPACITY),
+                VecDeque::with_capacity(QUEUE_CAPACITY),
+            ],
+            window,
+            mix: Vec::with_capacity(QUEUE_CAPACITY),
+        }
+    }
+
+    /// Play the input `speed` times faster without changing its pitch.
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed == 1.0 {
+            if let Some(continuation) = self.continuation.take() {
+                // The rest of the last grain fades out as the input continuing from it fades in, so passing the
+                // input through from there is seamless
+                for input in self.input.iter_mut() {
+                    input.drain(..continuation.min(input.len()));
+                }
+                for accumulator in self.accumulator.iter_mut() {
+                    accumulator.fill(0.0);
+                }
+                self.position = 0.0;
+
+                for (input, output) in self.input.iter_mut().zip(self.output.iter_mut()) {
+                    output.extend(input.drain(..));
+                }
+            }
+        }
+
+        self.speed = speed;
+    }
+
+    /// Discard the queued frames, e.g. after seeking.
+    pub fn reset(&mut self) {
+        for channel in 0..2 {
+            self.input[channel].clear();
+            self.output[channel].clear();
+            self.accumulator[channel].fill(0.0);
+        }
+        self.position = 0.0;
+        self.continuation = None;
+    }
+
+    /// The number of output frames ready to be read.
+    pub fn available(&self) -> usize {
+        self.output[0].len()
+    }
+
+    /// The number of input frames needed before `frames` output frames are available.
+    pub fn input_needed(&self, frames: usize) -> usize {
+        let missing = frames.saturating_sub(self.available());
+        if missing == 0 {
+            return 0;
+        }
+
+        if self.is_passthrough() {
+            return missing;
+        }
+
+        let grains = missing.div_ceil(HOP);
+        let end = self.position + ((grains - 1) * HOP) as f64 * self.speed;
+        (end as usize + TOLERANCE + GRAIN).saturating_sub(self.input[0].len()).max(1)
+    }
+
+    /// Add frames of the left and right channels to the input.
+    pub fn push(&mut self, left: &[f32], right: &[f32]) {
+        if self.is_passthrough() {
+            self.output[0].extend(left);
+            self.output[1].extend(right);
+            return;
+        }
+
+        self.input[0].extend(left);
+        self.input[1].extend(right);
+
+        while self.input[0].len() >= self.grain_end() {
+            self.add_grain();
+        }
+    }
+
+    /// Move `frames` output frames into the start of `left` and `right`, padded with silence if fewer are
+    /// available.
+    pub fn pop(&mut self, left: &mut [f32], right: &mut [f32], frames: usize) {
+        for (output, channel) in self.output.iter_mut().zip([left, right]) {
+            for sample in channel[..frames].iter_mut() {
+                *sample = output.pop_front().unwrap_or(0.0);
+            }
+        }
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.speed == 1.0 && self.continuation.is_none()
+    }
+
+    // The length of the input needed to add the next grain.
+    fn grain_end(&self) -> usize {
+        let nominal = self.position.round() as usize + TOLERANCE + GRAIN;
+        self.continuation.map_or(nominal, |continuation| nominal.max(continuation + OVERLAP))
+    }
+
+    fn add_grain(&mut self) {
+        for input in self.input.iter_mut() {
+            input.make_contiguous();
+        }
+
+        let nominal = self.position.round() as usize;
+        let start = match self.continuation {
+            Some(continuation) => self.best_start(nominal, continuation),
+            None => nominal,
+        };
+
+        for channel in 0..2 {
+            let input = &self.input[channel].as_slices().0[start..start + GRAIN];
+            let accumulator = &mut self.accumulator[channel];
+
+            for (i, sample) in input.iter().enumerate() {
+                // The first grain starts at full level
+                let gain = match self.continuation {
+                    None if i < HOP => 1.0,
+                    _ => self.window[i],
+                };
+                accumulator[i] += sample * gain;
+            }
+
+            self.output[channel].extend(&accumulator[..HOP]);
+            accumulator.copy_within(HOP.., 0);
+            accumulator[GRAIN - HOP..].fill(0.0);
+        }
+
+        let continuation = start + HOP;
+        self.position += HOP as f64 * self.speed;
+
+        // Drop the input before the earliest start of the next grain
+        let consumed = (self.position as usize).saturating_sub(TOLERANCE).min(continuation);
+        for input in self.input.iter_mut() {
+            input.drain(..consumed);
+        }
+        self.position -= consumed as f64;
+        self.continuation = Some(continuation - consumed);
+    }
+
+    // The start near `nominal` whose overlap best matches the input at `continuation`.
+    fn best_start(&mut self, nominal: usize, continuation: usize) -> usize {
+        let (left, right) = (self.input[0].as_slices().0, self.input[1].as_slices().0);
+        let end = (nominal + TOLERANCE + OVERLAP).max(continuation + OVERLAP);
+
+        self.mix.clear();
+        self.mix.extend(left[..end].iter().zip(&right[..end]).map(|(l, r)| l + r));
+
+        let target = &self.mix[continuation..continuation + OVERLAP];
+        (nominal.saturating_sub(TOLERANCE)..=nominal + TOLERANCE)
+            .map(|start| {
+                let candidate = &self.mix[start..start + OVERLAP];
+                let correlation: f32 =
+                    candidate.iter().zip(target).step_by(OVERLAP_STRIDE).map(|(a, b)| a * b).sum();
+                (start, correlation)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(nominal, |(start, _)| start)
+    }
+}
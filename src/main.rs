@@ -41,6 +41,8 @@ use engine::*;
 
 mod menus;
 
+mod cli;
+
 #[derive(Debug, Error)]
 #[error("App Error: ")]
 pub enum AppError {
@@ -50,32 +52,22 @@ pub enum AppError {
 }
 
 fn main() -> Result<(), AppError> {
+    // Commands such as `export` run without opening the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Initialize gc
     let collector = Collector::new();
 
-    let host = cpal::default_host();
-    let output_device = host.default_output_device().expect("no output found");
-    let config = output_device.default_output_config().expect("no default output config").config();
-
-    let sample_rate = config.sample_rate.0 as f64;
-    let num_channels = config.channels as usize;
-
-    // Create the sample player and controller
-    let (mut player, mut controller) = sample_player(&collector);
-
-    let callback = move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-        let buffer_size = data.len() / num_channels;
-        let context =
-            PlaybackContext { buffer_size, num_channels, sample_rate, output_buffer: data };
-
-        player.process(context);
-    };
-
-    let stream = output_device
-        .build_output_stream(&config, callback, |err| eprintln!("{}", err), None)
-        .expect("failed to open stream");
-
-    stream.play();
+    // Create the sample player and controller. The output stream is opened once the configuration is loaded.
+    let (player, controller) = sample_player(&collector);
+    let output = Box::new(CpalBackend::new(player));
 
     let icon = image::ImageReader::new(std::io::Cursor::new(include_bytes!(
         "../resources/icons/icon_32.png"
@@ -107,11 +99,13 @@ fn main() -> Result<(), AppError> {
 
         let timer =
             cx.add_timer(Duration::from_millis(10), None, |cx, action| cx.emit(AppEvent::Tick));
+        let output_timer =
+            cx.add_timer(Duration::from_secs(1), None, |cx, _| cx.emit(AppEvent::CheckOutput));
 
         // Uncomment to test in Spanish
         // cx.emit(EnvironmentEvent::SetLocale(langid!("es")));
 
-        AppData::new(collector, controller, timer).build(cx);
+        AppData::new(collector, controller, output, timer, output_timer).build(cx);
 
         cx.emit(ConfigEvent::Load);
 
@@ -129,6 +123,9 @@ fn main() -> Result<(), AppError> {
 
         about_dialog(cx, icon_clone.clone());
         settings_dialog(cx, AppData::settings_data, icon_clone.clone());
+        index_report_dialog(cx, icon_clone.clone());
+        export_dialog(cx, icon_clone.clone());
+        instrument_dialog(cx, icon_clone.clone());
 
         HStack::new(cx, |cx| {
             menu_bar(cx);
@@ -153,6 +150,9 @@ fn main() -> Result<(), AppError> {
                             SidebarView::Tags => {
                                 TagsPanel::new(cx);
                             }
+                            SidebarView::Kits => {
+                                KitsPanel::new(cx);
+                            }
                         },
                     );
                     // ResizableStack::new(
@@ -202,12 +202,15 @@ fn main() -> Result<(), AppError> {
                 WavePanel::new(cx).display(AppData::config.then(Config::waveview_visible));
             })
             .vertical_gap(Pixels(1.0));
+
+            PadsPanel::new(cx).display(AppData::config.then(Config::pads_visible));
+            InspectorPanel::new(cx).display(AppData::config.then(Config::inspector_visible));
         })
         .class("content")
         .horizontal_gap(Pixels(1.0))
         .size(Stretch(1.0));
 
-        HStack::new(cx, |cx| {}).class("bottom-bar");
+        StatusBar::new(cx).class("bottom-bar");
     })
     .title("Vizia Sample Browser")
     .inner_size(AppData::config.then(Config::window_size))
@@ -10,8 +10,9 @@ use vizia::{
 };
 
 use crate::{
-    data::AppData, data::SidebarView, AppEvent, Config, ConfigEvent, SampleEvent,
-    SamplePlayerController, SamplesData, SettingsEvent,
+    data::AppData, data::ExportEvent, data::InstrumentEvent, data::SidebarView, data::SlicesData,
+    data::SlicesEvent, database::prelude::key_name, engine::ChannelSolo, AppEvent, Config,
+    ConfigEvent, SampleEvent, SamplePlayerController, SamplesData, SettingsEvent,
 };
 
 pub fn file_menu(cx: &mut Context) {
@@ -85,6 +86,36 @@ pub fn file_menu(cx: &mut Context) {
                     );
                 },
             );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(AppEvent::CloseLibrary),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("close-collection"));
+                    })
+                },
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ExportEvent::ShowDialog),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("export-selection"));
+                    })
+                },
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(InstrumentEvent::ShowDialog),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("export-instrument"));
+                    })
+                },
+            );
             MenuDivider::new(cx);
             MenuButton::new(
                 cx,
@@ -267,6 +298,16 @@ pub fn view_menu(cx: &mut Context) {
                     })
                 },
             );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::ShowSidebarView(SidebarView::Kits)),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("show-kits"));
+                    })
+                },
+            );
             MenuButton::new(
                 cx,
                 |_| {},
@@ -282,6 +323,36 @@ pub fn view_menu(cx: &mut Context) {
                 },
             )
             .on_press(|cx| cx.emit(ConfigEvent::ToggleWaveviewVisibility));
+            MenuButton::new(
+                cx,
+                |_| {},
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::inspector_visible))
+                            .class("icon");
+                        Label::new(cx, Localized::new("show-inspector"));
+                        Spacer::new(cx);
+                        Label::new(cx, "Ctrl+Shift+I").class("shortcut");
+                    })
+                },
+            )
+            .on_press(|cx| cx.emit(ConfigEvent::ToggleInspectorVisibility));
+            MenuButton::new(
+                cx,
+                |_| {},
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::pads_visible))
+                            .class("icon");
+                        Label::new(cx, Localized::new("show-pads"));
+                        Spacer::new(cx);
+                        Label::new(cx, "").class("shortcut");
+                    })
+                },
+            )
+            .on_press(|cx| cx.emit(ConfigEvent::TogglePadsVisibility));
 
             MenuDivider::new(cx);
 
@@ -505,6 +576,25 @@ pub fn columns_menu(cx: &mut Context) {
                 },
             )
             .on_press(|cx| cx.emit(SampleEvent::ToggleColumn(7)));
+            MenuButton::new(
+                cx,
+                |_| {},
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(
+                                AppData::samples_data
+                                    .then(SamplesData::table_headers)
+                                    .map(|headers| headers[8].1),
+                            )
+                            .class("icon");
+                        Label::new(cx, Localized::new("note"));
+                        Spacer::new(cx);
+                        Label::new(cx, "").class("shortcut");
+                    })
+                },
+            )
+            .on_press(|cx| cx.emit(SampleEvent::ToggleColumn(8)));
             MenuButton::new(
                 cx,
                 |_| {},
@@ -517,7 +607,26 @@ pub fn columns_menu(cx: &mut Context) {
                     })
                 },
             )
-            .on_press(|cx| cx.emit(SampleEvent::ToggleColumn(8)));
+            .on_press(|cx| cx.emit(SampleEvent::ToggleColumn(9)));
+            MenuButton::new(
+                cx,
+                |_| {},
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(
+                                AppData::samples_data
+                                    .then(SamplesData::table_headers)
+                                    .map(|headers| headers[10].1),
+                            )
+                            .class("icon");
+                        Label::new(cx, Localized::new("format"));
+                        Spacer::new(cx);
+                        Label::new(cx, "").class("shortcut");
+                    })
+                },
+            )
+            .on_press(|cx| cx.emit(SampleEvent::ToggleColumn(10)));
         },
     );
 }
@@ -604,11 +713,197 @@ pub fn wave_panel_menu(cx: &mut Context) {
                     );
                 },
             );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::ToggleTrimPreview),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::trim_preview))
+                            .class("icon");
+                        Label::new(cx, Localized::new("trim-silence"));
+                    })
+                },
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::ToggleSyncResample),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::sync_resample))
+                            .class("icon");
+                        Label::new(cx, Localized::new("sync-resample"));
+                    })
+                },
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(SlicesEvent::ToggleSlicing),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(
+                                AppData::slices_data.then(SlicesData::markers).map(Option::is_some),
+                            )
+                            .class("icon");
+                        Label::new(cx, Localized::new("slice"));
+                    })
+                },
+            );
+            retune_menu(cx);
+            channels_menu(cx);
+            crossfade_menu(cx);
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::ToggleSpectrum),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::show_spectrum))
+                            .class("icon");
+                        Label::new(cx, Localized::new("show-spectrum"));
+                    })
+                },
+            );
         },
     )
     .class("panel-menu");
 }
 
+// The part of the stereo signal previewed on both channels, and the layout of four channel files
+fn channels_menu(cx: &mut Context) {
+    Submenu::new(
+        cx,
+        |cx| {
+            HStack::new(cx, |cx| {
+                Element::new(cx).class("icon");
+                Label::new(cx, Localized::new("channels"));
+            })
+        },
+        |cx| {
+            for (solo, name) in [
+                (ChannelSolo::Both, "channels-both"),
+                (ChannelSolo::Left, "channels-left"),
+                (ChannelSolo::Right, "channels-right"),
+                (ChannelSolo::Mid, "channels-mid"),
+                (ChannelSolo::Side, "channels-side"),
+            ] {
+                MenuButton::new(
+                    cx,
+                    move |cx| cx.emit(AppEvent::SetChannelSolo(solo)),
+                    move |cx| {
+                        HStack::new(cx, |cx| {
+                            Svg::new(cx, ICON_CHECK)
+                                .visibility(
+                                    AppData::channel_solo.map(move |current| *current == solo),
+                                )
+                                .class("icon");
+                            Label::new(cx, Localized::new(name));
+                        })
+                    },
+                );
+            }
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::ToggleAmbisonic),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(AppData::config.then(Config::ambisonic))
+                            .class("icon");
+                        Label::new(cx, Localized::new("ambisonic"));
+                    })
+                },
+            );
+        },
+    );
+}
+
+// The length of the crossfade when stepping to another file during playback
+fn crossfade_menu(cx: &mut Context) {
+    Submenu::new(
+        cx,
+        |cx| {
+            HStack::new(cx, |cx| {
+                Element::new(cx).class("icon");
+                Label::new(cx, Localized::new("crossfade"));
+            })
+        },
+        |cx| {
+            for milliseconds in [0, 10, 20, 50, 100] {
+                let crossfade = milliseconds as f32 / 1000.0;
+                MenuButton::new(
+                    cx,
+                    move |cx| cx.emit(ConfigEvent::SetCrossfade(crossfade)),
+                    move |cx| {
+                        HStack::new(cx, |cx| {
+                            Svg::new(cx, ICON_CHECK)
+                                .visibility(
+                                    AppData::config
+                                        .then(Config::crossfade)
+                                        .map(move |current| *current == crossfade),
+                                )
+                                .class("icon");
+                            match milliseconds {
+                                0 => Label::new(cx, Localized::new("crossfade-off")),
+                                _ => Label::new(cx, format!("{} ms", milliseconds)),
+                            };
+                        })
+                    },
+                );
+            }
+        },
+    );
+}
+
+// The root to which tonal samples are retuned when auditioned
+fn retune_menu(cx: &mut Context) {
+    Submenu::new(
+        cx,
+        |cx| {
+            HStack::new(cx, |cx| {
+                Element::new(cx).class("icon");
+                Label::new(cx, Localized::new("retune-to"));
+            })
+        },
+        |cx| {
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ConfigEvent::SetTuningRoot(None)),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Svg::new(cx, ICON_CHECK)
+                            .visibility(
+                                AppData::config.then(Config::tuning_root).map(Option::is_none),
+                            )
+                            .class("icon");
+                        Label::new(cx, Localized::new("as-recorded"));
+                    })
+                },
+            );
+            for root in 0..12 {
+                MenuButton::new(
+                    cx,
+                    move |cx| cx.emit(ConfigEvent::SetTuningRoot(Some(root))),
+                    move |cx| {
+                        HStack::new(cx, |cx| {
+                            Svg::new(cx, ICON_CHECK)
+                                .visibility(
+                                    AppData::config
+                                        .then(Config::tuning_root)
+                                        .map(move |tuning_root| *tuning_root == Some(root)),
+                                )
+                                .class("icon");
+                            Label::new(cx, key_name(root));
+                        })
+                    },
+                );
+            }
+        },
+    );
+}
+
 pub fn tags_panel_menu(cx: &mut Context) {
     Submenu::new(
         cx,
@@ -655,6 +950,95 @@ pub fn samples_panel_menu(cx: &mut Context) {
         cx,
         |cx| Svg::new(cx, ICON_MENU_2),
         |cx| {
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(SampleEvent::FindSimilar),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("find-similar"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::samples_data
+                    .then(SamplesData::selected)
+                    .map(|selected| selected.is_none()),
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(SampleEvent::PinToLayer),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("pin-to-layer"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::samples_data
+                    .then(SamplesData::selected)
+                    .map(|selected| selected.is_none()),
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(SampleEvent::PinToPad),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("pin-to-pad"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::samples_data
+                    .then(SamplesData::selected)
+                    .map(|selected| selected.is_none()),
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(SampleEvent::SendToKit),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("send-to-kit"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::kits_data
+                    .map(|kits| kits.selected_kit().map_or(true, |kit| kit.slots.is_empty())),
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(ExportEvent::ShowDialog),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("export-selection"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::samples_data
+                    .then(SamplesData::selection)
+                    .map(|selection| selection.is_empty()),
+            );
+            MenuButton::new(
+                cx,
+                |cx| cx.emit(InstrumentEvent::ShowDialog),
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Element::new(cx).class("icon");
+                        Label::new(cx, Localized::new("export-instrument"));
+                    })
+                },
+            )
+            .disabled(
+                AppData::samples_data
+                    .then(SamplesData::selection)
+                    .map(|selection| selection.is_empty()),
+            );
             columns_menu(cx);
         },
     )
@@ -0,0 +1,45 @@
+use vizia::prelude::*;
+
+use crate::app_data::AppData;
+use crate::database::prelude::MetadataEntry;
+
+pub struct InspectorPanel {}
+
+impl InspectorPanel {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self {}.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, Localized::new("inspector")).class("title");
+            })
+            .class("header");
+
+            VStack::new(cx, |cx| {
+                Label::new(cx, AppData::selected_file_name).class("file-name");
+
+                // Embedded metadata of the selected file
+                List::new(cx, AppData::selected_metadata, |cx, _, entry| {
+                    VStack::new(cx, |cx| {
+                        Label::new(
+                            cx,
+                            entry.map(|entry| format!("{} ({})", entry.key, entry.source)),
+                        )
+                        .class("key");
+                        Label::new(cx, entry.then(MetadataEntry::value)).class("value");
+                    })
+                    .class("metadata-entry");
+                });
+
+                Label::new(cx, Localized::new("no-metadata"))
+                    .display(AppData::selected_metadata.map(|metadata| metadata.is_empty()))
+                    .class("empty");
+            })
+            .class("inspector-content");
+        })
+    }
+}
+
+impl View for InspectorPanel {
+    fn element(&self) -> Option<&'static str> {
+        Some("inspector-panel")
+    }
+}
@@ -0,0 +1,150 @@
+use vizia::icons::{ICON_PLUS, ICON_X};
+use vizia::prelude::*;
+
+use crate::app_data::AppData;
+use crate::data::{KitsData, KitsEvent};
+use crate::database::prelude::{Kit, KitSlot};
+
+pub struct KitsPanel {}
+
+impl KitsPanel {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self {}.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, Localized::new("kits")).class("title");
+                Spacer::new(cx);
+                Button::new(cx, |cx| Svg::new(cx, ICON_PLUS))
+                    .on_press(|cx| cx.emit(KitsEvent::Create))
+                    .tooltip(|cx| {
+                        Tooltip::new(cx, |cx| {
+                            Label::new(cx, Localized::new("new-kit"));
+                        })
+                    });
+            })
+            .class("header");
+
+            // The kits of the library
+            List::new(cx, AppData::kits_data.then(KitsData::kits), |cx, index, kit| {
+                Label::new(cx, kit.then(Kit::name))
+                    .on_press(move |cx| cx.emit(KitsEvent::Select(index)))
+                    .toggle_class(
+                        "selected",
+                        AppData::kits_data
+                            .then(KitsData::selected)
+                            .map(move |selected| *selected == Some(index)),
+                    )
+                    .class("kit");
+            })
+            .class("kit-list");
+
+            // The slots of the shown kit, filled by dropping rows of the samples table onto them
+            VStack::new(cx, |cx| {
+                Textbox::new(cx, shown_kit().map(|kit| kit.name.clone()))
+                    .on_submit(|cx, text, _| cx.emit(KitsEvent::Rename(text)))
+                    .width(Stretch(1.0))
+                    .class("kit-name");
+
+                ScrollView::new(cx, |cx| {
+                    List::new(cx, shown_kit().map(|kit| kit.slots.clone()), |cx, index, slot| {
+                        HStack::new(cx, |cx| {
+                            Label::new(cx, format!("{}", index + 1)).class("number");
+                            VStack::new(cx, |cx| {
+                                Textbox::new(cx, slot.then(KitSlot::name))
+                                    .on_submit(move |cx, text, _| {
+                                        cx.emit(KitsEvent::RenameSlot(index, text))
+                                    })
+                                    .width(Stretch(1.0));
+                                Label::new(
+                                    cx,
+                                    slot.then(KitSlot::sample).map(|sample| {
+                                        sample
+                                            .as_ref()
+                                            .map(|sample| sample.name.clone())
+                                            .unwrap_or_default()
+                                    }),
+                                )
+                                .display(slot.then(KitSlot::sample).map(Option::is_some))
+                                .class("sample");
+                                Label::new(cx, Localized::new("kit-drop-hint"))
+                                    .display(slot.then(KitSlot::sample).map(Option::is_none))
+                                    .class("hint");
+                            })
+                            .class("slot-details");
+                            Button::new(cx, |cx| Svg::new(cx, ICON_X))
+                                .on_press(move |cx| cx.emit(KitsEvent::ClearSlot(index)))
+                                .disabled(slot.then(KitSlot::sample).map(Option::is_none));
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("remove-slot")))
+                                .on_press(move |cx| cx.emit(KitsEvent::RemoveSlot(index)));
+                        })
+                        .on_press(move |cx| cx.emit(KitsEvent::SelectSlot(index)))
+                        .on_drop(move |cx, _| cx.emit(KitsEvent::Drop(index)))
+                        .toggle_class(
+                            "selected",
+                            AppData::kits_data
+                                .then(KitsData::selected_slot)
+                                .map(move |selected| *selected == index),
+                        )
+                        .class("slot");
+                    });
+                })
+                .class("slots");
+
+                HStack::new(cx, |cx| {
+                    Button::new(cx, |cx| Label::new(cx, Localized::new("add-slot")))
+                        .on_press(|cx| cx.emit(KitsEvent::AddSlot));
+                    Button::new(cx, |cx| Label::new(cx, Localized::new("play-as-pads")))
+                        .on_press(|cx| cx.emit(KitsEvent::PlayAsPads));
+                })
+                .class("kit-buttons");
+                HStack::new(cx, |cx| {
+                    Button::new(cx, |cx| Label::new(cx, Localized::new("export-kit")))
+                        .on_press(|cx| cx.emit(KitsEvent::Export))
+                        .disabled(AppData::kits_data.then(KitsData::exporting));
+                    Button::new(cx, |cx| Label::new(cx, Localized::new("remove-kit")))
+                        .on_press(|cx| cx.emit(KitsEvent::Remove));
+                })
+                .class("kit-buttons");
+
+                // The outcome of the last export
+                Label::new(
+                    cx,
+                    Localized::new("kit-exported").arg(
+                        "folder",
+                        AppData::kits_data
+                            .then(KitsData::exported)
+                            .map(|exported| exported.clone().unwrap_or_default()),
+                    ),
+                )
+                .display(
+                    AppData::kits_data.then(KitsData::exported).map(|exported| exported.is_some()),
+                )
+                .class("hint");
+                Label::new(
+                    cx,
+                    AppData::kits_data
+                        .then(KitsData::error)
+                        .map(|error| error.clone().unwrap_or_default()),
+                )
+                .display(AppData::kits_data.then(KitsData::error).map(Option::is_some))
+                .class("hint");
+            })
+            .display(AppData::kits_data.map(|data| data.selected_kit().is_some()))
+            .class("kit-details");
+
+            Label::new(cx, Localized::new("kits-empty"))
+                .display(AppData::kits_data.then(KitsData::kits).map(|kits| kits.is_empty()))
+                .class("hint");
+        })
+    }
+}
+
+impl View for KitsPanel {
+    fn element(&self) -> Option<&'static str> {
+        Some("kits-panel")
+    }
+}
+
+// The kit shown in the panel, or an empty kit while there is none
+fn shown_kit() -> impl Lens<Target = Kit> {
+    AppData::kits_data.map(|data| data.selected_kit().cloned().unwrap_or_default())
+}
@@ -10,5 +10,17 @@ pub use sidebar::*;
 pub mod tags;
 pub use tags::*;
 
+pub mod kits;
+pub use kits::*;
+
 pub mod wave;
 pub use wave::*;
+
+pub mod inspector;
+pub use inspector::*;
+
+pub mod pads;
+pub use pads::*;
+
+pub mod status_bar;
+pub use status_bar::*;
@@ -0,0 +1,128 @@
+use vizia::icons::ICON_X;
+use vizia::prelude::*;
+
+use crate::app_data::AppData;
+use crate::data::{PadData, PadsData, PadsEvent};
+use crate::engine::{PadMode, NUM_PADS};
+
+// The number of pads in each row of the grid
+const PADS_PER_ROW: usize = 4;
+
+pub struct PadsPanel {}
+
+impl PadsPanel {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self {}.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, Localized::new("pads")).class("title");
+            })
+            .class("header");
+
+            // The grid of pads, the first pad at the bottom left like the keys playing them
+            VStack::new(cx, |cx| {
+                for row in (0..NUM_PADS / PADS_PER_ROW).rev() {
+                    HStack::new(cx, |cx| {
+                        for column in 0..PADS_PER_ROW {
+                            Pad::new(cx, row * PADS_PER_ROW + column);
+                        }
+                    })
+                    .class("pad-row");
+                }
+            })
+            .class("pad-grid");
+
+            // The settings of the selected pad
+            Binding::new(cx, AppData::pads_data.then(PadsData::selected), |cx, selected| {
+                let index = selected.get(cx);
+
+                VStack::new(cx, |cx| {
+                    HStack::new(cx, |cx| {
+                        Label::new(cx, format!("{}", index + 1)).class("number");
+                        Label::new(cx, pad(index).map(|pad| pad.name.clone())).class("name");
+                        Button::new(cx, |cx| Svg::new(cx, ICON_X))
+                            .on_press(move |cx| cx.emit(PadsEvent::Clear(index)))
+                            .disabled(pad(index).map(|pad| pad.name.is_empty()));
+                    })
+                    .class("pad-title");
+
+                    HStack::new(cx, |cx| {
+                        for (mode, name) in
+                            [(PadMode::OneShot, "pad-one-shot"), (PadMode::Gate, "pad-gate")]
+                        {
+                            ToggleButton::new(
+                                cx,
+                                pad(index).map(move |pad| pad.settings.mode == mode),
+                                move |cx| Label::new(cx, Localized::new(name)),
+                            )
+                            .on_press(move |cx| cx.emit(PadsEvent::SetMode(index, mode)));
+                        }
+
+                        Button::new(cx, |cx| {
+                            Label::new(
+                                cx,
+                                Localized::new("pad-choke")
+                                    .arg("group", pad(index).map(|pad| pad.settings.choke_group)),
+                            )
+                        })
+                        .on_press(move |cx| cx.emit(PadsEvent::CycleChokeGroup(index)));
+                    })
+                    .class("pad-settings");
+                })
+                .class("pad-details");
+            });
+        })
+    }
+}
+
+impl View for PadsPanel {
+    fn element(&self) -> Option<&'static str> {
+        Some("pads-panel")
+    }
+}
+
+// The pad at `index`
+fn pad(index: usize) -> impl Lens<Target = PadData> {
+    AppData::pads_data.then(PadsData::pads).map(move |pads| pads[index].clone())
+}
+
+// A pad of the grid, which is selected and played while pressed with the mouse
+struct Pad {
+    index: usize,
+}
+
+impl Pad {
+    fn new(cx: &mut Context, index: usize) -> Handle<Self> {
+        Self { index }
+            .build(cx, |cx| {
+                Label::new(cx, PadsData::key_name(index)).class("key");
+                Label::new(cx, pad(index).map(|pad| pad.name.clone())).class("name");
+            })
+            .toggle_class("held", pad(index).map(|pad| pad.held))
+            .toggle_class("empty", pad(index).map(|pad| pad.name.is_empty()))
+            .toggle_class(
+                "selected",
+                AppData::pads_data.then(PadsData::selected).map(move |selected| *selected == index),
+            )
+    }
+}
+
+impl View for Pad {
+    fn element(&self) -> Option<&'static str> {
+        Some("pad")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, _| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.capture();
+                cx.emit(PadsEvent::Select(self.index));
+                cx.emit(PadsEvent::Trigger(self.index));
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                cx.release();
+                cx.emit(PadsEvent::Release(self.index));
+            }
+            _ => {}
+        });
+    }
+}
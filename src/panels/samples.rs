@@ -1,8 +1,9 @@
-use vizia::icons::{ICON_LIST_SEARCH, ICON_SEARCH};
+use vizia::icons::{ICON_LIST_SEARCH, ICON_SEARCH, ICON_X};
 use vizia::prelude::*;
 
 use crate::app_data::AppData;
-use crate::database::prelude::AudioFile;
+use crate::data::KitsEvent;
+use crate::database::prelude::{key_name, note_name, AudioFile, SampleFormat};
 use crate::menus::samples_panel_menu;
 use crate::{SampleEvent, SamplesData};
 
@@ -17,8 +18,8 @@ impl SamplesPanel {
                         .class("icon-before")
                         .width(Stretch(1.0))
                         .class("search")
-                        .placeholder(Localized::new("search"));
-                    // .on_edit(|cx, text| cx.emit(AppDataSetter::EditableText(text)));
+                        .placeholder(Localized::new("search"))
+                        .on_edit(|cx, text| cx.emit(SampleEvent::Search(text)));
                     Svg::new(cx, ICON_SEARCH)
                         .class("icon")
                         .size(Pixels(20.0))
@@ -28,6 +29,33 @@ impl SamplesPanel {
                 .alignment(Alignment::Center)
                 .width(Stretch(1.0));
 
+                // Shown while the table lists the audio files similar to another
+                HStack::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        Localized::new("similar-to").arg(
+                            "name",
+                            AppData::samples_data.then(SamplesData::similar_to).map(|similar_to| {
+                                similar_to
+                                    .as_ref()
+                                    .map(|(_, name)| name.clone())
+                                    .unwrap_or_default()
+                            }),
+                        ),
+                    );
+                    Button::new(cx, |cx| Svg::new(cx, ICON_X))
+                        .on_press(|cx| cx.emit(SampleEvent::ClearSimilar));
+                })
+                .display(
+                    AppData::samples_data
+                        .then(SamplesData::similar_to)
+                        .map(|similar_to| similar_to.is_some()),
+                )
+                .size(Auto)
+                .alignment(Alignment::Center)
+                .horizontal_gap(Pixels(4.0))
+                .class("similar-to");
+
                 samples_panel_menu(cx);
             })
             .horizontal_gap(Pixels(8.0))
@@ -45,7 +73,11 @@ impl SamplesPanel {
                 |cx, index, item| match index {
                     // Name
                     0 => {
-                        Label::new(cx, item.then(AudioFile::name));
+                        // Rows are dragged by their name onto the slots of a kit
+                        Label::new(cx, item.then(AudioFile::name)).on_drag(move |cx| {
+                            cx.emit(KitsEvent::Drag(item.then(AudioFile::id).get(cx)));
+                            cx.set_drop_data(cx.current());
+                        });
                     }
                     // Tags
                     1 => {}
@@ -69,7 +101,18 @@ impl SamplesPanel {
                     }
                     // Bit Depth
                     4 => {
-                        Label::new(cx, item.then(AudioFile::bit_depth));
+                        Label::new(
+                            cx,
+                            item.map(|audio_file| {
+                                match (audio_file.bit_depth, audio_file.sample_format) {
+                                    (Some(bits), Some(SampleFormat::Float)) => {
+                                        format!("{} float", bits)
+                                    }
+                                    (Some(bits), _) => format!("{}", bits),
+                                    (None, _) => String::from("-"),
+                                }
+                            }),
+                        );
                     }
                     // Num Channels
                     5 => {
@@ -88,30 +131,75 @@ impl SamplesPanel {
                         Label::new(
                             cx,
                             item.then(AudioFile::key)
-                                .map(|k| k.map(|k| format!("{}", k)).unwrap_or(String::from("-"))),
+                                .map(|k| k.map(key_name).unwrap_or("-").to_string()),
                         );
                     }
-                    // Size
+                    // Note
                     8 => {
-                        Label::new(cx, item.then(AudioFile::size));
+                        Label::new(
+                            cx,
+                            item.map(|audio_file| {
+                                match (audio_file.pitch, audio_file.sampler.root_note) {
+                                    (Some(pitch), _) => {
+                                        format!("{} {:+.0}", note_name(pitch.note), pitch.cents)
+                                    }
+                                    (None, Some(note)) => note_name(note),
+                                    (None, None) => String::from("-"),
+                                }
+                            }),
+                        );
+                    }
+                    // Size
+                    9 => {
+                        Label::new(cx, item.then(AudioFile::size).map(|size| format_size(*size)));
+                    }
+                    // Format
+                    10 => {
+                        Label::new(
+                            cx,
+                            item.map(|audio_file| {
+                                format!("{} ({})", audio_file.format, audio_file.codec)
+                            }),
+                        );
                     }
                     _ => {}
                 },
             )
-            .selectable(Selectable::Single)
-            .selected(AppData::samples_data.then(SamplesData::selected).map(|selected| {
-                if let Some(selected) = selected {
-                    vec![*selected]
+            .selectable(Selectable::Multi)
+            .selected(AppData::samples_data.then(SamplesData::selection))
+            .selection_follows_focus(true)
+            .on_select(|cx, index| {
+                // Ctrl and Shift add rows to the selection for batch actions without previewing them
+                if cx.modifiers().contains(Modifiers::CTRL) {
+                    cx.emit(SampleEvent::AddSelection(index));
+                } else if cx.modifiers().contains(Modifiers::SHIFT) {
+                    cx.emit(SampleEvent::SelectRange(index));
                 } else {
-                    vec![]
+                    cx.emit(SampleEvent::Select(index));
                 }
-            }))
-            .selection_follows_focus(true)
-            .on_select(|cx, index| cx.emit(SampleEvent::Select(index)));
+            });
         })
     }
 }
 
+// Format a size in bytes for display, e.g. `1.5 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 impl View for SamplesPanel {
     fn element(&self) -> Option<&'static str> {
         Some("samples-panel")
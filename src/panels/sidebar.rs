@@ -1,4 +1,4 @@
-use vizia::icons::{ICON_SETTINGS, ICON_TAG};
+use vizia::icons::{ICON_LAYOUT_GRID, ICON_SETTINGS, ICON_TAG};
 use vizia::prelude::*;
 use vizia::{
     icons::{ICON_FOLDER_OPEN, ICON_SEARCH, ICON_SELECT_ALL},
@@ -29,6 +29,13 @@ impl Sidebar {
                             .map(|view| *view == SidebarView::Tags),
                     )
                     .on_press(|cx| cx.emit(ConfigEvent::ShowSidebarView(SidebarView::Tags)));
+                Button::new(cx, |cx| Svg::new(cx, ICON_LAYOUT_GRID))
+                    .checked(
+                        AppData::config
+                            .then(Config::sidebar_view)
+                            .map(|view| *view == SidebarView::Kits),
+                    )
+                    .on_press(|cx| cx.emit(ConfigEvent::ShowSidebarView(SidebarView::Kits)));
                 Spacer::new(cx);
                 Button::new(cx, |cx| Svg::new(cx, ICON_SETTINGS))
                     .on_press(|cx| cx.emit(AppEvent::ShowSettingsDialog));
@@ -0,0 +1,72 @@
+use vizia::icons::{ICON_ALERT_TRIANGLE, ICON_X};
+use vizia::prelude::*;
+
+use crate::data::{AppData, IndexingData, IndexingEvent, IndexingProgress};
+
+pub struct StatusBar {}
+
+impl StatusBar {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self {}.build(cx, |cx| {
+            // Running indexing jobs
+            List::new(cx, AppData::indexing_data.then(IndexingData::jobs), |cx, _, job| {
+                HStack::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        Localized::new("indexing-progress")
+                            .arg("name", job.then(IndexingProgress::name))
+                            .arg("scanned", job.then(IndexingProgress::scanned))
+                            .arg("total", job.then(IndexingProgress::total)),
+                    );
+
+                    ProgressBar::horizontal(
+                        cx,
+                        job.map(|job| match job.total {
+                            0 => 0.0,
+                            total => job.scanned as f32 / total as f32,
+                        }),
+                    );
+
+                    Button::new(cx, |cx| Svg::new(cx, ICON_X))
+                        .on_press(move |cx| {
+                            let path = job.get(cx).path;
+                            cx.emit(IndexingEvent::Cancel(path));
+                        })
+                        .name(Localized::new("cancel-indexing"))
+                        .tooltip(|cx| {
+                            Tooltip::new(cx, |cx| {
+                                Label::new(cx, Localized::new("cancel-indexing"));
+                            })
+                        });
+                })
+                .class("indexing-job");
+            })
+            .horizontal(true);
+
+            Spacer::new(cx);
+
+            // Indexing errors
+            Button::new(cx, |cx| {
+                HStack::new(cx, |cx| {
+                    Svg::new(cx, ICON_ALERT_TRIANGLE).class("icon");
+                    Label::new(
+                        cx,
+                        Localized::new("indexing-errors").arg(
+                            "count",
+                            AppData::indexing_data.then(IndexingData::errors).map(|e| e.len()),
+                        ),
+                    );
+                })
+            })
+            .on_press(|cx| cx.emit(IndexingEvent::ShowReport))
+            .display(AppData::indexing_data.then(IndexingData::errors).map(|e| !e.is_empty()))
+            .class("indexing-errors");
+        })
+    }
+}
+
+impl View for StatusBar {
+    fn element(&self) -> Option<&'static str> {
+        Some("status-bar")
+    }
+}
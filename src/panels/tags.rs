@@ -8,7 +8,7 @@ use vizia::icons::{
 use crate::app_data::AppData;
 use crate::data::{TagsData, TagsEvent};
 use crate::menus::tags_panel_menu;
-use crate::Tag;
+use crate::{Tag, TagSuggestion};
 
 #[derive(Lens)]
 pub struct TagsPanel {
@@ -119,6 +119,70 @@ impl TagsPanel {
             )
             .height(Stretch(1.0));
 
+            // Suggested Tags
+            VStack::new(cx, |cx| {
+                HStack::new(cx, |cx| {
+                    Label::new(cx, Localized::new("suggested-tags")).class("title");
+                    Spacer::new(cx);
+                    Label::new(
+                        cx,
+                        Localized::new("min-confidence").arg(
+                            "confidence",
+                            AppData::tags_data
+                                .then(TagsData::min_confidence)
+                                .map(|confidence| (confidence * 100.0).round() as u32),
+                        ),
+                    );
+                })
+                .height(Auto);
+
+                Slider::new(cx, AppData::tags_data.then(TagsData::min_confidence))
+                    .on_change(|cx, value| cx.emit(TagsEvent::SetMinConfidence(value)));
+
+                List::new(
+                    cx,
+                    AppData::tags_data.then(TagsData::suggestions),
+                    |cx, _, suggestion| {
+                        let tag = suggestion.then(TagSuggestion::tag);
+                        HStack::new(cx, |cx| {
+                            Element::new(cx)
+                                .background_color(
+                                    tag.then(Tag::color).map(|col| Color::from(col.as_str())),
+                                )
+                                .class("tag-color");
+                            Label::new(cx, tag.then(Tag::name)).class("tag-name");
+                            Label::new(
+                                cx,
+                                suggestion.map(|suggestion| {
+                                    format!(
+                                        "{} ({:.0}%)",
+                                        suggestion.count,
+                                        suggestion.confidence * 100.0
+                                    )
+                                }),
+                            )
+                            .class("tag-num");
+
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("accept")))
+                                .on_press(move |cx| {
+                                    cx.emit(TagsEvent::AcceptSuggestions(tag.get(cx).id))
+                                });
+                            Button::new(cx, |cx| Label::new(cx, Localized::new("reject")))
+                                .on_press(move |cx| {
+                                    cx.emit(TagsEvent::RejectSuggestions(tag.get(cx).id))
+                                });
+                        })
+                        .class("tag");
+                    },
+                );
+            })
+            .display(
+                AppData::tags_data
+                    .then(TagsData::suggestions)
+                    .map(|suggestions| !suggestions.is_empty()),
+            )
+            .class("suggestions");
+
             // Footer
             HStack::new(cx, |cx| {
                 Label::new(
@@ -4,17 +4,24 @@ use vizia::prelude::*;
 
 use vizia::icons::{
     ICON_CHEVRON_DOWN, ICON_FILTER, ICON_FOLDER, ICON_FOLDER_FILLED, ICON_FOLDER_OPEN,
-    ICON_LETTER_CASE, ICON_LIST, ICON_LIST_TREE, ICON_MENU_2, ICON_PLAYER_PAUSE, ICON_PLAYER_PLAY,
-    ICON_PLAYER_SKIP_BACK, ICON_PLAYER_SKIP_FORWARD, ICON_PLAYER_STOP, ICON_RELOAD, ICON_SEARCH,
-    ICON_TAG, ICON_WAVE_SINE,
+    ICON_LETTER_CASE, ICON_LIST, ICON_LIST_TREE, ICON_MENU_2, ICON_MINUS, ICON_PLAYER_PAUSE,
+    ICON_PLAYER_PLAY, ICON_PLAYER_SKIP_BACK, ICON_PLAYER_SKIP_FORWARD, ICON_PLAYER_STOP, ICON_PLUS,
+    ICON_RELOAD, ICON_SEARCH, ICON_TAG, ICON_WAVE_SINE, ICON_X,
 };
 
-use crate::app_data::AppData;
+use crate::app_data::{AppData, PinnedLayer, MAX_SPEED, MIN_SPEED};
 use crate::data::browser_data::{BrowserData, BrowserEvent};
 use crate::data::AppEvent;
+use crate::data::MeterData;
+use crate::data::{SlicesData, SlicesEvent};
+use crate::database::prelude::{key_name, note_name, SamplerInfo};
+use crate::engine::{
+    FilterType, LayerMix, PreviewEffects, MAX_CUTOFF, MAX_FADE, MAX_LAYER_GAIN, MIN_CUTOFF,
+    MIN_LAYER_GAIN,
+};
 use crate::menus::wave_panel_menu;
-use crate::views::Waveview;
-use crate::{ConfigEvent, PlayerState, SampleEvent, SamplePlayerController};
+use crate::views::{LevelMeter, Spectrum, Waveview};
+use crate::{Config, ConfigEvent, PlayerState, SampleEvent, SamplePlayerController};
 
 #[derive(Lens)]
 pub struct WavePanel {}
@@ -41,13 +48,61 @@ impl WavePanel {
 
                 Label::new(cx, AppData::selected_file_name).right(Stretch(1.0)).class("title");
 
-                Chip::new(cx, AppData::selected_file_bit_depth.map(|bd| format!("{} bit", bd)));
+                Chip::new(
+                    cx,
+                    AppData::selected_file_bit_depth
+                        .map(|bd| bd.map(|bd| format!("{} bit", bd)).unwrap_or(String::from("-"))),
+                );
                 Chip::new(cx, AppData::selected_file_sample_rate.map(|bd| format!("{} Hz", bd)));
                 Chip::new(
                     cx,
                     AppData::selected_file_num_channels.map(|bd| format!("{} channel", bd)),
                 );
 
+                // Sampler chunks
+                let sampler = AppData::selected_file_sampler;
+                Chip::new(
+                    cx,
+                    sampler
+                        .then(SamplerInfo::root_note)
+                        .map(|note| note.map(note_name).unwrap_or_default()),
+                )
+                .display(sampler.then(SamplerInfo::root_note).map(Option::is_some));
+                Chip::new(
+                    cx,
+                    sampler.map(|info| match (info.tempo, info.beats) {
+                        (Some(tempo), Some(beats)) => format!("{} BPM, {} beats", tempo, beats),
+                        (Some(tempo), None) => format!("{} BPM", tempo),
+                        _ => String::new(),
+                    }),
+                )
+                .display(sampler.then(SamplerInfo::tempo).map(Option::is_some));
+                Chip::new(cx, Localized::new("one-shot"))
+                    .display(sampler.then(SamplerInfo::one_shot));
+                Chip::new(cx, Localized::new("embedded-loop"))
+                    .display(sampler.map(|info| info.loop_points().is_some()));
+
+                // Detected pitch
+                Chip::new(
+                    cx,
+                    AppData::selected_file_pitch.map(|pitch| {
+                        pitch
+                            .map(|pitch| {
+                                format!("{} {:+.0} ct", note_name(pitch.note), pitch.cents)
+                            })
+                            .unwrap_or_default()
+                    }),
+                )
+                .display(AppData::selected_file_pitch.map(Option::is_some));
+                Chip::new(
+                    cx,
+                    AppData::config.then(Config::tuning_root).map(|root| {
+                        root.map(|root| format!("→ {}", key_name(root))).unwrap_or_default()
+                    }),
+                )
+                .display(AppData::config.then(Config::tuning_root).map(Option::is_some))
+                .class("retune");
+
                 wave_panel_menu(cx);
             })
             .class("header");
@@ -62,8 +117,73 @@ impl WavePanel {
                 AppData::controller
                     .then(SamplePlayerController::playhead)
                     .map(|p| p.load(Ordering::SeqCst)),
+                AppData::selected_file_audible,
+                AppData::wave_selection,
+                AppData::slices_data.then(SlicesData::markers),
             );
 
+            // Slicing the file into one-shots
+            HStack::new(cx, |cx| {
+                let slices = AppData::slices_data;
+                Label::new(
+                    cx,
+                    Localized::new("slice-count").arg("count", slices.map(SlicesData::count)),
+                )
+                .class("name");
+                Button::new(cx, |cx| Label::new(cx, Localized::new("slice-detect")))
+                    .on_press(|cx| cx.emit(SlicesEvent::Detect))
+                    .disabled(slices.then(SlicesData::detecting));
+                Button::new(cx, |cx| Label::new(cx, Localized::new("slice-clear")))
+                    .on_press(|cx| cx.emit(SlicesEvent::Clear));
+                Button::new(cx, |cx| Label::new(cx, Localized::new("export-slices")))
+                    .on_press(|cx| cx.emit(SlicesEvent::Export))
+                    .disabled(slices.map(|data| data.exporting || data.count() == 0));
+                Label::new(
+                    cx,
+                    Localized::new("slices-exported").arg(
+                        "count",
+                        slices
+                            .then(SlicesData::exported)
+                            .map(|exported| exported.unwrap_or_default()),
+                    ),
+                )
+                .display(slices.then(SlicesData::exported).map(Option::is_some));
+                Label::new(cx, Localized::new("slice-hint")).class("slice-hint");
+            })
+            .class("slice-strip")
+            .display(AppData::slices_data.then(SlicesData::markers).map(Option::is_some));
+
+            // Samples layered over the preview
+            List::new(cx, AppData::layers, |cx, index, layer| {
+                let mix = layer.then(PinnedLayer::mix);
+                HStack::new(cx, |cx| {
+                    Label::new(cx, layer.then(PinnedLayer::name)).class("name");
+
+                    Label::new(cx, Localized::new("layer-gain"));
+                    Slider::new(cx, mix.then(LayerMix::gain))
+                        .range(MIN_LAYER_GAIN..MAX_LAYER_GAIN)
+                        .on_change(move |cx, gain| cx.emit(AppEvent::SetLayerGain(index, gain)));
+                    Label::new(cx, mix.then(LayerMix::gain).map(|gain| format!("{:+.1} dB", gain)))
+                        .class("value");
+
+                    Label::new(cx, Localized::new("layer-pan"));
+                    Slider::new(cx, mix.then(LayerMix::pan))
+                        .range(-1.0..1.0)
+                        .on_change(move |cx, pan| cx.emit(AppEvent::SetLayerPan(index, pan)));
+
+                    ToggleButton::new(cx, mix.then(LayerMix::muted), |cx| {
+                        Label::new(cx, Localized::new("layer-mute"))
+                    })
+                    .on_press(move |cx| cx.emit(AppEvent::ToggleLayerMute(index)));
+                    Button::new(cx, |cx| Svg::new(cx, ICON_X))
+                        .on_press(move |cx| cx.emit(AppEvent::RemoveLayer(index)));
+                })
+                .class("layer");
+            })
+            .selectable(Selectable::None)
+            .class("layer-strip")
+            .display(AppData::layers.map(|layers| !layers.is_empty()));
+
             // Footer
             HStack::new(cx, |cx| {
                 // toolbar here
@@ -105,12 +225,175 @@ impl WavePanel {
                         .on_press(|cx| cx.emit(SampleEvent::SelectNext));
                 })
                 .class("transport-controls");
+
+                // Varispeed, transposition and tempo sync
+                HStack::new(cx, |cx| {
+                    Label::new(cx, Localized::new("speed"));
+                    Slider::new(cx, AppData::speed)
+                        .range(MIN_SPEED..MAX_SPEED)
+                        .on_change(|cx, speed| cx.emit(AppEvent::SetSpeed(speed)));
+                    Button::new(cx, |cx| {
+                        Label::new(cx, AppData::speed.map(|speed| format!("{:.2}×", speed)))
+                    })
+                    .on_press(|cx| cx.emit(AppEvent::SetSpeed(1.0)))
+                    .class("value");
+
+                    Label::new(cx, Localized::new("transpose"));
+                    Button::new(cx, |cx| Svg::new(cx, ICON_MINUS)).on_press(|cx| {
+                        let transpose = AppData::transpose.get(cx);
+                        cx.emit(AppEvent::SetTranspose(transpose - 1));
+                    });
+                    Button::new(cx, |cx| {
+                        Label::new(
+                            cx,
+                            AppData::transpose.map(|semitones| format!("{:+} st", semitones)),
+                        )
+                    })
+                    .on_press(|cx| cx.emit(AppEvent::SetTranspose(0)))
+                    .class("value");
+                    Button::new(cx, |cx| Svg::new(cx, ICON_PLUS)).on_press(|cx| {
+                        let transpose = AppData::transpose.get(cx);
+                        cx.emit(AppEvent::SetTranspose(transpose + 1));
+                    });
+
+                    ToggleButton::new(cx, AppData::config.then(Config::tempo_sync), |cx| {
+                        Label::new(cx, Localized::new("tempo-sync"))
+                    })
+                    .on_press(|cx| cx.emit(ConfigEvent::ToggleTempoSync));
+                    Textbox::new(
+                        cx,
+                        AppData::config
+                            .then(Config::project_tempo)
+                            .map(|tempo| format!("{}", tempo)),
+                    )
+                    .on_submit(|cx, text, _| {
+                        if let Ok(tempo) = text.trim().parse() {
+                            cx.emit(ConfigEvent::SetProjectTempo(tempo));
+                        }
+                    })
+                    .class("tempo");
+                    Label::new(cx, Localized::new("bpm"));
+
+                    // Metronome and quantized start
+                    Textbox::new(
+                        cx,
+                        AppData::config
+                            .map(|config| format!("{}/{}", config.beats_per_bar, config.beat_unit)),
+                    )
+                    .on_submit(|cx, text, _| {
+                        if let Some((beats_per_bar, beat_unit)) = text.trim().split_once('/') {
+                            if let (Ok(beats_per_bar), Ok(beat_unit)) =
+                                (beats_per_bar.trim().parse(), beat_unit.trim().parse())
+                            {
+                                cx.emit(ConfigEvent::SetTimeSignature(beats_per_bar, beat_unit));
+                            }
+                        }
+                    })
+                    .class("time-signature");
+                    ToggleButton::new(cx, AppData::config.then(Config::metronome), |cx| {
+                        Label::new(cx, Localized::new("metronome"))
+                    })
+                    .on_press(|cx| cx.emit(ConfigEvent::ToggleMetronome));
+                    ToggleButton::new(cx, AppData::config.then(Config::quantize_start), |cx| {
+                        Label::new(cx, Localized::new("quantize-start"))
+                    })
+                    .on_press(|cx| cx.emit(ConfigEvent::ToggleQuantizeStart));
+                })
+                .class("playback-controls");
+
+                // Reverse, filter and fades
+                HStack::new(cx, |cx| {
+                    let effects = AppData::effects;
+                    ToggleButton::new(cx, effects.then(PreviewEffects::reverse), |cx| {
+                        Label::new(cx, Localized::new("reverse"))
+                    })
+                    .on_press(|cx| cx.emit(AppEvent::ToggleReverse));
+
+                    for (filter_type, name) in
+                        [(FilterType::LowPass, "low-pass"), (FilterType::HighPass, "high-pass")]
+                    {
+                        ToggleButton::new(
+                            cx,
+                            effects
+                                .then(PreviewEffects::filter)
+                                .map(move |f| *f == Some(filter_type)),
+                            move |cx| Label::new(cx, Localized::new(name)),
+                        )
+                        .on_press(move |cx| {
+                            let filter = effects.then(PreviewEffects::filter).get(cx);
+                            let filter =
+                                if filter == Some(filter_type) { None } else { Some(filter_type) };
+                            cx.emit(AppEvent::SetFilter(filter));
+                        });
+                    }
+                    Slider::new(
+                        cx,
+                        effects.then(PreviewEffects::cutoff).map(|c| cutoff_to_position(*c)),
+                    )
+                    .on_change(|cx, position| {
+                        cx.emit(AppEvent::SetFilterCutoff(position_to_cutoff(position)))
+                    });
+                    Label::new(
+                        cx,
+                        effects.then(PreviewEffects::cutoff).map(|cutoff| match *cutoff {
+                            cutoff if cutoff >= 1000.0 => format!("{:.1} kHz", cutoff / 1000.0),
+                            cutoff => format!("{:.0} Hz", cutoff),
+                        }),
+                    )
+                    .class("value");
+
+                    Label::new(cx, Localized::new("fade-in"));
+                    Slider::new(cx, effects.then(PreviewEffects::fade_in))
+                        .range(0.0..MAX_FADE)
+                        .on_change(|cx, fade_in| cx.emit(AppEvent::SetFadeIn(fade_in)));
+                    Label::new(
+                        cx,
+                        effects
+                            .then(PreviewEffects::fade_in)
+                            .map(|f| format!("{:.0} ms", f * 1000.0)),
+                    )
+                    .class("value");
+
+                    Label::new(cx, Localized::new("fade-out"));
+                    Slider::new(cx, effects.then(PreviewEffects::fade_out))
+                        .range(0.0..MAX_FADE)
+                        .on_change(|cx, fade_out| cx.emit(AppEvent::SetFadeOut(fade_out)));
+                    Label::new(
+                        cx,
+                        effects
+                            .then(PreviewEffects::fade_out)
+                            .map(|f| format!("{:.0} ms", f * 1000.0)),
+                    )
+                    .class("value");
+                })
+                .class("effects-controls");
+
+                // Output levels and spectrum
+                HStack::new(cx, |cx| {
+                    VStack::new(cx, |cx| {
+                        LevelMeter::new(cx, AppData::meter.then(MeterData::left));
+                        LevelMeter::new(cx, AppData::meter.then(MeterData::right));
+                    })
+                    .class("level-meters");
+                    Spectrum::new(cx, AppData::spectrum)
+                        .display(AppData::config.then(Config::show_spectrum));
+                })
+                .class("meters");
             })
             .class("footer");
         })
     }
 }
 
+// The filter cutoff slider is logarithmic, so that each octave takes the same width.
+fn cutoff_to_position(cutoff: f32) -> f32 {
+    (cutoff / MIN_CUTOFF).ln() / (MAX_CUTOFF / MIN_CUTOFF).ln()
+}
+
+fn position_to_cutoff(position: f32) -> f32 {
+    MIN_CUTOFF * (MAX_CUTOFF / MIN_CUTOFF).powf(position)
+}
+
 impl View for WavePanel {
     fn element(&self) -> Option<&'static str> {
         Some("wave-panel")
@@ -0,0 +1,113 @@
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::data::ChannelLevel;
+use crate::engine::MIN_LEVEL_DB;
+
+// The position of a level in dB along a meter, from 0 at `MIN_LEVEL_DB` to 1 at full scale.
+fn level_position(level: f32) -> f32 {
+    (1.0 - level / MIN_LEVEL_DB).clamp(0.0, 1.0)
+}
+
+fn fill_rect(canvas: &Canvas, rect: vg::Rect, color: Color) {
+    let mut path = vg::Path::new();
+    path.add_rect(rect, None);
+
+    let mut paint = vg::Paint::default();
+    paint.set_color(color);
+    paint.set_anti_alias(false);
+    paint.set_style(vg::PaintStyle::Fill);
+    canvas.draw_path(&mut path, &paint);
+}
+
+/// A horizontal meter showing the RMS and peak level of an output channel, and its held peak.
+pub struct LevelMeter<L: Lens<Target = ChannelLevel>> {
+    level_lens: L,
+}
+
+impl<L: Lens<Target = ChannelLevel>> LevelMeter<L> {
+    pub fn new(cx: &mut Context, level_lens: L) -> Handle<Self> {
+        Self { level_lens }
+            .build(cx, |_| {})
+            .bind(level_lens, |mut handle, _| handle.needs_redraw())
+    }
+}
+
+impl<L: Lens<Target = ChannelLevel>> View for LevelMeter<L> {
+    fn element(&self) -> Option<&'static str> {
+        Some("level-meter")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &Canvas) {
+        let bounds = cx.bounds();
+        let level = self.level_lens.get(cx);
+        let (x, y, w, h) = (bounds.x, bounds.y, bounds.w, bounds.h);
+
+        fill_rect(canvas, vg::Rect::new(x, y, x + w, y + h), Color::rgba(0, 0, 0, 255));
+
+        let peakx = x + w * level_position(level.peak);
+        fill_rect(canvas, vg::Rect::new(x, y, peakx, y + h), Color::rgba(50, 200, 50, 120));
+
+        let rmsx = x + w * level_position(level.rms);
+        fill_rect(canvas, vg::Rect::new(x, y, rmsx, y + h), Color::rgba(50, 200, 50, 255));
+
+        // The held peak turns red when the output clips
+        if level.hold > MIN_LEVEL_DB {
+            let holdx = (x + w * level_position(level.hold)).min(x + w - 1.0).floor();
+            let color = if level.hold >= 0.0 {
+                Color::rgba(230, 50, 50, 255)
+            } else {
+                Color::rgba(235, 235, 235, 255)
+            };
+            fill_rect(canvas, vg::Rect::new(holdx, y, holdx + 1.0, y + h), color);
+        }
+    }
+}
+
+/// A bar graph of the level in dB of each band of the spectrum of the output.
+pub struct Spectrum<L: Lens<Target = Vec<f32>>> {
+    bands_lens: L,
+}
+
+impl<L: Lens<Target = Vec<f32>>> Spectrum<L> {
+    pub fn new(cx: &mut Context, bands_lens: L) -> Handle<Self> {
+        Self { bands_lens }
+            .build(cx, |_| {})
+            .bind(bands_lens, |mut handle, _| handle.needs_redraw())
+    }
+}
+
+impl<L: Lens<Target = Vec<f32>>> View for Spectrum<L> {
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrum")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &Canvas) {
+        let bounds = cx.bounds();
+        let (x, y, w, h) = (bounds.x, bounds.y, bounds.w, bounds.h);
+
+        fill_rect(canvas, vg::Rect::new(x, y, x + w, y + h), Color::rgba(0, 0, 0, 255));
+
+        let bands = self.bands_lens.get(cx);
+        if bands.is_empty() {
+            return;
+        }
+
+        let mut path = vg::Path::new();
+        let band_width = w / bands.len() as f32;
+        for (band, level) in bands.iter().enumerate() {
+            let left = x + band as f32 * band_width;
+            let top = y + h * (1.0 - level_position(*level));
+            path.add_rect(
+                vg::Rect::new(left, top, left + (band_width - 1.0).max(1.0), y + h),
+                None,
+            );
+        }
+
+        let mut paint = vg::Paint::default();
+        paint.set_color(Color::rgba(50, 50, 255, 255));
+        paint.set_anti_alias(false);
+        paint.set_style(vg::PaintStyle::Fill);
+        canvas.draw_path(&mut path, &paint);
+    }
+}
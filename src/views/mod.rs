@@ -1,2 +1,5 @@
 pub mod waveview;
 pub use waveview::*;
+
+pub mod meter;
+pub use meter::*;
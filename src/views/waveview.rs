@@ -8,28 +8,48 @@ use crate::app_data::AppEvent;
 use crate::app_data::UnitsMode;
 use crate::app_data::ZoomMode;
 use crate::data::AppData;
+use crate::data::SlicesEvent;
 use crate::waveform::Waveform;
 use crate::SamplePlayerController;
 
+// How far from a slice marker in logical pixels it can be dragged or removed
+const MARKER_REACH: f32 = 4.0;
+
 pub struct Waveview<
     L1: Lens<Target = Option<Arc<Waveform>>>,
     L2: Lens<Target = usize>,
     L3: Lens<Target = usize>,
     L4: Lens<Target = usize>,
+    L5: Lens<Target = Option<(usize, Option<usize>)>>,
+    L6: Lens<Target = Option<(usize, usize)>>,
+    L7: Lens<Target = Option<Vec<usize>>>,
 > {
     waveform_lens: L1,
     zoom_level_lens: L2,
     start_lens: L3,
     playhead_lens: L4,
+    // The frames between which the file is audible, outside of which it is shaded
+    audible_lens: L5,
+    // The selected frames, which are highlighted
+    selection_lens: L6,
+    // The frames at which the slices start, `None` when not slicing
+    slices_lens: L7,
     units_mode: UnitsMode,
+    // The frame at which the selection started while dragging over the waveform
+    drag_start: Option<usize>,
+    // The index of the slice marker being dragged
+    dragged_marker: Option<usize>,
 }
 
-impl<L1, L2, L3, L4> Waveview<L1, L2, L3, L4>
+impl<L1, L2, L3, L4, L5, L6, L7> Waveview<L1, L2, L3, L4, L5, L6, L7>
 where
     L1: Lens<Target = Option<Arc<Waveform>>>,
     L2: Lens<Target = usize>,
     L3: Lens<Target = usize>,
     L4: Lens<Target = usize>,
+    L5: Lens<Target = Option<(usize, Option<usize>)>>,
+    L6: Lens<Target = Option<(usize, usize)>>,
+    L7: Lens<Target = Option<Vec<usize>>>,
 {
     pub fn new(
         cx: &mut Context,
@@ -37,26 +57,63 @@ where
         zoom_level_lens: L2,
         start_lens: L3,
         playhead_lens: L4,
+        audible_lens: L5,
+        selection_lens: L6,
+        slices_lens: L7,
     ) -> Handle<Self> {
         Self {
             waveform_lens,
             zoom_level_lens,
             start_lens,
             playhead_lens,
+            audible_lens,
+            selection_lens,
+            slices_lens,
             units_mode: UnitsMode::Linear,
+            drag_start: None,
+            dragged_marker: None,
         }
         .build(cx, |cx| {})
         .bind(waveform_lens, |mut handle, _| handle.needs_redraw())
         .bind(playhead_lens, |mut handle, _| handle.needs_redraw())
+        .bind(audible_lens, |mut handle, _| handle.needs_redraw())
+        .bind(selection_lens, |mut handle, _| handle.needs_redraw())
+        .bind(slices_lens, |mut handle, _| handle.needs_redraw())
+    }
+
+    // The frame under the horizontal position `x` of the mouse, `None` before the waveform is loaded
+    fn frame_at(&self, cx: &EventContext, x: f32) -> Option<usize> {
+        let waveform = self.waveform_lens.get(cx)?;
+        let offset = (x - cx.bounds().x).max(0.0);
+        Some((offset * waveform.samples_per_pixel as f32) as usize)
+    }
+
+    // The index of the slice marker within reach of the horizontal position `x` of the mouse, `None` when not
+    // slicing
+    fn marker_at(&self, cx: &EventContext, x: f32) -> Option<usize> {
+        let waveform = self.waveform_lens.get(cx)?;
+        let markers = self.slices_lens.get(cx)?;
+        let samples_per_pixel = waveform.samples_per_pixel.max(1) as f32;
+        let reach = MARKER_REACH * cx.scale_factor();
+        markers
+            .iter()
+            .map(|marker| (cx.bounds().x + *marker as f32 / samples_per_pixel - x).abs())
+            .enumerate()
+            .filter(|(_, distance)| *distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
     }
 }
 
-impl<L1, L2, L3, L4> View for Waveview<L1, L2, L3, L4>
+impl<L1, L2, L3, L4, L5, L6, L7> View for Waveview<L1, L2, L3, L4, L5, L6, L7>
 where
     L1: Lens<Target = Option<Arc<Waveform>>>,
     L2: Lens<Target = usize>,
     L3: Lens<Target = usize>,
     L4: Lens<Target = usize>,
+    L5: Lens<Target = Option<(usize, Option<usize>)>>,
+    L6: Lens<Target = Option<(usize, usize)>>,
+    L7: Lens<Target = Option<Vec<usize>>>,
 {
     fn element(&self) -> Option<&'static str> {
         Some("waveview")
@@ -64,6 +121,61 @@ where
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|window_event, _| match window_event {
+            // When slicing, markers are dragged, and a click plays the slice under the mouse
+            WindowEvent::MouseDown(MouseButton::Left) if self.slices_lens.get(cx).is_some() => {
+                let x = cx.mouse().cursor_x;
+                self.dragged_marker = self.marker_at(cx, x);
+                if self.dragged_marker.is_some() {
+                    cx.capture();
+                } else if let (Some(markers), Some(frame)) =
+                    (self.slices_lens.get(cx), self.frame_at(cx, x))
+                {
+                    if let Some(slice) = markers.iter().rposition(|marker| *marker <= frame) {
+                        cx.emit(SlicesEvent::Audition(slice));
+                    }
+                }
+            }
+
+            // A double click adds a marker, and a right click removes the marker under the mouse
+            WindowEvent::MouseDoubleClick(MouseButton::Left) => {
+                if self.slices_lens.get(cx).is_some() {
+                    if let Some(frame) = self.frame_at(cx, cx.mouse().cursor_x) {
+                        cx.emit(SlicesEvent::Add(frame));
+                    }
+                }
+            }
+
+            WindowEvent::MouseDown(MouseButton::Right) => {
+                if let Some(marker) = self.marker_at(cx, cx.mouse().cursor_x) {
+                    cx.emit(SlicesEvent::Remove(marker));
+                }
+            }
+
+            // Dragging selects the frames to export, and a click clears the selection
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                self.drag_start = self.frame_at(cx, cx.mouse().cursor_x);
+                if self.drag_start.is_some() {
+                    cx.capture();
+                    cx.emit(AppEvent::SetWaveSelection(None));
+                }
+            }
+
+            WindowEvent::MouseMove(x, _) => {
+                if let (Some(marker), Some(frame)) = (self.dragged_marker, self.frame_at(cx, *x)) {
+                    cx.emit(SlicesEvent::Move(marker, frame));
+                } else if let (Some(start), Some(frame)) = (self.drag_start, self.frame_at(cx, *x))
+                {
+                    let selection = (start != frame).then(|| (start.min(frame), start.max(frame)));
+                    cx.emit(AppEvent::SetWaveSelection(selection));
+                }
+            }
+
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag_start.take().is_some() || self.dragged_marker.take().is_some() {
+                    cx.release();
+                }
+            }
+
             WindowEvent::MouseScroll(x, y) => {
                 // println!("scroll {} {}", x, y);
                 // if *y > 0.0 {
@@ -140,10 +252,77 @@ where
                 paint.set_style(vg::PaintStyle::Stroke);
                 canvas.draw_path(&mut path1, &paint);
 
+                let pixels_per_sample = 1.0 / waveform.samples_per_pixel as f32;
+
+                // Shade the leading silence and noise tail
+                if let Some((audible_start, audible_end)) = self.audible_lens.get(cx) {
+                    let mut path = vg::Path::new();
+
+                    let startx = (x + pixels_per_sample * audible_start as f32).min(x + w);
+                    if startx > x {
+                        path.add_rect(vg::Rect::new(x, y, startx, y + h), None);
+                    }
+
+                    if let Some(audible_end) = audible_end {
+                        let endx = (x + pixels_per_sample * audible_end as f32).max(startx);
+                        if endx < x + w {
+                            path.add_rect(vg::Rect::new(endx, y, x + w, y + h), None);
+                        }
+                    }
+
+                    let mut paint = vg::Paint::default();
+                    paint.set_color(Color::rgba(0, 0, 0, 80));
+                    paint.set_anti_alias(false);
+                    paint.set_style(vg::PaintStyle::Fill);
+                    canvas.draw_path(&mut path, &paint);
+                }
+
+                // Highlight the selected frames
+                if let Some((selection_start, selection_end)) = self.selection_lens.get(cx) {
+                    let startx = (x + pixels_per_sample * selection_start as f32).min(x + w);
+                    let endx = (x + pixels_per_sample * selection_end as f32).min(x + w);
+
+                    let mut path = vg::Path::new();
+                    path.add_rect(vg::Rect::new(startx, y, endx, y + h), None);
+
+                    let mut paint = vg::Paint::default();
+                    paint.set_color(Color::rgba(255, 255, 255, 40));
+                    paint.set_anti_alias(false);
+                    paint.set_style(vg::PaintStyle::Fill);
+                    canvas.draw_path(&mut path, &paint);
+                }
+
+                // Draw the slice markers, with a handle at the top to drag them by
+                if let Some(markers) = self.slices_lens.get(cx) {
+                    let mut lines = vg::Path::new();
+                    let mut handles = vg::Path::new();
+                    for marker in markers {
+                        let markerx = (x + pixels_per_sample * marker as f32).floor() + 0.5;
+                        if markerx > x + w {
+                            break;
+                        }
+
+                        lines.move_to((markerx, y));
+                        lines.line_to((markerx, y + h));
+                        handles.add_rect(
+                            vg::Rect::new(markerx - 3.0, y, markerx + 3.0, y + 6.0),
+                            None,
+                        );
+                    }
+
+                    let mut paint = vg::Paint::default();
+                    paint.set_color(Color::rgba(255, 160, 40, 255));
+                    paint.set_stroke_width(1.0);
+                    paint.set_anti_alias(false);
+                    paint.set_style(vg::PaintStyle::Stroke);
+                    canvas.draw_path(&mut lines, &paint);
+                    paint.set_style(vg::PaintStyle::Fill);
+                    canvas.draw_path(&mut handles, &paint);
+                }
+
                 // Draw playhead
                 let playhead = self.playhead_lens.get(cx);
 
-                let pixels_per_sample = 1.0 / waveform.samples_per_pixel as f32;
                 let playheadx = 1.0 + x + pixels_per_sample * playhead as f32;
 
                 let mut path = vg::Path::new();